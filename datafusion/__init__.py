@@ -16,6 +16,7 @@
 # under the License.
 
 from abc import ABCMeta, abstractmethod
+from decimal import Decimal
 from typing import List
 
 try:
@@ -29,6 +30,10 @@ from ._internal import (
     AggregateUDF,
     Config,
     DataFrame,
+    ExecutionPlan,
+    GroupBy,
+    LogicalPlan,
+    ParquetMetadata,
     SessionContext,
     SessionConfig,
     RuntimeConfig,
@@ -92,6 +97,10 @@ __version__ = importlib_metadata.version(__name__)
 __all__ = [
     "Config",
     "DataFrame",
+    "ExecutionPlan",
+    "GroupBy",
+    "LogicalPlan",
+    "ParquetMetadata",
     "SessionContext",
     "SessionConfig",
     "RuntimeConfig",
@@ -149,20 +158,42 @@ __all__ = [
 
 
 class Accumulator(metaclass=ABCMeta):
+    """
+    Interface of a user-defined aggregation.
+
+    Aggregations run on several partitions in parallel, and may spill to disk,
+    so the intermediate result of an accumulator is exported with ``state``
+    and combined into other accumulators with ``merge``.
+    """
+
     @abstractmethod
     def state(self) -> List[pa.Scalar]:
+        """
+        Return the intermediate result, one value per field of the state type.
+        Each value is either a ``pyarrow.Scalar``, cast to the state type if
+        needed, or a plain Python value convertible to it.
+        """
         pass
 
     @abstractmethod
     def update(self, values: pa.Array) -> None:
+        """Update the accumulator with a batch of input values."""
         pass
 
     @abstractmethod
     def merge(self, states: pa.Array) -> None:
+        """
+        Merge the states of other accumulators. It receives one array per
+        field of the state type, each holding that field of every state.
+        """
         pass
 
     @abstractmethod
     def evaluate(self) -> pa.Scalar:
+        """
+        Return the final result, a ``pyarrow.Scalar`` or a plain Python value
+        convertible to the return type.
+        """
         pass
 
 
@@ -173,8 +204,25 @@ def column(value):
 col = column
 
 
+def _decimal_type(value):
+    """
+    The smallest decimal type that holds ``value`` exactly, ``decimal256``
+    when it needs more than 38 digits of precision
+    """
+    _, digits, exponent = value.as_tuple()
+    if not isinstance(exponent, int):
+        raise ValueError(f"Can't create a literal from the decimal {value}")
+    scale = max(-exponent, 0)
+    precision = max(len(digits) + max(exponent, 0), scale, 1)
+    if precision <= 38:
+        return pa.decimal128(precision, scale)
+    return pa.decimal256(precision, scale)
+
+
 def literal(value):
-    if not isinstance(value, pa.Scalar):
+    if isinstance(value, Decimal):
+        value = pa.scalar(value, type=_decimal_type(value))
+    elif not isinstance(value, pa.Scalar):
         value = pa.scalar(value)
     return Expr.literal(value)
 
@@ -199,11 +247,10 @@ def udf(func, input_types, return_type, volatility, name=None):
     )
 
 
-def udaf(accum, input_type, return_type, state_type, volatility, name=None):
-    """
-    Create a new User Defined Aggregate Function
-    """
-    if not issubclass(accum, Accumulator):
+def _create_udaf(
+    accum, input_type, return_type, state_type, volatility, name=None
+):
+    if not isinstance(accum, type) or not issubclass(accum, Accumulator):
         raise TypeError(
             "`accum` must implement the abstract base class Accumulator"
         )
@@ -217,3 +264,31 @@ def udaf(accum, input_type, return_type, state_type, volatility, name=None):
         state_type=state_type,
         volatility=volatility,
     )
+
+
+def udaf(*args, **kwargs):
+    """
+    Create a new User Defined Aggregate Function
+
+    Either called with the accumulator class, ``udaf(accum, input_type,
+    return_type, state_type, volatility, name=None)``, or used as a class
+    decorator with the remaining arguments::
+
+        @udaf(pa.float64(), pa.float64(), [pa.float64()], "immutable")
+        class MySum(Accumulator):
+            ...
+
+        df.aggregate([], [MySum(column("a"))])
+
+    ``input_type`` is a single type, or a list of types for an aggregate
+    of several arguments. The result is an ``AggregateUDF``, which is called
+    with expressions to create an aggregate expression and can be registered
+    with ``SessionContext.register_udaf`` to use it from SQL.
+    """
+    if "accum" in kwargs or (args and isinstance(args[0], type)):
+        return _create_udaf(*args, **kwargs)
+
+    def decorator(accum):
+        return _create_udaf(accum, *args, **kwargs)
+
+    return decorator
@@ -125,3 +125,126 @@ def test_built_in_aggregation(df):
     np.testing.assert_array_almost_equal(
         result.column(21), np.var(values_c, ddof=1)
     )
+
+
+def test_percentiles(df):
+    agg_df = df.aggregate(
+        [column("b")],
+        [
+            f.percentile_cont(column("c"), 0.5).alias("cont"),
+            f.percentile_cont(column("c"), 0.25).alias("cont_25"),
+            f.percentile_disc(column("c"), 0.5).alias("disc"),
+            f.percentile_disc(column("a"), 1).alias("disc_max"),
+        ],
+    )
+    result = agg_df.sort(column("b")).to_pydict()
+    assert result["cont"] == [8.5, 5.0]
+    assert result["cont_25"] == [8.25, 5.0]
+    assert result["disc"] == [8, 5]
+    assert result["disc_max"] == [2, 3]
+    assert agg_df.schema().field("disc").type == pa.int64()
+
+    windowed = df.select(
+        column("a"),
+        f.window(
+            "percentile_cont", [column("c"), lit(0.5)], [column("b")]
+        ).alias("median"),
+    )
+    result = windowed.sort(column("a")).to_pydict()
+    assert result["median"] == [8.5, 8.5, 5.0]
+
+    with pytest.raises(ValueError, match="must be between 0 and 1, got 1.5"):
+        f.percentile_cont(column("c"), 1.5)
+    with pytest.raises(ValueError, match="must be between 0 and 1, got -1"):
+        f.window("percentile_disc", [column("c"), lit(-1)])
+
+
+def test_aggregation_string_specs(df):
+    agg_df = df.aggregate(
+        ["b"], ["sum(a) AS total", "max(c)", f.min(column("c"))]
+    )
+
+    assert agg_df.schema().names[:3] == ["b", "total", "max(c)"]
+
+    result = agg_df.sort(column("b")).to_pydict()
+    assert result["b"] == [4, 6]
+    assert result["total"] == [3, 3]
+    assert result["max(c)"] == [9, 5]
+
+    agg_df = df.aggregate(["b % 2 AS parity"], ["count(*) AS n"])
+    assert agg_df.to_pydict() == {"parity": [0], "n": [3]}
+
+
+def test_group_by(df):
+    grouped = df.group_by("b")
+    assert repr(grouped) == "GroupBy(b)"
+
+    result = (
+        grouped.agg(f.sum(column("a")).alias("sum_a"), "max(c) AS max_c")
+        .sort(column("b"))
+        .to_pydict()
+    )
+    assert result == {"b": [4, 6], "sum_a": [3, 3], "max_c": [9, 5]}
+    assert grouped.agg(f.sum(column("a"))).schema() == df.aggregate(
+        [column("b")], [f.sum(column("a"))]
+    ).schema()
+
+    result = grouped.count().sort(column("b")).to_pydict()
+    assert result == {"b": [4, 6], "count": [2, 1]}
+
+    result = grouped.count("n").sort(column("b")).to_pydict()
+    assert result == {"b": [4, 6], "n": [2, 1]}
+    with pytest.raises(ValueError, match="b, the name of a key"):
+        grouped.count("b")
+
+    result = grouped.sum().sort(column("b")).to_pydict()
+    assert result == {"b": [4, 6], "a": [3, 3], "c": [17, 5]}
+
+    result = df.group_by(column("b")).mean("c").sort(column("b")).to_pydict()
+    assert result == {"b": [4, 6], "c": [8.5, 5.0]}
+
+    with pytest.raises(ValueError, match="is not an aggregate expression"):
+        grouped.agg("a + 1")
+    with pytest.raises(ValueError, match="No field named missing"):
+        grouped.sum("missing")
+
+
+def test_aggregation_string_specs_invalid(df):
+    with pytest.raises(
+        ValueError, match="'a \\+ 1' is not an aggregate expression"
+    ):
+        df.aggregate([], ["a + 1"])
+
+    with pytest.raises(Exception, match="missing"):
+        df.aggregate([], ["sum(missing)"])
+
+
+def test_rollup(df):
+    agg_df = df.aggregate([f.rollup(column("b"))], [f.sum(column("a"))])
+    result = agg_df.sort_by(["b"]).to_pydict()
+
+    assert result["b"] == [None, 4, 6]
+    assert list(result.values())[1] == [6, 3, 3]
+
+
+def test_cube(df):
+    agg_df = df.aggregate(
+        [f.cube(column("b"), column("c"))], [f.count(column("a"))]
+    )
+
+    # (b, c), (b), (c) and the grand total
+    assert agg_df.count() == 3 + 2 + 3 + 1
+
+
+def test_grouping_sets(df):
+    agg_df = df.aggregate(
+        [f.grouping_sets([[column("b")], [column("c")]])],
+        [f.sum(column("a")).alias("total")],
+    )
+    result = agg_df.sort_by(["b", "c"]).to_pydict()
+
+    assert result == {
+        "b": [None, None, None, 4, 6],
+        "c": [5, 8, 9, None, None],
+        "total": [3, 2, 1, 3, 3],
+    }
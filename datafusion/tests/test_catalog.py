@@ -16,6 +16,7 @@
 # under the License.
 
 import pyarrow as pa
+import pyarrow.dataset as ds
 import pytest
 
 
@@ -38,3 +39,71 @@ def test_basic(ctx, database):
             pa.field("float", pa.float64(), nullable=True),
         ]
     )
+
+
+class DatasetCatalog:
+    def __init__(self, tables):
+        self.tables = tables
+        self.requested = []
+
+    def schema_names(self):
+        return list(self.tables.keys())
+
+    def table_names(self, schema):
+        return list(self.tables[schema].keys())
+
+    def table(self, schema, name):
+        self.requested.append((schema, name))
+        return self.tables[schema].get(name)
+
+
+def test_register_catalog_provider(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    provider = DatasetCatalog({"s": {"t": ds.dataset([batch])}})
+    ctx.register_catalog_provider("lazy", provider)
+
+    assert "lazy" in ctx.catalogs()
+    assert ctx.schemas("lazy") == ["s"]
+    assert provider.requested == []
+
+    result = ctx.sql("SELECT a + b AS c FROM lazy.s.t").collect()
+    assert result[0].column(0) == pa.array([5, 7, 9])
+    assert ("s", "t") in provider.requested
+
+    with pytest.raises(Exception, match="not found"):
+        ctx.sql("SELECT * FROM lazy.s.missing")
+
+
+def test_catalog_provider_error(ctx):
+    class FailingCatalog(DatasetCatalog):
+        def table(self, schema, name):
+            raise ValueError("metadata store unavailable")
+
+    ctx.register_catalog_provider(
+        "failing", FailingCatalog({"s": {"t": None}})
+    )
+
+    with pytest.raises(ValueError, match="metadata store unavailable"):
+        ctx.sql("SELECT * FROM failing.s.t")
+
+
+def test_set_default_catalog_and_schema(ctx):
+    batch = pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], names=["a"])
+    provider = DatasetCatalog(
+        {"s": {"t": ds.dataset([batch])}, "other": {"u": None}}
+    )
+    ctx.register_catalog_provider("tenant", provider)
+
+    with pytest.raises(KeyError, match="missing"):
+        ctx.set_default_catalog("missing")
+    ctx.set_default_catalog("tenant")
+    with pytest.raises(KeyError, match="public"):
+        ctx.set_default_schema("public")
+    ctx.set_default_schema("s")
+
+    assert ctx.sql("SELECT sum(a) AS s FROM t").to_pydict() == {"s": [6]}
+    assert ctx.table("t").count() == 3
+    assert ctx.tables() == ["t"]
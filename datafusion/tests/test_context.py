@@ -14,15 +14,23 @@
 # KIND, either express or implied.  See the License for the
 # specific language governing permissions and limitations
 # under the License.
+import base64
+import gc
 import gzip
+import io
+import json
 import os
+import re
+from datetime import date
 
 import pyarrow as pa
 import pyarrow.dataset as ds
+import pyarrow.parquet as pq
 
 from datafusion import (
     column,
     literal,
+    udf,
     SessionContext,
     SessionConfig,
     RuntimeConfig,
@@ -59,6 +67,196 @@ def test_create_context_with_all_valid_args():
         ctx.catalog("datafusion")
 
 
+def test_temp_file_path(tmp_path):
+    spill_dir = tmp_path / "spill"
+    runtime = RuntimeConfig().with_temp_file_path(str(spill_dir))
+    ctx = SessionContext(runtime=runtime)
+
+    # each context works in its own directory, removed with the context
+    (work_dir,) = spill_dir.iterdir()
+    assert work_dir.name.startswith("datafusion-")
+    assert list(work_dir.iterdir()) == []
+    del ctx
+    gc.collect()
+    assert list(spill_dir.iterdir()) == []
+
+    not_a_dir = tmp_path / "file"
+    not_a_dir.write_text("")
+    runtime = RuntimeConfig().with_temp_file_path(str(not_a_dir / "spill"))
+    with pytest.raises(ValueError, match="Unable to create a temporary"):
+        SessionContext(runtime=runtime)
+
+
+def test_memory_pool_usage():
+    ctx = SessionContext()
+    assert ctx.memory_pool_usage() == {"reserved": 0, "limit": None}
+
+    runtime = RuntimeConfig().with_greedy_memory_pool(100000000)
+    ctx = SessionContext(runtime=runtime)
+    assert ctx.memory_pool_usage() == {"reserved": 0, "limit": 100000000}
+
+    # memory reserved by a query is released once it completes
+    df = ctx.from_pydict({"a": list(range(1000))}).sort(column("a"))
+    df.collect()
+    assert ctx.memory_pool_usage()["reserved"] == 0
+
+
+def test_information_schema(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+
+    tables = ctx.sql(
+        "SELECT table_catalog, table_schema, table_name "
+        "FROM information_schema.tables WHERE table_name = 't'"
+    )
+    assert tables.to_pylist() == [
+        {
+            "table_catalog": "datafusion",
+            "table_schema": "public",
+            "table_name": "t",
+        }
+    ]
+
+    columns = ctx.sql(
+        "SELECT column_name FROM information_schema.columns "
+        "WHERE table_name = 't' ORDER BY column_name"
+    )
+    assert columns.to_pydict() == {"column_name": ["a", "b"]}
+
+
+def test_information_schema_disabled():
+    config = SessionConfig().with_information_schema(False)
+    ctx = SessionContext(config)
+
+    with pytest.raises(Exception):
+        ctx.sql("SELECT * FROM information_schema.tables")
+
+
+def test_catalogs_schemas_tables(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+    ctx.sql("CREATE VIEW v AS SELECT a FROM t").collect()
+
+    assert ctx.catalogs() == ["datafusion"]
+    assert ctx.schemas() == ["information_schema", "public"]
+    assert ctx.schemas("datafusion") == ["information_schema", "public"]
+    assert ctx.tables() == ["t", "v"]
+    assert ctx.tables("datafusion", "public") == ["t", "v"]
+
+    with pytest.raises(KeyError):
+        ctx.schemas("missing")
+
+    with pytest.raises(KeyError):
+        ctx.tables(schema="missing")
+
+
+def test_functions(ctx):
+    functions = {(f["name"], f["kind"]): f for f in ctx.functions()}
+
+    assert functions[("abs", "scalar")]["user_defined"] is False
+    assert functions[("sum", "aggregate")]["user_defined"] is False
+    assert functions[("row_number", "window")]["signatures"] == [""]
+    assert ("my_udf", "scalar") not in functions
+
+    is_null = udf(
+        lambda x: x.is_null(),
+        [pa.int64()],
+        pa.bool_(),
+        "immutable",
+        name="my_udf",
+    )
+    ctx.register_udf(is_null)
+
+    my_udf = {f["name"]: f for f in ctx.functions()}["my_udf"]
+    assert my_udf == {
+        "name": "my_udf",
+        "kind": "scalar",
+        "user_defined": True,
+        "signatures": ["Int64"],
+    }
+
+
+def test_udf_lookup(ctx):
+    df = ctx.from_pydict({"a": [1, None, 3]})
+    is_null = udf(
+        lambda x: x.is_null(),
+        [pa.int64()],
+        pa.bool_(),
+        "immutable",
+        name="is_missing",
+    )
+    registered = ctx.register_udf(is_null)
+
+    expected = {"m": [False, True, False]}
+    result = df.select(registered(column("a")).alias("m"))
+    assert result.to_pydict() == expected
+    result = df.select(ctx.udf("is_missing")(column("a")).alias("m"))
+    assert result.to_pydict() == expected
+
+    with pytest.raises(KeyError, match="is_present"):
+        ctx.udf("is_present")
+
+
+def test_sql_parser_options():
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3])], names=["MyColumn"]
+    )
+
+    ctx = SessionContext()
+    ctx.register_record_batches("t", [[batch]])
+    with pytest.raises(Exception, match="mycolumn"):
+        ctx.sql("SELECT MyColumn FROM t")
+    assert ctx.sql('SELECT "MyColumn" FROM t').collect()[0].num_rows == 3
+
+    config = SessionConfig().with_enable_ident_normalization(False)
+    key = "datafusion.sql_parser.enable_ident_normalization"
+    assert config.get(key) == "false"
+    ctx = SessionContext(config)
+    ctx.register_record_batches("t", [[batch]])
+    assert ctx.sql("SELECT MyColumn FROM t").collect()[0].num_rows == 3
+
+    config = SessionConfig().with_parse_float_as_decimal(True)
+    ctx = SessionContext(config)
+    result = ctx.sql("SELECT 1.5 AS v").collect()[0]
+    assert pa.types.is_decimal(result.schema.field("v").type)
+
+    config = SessionConfig().with_sql_dialect("PostgreSQL")
+    assert config.get("datafusion.sql_parser.dialect") == "PostgreSQL"
+    with pytest.raises(ValueError, match="Unsupported SQL dialect"):
+        SessionConfig().with_sql_dialect("cobol")
+    with pytest.raises(KeyError):
+        SessionConfig().get("datafusion.not_an_option")
+
+
+def test_optimizer_rules_disabled():
+    def plan(config):
+        ctx = SessionContext(config)
+        ctx.from_pydict({"a": [1, 2, 3]}, name="t")
+        df = ctx.sql("SELECT a FROM t WHERE a > 1 + 1")
+        assert df.to_pydict() == {"a": [3]}
+        return df.optimized_logical_plan().display_indent()
+
+    assert "t.a > Int64(2)" in plan(SessionConfig())
+    config = SessionConfig().with_optimizer_rules_disabled(
+        ["simplify_expressions"]
+    )
+    assert "t.a > Int64(1) + Int64(1)" in plan(config)
+    unoptimized = plan(SessionConfig().with_optimization(False))
+    assert "t.a > Int64(1) + Int64(1)" in unoptimized
+    assert unoptimized.startswith("Projection: t.a")
+    enabled = config.with_optimization(True)
+    assert "t.a > Int64(2)" in plan(enabled)
+
+    with pytest.warns(UserWarning, match="no optimizer rule named pushdown"):
+        SessionConfig().with_optimizer_rules_disabled(["pushdown"])
+
+
 def test_register_record_batches(ctx):
     # create a RecordBatch and register it as memtable
     batch = pa.RecordBatch.from_arrays(
@@ -68,7 +266,7 @@ def test_register_record_batches(ctx):
 
     ctx.register_record_batches("t", [[batch]])
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     result = ctx.sql("SELECT a+b, a-b FROM t").collect()
 
@@ -140,6 +338,57 @@ def test_from_arrow_table_with_name(ctx):
     assert tables[0] == "tbl"
 
 
+class ArrowStreamExporter:
+    """Exposes data only through the Arrow PyCapsule stream interface"""
+
+    def __init__(self, table):
+        self.table = table
+
+    def __arrow_c_stream__(self, requested_schema=None):
+        return self.table.__arrow_c_stream__(requested_schema)
+
+
+requires_pycapsule = pytest.mark.skipif(
+    not hasattr(pa.Table, "__arrow_c_stream__"),
+    reason="pyarrow does not implement the Arrow PyCapsule interface",
+)
+
+
+@requires_pycapsule
+def test_from_arrow_pycapsule(ctx):
+    table = pa.Table.from_pydict({"a": [1, 2, 3], "b": [4, 5, 6]})
+
+    df = ctx.from_arrow(ArrowStreamExporter(table), name="tbl")
+
+    assert ctx.tables() == ["tbl"]
+    assert df.schema() == table.schema
+    assert pa.Table.from_batches(df.collect()) == table
+
+    batch = table.to_batches()[0]
+    df = ctx.from_arrow(batch)
+    assert df.collect()[0] == batch
+
+
+@requires_pycapsule
+def test_register_record_batches_pycapsule(ctx):
+    table = pa.Table.from_pydict({"a": [1, 2, 3], "b": [4, 5, 6]})
+
+    ctx.register_record_batches("t", ArrowStreamExporter(table))
+
+    result = ctx.sql("SELECT a+b FROM t").collect()
+    assert result[0].column(0) == pa.array([5, 7, 9])
+
+
+def test_from_arrow_pyarrow_fallback(ctx):
+    table = pa.Table.from_pydict({"a": [1, 2, 3], "b": [4, 5, 6]})
+
+    df = ctx.from_arrow(table)
+    assert pa.Table.from_batches(df.collect()) == table
+
+    df = ctx.from_arrow(table.to_batches())
+    assert pa.Table.from_batches(df.collect()) == table
+
+
 def test_from_pylist(ctx):
     # create a dataframe from Python list
     data = [
@@ -172,6 +421,26 @@ def test_from_pydict(ctx):
     assert df.collect()[0].num_rows == 3
 
 
+def test_schema_from_type_names(ctx, tmp_path):
+    schema = {"a": "int16", "b": "string", "c": pa.float32()}
+    df = ctx.from_pylist([{"a": 1, "b": "x", "c": 1.5}], schema=schema)
+    assert df.schema() == pa.schema(
+        [("a", pa.int16()), ("b", pa.string()), ("c", pa.float32())]
+    )
+
+    df = ctx.from_pydict({"a": [1, 2]}, schema={"a": "uint8"})
+    assert df.schema().field("a").type == pa.uint8()
+
+    path = tmp_path / "test.csv"
+    path.write_text("a,b\n1,2020-01-02\n")
+    ctx.register_csv("csv", path, schema={"a": "int8", "b": "date32"})
+    result = ctx.sql("SELECT * FROM csv").collect()[0]
+    assert result.schema.types == [pa.int8(), pa.date32()]
+
+    with pytest.raises(ValueError, match="supported values are: null, bool"):
+        ctx.from_pydict({"a": [1]}, schema={"a": "integer"})
+
+
 def test_from_pandas(ctx):
     # create a dataframe from pandas dataframe
     pd = pytest.importorskip("pandas")
@@ -184,9 +453,36 @@ def test_from_pandas(ctx):
     assert df
     assert len(tables) == 1
     assert type(df) == DataFrame
-    assert set(df.schema().names) == {"a", "b"}
+    assert df.schema().names == ["index", "a", "b"]
     assert df.collect()[0].num_rows == 3
 
+    df = ctx.from_pandas(pandas_df, preserve_index=False)
+    assert df.schema().names == ["a", "b"]
+
+
+def test_from_pandas_preserve_index(ctx):
+    pd = pytest.importorskip("pandas")
+
+    pandas_df = pd.DataFrame(
+        {"price": [1.5, 2.0]}, index=pd.Index(["x", "y"], name="symbol")
+    )
+    df = ctx.from_pandas(pandas_df)
+    assert df.to_pydict() == {"symbol": ["x", "y"], "price": [1.5, 2.0]}
+
+    index = pd.MultiIndex.from_tuples([(1, "x"), (2, "y")], names=["a", None])
+    pandas_df = pd.DataFrame({"index": [3, 4], "a": [5, 6]}, index=index)
+    df = ctx.from_pandas(pandas_df)
+    assert df.to_pydict() == {
+        "a.1": [1, 2],
+        "level_1": ["x", "y"],
+        "index": [3, 4],
+        "a": [5, 6],
+    }
+
+    pandas_df = pd.DataFrame({"index": [3, 4]}, index=[10, 20])
+    df = ctx.from_pandas(pandas_df)
+    assert df.to_pydict() == {"level_0": [10, 20], "index": [3, 4]}
+
 
 def test_from_polars(ctx):
     # create a dataframe from Polars dataframe
@@ -233,6 +529,109 @@ def test_deregister_table(ctx, database):
     assert public.names() == {"csv1", "csv2"}
 
 
+def test_clone_session(ctx):
+    ctx.from_pydict({"a": [1, 2]}, name="t")
+    ctx.register_udf(
+        udf(lambda x: x, [pa.int64()], pa.int64(), "immutable", name="same")
+    )
+    child = ctx.clone_session()
+
+    assert child.sql("SELECT same(a) AS a FROM t").to_pydict() == {
+        "a": [1, 2]
+    }
+
+    child.from_pydict({"b": [3]}, name="u")
+    child.sql("CREATE VIEW w AS SELECT a + 1 AS c FROM t")
+    assert child.table_exist("u")
+    assert child.table_exist("w")
+    assert not ctx.table_exist("u")
+    assert not ctx.table_exist("w")
+
+    # a table of the child shadows the one of the parent
+    child.from_pydict({"a": [10]}, name="t")
+    assert child.sql("SELECT a FROM t").to_pydict() == {"a": [10]}
+    assert ctx.sql("SELECT a FROM t").to_pydict() == {"a": [1, 2]}
+
+    # later registrations in the parent are visible to the child
+    ctx.from_pydict({"d": [4]}, name="v")
+    assert child.sql("SELECT d FROM v").to_pydict() == {"d": [4]}
+
+    child.register_udf(
+        udf(lambda x: x, [pa.int64()], pa.int64(), "immutable", name="own")
+    )
+    with pytest.raises(KeyError):
+        ctx.udf("own")
+
+    # deregistering the table of the child uncovers the one of the parent,
+    # which is hidden when it is dropped in turn
+    child.deregister_table("t")
+    assert child.sql("SELECT a FROM t").to_pydict() == {"a": [1, 2]}
+    child.sql("DROP TABLE t")
+    assert not child.table_exist("t")
+    assert ctx.table_exist("t")
+    with pytest.raises(Exception, match="Table 't' doesn't exist"):
+        child.sql("DROP TABLE t")
+    child.from_pydict({"a": [20]}, name="t")
+    assert child.sql("SELECT a FROM t").to_pydict() == {"a": [20]}
+
+    # schemas created in the parent later are visible to the child
+    ctx.sql("CREATE SCHEMA extra")
+    ctx.sql("CREATE TABLE extra.e AS SELECT 5 AS e")
+    assert child.sql("SELECT e FROM extra.e").to_pydict() == {"e": [5]}
+    child.sql("CREATE TABLE extra.f AS SELECT 6 AS f")
+    assert child.table_exist("extra.f")
+    assert not ctx.table_exist("extra.f")
+
+
+def test_result_cache(ctx):
+    calls = []
+
+    def count(x):
+        calls.append(len(x))
+        return x
+
+    ctx.register_udf(
+        udf(count, [pa.int64()], pa.int64(), "immutable", name="count_calls")
+    )
+    ctx.from_pydict({"a": [1, 2, 3]}, name="t")
+    query = "SELECT count_calls(a) AS a FROM t WHERE a > {}"
+
+    def executes(sql):
+        before = len(calls)
+        ctx.sql(sql).collect()
+        return len(calls) > before
+
+    # disabled by default
+    assert executes(query.format(1))
+    assert executes(query.format(1))
+
+    ctx.enable_result_cache(1 << 20)
+    assert executes(query.format(1))
+    assert not executes(query.format(1))
+    assert ctx.sql(query.format(1)).to_pydict() == {"a": [2, 3]}
+
+    # another parameter is another plan
+    assert executes(query.format(2))
+    assert ctx.sql(query.format(2)).to_pydict() == {"a": [3]}
+
+    # a table registered again under the same name isn't the cached one
+    ctx.deregister_table("t")
+    ctx.from_pydict({"a": [4, 5]}, name="t")
+    assert ctx.sql(query.format(1)).to_pydict() == {"a": [4, 5]}
+
+    ctx.clear_result_cache()
+    assert executes(query.format(1))
+
+    # volatile functions are computed again
+    first = ctx.sql("SELECT random() AS r").to_pydict()
+    assert ctx.sql("SELECT random() AS r").to_pydict() != first
+
+    # results larger than the budget aren't cached
+    ctx.enable_result_cache(1)
+    assert executes(query.format(1))
+    assert executes(query.format(1))
+
+
 def test_register_dataset(ctx):
     # create a RecordBatch and register it as a pyarrow.dataset.Dataset
     batch = pa.RecordBatch.from_arrays(
@@ -242,7 +641,7 @@ def test_register_dataset(ctx):
     dataset = ds.dataset([batch])
     ctx.register_dataset("t", dataset)
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     result = ctx.sql("SELECT a+b, a-b FROM t").collect()
 
@@ -259,7 +658,7 @@ def test_dataset_filter(ctx, capfd):
     dataset = ds.dataset([batch])
     ctx.register_dataset("t", dataset)
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
     df = ctx.sql("SELECT a+b, a-b FROM t WHERE a BETWEEN 2 and 3 AND b > 5")
 
     # Make sure the filter was pushed down in Physical Plan
@@ -286,7 +685,7 @@ def test_dataset_filter_nested_data(ctx):
     dataset = ds.dataset([batch])
     ctx.register_dataset("t", dataset)
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     df = ctx.table("t")
 
@@ -346,6 +745,17 @@ def test_read_json(ctx):
     assert result[0].column(1) == pa.array([1, 2, 3])
 
 
+def test_read_json_array(ctx, tmp_path):
+    path = tmp_path / "data.json"
+    path.write_text('[{"a": "x", "b": 1}, {"a": "y", "b": 2}]')
+
+    df = ctx.read_json(path, format="array")
+    assert df.to_pydict() == {"a": ["x", "y"], "b": [1, 2]}
+
+    df = ctx.read_json_bytes(path.read_bytes(), format="array")
+    assert df.to_pydict() == {"a": ["x", "y"], "b": [1, 2]}
+
+
 def test_read_json_compressed(ctx, tmp_path):
     path = os.path.dirname(os.path.abspath(__file__))
     test_data_path = os.path.join(path, "data_test_context", "data.json")
@@ -392,6 +802,271 @@ def test_read_parquet(ctx):
     csv_df.show()
 
 
+def test_read_parquet_file_list(ctx, tmp_path):
+    paths = []
+    for i in range(3):
+        path = str(tmp_path / f"part-{i}.parquet")
+        pq.write_table(pa.table({"a": [i, i + 10]}), path)
+        paths.append(path)
+
+    df = ctx.read_parquet([paths[0], paths[2]])
+    assert sorted(df.to_pydict()["a"]) == [0, 2, 10, 12]
+
+    ctx.register_parquet("t", paths[1:])
+    assert sorted(ctx.table("t").to_pydict()["a"]) == [1, 2, 11, 12]
+
+    other = str(tmp_path / "other.parquet")
+    pq.write_table(pa.table({"b": ["x"]}), other)
+    with pytest.raises(ValueError, match="merge_schemas"):
+        ctx.read_parquet([paths[0], other])
+
+    df = ctx.read_parquet([paths[0], other], merge_schemas=True)
+    assert df.schema().names == ["a", "b"]
+    assert df.count() == 3
+
+
+def test_read_parquet_filter(ctx, tmp_path):
+    path = str(tmp_path / "events.parquet")
+    dates = pa.array(
+        [date(2023, 12, 30), date(2023, 12, 31), date(2024, 1, 1)]
+    )
+    pq.write_table(pa.table({"event_date": dates, "id": [1, 2, 3]}), path)
+
+    df = ctx.read_parquet(path, filter="event_date >= '2024-01-01'")
+    assert df.to_pydict()["id"] == [3]
+    scan = df.optimized_logical_plan().display_indent()
+    assert "_filters=[" in scan
+
+    df = ctx.read_parquet([path], filter="id < 3 AND id > 1")
+    assert df.to_pydict()["id"] == [2]
+
+    with pytest.raises(Exception, match="missing"):
+        ctx.read_parquet(path, filter="missing > 1")
+
+
+def test_read_parquet_metadata(ctx, tmp_path):
+    path = str(tmp_path / "data.parquet")
+    table = pa.table({"a": [1, 2, 3], "b": ["x", "y", "z"]})
+    table = table.replace_schema_metadata({"owner": "analytics"})
+    pq.write_table(table, path, row_group_size=2)
+
+    metadata = ctx.read_parquet_metadata(path)
+    assert metadata.schema == table.schema
+    assert metadata.num_rows == 3
+    assert metadata.num_row_groups == 2
+    assert [rg["num_rows"] for rg in metadata.row_groups] == [2, 1]
+    assert all(rg["num_columns"] == 2 for rg in metadata.row_groups)
+    assert metadata.metadata["owner"] == "analytics"
+    assert metadata.created_by.startswith("parquet-cpp")
+
+    with pytest.raises(Exception):
+        ctx.read_parquet_metadata(str(tmp_path / "missing.parquet"))
+
+
+def test_read_bytes(ctx):
+    table = pa.table({"a": [1, 2, 3], "b": ["x", "y", "z"]})
+    buffer = io.BytesIO()
+    pq.write_table(table, buffer)
+
+    df = ctx.read_parquet_bytes(buffer.getvalue())
+    assert df.to_arrow_table().equals(table)
+    df = ctx.read_parquet_bytes(memoryview(buffer.getvalue()))
+    assert df.to_pydict() == table.to_pydict()
+
+    df = ctx.read_csv_bytes(b"a,b\n1,x\n2,y\n3,z\n")
+    assert df.schema() == table.schema
+    assert df.to_pydict() == table.to_pydict()
+
+    data = gzip.compress(b"a;b\n1;x\n2;y\n3;z\n")
+    df = ctx.read_csv_bytes(
+        bytearray(data), delimiter=";", file_compression_type="gzip"
+    )
+    assert df.to_pydict() == table.to_pydict()
+
+    data = b'{"a": 1, "b": "x"}\n{"a": 2, "b": "y"}\n{"a": 3, "b": "z"}\n'
+    assert ctx.read_json_bytes(data).to_pydict() == table.to_pydict()
+
+
+def test_read_bytes_deleted(ctx):
+    df = ctx.read_csv_bytes(b"a\n1\n2\n")
+    schema = pa.schema([("a", pa.int64())])
+    stored = ctx.read_csv("memory://bytes/", schema=schema)
+    assert stored.count() == 2
+
+    # the file is kept for the streams of the DataFrame
+    stream = df.execute_stream()
+    del df
+    assert stored.count() == 2
+    assert sum(batch.to_pyarrow().num_rows for batch in stream) == 2
+
+    del stream
+    assert stored.count() == 0
+
+
+def scan_metric(ctx, query, metric):
+    plan = ctx.sql(f"EXPLAIN ANALYZE {query}").to_pydict()["plan"]
+    counts = re.findall(rf"{metric}=(\d+)", "\n".join(plan))
+    return sum(int(count) for count in counts)
+
+
+def page_index_rows_filtered(ctx, query):
+    return scan_metric(ctx, query, "page_index_rows_filtered")
+
+
+def test_parquet_enable_page_index(ctx, tmp_path):
+    path = str(tmp_path / "sorted.parquet")
+    table = pa.table({"a": pa.array(range(100_000))})
+    try:
+        pq.write_table(table, path, data_page_size=1024, write_page_index=True)
+    except TypeError:
+        pytest.skip("pyarrow can't write the page index")
+
+    query = "SELECT * FROM t WHERE a = 5"
+    ctx.register_parquet("t", path, enable_page_index=True)
+    assert page_index_rows_filtered(ctx, query) > 0
+
+    ctx.deregister_table("t")
+    ctx.register_parquet("t", path, enable_page_index=False)
+    assert page_index_rows_filtered(ctx, query) == 0
+
+    ctx = SessionContext(SessionConfig().with_parquet_page_index(False))
+    ctx.register_parquet("t", path)
+    assert page_index_rows_filtered(ctx, query) == 0
+
+    df = ctx.read_parquet(path, enable_page_index=True)
+    result = df.filter(column("a") == literal(5)).collect()
+    assert result[0].column(0) == pa.array([5])
+
+
+def test_parquet_bloom_filter_on_read(ctx, tmp_path):
+    path = str(tmp_path / "bloom.parquet")
+    # every row group spans the whole range of values, so that only the bloom
+    # filters tell that a value is absent from it
+    values = [(i % 10) * 100 + i // 10 for i in range(1000)]
+    batch = pa.RecordBatch.from_pydict({"a": values})
+    ctx.register_record_batches("source", [[batch]])
+    ctx.sql(
+        f"COPY source TO '{path}' (FORMAT parquet, SINGLE_FILE_OUTPUT true, "
+        "BLOOM_FILTER_ENABLED true, BLOOM_FILTER_NDV 100, "
+        "MAX_ROW_GROUP_SIZE 100)"
+    ).collect()
+
+    def pruned(ctx, query):
+        return scan_metric(ctx, query, "row_groups_pruned_bloom_filter")
+
+    query = "SELECT * FROM t WHERE a = 555"
+    ctx.register_parquet("t", path, bloom_filter_on_read=True)
+    assert pruned(ctx, query) == 9
+    assert pruned(ctx, "SELECT * FROM t WHERE a IN (5, 555, 7000)") == 8
+    assert ctx.sql(query).to_pydict() == {"a": [555]}
+
+    ctx.deregister_table("t")
+    ctx.register_parquet("t", path)
+    assert pruned(ctx, query) == 0
+
+    config = SessionConfig().with_parquet_bloom_filter_on_read(True)
+    ctx = SessionContext(config)
+    ctx.register_parquet("t", path)
+    assert pruned(ctx, query) == 9
+
+    df = ctx.read_parquet(path, bloom_filter_on_read=False)
+    assert df.filter(column("a") == literal(555)).count() == 1
+
+
+def test_parquet_metadata_size_hint(ctx, tmp_path):
+    path = str(tmp_path / "a.parquet")
+    pq.write_table(pa.table({"a": [1, 2, 3]}), path)
+
+    ctx.register_parquet("t", path, metadata_size_hint=64 * 1024)
+    assert ctx.sql("SELECT SUM(a) AS s FROM t").to_pydict() == {"s": [6]}
+    df = ctx.read_parquet(path, metadata_size_hint=16)
+    assert df.to_pydict() == {"a": [1, 2, 3]}
+
+    config = SessionConfig().with_parquet_metadata_size_hint(1024)
+    key = "datafusion.execution.parquet.metadata_size_hint"
+    assert config.get(key) == "1024"
+    ctx = SessionContext(config)
+    assert ctx.read_parquet(path).count() == 3
+
+    with pytest.raises(ValueError, match="at least 8 bytes"):
+        ctx.read_parquet(path, metadata_size_hint=4)
+    with pytest.raises(ValueError, match="at least 8 bytes"):
+        SessionConfig().with_parquet_metadata_size_hint(0)
+
+
+def write_encrypted_parquet(path, table, algorithm="AES_GCM_V1"):
+    pe = pytest.importorskip("pyarrow.parquet.encryption")
+
+    # Wraps a key by prefixing it with its master key, which is only
+    # acceptable in a test
+    class PrefixKmsClient(pe.KmsClient):
+        def __init__(self, config):
+            pe.KmsClient.__init__(self)
+            self.master_keys = config.custom_kms_conf
+
+        def wrap_key(self, key_bytes, master_key_identifier):
+            master_key = self.master_keys[master_key_identifier].encode()
+            return base64.b64encode(master_key + key_bytes)
+
+        def unwrap_key(self, wrapped_key, master_key_identifier):
+            return base64.b64decode(wrapped_key)[16:]
+
+    kms_config = pe.KmsConnectionConfig(
+        custom_kms_conf={
+            "footer": "0123456789012345",
+            "secret": "5432109876543210",
+        }
+    )
+    config = pe.EncryptionConfiguration(
+        footer_key="footer",
+        column_keys={"secret": ["b"]},
+        encryption_algorithm=algorithm,
+        double_wrapping=False,
+    )
+    factory = pe.CryptoFactory(lambda config: PrefixKmsClient(config))
+    properties = factory.file_encryption_properties(kms_config, config)
+    with pq.ParquetWriter(
+        path, table.schema, encryption_properties=properties
+    ) as writer:
+        writer.write_table(table)
+
+
+@pytest.mark.parametrize("algorithm", ["AES_GCM_V1", "AES_GCM_CTR_V1"])
+def test_read_encrypted_parquet(ctx, tmp_path, algorithm):
+    path = str(tmp_path / "encrypted.parquet")
+    table = pa.table({"a": [1, 2, 3], "b": ["x", "y", "z"]})
+    write_encrypted_parquet(path, table, algorithm)
+
+    keys = []
+
+    def unwrap(key_metadata):
+        material = json.loads(key_metadata)
+        key = base64.b64decode(material["wrappedDEK"])[16:]
+        keys.append(key)
+        return key
+
+    df = ctx.read_parquet(path, decryption_key=unwrap)
+    assert df.to_pydict() == table.to_pydict()
+    ctx.register_parquet("t", str(tmp_path), decryption_key=unwrap)
+    result = ctx.sql("SELECT b FROM t WHERE a = 2").to_pydict()
+    assert result == {"b": ["y"]}
+
+    # the files are decrypted when they are scanned, so files added to the
+    # directory later are read too
+    write_encrypted_parquet(str(tmp_path / "added.parquet"), table, algorithm)
+    result = ctx.sql("SELECT count(*) AS n FROM t WHERE a = 2").to_pydict()
+    assert result == {"n": [2]}
+
+    with pytest.raises(Exception, match="is encrypted, pass its key"):
+        ctx.read_parquet(path)
+    with pytest.raises(Exception, match="the footer key is wrong"):
+        ctx.read_parquet(path, decryption_key=lambda _: b"0" * 16)
+    with pytest.raises(Exception, match="column b is encrypted with its own"):
+        ctx.read_parquet(path, decryption_key=keys[0])
+    with pytest.raises(ValueError, match="must be bytes or a function"):
+        ctx.read_parquet(path, decryption_key=16)
+
+
 def test_read_avro(ctx):
     csv_df = ctx.read_avro(path="testing/data/avro/alltypes_plain.avro")
     csv_df.show()
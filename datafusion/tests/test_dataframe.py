@@ -14,14 +14,30 @@
 # KIND, either express or implied.  See the License for the
 # specific language governing permissions and limitations
 # under the License.
+import _thread
+import datetime
+import json
 import os
+import re
+import threading
+import time
+from concurrent.futures import ThreadPoolExecutor
+from decimal import Decimal
 
 import pyarrow as pa
+import pyarrow.dataset as ds
 import pyarrow.parquet as pq
 import pytest
 
 from datafusion import functions as f
-from datafusion import DataFrame, SessionContext, column, literal, udf
+from datafusion import (
+    DataFrame,
+    SessionConfig,
+    SessionContext,
+    column,
+    literal,
+    udf,
+)
 
 
 @pytest.fixture
@@ -107,6 +123,212 @@ def test_sort(df):
     assert table.to_pydict() == expected
 
 
+def test_sort_multiple_keys(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 1, 2, 2, None]), pa.array([4, 5, 6, None, 7])],
+        names=["a", "b"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    sorted_df = df.sort(
+        [
+            column("a").sort(ascending=True, nulls_first=False),
+            column("b").sort(ascending=False, nulls_first=True),
+        ]
+    )
+    expected = {"a": [1, 1, 2, 2, None], "b": [5, 4, None, 6, 7]}
+    assert sorted_df.to_pydict() == expected
+
+    assert df.sort_by(["a", "b"], ascending=[True, False]).to_pydict() == {
+        "a": [None, 1, 1, 2, 2],
+        "b": [7, 5, 4, None, 6],
+    }
+    assert df.sort_by(["b"], ascending=False).to_pydict() == {
+        "a": [2, None, 2, 1, 1],
+        "b": [None, 7, 6, 5, 4],
+    }
+    assert df.sort_by(["b"]).to_pydict()["b"] == [None, 4, 5, 6, 7]
+
+    with pytest.raises(
+        ValueError, match="ascending has 1 values but 2 columns"
+    ):
+        df.sort_by(["a", "b"], ascending=[True])
+
+
+def test_sort_default_null_ordering():
+    config = SessionConfig().with_default_null_ordering("last")
+    ctx = SessionContext(config)
+    df = ctx.from_pydict({"a": [2, None, 1]})
+
+    assert df.sort(column("a")).to_pydict() == {"a": [1, 2, None]}
+    assert df.sort(column("a").sort(ascending=False)).to_pydict() == {
+        "a": [2, 1, None]
+    }
+    assert df.sort_by(["a"], ascending=False).to_pydict() == {
+        "a": [2, 1, None]
+    }
+    # an explicit null ordering wins
+    assert df.sort(column("a").sort(nulls_first=True)).to_pydict() == {
+        "a": [None, 1, 2]
+    }
+    assert df.sort(f.order_by(column("a"), nulls_first=True)).to_pydict() == {
+        "a": [None, 1, 2]
+    }
+
+    # without the option nulls sort first
+    df = SessionContext().from_pydict({"a": [2, None, 1]})
+    assert df.sort(column("a").sort(ascending=False)).to_pydict() == {
+        "a": [None, 2, 1]
+    }
+    assert df.sort_by(["a"]).to_pydict() == {"a": [None, 1, 2]}
+
+    with pytest.raises(ValueError, match="Unrecognized null ordering middle"):
+        SessionConfig().with_default_null_ordering("middle")
+
+
+def test_show(ctx, capsys):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array(["abcdefghij", "x", "y"])],
+        names=["a", "long_name"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    df.show(num=2)
+    out = capsys.readouterr().out
+    assert "abcdefghij" in out
+    assert "| 3 " not in out
+
+    df.show(max_width=5)
+    assert "| ab...     |" in capsys.readouterr().out
+
+    df.show(max_width=4, truncate=False)
+    lines = capsys.readouterr().out.splitlines()
+    assert "| 1 | abcd      |" in lines
+    assert "|   | efgh      |" in lines
+
+    with pytest.raises(ValueError, match="max_width must be at least 3"):
+        df.show(max_width=2)
+    df.show(max_width=2, truncate=False)
+    assert "|   | gh        |" in capsys.readouterr().out.splitlines()
+
+    df.filter(column("a") > literal(3)).show()
+    lines = capsys.readouterr().out.splitlines()
+    assert "| a | long_name |" in lines
+
+
+def test_show_footer(df, capsys):
+    df.show(num=2)
+    assert capsys.readouterr().out.splitlines()[-1] == "showing 2 of 3 rows"
+
+    df.filter(column("a") > literal(0)).show(num=2)
+    lines = capsys.readouterr().out.splitlines()
+    assert lines[-1] == "showing the first 2 rows"
+
+    df.show()
+    assert "showing" not in capsys.readouterr().out
+
+
+def test_show_interrupted(ctx):
+    batch = pa.RecordBatch.from_arrays([pa.array(range(2000))], names=["x"])
+    ctx.register_record_batches("t", [[batch]])
+    # the cross join of 8 billion rows keeps none, so the preview never fills
+    df = ctx.sql("SELECT a.x FROM t a, t b, t c WHERE a.x + b.x + c.x < 0")
+
+    for preview in [df.show, df._repr_html_]:
+        timer = threading.Timer(0.5, _thread.interrupt_main)
+        timer.start()
+        with pytest.raises(KeyboardInterrupt):
+            preview()
+        timer.join()
+
+
+def test_binary_columns(ctx, capsys):
+    hashes = pa.array([b"\x01\x02\xab\xcd", b"\xff\x00\x00\x01"], pa.binary(4))
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([b"a", b"\x00b"]), hashes], names=["blob", "hash"]
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    table = df.to_arrow_table()
+    assert table.schema.types == [pa.binary(), pa.binary(4)]
+    assert table.column("hash").to_pylist() == hashes.to_pylist()
+    assert df.collect()[0].column(1) == hashes
+
+    matched = df.filter(
+        column("hash") == f.lit_fixed_binary(b"\xff\x00\x00\x01", 4)
+    )
+    assert matched.to_pydict()["blob"] == [b"\x00b"]
+    matched = df.filter(column("blob") == literal(b"a"))
+    assert matched.to_pydict()["hash"] == [b"\x01\x02\xab\xcd"]
+
+    df.show()
+    out = capsys.readouterr().out
+    assert "| 0062 | ff000001 |" in out
+
+    with pytest.raises(ValueError, match="expects a value of 4 bytes"):
+        f.lit_fixed_binary(b"\x01", 4)
+
+
+def test_show_display_options(capsys):
+    batch = pa.RecordBatch.from_arrays(
+        [
+            pa.array([1.23456, None]),
+            pa.array([datetime.datetime(2023, 1, 2, 3, 4, 5), None]),
+        ],
+        names=["f", "ts"],
+    )
+    config = SessionConfig().with_display_options(
+        timestamp_format="%Y/%m/%d", float_precision=2, null_repr="NULL"
+    )
+    df = SessionContext(config).create_dataframe([[batch]])
+
+    df.show()
+    out = capsys.readouterr().out
+    assert "| 1.23 | 2023/01/02 |" in out
+    assert "| NULL | NULL       |" in out
+
+    # per-call options override the session
+    df.show(float_precision=4, null_repr="-")
+    out = capsys.readouterr().out
+    assert "| 1.2346 | 2023/01/02 |" in out
+    assert "| -      | -          |" in out
+
+    # the data itself is unchanged
+    assert df.to_pydict()["f"] == [1.23456, None]
+
+
+def test_repr_html(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array(range(20)), pa.array(["<b>"] * 20)],
+        names=["a", "b"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    html = df._repr_html_()
+    assert "<th>a<br><small>Int64</small></th>" in html
+    assert "<th>b<br><small>Utf8</small></th>" in html
+    assert html.count("<td>&lt;b&gt;</td>") == 10
+    assert "<td>...</td>" in html
+    assert "<p>showing 10 of 20 rows</p>" in html
+
+    # the row count isn't known without running a filter
+    html = df.filter(column("a") >= literal(0))._repr_html_()
+    assert html.count("<td>&lt;b&gt;</td>") == 10
+    assert "<p>showing the first 10 rows</p>" in html
+
+    html = df.limit(3)._repr_html_()
+    assert html.count("<tr>") == 4
+    assert "<td>...</td>" not in html
+    assert "<p>3 rows</p>" in html
+
+
+def test_show_vertical(df, capsys):
+    df.show_vertical(num=1)
+    out = capsys.readouterr().out
+
+    assert out.splitlines() == ["-[ RECORD 1 ]-", "a | 1", "b | 4", "c | 8"]
+
+
 def test_limit(df):
     df = df.limit(1)
 
@@ -155,6 +377,55 @@ def test_with_column_renamed(df):
     assert result.schema.field(2).name == "sum"
 
 
+def test_rename_all(df):
+    ugly = df.select(column("a") + literal(1), column("b") * column("c"))
+    renamed = ugly.union(ugly).rename_all(["x", "y"])
+
+    assert renamed.schema().names == ["x", "y"]
+    assert sorted(renamed.to_pydict()["x"]) == [2, 2, 3, 3, 4, 4]
+
+    with pytest.raises(ValueError, match="Expected 3 names"):
+        df.rename_all(["x", "y"])
+    with pytest.raises(ValueError, match="'x' is given more than once"):
+        df.rename_all(["x", "y", "x"])
+
+
+def test_flatten():
+    ctx = SessionContext()
+    df = ctx.from_pydict(
+        {
+            "a": [{"b": 1, "c": {"d": "x"}}, {"b": 2, "c": None}],
+            "e": [{"f": 3.0}, None],
+            "a.b": [5, 6],
+        }
+    )
+
+    flat = df.flatten(columns=["e"])
+    assert flat.schema().names == ["a", "e.f", "a.b"]
+    assert flat.to_pydict()["e.f"] == [3.0, None]
+
+    flat = df.flatten(separator="_")
+    assert flat.to_pydict() == {
+        "a_b": [1, 2],
+        "a_c_d": ["x", None],
+        "e_f": [3.0, None],
+        "a.b": [5, 6],
+    }
+    assert df.flatten(separator="_", max_depth=1).schema().names == [
+        "a_b",
+        "a_c",
+        "e_f",
+        "a.b",
+    ]
+
+    with pytest.raises(ValueError, match="'a.b' more than once"):
+        df.flatten()
+    with pytest.raises(ValueError, match="'a.b' is not a struct"):
+        df.flatten(columns=["a.b"])
+    with pytest.raises(ValueError, match="'g' doesn't exist"):
+        df.flatten(columns=["g"])
+
+
 def test_udf(df):
     # is_null is a pa function over arrays
     is_null = udf(
@@ -170,6 +441,78 @@ def test_udf(df):
     assert result == pa.array([False, False, False])
 
 
+def test_udf_nested_return_type(df):
+    label_type = pa.struct([("label", pa.string()), ("score", pa.float64())])
+
+    def classify(x):
+        labels = [
+            {"label": "odd" if v % 2 else "even", "score": v / 10}
+            for v in x.to_pylist()
+        ]
+        return pa.array(labels, type=label_type)
+
+    classify_udf = udf(classify, [pa.int64()], label_type, "immutable")
+    result = df.select(classify_udf(column("a")).alias("c")).collect()[0]
+    assert result.schema.field("c").type == label_type
+    assert result.column(0).to_pylist() == [
+        {"label": "odd", "score": 0.1},
+        {"label": "even", "score": 0.2},
+        {"label": "odd", "score": 0.3},
+    ]
+
+    # the items of pyarrow lists are named differently than those declared
+    split = udf(
+        lambda x: pa.array([[v] * v for v in x.to_pylist()]),
+        [pa.int64()],
+        pa.list_(pa.field("value", pa.int64())),
+        "immutable",
+    )
+    result = df.select(split(column("a")).alias("s")).to_pydict()
+    assert result == {"s": [[1], [2, 2], [3, 3, 3]]}
+
+    wrong = udf(
+        lambda x: pa.array([{"name": "a"}] * len(x)),
+        [pa.int64()],
+        label_type,
+        "immutable",
+        name="wrong",
+    )
+    with pytest.raises(Exception, match="UDF wrong returned an array of type"):
+        df.select(wrong(column("a"))).collect()
+
+    not_array = udf(
+        lambda x: x.to_pylist(),
+        [pa.int64()],
+        pa.int64(),
+        "immutable",
+        name="not_array",
+    )
+    with pytest.raises(Exception, match="UDF not_array must return a pyarrow"):
+        df.select(not_array(column("a"))).collect()
+
+
+def test_profile_udf_overhead(df):
+    is_null = udf(
+        lambda x: x.is_null(),
+        [pa.int64()],
+        pa.bool_(),
+        volatility="immutable",
+    )
+
+    profile = df.profile_udf_overhead(is_null(column("a")))
+    assert profile["batches"] == 1
+    assert profile["rows"] == 3
+    assert 0 < profile["conversion_seconds"] < profile["total_seconds"]
+    assert 0 < profile["udf_seconds"] < profile["total_seconds"]
+    assert (
+        profile["conversion_seconds_per_batch"]
+        == profile["conversion_seconds"]
+    )
+
+    with pytest.raises(ValueError, match="doesn't call a Python UDF"):
+        df.profile_udf_overhead(column("a") + literal(1))
+
+
 def test_join():
     ctx = SessionContext()
 
@@ -194,6 +537,345 @@ def test_join():
     assert table.to_pydict() == expected
 
 
+def test_join_null_equals_null():
+    ctx = SessionContext()
+
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, None]), pa.array([4, 5])],
+        names=["a", "b"],
+    )
+    df = ctx.create_dataframe([[batch]], "l")
+
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, None]), pa.array([8, 10])],
+        names=["a", "c"],
+    )
+    df1 = ctx.create_dataframe([[batch]], "r")
+
+    joined = df.join(df1, join_keys=(["a"], ["a"]), how="inner")
+    table = pa.Table.from_batches(joined.collect())
+    assert table.num_rows == 1
+
+    joined = df.join(
+        df1, join_keys=(["a"], ["a"]), how="inner", null_equals_null=True
+    )
+    joined = joined.select(column("b"), column("c")).sort(
+        column("b").sort(ascending=True)
+    )
+    table = pa.Table.from_batches(joined.collect())
+    assert table.to_pydict() == {"b": [4, 5], "c": [8, 10]}
+
+
+@pytest.mark.parametrize(
+    "how, expected",
+    [
+        ("semi", {"a": [1, 2], "b": [4, 5]}),
+        ("leftsemi", {"a": [1, 2], "b": [4, 5]}),
+        ("anti", {"a": [3], "b": [6]}),
+        ("leftanti", {"a": [3], "b": [6]}),
+        ("rightsemi", {"a": [1, 2], "c": [8, 10]}),
+        ("rightanti", {"a": [7], "c": [12]}),
+    ],
+)
+def test_join_semi_anti(how, expected):
+    ctx = SessionContext()
+
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    df = ctx.create_dataframe([[batch]], "l")
+
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 7]), pa.array([8, 10, 12])],
+        names=["a", "c"],
+    )
+    df1 = ctx.create_dataframe([[batch]], "r")
+
+    df = df.join(df1, join_keys=(["a"], ["a"]), how=how)
+    table = pa.Table.from_batches(df.collect())
+
+    assert table.sort_by("a").to_pydict() == expected
+
+
+def test_join_invalid_how(df):
+    with pytest.raises(Exception, match="supported values are"):
+        df.join(df, join_keys=(["a"], ["a"]), how="outer")
+
+
+def test_filter_exists(ctx):
+    customers = ctx.from_pydict(
+        {"id": [1, 2, 3], "name": ["Alice", "Bob", "Charlie"]}, "customers"
+    )
+    orders = ctx.from_pydict(
+        {"order_id": [10, 11, 12], "customer_id": [1, 1, 3]}, "orders"
+    )
+
+    has_orders = orders.filter(
+        column("customer_id") == f.outer_col("customers.id")
+    )
+
+    df = customers.filter(f.exists(has_orders)).select_columns("name")
+    table = pa.Table.from_batches(df.collect())
+    assert table.sort_by("name").to_pydict() == {"name": ["Alice", "Charlie"]}
+
+    df = customers.filter(f.not_exists(has_orders)).select_columns("name")
+    table = pa.Table.from_batches(df.collect())
+    assert table.to_pydict() == {"name": ["Bob"]}
+
+
+def test_filter_exists_unresolved_outer_column(ctx):
+    customers = ctx.from_pydict({"id": [1, 2, 3]}, "customers")
+    orders = ctx.from_pydict({"customer_id": [1, 1, 3]}, "orders")
+
+    subquery = orders.filter(
+        column("customer_id") == f.outer_col("customers.missing")
+    )
+
+    with pytest.raises(
+        Exception, match="Unable to resolve outer column reference"
+    ):
+        customers.filter(f.exists(subquery))
+
+
+def test_with_schema(df):
+    target = pa.schema([("c", pa.float64()), ("a", pa.string())])
+
+    result = df.with_schema(target)
+    assert result.schema() == target
+
+    batch = result.collect()[0]
+    assert batch.column(0) == pa.array([8.0, 5.0, 8.0])
+    assert batch.column(1) == pa.array(["1", "2", "3"])
+
+    with pytest.raises(Exception, match="Columns not in the target schema: b"):
+        df.with_schema(target, strict=True)
+
+    with pytest.raises(
+        Exception, match="Column 'd' of the target schema is missing"
+    ):
+        df.with_schema(pa.schema([("d", pa.int64())]))
+
+    with pytest.raises(Exception, match="Cannot cast column 'a'"):
+        df.with_schema(pa.schema([("a", pa.struct([("x", pa.int64())]))]))
+
+
+def test_with_schema_nullability(ctx):
+    df = ctx.from_pydict({"a": [1, None, 3], "b": [4, 5, 6]})
+    target = pa.schema(
+        [pa.field("b", pa.int64(), nullable=False), ("a", pa.float64())]
+    )
+
+    result = df.with_schema(target)
+    assert result.schema() == target
+    batch = result.collect()[0]
+    assert batch.schema == target
+    assert batch.column(1) == pa.array([1.0, None, 3.0])
+
+    strict = df.with_schema(
+        pa.schema([pa.field("a", pa.int64(), nullable=False)])
+    )
+    with pytest.raises(Exception, match="'a' is declared as non-nullable"):
+        strict.collect()
+
+
+def test_cast(ctx, df):
+    result = df.cast({"a": "string", "c": pa.float64()})
+    assert result.schema() == pa.schema(
+        [("a", pa.string()), ("b", pa.int64()), ("c", pa.float64())]
+    )
+    batch = result.collect()[0]
+    assert batch.column(0) == pa.array(["1", "2", "3"])
+    assert batch.column(2) == pa.array([8.0, 5.0, 8.0])
+
+    messy = ctx.from_pydict({"a": ["1", "two"]})
+    with pytest.raises(Exception, match="Cannot cast string 'two'"):
+        messy.cast({"a": "int64"}).collect()
+    result = messy.cast({"a": "int64"}, safe=True).collect()[0]
+    assert result.column(0) == pa.array([1, None])
+    result = messy.cast({"a": "int64"}, errors="null").collect()[0]
+    assert result.column(0) == pa.array([1, None])
+
+    with pytest.raises(Exception, match="Column 'd' to cast is missing"):
+        df.cast({"d": "int64"})
+
+    with pytest.raises(Exception, match="Cannot cast column 'a'"):
+        df.cast({"a": pa.struct([("x", pa.int64())])})
+
+
+def test_select_by_key(df):
+    result = df["a"]
+    assert type(result) == DataFrame
+    assert result.schema().names == ["a"]
+    assert result.collect()[0].column(0) == pa.array([1, 2, 3])
+
+    assert df[["c", "a"]].schema().names == ["c", "a"]
+    assert df["b", "a"].schema().names == ["b", "a"]
+
+    with pytest.raises(KeyError, match="Column 'd' not found"):
+        df["d"]
+    with pytest.raises(KeyError, match="Column 'd' not found"):
+        df[["a", "d"]]
+
+
+def test_row_access(df):
+    assert df.row(0) == {"a": 1, "b": 4, "c": 8}
+    assert df[1] == {"a": 2, "b": 5, "c": 5}
+    assert df[-1] == {"a": 3, "b": 6, "c": 8}
+
+    assert [row["a"] for row in df[:2]] == [1, 2]
+    assert [row["a"] for row in df[1:]] == [2, 3]
+    assert [row["a"] for row in df[-2:]] == [2, 3]
+    assert [row["a"] for row in df[::2]] == [1, 3]
+    assert [row["a"] for row in df[::-1]] == [3, 2, 1]
+    assert df[5:] == []
+
+    with pytest.raises(IndexError, match="Row index 3 is out of range"):
+        df.row(3)
+    with pytest.raises(IndexError, match="Row index -4 is out of range"):
+        df[-4]
+    with pytest.raises(TypeError, match="can't be indexed by a bool"):
+        df[True]
+    with pytest.raises(TypeError, match="can't be indexed by a bool"):
+        df[False]
+
+    with pytest.raises(TypeError, match="DataFrame is not iterable"):
+        list(df)
+    with pytest.raises(TypeError, match="DataFrame is not iterable"):
+        {"a": 1, "b": 4, "c": 8} in df
+
+
+def test_pipe(df):
+    def add_total(df, *columns, name="total"):
+        total = columns[0]
+        for c in columns[1:]:
+            total = total + c
+        return df.with_column(name, total)
+
+    def larger_than(df, column_name, value):
+        return df.filter(column(column_name) > literal(value))
+
+    result = (
+        df.pipe(add_total, column("a"), column("b"), name="a_b")
+        .pipe(larger_than, "a_b", 6)
+        .select_columns("a", "a_b")
+    )
+    assert result.to_pydict() == {"a": [2, 3], "a_b": [7, 9]}
+
+    # the result of the function is returned as is
+    assert df.pipe(lambda df: df.count()) == 3
+    with pytest.raises(TypeError):
+        df.pipe(lambda df, x: df, 1, 2)
+
+
+def test_assert_frame_equal(ctx, df):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([3, 1, 2]), pa.array([6, 4, 5]), pa.array([8, 8, 5])],
+        names=["a", "b", "c"],
+    )
+    shuffled = ctx.create_dataframe([[batch]])
+
+    df.assert_frame_equal(shuffled)
+    with pytest.raises(
+        AssertionError, match="Values differ at row 0, column 'a': 1 != 3"
+    ):
+        df.assert_frame_equal(shuffled, check_row_order=True)
+
+    with pytest.raises(AssertionError, match="Column names differ"):
+        df.assert_frame_equal(shuffled.select_columns("a", "b"))
+
+    with pytest.raises(AssertionError, match="Row counts differ: 3 != 2"):
+        df.assert_frame_equal(shuffled.limit(2))
+
+    narrow = shuffled.select(
+        column("a").cast(pa.int32()).alias("a"), column("b"), column("c")
+    )
+    with pytest.raises(
+        AssertionError, match="Column 'a' types differ: Int64 != Int32"
+    ):
+        df.assert_frame_equal(narrow)
+    df.assert_frame_equal(narrow, check_dtype=False)
+
+    changed = shuffled.with_column("c", column("c") + literal(1))
+    with pytest.raises(
+        AssertionError,
+        match="row 0 of this DataFrame and row 1 of the other, column 'c'",
+    ):
+        df.assert_frame_equal(changed)
+
+    schema = pa.schema(
+        [pa.field(name, pa.int64(), nullable=False) for name in "abc"]
+    )
+    batch = pa.RecordBatch.from_arrays(batch.columns, schema=schema)
+    required = ctx.create_dataframe([[batch]])
+    with pytest.raises(
+        AssertionError,
+        match="Column 'a' nullability differs: not nullable != nullable",
+    ):
+        required.assert_frame_equal(shuffled)
+    required.assert_frame_equal(shuffled, check_dtype=False)
+
+    with_null = ctx.from_pydict(
+        {"a": [3, None, 2], "b": [6, 4, 5], "c": [8, 8, 5]}
+    )
+    with pytest.raises(
+        AssertionError, match="row 0 of this DataFrame and row 1 of the other"
+    ):
+        required.assert_frame_equal(with_null, check_dtype=False)
+
+
+def test_cast_safe(ctx):
+    df = ctx.from_pydict({"a": ["1", "x", "3"]})
+
+    with pytest.raises(Exception, match="Cannot cast string 'x'"):
+        df.select(column("a").cast(pa.int64())).collect()
+    with pytest.raises(Exception, match="Cannot cast string 'x'"):
+        df.select(column("a").cast(pa.int64(), errors="raise")).collect()
+
+    expected = {"a": [1, None, 3]}
+    safe = df.select(column("a").cast(pa.int64(), safe=True).alias("a"))
+    assert safe.to_pydict() == expected
+    nulls = df.select(column("a").cast(pa.int64(), errors="null").alias("a"))
+    assert nulls.to_pydict() == expected
+    try_cast = df.select(column("a").try_cast(pa.int64()).alias("a"))
+    assert try_cast.to_pydict() == expected
+
+    with pytest.raises(ValueError, match="Unrecognized errors ignore"):
+        column("a").cast(pa.int64(), errors="ignore")
+    with pytest.raises(ValueError, match="contradicts errors"):
+        column("a").cast(pa.int64(), safe=True, errors="raise")
+
+
+def test_decimal_literal_and_cast(ctx):
+    df = ctx.from_pydict(
+        {"price": [Decimal("1.25"), Decimal("-1.25"), Decimal("2.10")]}
+    )
+    wide = Decimal("12345678901234567890123456789012345678.9")
+    result = df.select(
+        literal(Decimal("1.50")).alias("rate"),
+        (column("price") * literal(Decimal("1.50"))).alias("total"),
+        column("price").cast_decimal(5, 1).alias("rounded"),
+        literal(wide).alias("wide"),
+    )
+
+    assert result.schema().field("rate").type == pa.decimal128(3, 2)
+    assert result.schema().field("rounded").type == pa.decimal128(5, 1)
+    assert result.schema().field("wide").type == pa.decimal256(39, 1)
+    assert result.to_pydict() == {
+        "rate": [Decimal("1.50")] * 3,
+        "total": [Decimal("1.875"), Decimal("-1.875"), Decimal("3.15")],
+        "rounded": [Decimal("1.3"), Decimal("-1.3"), Decimal("2.1")],
+        "wide": [wide] * 3,
+    }
+
+    with pytest.raises(ValueError, match="should satisfy"):
+        column("price").cast_decimal(77, 2)
+    with pytest.raises(
+        ValueError, match="Can't create a literal from the decimal NaN"
+    ):
+        literal(Decimal("NaN"))
+
+
 def test_distinct():
     ctx = SessionContext()
 
@@ -345,6 +1027,59 @@ def test_explain(df):
     df.explain()
 
 
+def test_explain_with_statistics(df, capsys):
+    df.explain(with_statistics=True)
+    output = capsys.readouterr().out
+    logical, physical = output.split("physical_plan")
+
+    assert "logical_plan" in logical
+    assert "TableScan" in logical
+    assert "statistics=[rows=3" in logical
+    assert "statistics=[rows=3" in physical
+
+    # the filter has no estimates without the bounds of the values, unlike
+    # the scan below it
+    df.filter(column("a") > literal(1)).explain(with_statistics=True)
+    logical = capsys.readouterr().out.split("physical_plan")[0]
+    assert "a > Int64(1), statistics=[]" in logical
+    assert "statistics=[rows=3, bytes=" in logical
+
+
+def test_explain_cached(df, capsys):
+    # now() is folded into a constant when the plan is optimized, so the plan
+    # shows the same time only if it was optimized once
+    timed = df.select(f.now().alias("t"))
+    timed.explain()
+    first = capsys.readouterr().out
+    timed.explain()
+    assert capsys.readouterr().out == first
+    assert "logical_plan" in first
+    assert "physical_plan" in first
+
+    # schema() doesn't optimize the plan again nor drop the cached one
+    assert timed.schema().names == ["t"]
+    timed.explain()
+    assert capsys.readouterr().out == first
+
+    def folded_now(df):
+        plan = df.optimized_logical_plan().display_indent()
+        return int(re.search(r"TimestampNanosecond\((\d+)", plan).group(1))
+
+    cached = folded_now(timed)
+    assert str(cached) in first
+
+    # a derived DataFrame is optimized anew, while its parent keeps its plan
+    time.sleep(0.01)
+    derived = timed.filter(column("t").is_not_null())
+    assert folded_now(derived) > cached
+    assert folded_now(timed) == cached
+    derived.explain()
+    assert capsys.readouterr().out != first
+
+    df.filter(column("a") > literal(1)).explain()
+    assert "FilterExec" in capsys.readouterr().out
+
+
 def test_logical_plan(aggregate_df):
     plan = aggregate_df.logical_plan()
 
@@ -376,6 +1111,36 @@ def test_optimized_logical_plan(aggregate_df):
     assert expected == plan.display_indent()
 
 
+def test_plan_fingerprint(df, tmp_path):
+    def query(df, value):
+        return df.filter(column("a") > literal(value)).select(column("b"))
+
+    fingerprint = query(df, 1).plan_fingerprint()
+    assert len(fingerprint) == 64
+    assert query(df, 1).plan_fingerprint() == fingerprint
+    assert query(df, 2).plan_fingerprint() != fingerprint
+    cast = df.select(column("b").cast(pa.float64()))
+    assert cast.plan_fingerprint() != df.select(column("b")).plan_fingerprint()
+
+    # registered tables are identified by their name, in any session
+    for path in ["a.parquet", "b.parquet"]:
+        pq.write_table(pa.table({"a": [1, 2, 3]}), tmp_path / path)
+    fingerprints = []
+    for _ in range(2):
+        ctx = SessionContext()
+        ctx.register_parquet("t", str(tmp_path / "a.parquet"))
+        df = ctx.sql("SELECT a FROM t WHERE a > 1")
+        fingerprints.append(df.plan_fingerprint())
+    assert fingerprints[0] == fingerprints[1]
+
+    # unregistered files by their paths
+    a = ctx.read_parquet(str(tmp_path / "a.parquet"))
+    b = ctx.read_parquet(str(tmp_path / "b.parquet"))
+    assert a.plan_fingerprint() != b.plan_fingerprint()
+    again = ctx.read_parquet(str(tmp_path / "a.parquet"))
+    assert a.plan_fingerprint() == again.plan_fingerprint()
+
+
 def test_execution_plan(aggregate_df):
     plan = aggregate_df.execution_plan()
 
@@ -486,6 +1251,228 @@ def test_collect_partitioned():
     assert [[batch]] == ctx.create_dataframe([[batch]]).collect_partitioned()
 
 
+def test_collect_single_batch(ctx):
+    batches = [
+        pa.RecordBatch.from_arrays(
+            [pa.array([i, i + 10]), pa.array([str(i), None])], names=["a", "b"]
+        )
+        for i in range(3)
+    ]
+    df = ctx.create_dataframe([batches[:2], batches[2:]])
+
+    batch = df.collect_single_batch()
+    assert isinstance(batch, pa.RecordBatch)
+    assert batch.num_rows == 6
+    assert sorted(batch.column(0).to_pylist()) == [0, 1, 2, 10, 11, 12]
+
+    empty = df.filter(column("a") > literal(100)).collect_single_batch()
+    assert empty.num_rows == 0
+    assert empty.schema.names == ["a", "b"]
+
+    # the offsets of the nested values are checked too
+    nested = pa.RecordBatch.from_arrays(
+        [
+            pa.array([["x", "y"], None, ["z"]], pa.list_(pa.string())),
+            pa.array([[("k", 1)], [], None], pa.map_(pa.string(), pa.int64())),
+        ],
+        names=["tags", "counts"],
+    )
+    df = ctx.create_dataframe([[nested.slice(0, 1)], [nested.slice(1)]])
+    batch = df.collect_single_batch()
+    assert batch.num_rows == 3
+    assert sorted(batch.column(0).to_pylist(), key=str) == sorted(
+        nested.column(0).to_pylist(), key=str
+    )
+
+
+def test_collect_nested_zero_copy(ctx):
+    rows = 200_000
+    values = pa.array(range(rows))
+    batch = pa.RecordBatch.from_arrays(
+        [
+            pa.array([i % 10 for i in range(rows)]),
+            values,
+            pa.StructArray.from_arrays([values], names=["v"]),
+        ],
+        names=["g", "v", "s"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    def addresses(array):
+        return [buffer.address for buffer in array.buffers() if buffer]
+
+    # the arrays are handed to DataFusion and back through the Arrow C data
+    # interface, so the struct collected is the memory pyarrow allocated
+    collected = df.select(column("s")).collect()[0]
+    assert pa.types.is_struct(collected.schema.field("s").type)
+    assert addresses(collected.column(0)) == addresses(batch.column(2))
+
+    # both columns are the list array DataFusion built, which pyarrow shares
+    # instead of copying it for each column
+    lists = df.aggregate([column("g")], [f.array_agg(column("v")).alias("l")])
+    lists = lists.select(column("l"), column("l").alias("m"))
+    result = lists.collect()
+    for collected in result:
+        assert pa.types.is_list(collected.schema.field("l").type)
+        assert addresses(collected.column(0)) == addresses(collected.column(1))
+    lists = pa.Table.from_batches(result).column("l").to_pylist()
+    assert sum(len(values) for values in lists) == rows
+
+    # collecting a large list column takes a fraction of the time building it
+    # from Python objects would
+    offsets = pa.array(range(0, 2_000_001, 4), pa.int32())
+    large = pa.ListArray.from_arrays(offsets, pa.array(range(2_000_000)))
+    large_df = ctx.create_dataframe(
+        [[pa.RecordBatch.from_arrays([large], names=["l"])]]
+    )
+    start = time.perf_counter()
+    collected = large_df.collect()[0].column(0)
+    collect_time = time.perf_counter() - start
+    start = time.perf_counter()
+    rebuilt = pa.array(large.to_pylist())
+    python_time = time.perf_counter() - start
+    assert collected == rebuilt
+    assert collect_time * 10 < python_time
+
+
+def test_execute_stream_partitioned(ctx):
+    batches = [
+        pa.RecordBatch.from_arrays([pa.array([i, i + 10])], names=["a"])
+        for i in range(4)
+    ]
+    df = ctx.create_dataframe([[batch] for batch in batches])
+    df = df.select(column("a") + literal(1))
+
+    streams = df.execute_stream_partitioned()
+    assert len(streams) == 4
+
+    def consume(stream):
+        return [b.to_pyarrow().column(0).to_pylist() for b in stream]
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(consume, streams))
+
+    values = sorted(v for result in results for batch in result for v in batch)
+    assert values == [1, 2, 3, 4, 11, 12, 13, 14]
+
+
+def test_collect_progress_callback(ctx):
+    batches = [
+        pa.RecordBatch.from_arrays([pa.array(range(i + 1))], names=["a"])
+        for i in range(3)
+    ]
+    df = ctx.create_dataframe([batches])
+
+    progress = []
+    result = df.collect(progress_callback=progress.append)
+    assert result == batches
+    assert progress == [1, 3, 6]
+
+    progress = []
+    stream = df.execute_stream(progress_callback=progress.append)
+    assert [b.to_pyarrow() for b in stream] == batches
+    assert progress == [1, 3, 6]
+
+    def fail(rows):
+        raise RuntimeError("stop")
+
+    with pytest.raises(RuntimeError, match="stop"):
+        df.collect(progress_callback=fail)
+
+
+def test_collect_with_metrics(df):
+    df = df.filter(column("a") > literal(1))
+    batches, metrics = df.collect_with_metrics()
+
+    assert pa.Table.from_batches(batches).to_pydict()["a"] == [2, 3]
+    assert metrics[0]["depth"] == 0
+    assert all("operator" in m and "metrics" in m for m in metrics)
+
+    filters = [m for m in metrics if m["operator"].startswith("FilterExec")]
+    assert len(filters) == 1
+    assert filters[0]["metrics"]["output_rows"] == 2
+    assert "elapsed_compute" in filters[0]["metrics"]
+
+
+def test_explain_analyze_to_metrics(df):
+    tree = df.filter(column("a") > literal(1)).explain_analyze_to_metrics()
+
+    def operators(node):
+        yield node
+        for child in node["children"]:
+            yield from operators(child)
+
+    nodes = list(operators(tree))
+    assert all(
+        set(node) == {
+            "operator",
+            "output_rows",
+            "elapsed_compute_nanos",
+            "metrics",
+            "children",
+        }
+        for node in nodes
+    )
+    [node] = [n for n in nodes if n["operator"].startswith("FilterExec")]
+    assert node["output_rows"] == 2
+    assert node["output_rows"] == node["metrics"]["output_rows"]
+    assert node["elapsed_compute_nanos"] >= 0
+    assert nodes[-1]["operator"].startswith("MemoryExec")
+    assert nodes[-1]["children"] == []
+
+
+def test_estimated_row_count(ctx, tmp_path):
+    path = tmp_path / "t.parquet"
+    pq.write_table(pa.table({"a": list(range(100))}), path)
+    df = ctx.read_parquet(str(path))
+
+    assert df.estimated_row_count() == 100
+    estimate = df.filter(column("a") >= literal(50)).estimated_row_count()
+    assert 0 < estimate < 100
+
+    dataset = ds.dataset(pa.table({"a": [1, 2, 3]}))
+    ctx.register_dataset("d", dataset)
+    assert ctx.table("d").estimated_row_count() is None
+
+
+def test_export_plan(df, tmp_path):
+    path = tmp_path / "plan"
+    df.filter(column("a") > literal(1)).select(column("b")).export_plan(
+        str(path)
+    )
+
+    assert sorted(os.listdir(path)) == [
+        "logical_plan.txt",
+        "optimized_plan.txt",
+        "physical_plan.txt",
+        "schema.txt",
+    ]
+    assert "Filter:" in (path / "logical_plan.txt").read_text()
+    assert "Projection:" in (path / "optimized_plan.txt").read_text()
+    assert "FilterExec" in (path / "physical_plan.txt").read_text()
+    assert (path / "schema.txt").read_text() == "b: Int64\n"
+
+
+def test_maintain_order(ctx):
+    partitions = [
+        [
+            pa.RecordBatch.from_arrays(
+                [pa.array(range(i * 1000, (i + 1) * 1000))], ["a"]
+            )
+        ]
+        for i in range(8)
+    ]
+    df = ctx.create_dataframe(partitions)
+
+    ordered = df.filter(column("a") > literal(10)).maintain_order()
+    assert ordered.to_pydict() == {"a": list(range(11, 8000))}
+    assert "ConcatPartitionsExec" in ordered.execution_plan().display_indent()
+
+    doubled = ordered.select((column("a") * literal(2)).alias("a")).limit(5)
+    assert doubled.to_pydict() == {"a": [22, 24, 26, 28, 30]}
+    assert ordered.maintain_order().to_pydict() == ordered.to_pydict()
+
+
 def test_union(ctx):
     batch = pa.RecordBatch.from_arrays(
         [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
@@ -548,6 +1535,67 @@ def test_count(df):
     assert df.count() == 3
 
 
+def test_is_empty(df):
+    assert not df.is_empty()
+    assert df.has_rows()
+
+    empty = df.filter(column("a") > literal(10))
+    assert empty.is_empty()
+    assert not empty.has_rows()
+
+
+def test_corr_and_cov():
+    ctx = SessionContext()
+    df = ctx.from_pydict(
+        {
+            "g": ["a", "a", "a", "a", "a"],
+            "x": [1, 2, 3, 4, None],
+            "y": [2.0, 4.0, 6.0, None, 10.0],
+        }
+    )
+
+    # only the rows where both columns are set are used
+    assert df.corr("x", "y") == pytest.approx(1.0)
+    assert df.cov("x", "y") == pytest.approx(2.0)
+    assert df.cov("x", "y", population=True) == pytest.approx(4 / 3)
+    assert df.limit(0).corr("x", "y") is None
+
+    grouped = df.aggregate(
+        [column("g")],
+        [
+            f.corr(column("x"), column("y")).alias("corr"),
+            f.covar_samp(column("x"), column("y")).alias("covar_samp"),
+            f.covar_pop(column("x"), column("y")).alias("covar_pop"),
+        ],
+    ).to_pydict()
+    assert grouped["corr"] == [pytest.approx(1.0)]
+    assert grouped["covar_samp"] == [pytest.approx(2.0)]
+    assert grouped["covar_pop"] == [pytest.approx(4 / 3)]
+
+    with pytest.raises(ValueError, match="'z' doesn't exist"):
+        df.corr("x", "z")
+
+
+def test_value_counts():
+    ctx = SessionContext()
+    df = ctx.from_pydict({"a": ["x", "y", "x", None, "z", "y", "x"]})
+
+    assert df.value_counts("a").to_pydict() == {
+        "a": ["x", "y", "z", None],
+        "count": [3, 2, 1, 1],
+    }
+
+    result = df.value_counts("a", normalize=True).to_pydict()
+    assert result["a"] == ["x", "y", "z", None]
+    assert result["proportion"] == pytest.approx([3 / 7, 2 / 7, 1 / 7, 1 / 7])
+
+    unsorted = df.value_counts("a", sort=False).to_pydict()
+    assert sorted(unsorted["count"]) == [1, 1, 2, 3]
+
+    with pytest.raises(ValueError, match="'b' doesn't exist"):
+        df.value_counts("b")
+
+
 def test_to_pandas(df):
     # Skip test if pandas is not installed
     pd = pytest.importorskip("pandas")
@@ -570,6 +1618,59 @@ def test_empty_to_pandas(df):
     assert set(pandas_df.columns) == {"a", "b", "c"}
 
 
+def test_to_pandas_decimal(ctx):
+    pd = pytest.importorskip("pandas")
+    if not hasattr(pd, "ArrowDtype"):
+        pytest.skip("pandas.ArrowDtype requires pandas 1.5")
+
+    price_type = pa.decimal128(20, 10)
+    batch = pa.RecordBatch.from_arrays(
+        [
+            pa.array([Decimal("12345678.0123456789"), None], price_type),
+            pa.array([1, 2]),
+        ],
+        names=["price", "id"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    pandas_df = df.to_pandas()
+    assert pandas_df["price"].dtype == pd.ArrowDtype(price_type)
+    assert pandas_df["price"][0] == Decimal("12345678.0123456789")
+    assert pandas_df["id"].dtype == "int64"
+
+    pandas_df = df.to_pandas(decimal="decimal")
+    assert pandas_df["price"].dtype == object
+    assert pandas_df["price"][0] == Decimal("12345678.0123456789")
+
+    pandas_df = df.to_pandas(decimal="float")
+    assert pandas_df["price"].dtype == "float64"
+    assert pandas_df["price"][0] == pytest.approx(12345678.0123456789)
+    assert pandas_df["price"].isna()[1]
+
+    with pytest.raises(ValueError, match="Unsupported decimal output"):
+        df.to_pandas(decimal="str")
+
+
+def test_to_pandas_iter():
+    pd = pytest.importorskip("pandas")
+
+    ctx = SessionContext()
+    batches = [
+        pa.RecordBatch.from_pydict({"a": [1, 2, 3]}),
+        pa.RecordBatch.from_pydict({"a": [4, 5, 6, 7]}),
+    ]
+    df = ctx.create_dataframe([batches])
+
+    chunks = list(df.to_pandas_iter(chunk_rows=2))
+    assert all(type(chunk) == pd.DataFrame for chunk in chunks)
+    assert [len(chunk) for chunk in chunks] == [2, 2, 2, 1]
+    assert pd.concat(chunks)["a"].tolist() == [1, 2, 3, 4, 5, 6, 7]
+
+    assert list(df.limit(0).to_pandas_iter()) == []
+    with pytest.raises(ValueError, match="greater than 0"):
+        df.to_pandas_iter(chunk_rows=0)
+
+
 def test_to_polars(df):
     # Skip test if polars is not installed
     pl = pytest.importorskip("polars")
@@ -626,6 +1727,26 @@ def test_to_pydict(df):
     assert pydict == {"a": [1, 2, 3], "b": [4, 5, 6], "c": [8, 5, 8]}
 
 
+def test_to_pydict_nested(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [
+            pa.array([{"x": 1, "y": [1, 2]}, {"x": 2, "y": []}]),
+            pa.array([[{"z": "a"}], None]),
+        ],
+        names=["s", "l"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    assert df.to_pydict() == {
+        "s": [{"x": 1, "y": [1, 2]}, {"x": 2, "y": []}],
+        "l": [[{"z": "a"}], None],
+    }
+    assert df.to_pylist() == [
+        {"s": {"x": 1, "y": [1, 2]}, "l": [{"z": "a"}]},
+        {"s": {"x": 2, "y": []}, "l": None},
+    ]
+
+
 def test_describe(df):
     # Calculate statistics
     df = df.describe()
@@ -659,6 +1780,68 @@ def test_write_parquet(df, tmp_path):
     assert result == expected
 
 
+def test_write_parquet_row_group_size(df, tmp_path):
+    df.write_parquet(
+        str(tmp_path),
+        max_row_group_size=1,
+        data_page_size_limit=1024,
+        write_batch_size=1,
+    )
+
+    files = [
+        os.path.join(root, file)
+        for root, _, files in os.walk(tmp_path)
+        for file in files
+        if file.endswith(".parquet")
+    ]
+    assert files
+    for file in files:
+        metadata = pq.ParquetFile(file).metadata
+        for i in range(metadata.num_row_groups):
+            assert metadata.row_group(i).num_rows == 1
+
+    assert pq.read_table(str(tmp_path)).to_pydict() == df.to_pydict()
+
+
+def test_write_parquet_statistics_enabled(df, tmp_path):
+    df.write_parquet(str(tmp_path), statistics_enabled={"b": False})
+
+    files = [
+        os.path.join(root, file)
+        for root, _, files in os.walk(tmp_path)
+        for file in files
+        if file.endswith(".parquet")
+    ]
+    assert files
+    for file in files:
+        metadata = pq.ParquetFile(file).metadata
+        names = metadata.schema.names
+        row_group = metadata.row_group(0)
+        columns = {
+            names[i]: row_group.column(i) for i in range(len(names))
+        }
+        assert columns["a"].is_stats_set
+        assert not columns["b"].is_stats_set
+        assert columns["c"].is_stats_set
+
+    assert pq.read_table(str(tmp_path)).to_pydict() == df.to_pydict()
+
+    with pytest.raises(ValueError, match="Column 'missing' doesn't exist"):
+        df.write_parquet(str(tmp_path), statistics_enabled={"missing": True})
+
+
+@pytest.mark.parametrize(
+    "option",
+    ["max_row_group_size", "data_page_size_limit", "write_batch_size"],
+)
+@pytest.mark.parametrize("value", [0, -1])
+def test_write_parquet_invalid_size(df, tmp_path, option, value):
+    with pytest.raises(
+        ValueError, match=f"{option} must be a positive integer"
+    ):
+        df.write_parquet(str(tmp_path), **{option: value})
+
+
 @pytest.mark.parametrize(
     "compression, compression_level",
     [("gzip", 6), ("brotli", 7), ("zstd", 15)],
@@ -712,3 +1895,68 @@ def test_write_compressed_parquet_missing_compression_level(
 
     with pytest.raises(ValueError):
         df.write_parquet(str(path), compression=compression)
+
+
+@pytest.mark.parametrize("compression", ["uncompressed", "lz4", "zstd"])
+def test_write_ipc(df, tmp_path, compression):
+    path = tmp_path / "data.arrow"
+    df.write_ipc(path, compression=compression)
+    with pa.ipc.open_file(path) as reader:
+        assert reader.read_all().to_pydict() == df.to_pydict()
+
+    ctx = SessionContext()
+    assert ctx.read_ipc(str(path)).to_pydict() == df.to_pydict()
+
+    path = tmp_path / "data.arrows"
+    df.write_ipc(path, format="stream", compression=compression)
+    with pa.ipc.open_stream(path) as reader:
+        assert reader.read_all().to_pydict() == df.to_pydict()
+
+
+def test_write_ipc_invalid_options(df, tmp_path):
+    with pytest.raises(ValueError, match="Unrecognized IPC format"):
+        df.write_ipc(tmp_path / "data.arrow", format="feather")
+    with pytest.raises(ValueError, match="Unrecognized compression type"):
+        df.write_ipc(tmp_path / "data.arrow", compression="snappy")
+
+
+def test_write_json(ctx, tmp_path):
+    df = ctx.from_pydict({"a": [1, None], "b": ["x", None]})
+
+    path = str(tmp_path / "rows.json")
+    df.write_json(path, format="array")
+    with open(path) as f:
+        assert json.load(f) == [{"a": 1, "b": "x"}, {}]
+
+    df.write_json(path, format="array", pretty=True, explicit_nulls=True)
+    with open(path) as f:
+        text = f.read()
+    assert text.startswith('[\n  {\n    "a": 1,')
+    assert json.loads(text) == [
+        {"a": 1, "b": "x"},
+        {"a": None, "b": None},
+    ]
+
+    # the fields are written in the order of the columns
+    path = str(tmp_path / "ordered.json")
+    ctx.from_pydict({"b": ["x"], "a": [1]}).write_json(path, format="array")
+    with open(path) as f:
+        assert f.read() == '[{"b":"x","a":1}]\n'
+
+    # explicit nulls keep the directory of files, one per partition
+    directory = tmp_path / "rows"
+    df.write_json(str(directory), explicit_nulls=True)
+    files = sorted(directory.iterdir())
+    lines = [line for file in files for line in file.read_text().splitlines()]
+    assert lines == ['{"a":1,"b":"x"}', '{"a":null,"b":null}']
+    df.write_json(str(tmp_path / "plain"))
+    assert len(list((tmp_path / "plain").iterdir())) == len(files)
+
+    df.filter(column("a") > literal(5)).write_json(path, format="array")
+    with open(path) as f:
+        assert json.load(f) == []
+
+    with pytest.raises(ValueError, match="Unrecognized JSON format"):
+        df.write_json(path, format="jsonl")
+    with pytest.raises(ValueError, match="pretty requires"):
+        df.write_json(path, pretty=True)
@@ -15,7 +15,8 @@
 # specific language governing permissions and limitations
 # under the License.
 
-from datafusion import SessionContext
+from datafusion import SessionContext, literal
+from datafusion import functions as f
 from datafusion.expr import Column, Literal, BinaryExpr, AggregateFunction
 from datafusion.expr import (
     Projection,
@@ -64,6 +65,17 @@ def test_projection(test_ctx):
     assert isinstance(plan, TableScan)
 
 
+def test_binary_literal():
+    expr = literal(b"\x01\x02")
+    assert expr.python_value() == b"\x01\x02"
+    assert expr.to_variant().value_binary() == b"\x01\x02"
+
+    expr = f.lit_fixed_binary(b"\x01\x02", 2).to_variant()
+    assert expr.data_type() == "FixedSizeBinary(2)"
+    assert expr.value_binary() == b"\x01\x02"
+    assert f.lit_fixed_binary(None, 2).to_variant().value_binary() is None
+
+
 def test_filter(test_ctx):
     df = test_ctx.sql("select c1 from test WHERE c1 > 5")
     plan = df.logical_plan()
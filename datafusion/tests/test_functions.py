@@ -18,10 +18,12 @@ import math
 
 import numpy as np
 import pyarrow as pa
+import pyarrow.parquet as pq
 import pytest
-from datetime import datetime
+from datetime import date, datetime
+from decimal import Decimal
 
-from datafusion import SessionContext, column
+from datafusion import LogicalPlan, SessionContext, column
 from datafusion import functions as f
 from datafusion import literal
 
@@ -68,6 +70,35 @@ def test_literal(df):
     assert result.column(5) == pa.array([b"hello world"] * 3)
 
 
+def test_lit_typed(df):
+    df = df.select(
+        f.lit_typed(1, pa.int32()),
+        f.lit_typed(date(2022, 12, 31), pa.date32()),
+        f.lit_typed(Decimal("1.5"), pa.decimal128(10, 2)),
+        f.lit_typed(pa.scalar(2), pa.uint8()),
+        f.lit_typed(None, pa.string()),
+        f.null_lit(pa.timestamp("us")),
+    )
+    result = df.collect()[0]
+    assert result.schema.types == [
+        pa.int32(),
+        pa.date32(),
+        pa.decimal128(10, 2),
+        pa.uint8(),
+        pa.string(),
+        pa.timestamp("us"),
+    ]
+    assert result.column(0) == pa.array([1] * 3, pa.int32())
+    assert result.column(2).to_pylist() == [Decimal("1.50")] * 3
+    assert result.column(4).null_count == 3
+    assert result.column(5).null_count == 3
+
+    with pytest.raises(ValueError, match="Can't create a literal of type"):
+        f.lit_typed("one", pa.int32())
+    with pytest.raises(ValueError, match="Can't create a literal of type"):
+        f.lit_typed(300, pa.int8())
+
+
 def test_lit_arith(df):
     """
     Test literals with arithmetic operations
@@ -457,6 +488,80 @@ def test_temporal_functions(df):
     )
 
 
+def test_parse_timestamps_with_formats():
+    ctx = SessionContext()
+    df = ctx.from_pydict(
+        {"a": ["31/12/2023", "2023-01-05 10:30", None, "05.01.2023"]}
+    )
+    formats = ["%d/%m/%Y", "%Y-%m-%d %H:%M", "%d.%m.%Y"]
+    result = df.select(
+        f.to_timestamp(column("a"), *formats).alias("ns"),
+        f.to_timestamp_seconds(column("a"), *formats).alias("s"),
+        f.to_timestamp_millis(column("a"), *formats).alias("ms"),
+        f.to_timestamp_micros(column("a"), *formats).alias("us"),
+        f.to_date(column("a"), *formats).alias("date"),
+        f.to_date(column("a"), "%d/%m/%Y", safe=True).alias("nulls"),
+    ).collect()[0]
+
+    timestamps = [
+        datetime(2023, 12, 31),
+        datetime(2023, 1, 5, 10, 30),
+        None,
+        datetime(2023, 1, 5),
+    ]
+    for i, unit in enumerate(["ns", "s", "ms", "us"]):
+        assert result.column(i) == pa.array(timestamps, pa.timestamp(unit))
+    assert result.column(4) == pa.array(
+        [date(2023, 12, 31), date(2023, 1, 5), None, date(2023, 1, 5)]
+    )
+    assert result.column(5) == pa.array(
+        [date(2023, 12, 31), None, None, None]
+    )
+
+    # without formats, unparseable strings can be turned into nulls too
+    df = ctx.from_pydict({"a": ["2023-01-05T10:30:00", "bad"]})
+    result = df.select(f.to_timestamp(column("a"), safe=True).alias("t"))
+    assert result.to_pydict() == {"t": [datetime(2023, 1, 5, 10, 30), None]}
+    result = df.select(f.to_timestamp(column("a"), errors="null").alias("t"))
+    assert result.to_pydict() == {"t": [datetime(2023, 1, 5, 10, 30), None]}
+    with pytest.raises(Exception, match="to_date can't parse 'bad'"):
+        df.select(f.to_date(column("a"))).collect()
+    with pytest.raises(Exception, match="'bad' with any of the formats %Y"):
+        df.select(f.to_timestamp(column("a"), "%Y-%m-%dT%H:%M:%S")).collect()
+    with pytest.raises(ValueError, match="Unrecognized errors coerce"):
+        f.to_timestamp(column("a"), errors="coerce")
+
+    # integers are still seconds since the epoch when errors become nulls
+    df = ctx.from_pydict({"a": [1, None]})
+    result = df.select(
+        f.to_timestamp_seconds(column("a"), safe=True).alias("t")
+    )
+    assert result.to_pydict() == {"t": [datetime(1970, 1, 1, 0, 0, 1), None]}
+
+
+def test_parse_timestamps_round_trip(tmp_path):
+    path = str(tmp_path / "days.parquet")
+    pq.write_table(pa.table({"a": ["05.01.2023", "bad"]}), path)
+    ctx = SessionContext()
+    ctx.register_parquet("t", path)
+    df = ctx.table("t").select(
+        f.to_date(column("a"), "%d.%m.%Y", safe=True).alias("d")
+    )
+
+    # the functions are registered under names of their own
+    other = SessionContext()
+    other.register_parquet("t", path)
+    plan = df.logical_plan().to_proto_bytes(ctx)
+    decoded = LogicalPlan.from_proto_bytes(other, plan)
+    df = other.create_dataframe_from_logical_plan(decoded)
+    assert df.to_pydict() == {"d": [date(2023, 1, 5), None]}
+
+    df = other.sql(
+        "SELECT try_to_date_with_formats(a, '%d.%m.%Y') AS d FROM t"
+    )
+    assert df.to_pydict() == {"d": [date(2023, 1, 5), None]}
+
+
 def test_case(df):
     df = df.select(
         f.case(column("b"))
@@ -479,6 +584,96 @@ def test_case(df):
     assert result.column(2) == pa.array(["Hola", "Mundo", None])
 
 
+def test_collate(df):
+    ci = "case_insensitive"
+    df = df.select(
+        column("a"),
+        (column("a").collate(ci) == literal("HELLO").collate(ci)).alias(
+            "eq"
+        ),
+        (column("a").collate("binary") == literal("HELLO")).alias("binary"),
+    ).sort(column("a").collate(ci).sort(ascending=False))
+
+    result = df.collect()[0]
+    assert result.column(0) == pa.array(["World", "Hello", "!"])
+    assert result.column(1) == pa.array([False, True, False])
+    assert result.column(2) == pa.array([False, False, False])
+
+    with pytest.raises(ValueError, match="Unsupported collation"):
+        column("a").collate("de_DE")
+
+
+def test_like():
+    ctx = SessionContext()
+    df = ctx.from_pydict({"a": ["50%", "500", "a_b", "axb", "Apple", None]})
+
+    def matches(predicate):
+        return df.filter(predicate).to_pydict()["a"]
+
+    assert matches(column("a").like("a%")) == ["a_b", "axb"]
+    assert matches(column("a").like("a_b")) == ["a_b", "axb"]
+    assert matches(column("a").like(literal("5__"))) == ["50%", "500"]
+    assert matches(column("a").not_like("a%")) == ["50%", "500", "Apple"]
+    assert matches(column("a").ilike("A%")) == ["a_b", "axb", "Apple"]
+    assert matches(column("a").not_ilike("A%")) == ["50%", "500"]
+
+    # an escaped wildcard matches itself
+    assert matches(column("a").like("50!%", escape="!")) == ["50%"]
+    assert matches(column("a").like("a!_b", escape="!")) == ["a_b"]
+    assert matches(column("a").like("a\\_b")) == ["a_b"]
+
+    assert matches(column("a").similar_to("(a|A)%")) == [
+        "a_b",
+        "axb",
+        "Apple",
+    ]
+    assert matches(column("a").similar_to("5[0-9]+")) == ["500"]
+    assert matches(column("a").similar_to("a.b")) == []
+
+    with pytest.raises(ValueError, match="ends with the escape character"):
+        column("a").like("50!", escape="!")
+    with pytest.raises(ValueError, match="requires a string pattern"):
+        column("a").like(column("a"), escape="!")
+
+
+def test_string_function_arguments():
+    ctx = SessionContext()
+    df = ctx.from_pydict({"a": ["xxhelloxx", "world", None]})
+    result = df.select(
+        f.substr(column("a"), 3, 5).alias("substr"),
+        f.substr(column("a"), 0, 3).alias("before"),
+        f.substr(column("a"), literal(2)).alias("rest"),
+        f.trim(column("a"), "x").alias("trim"),
+        f.ltrim(column("a"), "x").alias("ltrim"),
+        f.rtrim(column("a"), literal("x")).alias("rtrim"),
+        f.replace(column("a"), "l", "L").alias("replace"),
+        f.upper(column("a")).alias("upper"),
+        f.length(column("a")).alias("length"),
+        f.concat_ws("-", column("a"), literal("!")).alias("concat_ws"),
+    ).to_pydict()
+    assert result == {
+        "substr": ["hello", "rld", None],
+        "before": ["xx", "wo", None],
+        "rest": ["xhelloxx", "orld", None],
+        "trim": ["hello", "world", None],
+        "ltrim": ["helloxx", "world", None],
+        "rtrim": ["xxhello", "world", None],
+        "replace": ["xxheLLoxx", "worLd", None],
+        "upper": ["XXHELLOXX", "WORLD", None],
+        "length": [9, 5, None],
+        "concat_ws": ["xxhelloxx-!", "world-!", "!"],
+    }
+
+    with pytest.raises(ValueError, match="length of substr can't be negative"):
+        f.substr(column("a"), 1, -1)
+    with pytest.raises(ValueError, match="concat requires at least one"):
+        f.concat()
+    with pytest.raises(TypeError, match="Expected an Expr, a str or an int"):
+        f.trim(column("a"), 1.5)
+    with pytest.raises(TypeError):
+        f.upper(column("a"), column("a"))
+
+
 def test_binary_string_functions(df):
     df = df.select(
         f.encode(column("a"), literal("base64")),
@@ -491,3 +686,39 @@ def test_binary_string_functions(df):
     assert pa.array(result.column(1)).cast(pa.string()) == pa.array(
         ["Hello", "World", "!"]
     )
+
+
+def test_map_functions():
+    ctx = SessionContext()
+    map_type = pa.map_(pa.string(), pa.int64())
+    batch = pa.RecordBatch.from_arrays(
+        [
+            pa.array([["x", "y"], ["z"], None]),
+            pa.array([[1, 2], [3], [4]]),
+            pa.array([[("x", 1), ("y", None)], [], None], type=map_type),
+        ],
+        names=["keys", "values", "m"],
+    )
+    df = ctx.create_dataframe([[batch]])
+
+    built = df.select(f.map(column("keys"), column("values")).alias("m"))
+    assert built.collect()[0].column(0) == pa.array(
+        [[("x", 1), ("y", 2)], [("z", 3)], None], type=map_type
+    )
+
+    result = df.select(
+        f.map_extract(column("m"), literal("x")),
+        f.map_extract(column("m"), literal("y")),
+        f.map_extract(column("m"), literal("missing")),
+        f.map_keys(column("m")),
+        f.map_values(column("m")),
+    ).collect()[0]
+    assert result.column(0) == pa.array([1, None, None])
+    assert result.column(1) == pa.array([None, None, None], type=pa.int64())
+    assert result.column(2) == pa.array([None, None, None], type=pa.int64())
+    assert result.column(3).to_pylist() == [["x", "y"], [], None]
+    assert result.column(4).to_pylist() == [[1, None], [], None]
+
+    mismatched = df.select(f.map(column("keys"), f.make_array(literal(1))))
+    with pytest.raises(Exception, match="same number of keys and values"):
+        mismatched.collect()
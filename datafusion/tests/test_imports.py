@@ -21,6 +21,9 @@ import datafusion
 from datafusion import (
     AggregateUDF,
     DataFrame,
+    ExecutionPlan,
+    GroupBy,
+    LogicalPlan,
     SessionContext,
     ScalarUDF,
     functions,
@@ -103,6 +106,9 @@ def test_class_module_is_datafusion():
         DataFrame,
         ScalarUDF,
         AggregateUDF,
+        LogicalPlan,
+        ExecutionPlan,
+        GroupBy,
     ]:
         assert klass.__module__ == "datafusion"
 
@@ -22,7 +22,14 @@ import pyarrow as pa
 import pyarrow.dataset as ds
 import pytest
 
-from datafusion import udf
+from datafusion import (
+    ExecutionPlan,
+    LogicalPlan,
+    SessionContext,
+    column,
+    literal,
+    udf,
+)
 
 from . import generic as helpers
 
@@ -32,6 +39,54 @@ def test_no_table(ctx):
         ctx.sql("SELECT a FROM b").collect()
 
 
+def test_validate_sql(ctx):
+    ctx.from_pydict({"a": [1, 2, 3], "b": ["x", "y", "z"]}, name="t")
+
+    schema = ctx.validate_sql("SELECT a + 1 AS c, b FROM t WHERE a > 1")
+    assert schema == pa.schema([("c", pa.int64()), ("b", pa.string())])
+
+    with pytest.raises(
+        Exception, match="table 'datafusion.public.missing' not found"
+    ):
+        ctx.validate_sql("SELECT a FROM missing")
+    with pytest.raises(Exception, match="No field named d"):
+        ctx.validate_sql("SELECT d FROM t")
+
+    ctx.validate_sql("CREATE TABLE u AS SELECT * FROM t")
+    assert not ctx.table_exist("u")
+
+
+def test_with_cte(ctx):
+    ctx.from_pydict({"a": [1, 2, 3], "b": ["x", "y", "z"]}, name="t")
+    recent = ctx.with_cte(
+        "recent", ctx.table("t").filter(column("a") > literal(1))
+    )
+
+    assert recent.schema() == pa.schema(
+        [("a", pa.int64()), ("b", pa.string())]
+    )
+    assert ctx.sql("SELECT sum(a) AS s FROM recent").to_pydict() == {"s": [5]}
+
+    joined = (
+        ctx.table("t")
+        .join(
+            recent.select(column("a").alias("r")),
+            join_keys=(["a"], ["r"]),
+            how="inner",
+        )
+        .sort(column("a").sort())
+        .select(column("b"))
+    )
+    assert joined.to_pydict() == {"b": ["y", "z"]}
+
+    with pytest.raises(Exception, match="Recursive CTEs are not supported"):
+        ctx.sql(
+            "WITH RECURSIVE n AS "
+            "(SELECT 1 AS x UNION ALL SELECT x + 1 FROM n) "
+            "SELECT * FROM n"
+        )
+
+
 def test_register_csv(ctx, tmp_path):
     path = tmp_path / "test.csv"
     gzip_path = tmp_path / "test.csv.gz"
@@ -75,7 +130,7 @@ def test_register_csv(ctx, tmp_path):
     )
     ctx.register_csv("csv3", path, schema=alternative_schema)
 
-    assert ctx.tables() == {"csv", "csv1", "csv2", "csv3", "csv_gzip"}
+    assert ctx.tables() == ["csv", "csv1", "csv2", "csv3", "csv_gzip"]
 
     for table in ["csv", "csv1", "csv2", "csv_gzip"]:
         result = ctx.sql(f"SELECT COUNT(int) AS cnt FROM {table}").collect()
@@ -101,13 +156,34 @@ def test_register_csv(ctx, tmp_path):
 def test_register_parquet(ctx, tmp_path):
     path = helpers.write_parquet(tmp_path / "a.parquet", helpers.data())
     ctx.register_parquet("t", path)
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     result = ctx.sql("SELECT COUNT(a) AS cnt FROM t").collect()
     result = pa.Table.from_batches(result)
     assert result.to_pydict() == {"cnt": [100]}
 
 
+def test_register_parquet_columns(ctx, tmp_path):
+    path = tmp_path / "wide.parquet"
+    pa.parquet.write_table(
+        pa.table({"a": [1, 2], "b": ["x", "y"], "c": [1.5, 2.5]}), path
+    )
+
+    ctx.register_parquet("t", path, columns=["c", "a"])
+    assert ctx.table("t").schema().names == ["c", "a"]
+    result = ctx.sql("SELECT * FROM t WHERE a > 1").collect()
+    assert pa.Table.from_batches(result).to_pydict() == {"c": [2.5], "a": [2]}
+    assert ctx.sql("SELECT COUNT(*) AS cnt FROM t").to_pydict() == {
+        "cnt": [2]
+    }
+
+    with pytest.raises(ValueError, match="Column 'd' doesn't exist"):
+        ctx.register_parquet("unknown", path, columns=["a", "d"])
+    with pytest.raises(ValueError, match="more than once"):
+        ctx.register_parquet("twice", path, columns=["a", "a"])
+    assert "unknown" not in ctx.tables()
+
+
 def test_register_parquet_partitioned(ctx, tmp_path):
     dir_root = tmp_path / "dataset_parquet_partitioned"
     dir_root.mkdir(exist_ok=False)
@@ -132,7 +208,7 @@ def test_register_parquet_partitioned(ctx, tmp_path):
         parquet_pruning=True,
         file_extension=".parquet",
     )
-    assert ctx.tables() == {"datapp"}
+    assert ctx.tables() == ["datapp"]
 
     result = ctx.sql(
         "SELECT grp, COUNT(*) AS cnt FROM datapp GROUP BY grp"
@@ -143,12 +219,61 @@ def test_register_parquet_partitioned(ctx, tmp_path):
     assert dict(zip(rd["grp"], rd["cnt"])) == {"a": 3, "b": 1}
 
 
+def test_register_glob_exclude(ctx, tmp_path):
+    for year in [2022, 2023]:
+        (tmp_path / f"year={year}" / "month=1").mkdir(parents=True)
+        (tmp_path / f"year={year}" / "_temporary").mkdir()
+        for name in ["month=1/data.parquet", "_temporary/part.parquet"]:
+            helpers.write_parquet(
+                tmp_path / f"year={year}" / name, pa.array([year])
+            )
+    (tmp_path / "year=2023" / "month=1" / "data.parquet.crc").write_text("")
+
+    ctx.register_parquet("t", str(tmp_path), glob="year=2023/**/*.parquet")
+    result = ctx.sql("SELECT a FROM t").to_pydict()
+    assert result == {"a": [2023, 2023]}
+
+    ctx.register_parquet(
+        "u",
+        str(tmp_path),
+        glob="year=2023/**/*.parquet",
+        exclude=["_temporary", "*.crc"],
+    )
+    assert ctx.sql("SELECT a FROM u").to_pydict() == {"a": [2023]}
+
+    ctx.register_parquet("v", str(tmp_path), exclude=["_temporary"])
+    assert sorted(ctx.sql("SELECT a FROM v").to_pydict()["a"]) == [2022, 2023]
+
+    with pytest.raises(ValueError, match="No files"):
+        ctx.register_parquet("w", str(tmp_path), glob="year=2024/**")
+
+    with pytest.raises(ValueError, match="table_partition_cols"):
+        ctx.register_parquet(
+            "w",
+            str(tmp_path),
+            glob="**/*.parquet",
+            table_partition_cols=[("year", "string")],
+        )
+
+    csv_dir = tmp_path / "csv"
+    csv_dir.mkdir()
+    for name in ["keep.csv", "skip.csv"]:
+        pa.csv.write_csv(pa.table({"b": [name]}), csv_dir / name)
+    ctx.register_csv("c", csv_dir, exclude=["skip.*"])
+    assert ctx.sql("SELECT b FROM c").to_pydict() == {"b": ["keep.csv"]}
+
+    # the schemas of the CSV files are merged, as for a whole directory
+    (csv_dir / "wide.csv").write_text("b,c\nwide.csv,1\n")
+    ctx.register_csv("d", csv_dir, glob="[kw]*.csv")
+    assert ctx.table("d").schema().names == ["b", "c"]
+
+
 def test_register_dataset(ctx, tmp_path):
     path = helpers.write_parquet(tmp_path / "a.parquet", helpers.data())
     dataset = ds.dataset(path, format="parquet")
 
     ctx.register_dataset("t", dataset)
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     result = ctx.sql("SELECT COUNT(a) AS cnt FROM t").collect()
     result = pa.Table.from_batches(result)
@@ -187,7 +312,7 @@ def test_register_json(ctx, tmp_path):
     )
     ctx.register_json("json3", path, schema=alternative_schema)
 
-    assert ctx.tables() == {"json", "json1", "json2", "json3", "json_gzip"}
+    assert ctx.tables() == ["json", "json1", "json2", "json3", "json_gzip"]
 
     for table in ["json", "json1", "json2", "json_gzip"]:
         result = ctx.sql(f'SELECT COUNT("B") AS cnt FROM {table}').collect()
@@ -205,6 +330,39 @@ def test_register_json(ctx, tmp_path):
         ctx.register_json("json4", gzip_path, file_compression_type="rar")
 
 
+def test_register_json_array(ctx, tmp_path):
+    (tmp_path / "a.json").write_text(
+        '[\n  {"a": 1, "b": "x, ]"},\n  {"a": 2, "c": {"d": [1, 2]}}\n]\n'
+    )
+    with gzip.open(tmp_path / "b.json.gz", "wt") as gzipped_file:
+        gzipped_file.write('[{"a": 3}]')
+
+    ctx.register_json("t", tmp_path / "a.json", format="array")
+    ctx.register_json(
+        "t_gzip",
+        tmp_path,
+        file_extension=".gz",
+        file_compression_type="gzip",
+        format="array",
+    )
+
+    result = ctx.sql("SELECT a, b, c FROM t").collect()
+    assert pa.Table.from_batches(result).to_pydict() == {
+        "a": [1, 2],
+        "b": ["x, ]", None],
+        "c": [None, {"d": [1, 2]}],
+    }
+    result = ctx.sql("SELECT a FROM t_gzip").collect()
+    assert pa.Table.from_batches(result).to_pydict() == {"a": [3]}
+
+    (tmp_path / "b.json").write_text('{"a": 3}\n')
+    with pytest.raises(Exception, match="Expected the file to hold a JSON"):
+        ctx.register_json("t_ndjson", tmp_path / "b.json", format="array")
+
+    with pytest.raises(ValueError, match="Unsupported JSON format"):
+        ctx.register_json("t_csv", tmp_path / "a.json", format="csv")
+
+
 def test_register_avro(ctx):
     path = "testing/data/avro/alltypes_plain.avro"
     ctx.register_avro("alltypes_plain", path)
@@ -231,6 +389,58 @@ def test_register_avro(ctx):
     assert result.schema == alternative_schema
 
 
+def test_register_ipc(ctx, tmp_path):
+    def write_ipc(path, table):
+        with pa.ipc.new_file(path, table.schema) as writer:
+            writer.write_table(table)
+
+    write_ipc(tmp_path / "part-0.arrow", pa.table({"a": [1, 2]}))
+    write_ipc(tmp_path / "part-1.arrow", pa.table({"a": [3]}))
+    ctx.register_ipc("t", tmp_path)
+    result = ctx.sql("SELECT SUM(a) AS total FROM t").collect()
+    assert pa.Table.from_batches(result).to_pydict() == {"total": [6]}
+
+    ctx.register_ipc("single", tmp_path / "part-1.arrow")
+    assert ctx.table("single").to_pydict() == {"a": [3]}
+
+    write_ipc(tmp_path / "part-2.arrow", pa.table({"b": ["x"]}))
+    with pytest.raises(ValueError, match="differs from the schema"):
+        ctx.register_ipc("mismatch", tmp_path)
+
+    ctx.register_ipc("matching", tmp_path, glob="part-[01].arrow")
+    assert ctx.table("matching").count() == 3
+
+
+def test_register_detects_format(ctx, tmp_path):
+    import pyarrow.csv
+    import pyarrow.parquet as pq
+
+    table = pa.table({"a": [1, 2, 3]})
+    parquet_dir = tmp_path / "parquet"
+    parquet_dir.mkdir()
+    pq.write_table(table, parquet_dir / "part-0.parquet")
+    (parquet_dir / "_SUCCESS").touch()
+    pa.csv.write_csv(table, tmp_path / "data.csv")
+    (tmp_path / "data.jsonl").write_text('{"a": 1}\n{"a": 2}\n')
+    (tmp_path / "data.txt").write_text("a\n4\n")
+
+    ctx.register("p", parquet_dir)
+    assert ctx.table("p").to_pydict() == {"a": [1, 2, 3]}
+    ctx.register("c", tmp_path / "data.csv")
+    assert ctx.table("c").count() == 3
+    ctx.register("j", str(tmp_path / "data.jsonl"))
+    assert ctx.table("j").to_pydict() == {"a": [1, 2]}
+    ctx.register("t", tmp_path / "data.txt", format="csv")
+    assert ctx.table("t").to_pydict() == {"a": [4]}
+
+    with pytest.raises(ValueError, match="Can't detect the format"):
+        ctx.register("unsupported", tmp_path / "data.txt")
+    with pytest.raises(ValueError, match=r"extensions \.csv, \.jsonl"):
+        ctx.register("ambiguous", tmp_path)
+    with pytest.raises(ValueError, match="Unsupported format orc"):
+        ctx.register("orc", tmp_path / "data.txt", format="orc")
+
+
 def test_execute(ctx, tmp_path):
     data = [1, 1, 2, 2, 3, 11, 12]
 
@@ -238,7 +448,7 @@ def test_execute(ctx, tmp_path):
     path = helpers.write_parquet(tmp_path / "a.parquet", pa.array(data))
     ctx.register_parquet("t", path)
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     # count
     result = ctx.sql(
@@ -287,6 +497,47 @@ def test_execute(ctx, tmp_path):
     np.testing.assert_equal(expected[0].column(1), expected[0].column(1))
 
 
+def collect_partitions(plan, ctx=None):
+    values = []
+    for partition in range(plan.partition_count):
+        stream = plan.execute(partition, ctx)
+        batch = stream.next()
+        while batch is not None:
+            values.extend(batch.to_pyarrow().column(0).to_pylist())
+            batch = stream.next()
+    return sorted(values)
+
+
+def test_execution_plan_execute_partition(ctx, tmp_path):
+    path = helpers.write_parquet(
+        tmp_path / "a.parquet", pa.array([1, 2, 3, 11, 12])
+    )
+    ctx.register_parquet("t", path)
+    plan = ctx.sql("SELECT a FROM t WHERE a > 2").execution_plan()
+
+    assert collect_partitions(plan) == [3, 11, 12]
+    assert collect_partitions(plan, ctx) == [3, 11, 12]
+
+    with pytest.raises(IndexError):
+        plan.execute(plan.partition_count)
+
+
+def test_execution_plan_proto_round_trip(ctx, tmp_path):
+    path = helpers.write_parquet(
+        tmp_path / "a.parquet", pa.array([1, 2, 3, 11, 12])
+    )
+    ctx.register_parquet("t", path)
+    plan = ctx.sql("SELECT a FROM t WHERE a > 2").execution_plan()
+
+    proto_bytes = plan.to_proto_bytes()
+    assert type(proto_bytes) is bytes
+
+    worker = SessionContext()
+    decoded = ExecutionPlan.from_proto_bytes(worker, proto_bytes)
+    assert decoded.display_indent() == plan.display_indent()
+    assert collect_partitions(decoded, worker) == [3, 11, 12]
+
+
 def test_cast(ctx, tmp_path):
     """
     Verify that we can cast
@@ -350,6 +601,84 @@ def test_udf(
     assert result == pa.array(expected_values)
 
 
+def test_logical_plan_proto_round_trip(ctx, tmp_path):
+    path = helpers.write_parquet(
+        tmp_path / "a.parquet", pa.array([1, None, 3])
+    )
+    func = udf(
+        lambda x: x.is_null(),
+        [pa.int64()],
+        pa.bool_(),
+        name="is_missing",
+        volatility="immutable",
+    )
+    ctx.register_parquet("t", path)
+    ctx.register_udf(func)
+
+    plan = ctx.sql("SELECT a, is_missing(a) AS m FROM t WHERE a > 1")
+    proto_bytes = plan.logical_plan().to_proto_bytes(ctx)
+    assert type(proto_bytes) is bytes
+
+    other = SessionContext()
+    other.register_parquet("t", path)
+    other.register_udf(func)
+    decoded = LogicalPlan.from_proto_bytes(other, proto_bytes)
+    assert decoded.display_indent() == plan.logical_plan().display_indent()
+
+    df = other.create_dataframe_from_logical_plan(decoded)
+    assert df.collect()[0].to_pydict() == {"a": [3], "m": [False]}
+
+    with pytest.raises(Exception):
+        LogicalPlan.from_proto_bytes(SessionContext(), proto_bytes)
+
+    # the plan could only be decoded by a context the UDF isn't registered in
+    unregistered = udf(
+        lambda x: x,
+        [pa.int64()],
+        pa.int64(),
+        name="identity",
+        volatility="immutable",
+    )
+    df = ctx.table("t").select(unregistered(column("a")))
+    with pytest.raises(ValueError, match="function identity, which isn't"):
+        df.logical_plan().to_proto_bytes(ctx)
+
+
+def test_create_function(ctx):
+    ctx.from_pydict({"a": [1, 2], "b": ["x", "y"]}, name="t")
+
+    ctx.sql(
+        "CREATE FUNCTION add_one(x BIGINT) RETURNS BIGINT RETURN x + 1"
+    ).collect()
+    ctx.sql(
+        "CREATE FUNCTION greet(name VARCHAR) RETURNS VARCHAR "
+        "LANGUAGE SQL IMMUTABLE AS 'SELECT concat(''hi '', $1)'"
+    )
+    ctx.sql("CREATE FUNCTION answer() RETURNS INT RETURN 42")
+
+    result = ctx.sql(
+        "SELECT add_one(a) AS c, greet(b) AS g, answer() AS n FROM t"
+    ).to_pydict()
+    assert result == {"c": [2, 3], "g": ["hi x", "hi y"], "n": [42, 42]}
+    assert ctx.udf("add_one")
+
+    with pytest.raises(Exception, match="Function add_one already exists"):
+        ctx.sql("CREATE FUNCTION add_one(x BIGINT) RETURN x")
+    ctx.sql("CREATE OR REPLACE FUNCTION add_one(x BIGINT) RETURN x + 10")
+    result = ctx.sql("SELECT add_one(a) AS c FROM t").to_pydict()
+    assert result == {"c": [11, 12]}
+
+    with pytest.raises(
+        Exception,
+        match="CREATE FUNCTION with LANGUAGE python is not supported",
+    ):
+        ctx.sql("CREATE FUNCTION f(x INT) LANGUAGE python AS 'return x'")
+    with pytest.raises(
+        Exception, match=r"CREATE FUNCTION with SUM\(x\) in its body"
+    ):
+        ctx.sql("CREATE FUNCTION f(x BIGINT) RETURN sum(x)")
+
+
 _null_mask = np.array([False, True, False])
 
 
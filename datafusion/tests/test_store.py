@@ -19,7 +19,7 @@ import os
 import pytest
 
 from datafusion import SessionContext
-from datafusion.object_store import LocalFileSystem
+from datafusion.object_store import AmazonS3, LocalFileSystem
 
 
 @pytest.fixture
@@ -44,3 +44,28 @@ def test_read_parquet(ctx):
     )
     df = ctx.sql("SELECT * FROM test")
     assert isinstance(df.collect(), list)
+
+
+def test_retry_options():
+    s3 = AmazonS3(
+        bucket_name="bucket",
+        region="us-east-1",
+        access_key_id="key",
+        secret_access_key="secret",
+        max_retries=2,
+        retry_backoff=0.01,
+        retry_timeout=5,
+        timeout=1,
+    )
+    ctx = SessionContext()
+    ctx.register_object_store("s3://", s3, None)
+
+    for value in [0, -1, 1e-12, float("nan"), float("inf"), 1e30]:
+        with pytest.raises(
+            ValueError, match="retry_backoff must be a positive number"
+        ):
+            AmazonS3(bucket_name="bucket", retry_backoff=value)
+    with pytest.raises(ValueError, match="retry_timeout must be a positive"):
+        AmazonS3(bucket_name="bucket", retry_timeout=-1)
+    with pytest.raises(ValueError, match="timeout must be a positive"):
+        AmazonS3(bucket_name="bucket", timeout=0)
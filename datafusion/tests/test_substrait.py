@@ -17,7 +17,7 @@
 
 import pyarrow as pa
 
-from datafusion import SessionContext
+from datafusion import SessionContext, column, literal
 from datafusion import substrait as ss
 import pytest
 
@@ -35,7 +35,7 @@ def test_substrait_serialization(ctx):
 
     ctx.register_record_batches("t", [[batch]])
 
-    assert ctx.tables() == {"t"}
+    assert ctx.tables() == ["t"]
 
     # For now just make sure the method calls blow up
     substrait_plan = ss.substrait.serde.serialize_to_plan(
@@ -57,3 +57,264 @@ def test_substrait_serialization(ctx):
     substrait_plan = ss.substrait.producer.to_substrait_plan(
         df.logical_plan(), ctx
     )
+
+
+def test_substrait_sql_to_plan(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+
+    query = "SELECT a FROM t WHERE b > 4"
+    plan = ss.substrait.serde.sql_to_plan(query, ctx)
+    substrait_bytes = ss.substrait.serde.serialize_bytes(query, ctx)
+    assert plan == ss.substrait.serde.deserialize_bytes(substrait_bytes)
+    assert plan.encode() == substrait_bytes
+
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, plan)
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    assert df.collect()[0].column(0) == pa.array([2, 3])
+
+
+def test_substrait_to_datafusion_explain(ctx):
+    ctx.from_pydict({"a": [1, 2, 3], "b": [4, 5, 6]}, name="t")
+    plan = ss.substrait.serde.sql_to_plan("SELECT a FROM t WHERE b > 4", ctx)
+
+    explain = plan.to_datafusion_explain(ctx)
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, plan)
+    assert explain == logical_plan.display_indent()
+    assert "TableScan: t" in explain
+    assert "b > Int64(4)" in explain
+
+    explain = plan.to_datafusion_explain(ctx, with_schema=True)
+    assert explain == logical_plan.display_indent_schema()
+    assert "a:Int64" in explain
+
+
+def test_substrait_write(ctx):
+    ctx.from_pydict({"a": [1, 2, 3], "b": ["x", "y", "z"]}, name="src")
+    ctx.sql("CREATE TABLE dst (a BIGINT, b VARCHAR)")
+
+    plan = ss.substrait.serde.sql_to_plan(
+        "INSERT INTO dst SELECT a, b FROM src WHERE a > 1", ctx
+    )
+    assert plan.write_table() == "dst"
+    query = ss.substrait.serde.sql_to_plan("SELECT a FROM src", ctx)
+    assert query.write_table() is None
+
+    decoded = ss.substrait.serde.deserialize_bytes(plan.encode())
+    assert decoded == plan
+    assert decoded.write_table() == "dst"
+
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, decoded)
+    ctx.create_dataframe_from_logical_plan(logical_plan).collect()
+    result = ctx.sql("SELECT * FROM dst ORDER BY a").to_pydict()
+    assert result == {"a": [2, 3], "b": ["y", "z"]}
+
+
+def test_substrait_nullability(ctx):
+    schema = pa.schema(
+        [pa.field("a", pa.int64(), nullable=False), pa.field("b", pa.int64())]
+    )
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, None, 6])], schema=schema
+    )
+    ctx.register_record_batches("t", [[batch]])
+    logical_plan = ctx.sql("SELECT a, b FROM t WHERE b > 4").logical_plan()
+
+    producer = ss.substrait.producer
+    nullable = producer.to_substrait_plan(
+        logical_plan, ctx, nullability="nullable"
+    )
+    strict = producer.to_substrait_plan(
+        logical_plan, ctx, nullability="strict"
+    )
+    diff = strict.diff(nullable)
+    assert ".nullability: Required != Nullable" in diff
+    assert ".Literal.nullable: false != true" in diff
+
+    for plan in (nullable, strict):
+        consumed = ss.substrait.consumer.from_substrait_plan(ctx, plan)
+        df = ctx.create_dataframe_from_logical_plan(consumed)
+        assert df.to_pydict() == {"a": [3], "b": [6]}
+
+    with pytest.raises(ValueError, match="Unsupported nullability lenient"):
+        producer.to_substrait_plan(logical_plan, ctx, nullability="lenient")
+
+
+def test_substrait_replace_read(ctx):
+    ctx.from_pydict({"a": [100, 200], "b": [0, 1]}, name="t")
+    ctx.from_pydict({"a": [1, 2, 3], "b": [0, 1, 2]}, name="src")
+    plan = ss.substrait.serde.sql_to_plan(
+        "SELECT b + 1 AS c FROM t WHERE b > 0", ctx
+    )
+    subplan = ss.substrait.serde.sql_to_plan(
+        "SELECT a * 10 AS a, b FROM src WHERE a > 1", ctx
+    )
+
+    merged = plan.replace_read("t", subplan)
+    functions = merged.referenced_functions()
+    anchors = {f["anchor"]: f["name"] for f in functions}
+    assert len(anchors) == len(functions)
+    assert sorted(anchors.values()) == ["add", "gt", "multiply"]
+
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, merged)
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    assert df.collect()[0].column(0) == pa.array([2, 3])
+
+    with pytest.raises(Exception, match="doesn't read the table other"):
+        plan.replace_read("other", subplan)
+
+    # the subplan inserted in place of a read isn't searched for reads again
+    self_subplan = ss.substrait.serde.sql_to_plan(
+        "SELECT a, b FROM t WHERE a > 100", ctx
+    )
+    merged = plan.replace_read("t", self_subplan)
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, merged)
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    assert df.collect()[0].column(0) == pa.array([2])
+
+
+def test_substrait_explain_dataframe(ctx):
+    ctx.from_pydict({"a": [1, 2, 3], "b": [4, 5, 6]}, name="t")
+
+    explain = ctx.sql("SELECT a FROM t WHERE b > 4").explain_substrait()
+    assert "Filter: t.b > Int64(4)" in explain["original"]
+    assert "Filter: t.b > Int64(4)" in explain["round_trip"]
+    assert explain["schema_differences"] == []
+
+    # the round trip drops the alias of the computed column
+    explain = ctx.sql("SELECT a, b + 1 AS c FROM t").explain_substrait()
+    assert "AS c" in explain["original"]
+    assert "AS c" not in explain["round_trip"]
+    [difference] = explain["schema_differences"]
+    assert difference == "column 1: c: Int64 != t.b + Int64(1): Int64"
+
+
+def test_substrait_referenced_functions(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+
+    plan = ss.substrait.serde.sql_to_plan(
+        "SELECT a + b AS c FROM t WHERE b > 4", ctx
+    )
+    functions = plan.referenced_functions()
+    assert {function["name"] for function in functions} == {"add", "gt"}
+    assert len({function["anchor"] for function in functions}) == 2
+    uris = plan.extension_uris()
+    for function in functions:
+        assert function["uri"] is None or function["uri"] in uris
+
+    plan = ss.substrait.serde.sql_to_plan("SELECT a FROM t", ctx)
+    assert plan.referenced_functions() == []
+
+
+def test_substrait_table_map(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("input", [[batch]])
+    substrait_plan = ss.substrait.serde.serialize_to_plan(
+        "SELECT a FROM input WHERE b > 4", ctx
+    )
+    ctx.deregister_table("input")
+    ctx.register_record_batches("my_real_table", [[batch]])
+
+    logical_plan = ss.substrait.consumer.from_substrait_plan(
+        ctx, substrait_plan, table_map={"input": "my_real_table"}
+    )
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    assert df.collect()[0].column(0) == pa.array([2, 3])
+
+    # tables missing from the mapping resolve as usual
+    ctx.register_record_batches("input", [[batch]])
+    logical_plan = ss.substrait.consumer.from_substrait_plan(
+        ctx, substrait_plan, table_map={"other": "my_real_table"}
+    )
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    assert df.collect()[0].column(0) == pa.array([2, 3])
+
+    with pytest.raises(Exception):
+        ss.substrait.consumer.from_substrait_plan(
+            ctx, substrait_plan, table_map={"input": "missing"}
+        )
+
+
+# A plan reading the rows (1, "one"), (2, NULL) and (3, "three") of a virtual
+# table with the columns id BIGINT and name VARCHAR
+VIRTUAL_TABLE_PLAN = (
+    "1a5912570a4b0a49121a0a0269640a046e616d65120e0a043a0210010a046202"
+    "100118012a2b0a0b0a0238010a0562036f6e650a0d0a0238020a07ea01046202"
+    "10010a0d0a0238030a07620574687265651202696412046e616d65"
+)
+
+
+def test_substrait_virtual_table(ctx):
+    plan = ss.substrait.serde.deserialize_bytes(
+        bytes.fromhex(VIRTUAL_TABLE_PLAN)
+    )
+    logical_plan = ss.substrait.consumer.from_substrait_plan(ctx, plan)
+    df = ctx.create_dataframe_from_logical_plan(logical_plan)
+    expected = {"id": [1, 2, 3], "name": ["one", None, "three"]}
+    assert df.to_pydict() == expected
+
+    # the rows are materialized only for the conversion
+    assert ctx.tables() == []
+
+
+def test_substrait_plan_diff(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+
+    plan = ss.substrait.serde.serialize_to_plan(
+        "SELECT a FROM t WHERE b > 4", ctx
+    )
+    same = ss.substrait.serde.serialize_to_plan(
+        "SELECT a FROM t WHERE b > 4", ctx
+    )
+    other_literal = ss.substrait.serde.serialize_to_plan(
+        "SELECT a FROM t WHERE b > 5", ctx
+    )
+    other_relation = ss.substrait.serde.serialize_to_plan(
+        "SELECT a FROM t LIMIT 3", ctx
+    )
+
+    assert plan == same
+    assert plan != other_literal
+    assert plan.diff(same) == ""
+
+    diff = plan.diff(other_literal)
+    assert diff.endswith(".Literal.literal_type.I64: 4 != 5")
+    assert len(diff.splitlines()) == 1
+
+    diff = plan.diff(other_relation)
+    path = "relations[0].rel_type.Root.input.rel_type"
+    assert f"{path}: Project != Fetch" in diff
+
+
+def test_substrait_serialize_dataframe(ctx):
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 5, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+    df = ctx.table("t").filter(column("b") > literal(4)).select(column("a"))
+
+    substrait_bytes = df.to_substrait_bytes()
+    assert type(substrait_bytes) is bytes
+    assert ss.substrait.serde.serialize_df(df) == substrait_bytes
+
+    substrait_plan = ss.substrait.serde.deserialize_bytes(substrait_bytes)
+    logical_plan = ss.substrait.consumer.from_substrait_plan(
+        ctx, substrait_plan
+    )
+    result = ctx.create_dataframe_from_logical_plan(logical_plan).collect()
+    assert result[0].column(0) == pa.array([2, 3])
@@ -49,6 +49,38 @@ class Summarize(Accumulator):
         return self._sum
 
 
+class Mean(Accumulator):
+    """
+    Accumulator with a state of several fields, returned as plain Python
+    values.
+    """
+
+    def __init__(self):
+        self._sum = 0.0
+        self._count = 0
+        self._values = []
+
+    def state(self) -> List:
+        return [self._sum, self._count, self._values]
+
+    def update(self, values: pa.Array) -> None:
+        self._sum += pc.sum(values).as_py() or 0.0
+        self._count += len(values)
+        self._values.extend(values.to_pylist())
+
+    def merge(
+        self, sums: pa.Array, counts: pa.Array, values: pa.Array
+    ) -> None:
+        self._sum += pc.sum(sums).as_py()
+        self._count += pc.sum(counts).as_py()
+        for merged in values.to_pylist():
+            self._values.extend(merged)
+
+    def evaluate(self):
+        assert len(self._values) == self._count
+        return self._sum / self._count if self._count else None
+
+
 class NotSubclassOfAccumulator:
     pass
 
@@ -134,3 +166,84 @@ def test_group_by(df):
     arrays = [batch.column(1) for batch in batches]
     joined = pa.concat_arrays(arrays)
     assert joined == pa.array([1.0 + 2.0, 3.0])
+
+
+def test_multi_partition_state_merge():
+    ctx = SessionContext()
+    partitions = [
+        [
+            pa.RecordBatch.from_arrays(
+                [pa.array([float(i), float(i + 1)]), pa.array([i % 2, i % 2])],
+                names=["a", "b"],
+            )
+        ]
+        for i in range(8)
+    ]
+    df = ctx.create_dataframe(partitions)
+
+    mean = udaf(
+        Mean,
+        pa.float64(),
+        pa.float64(),
+        [pa.float64(), pa.int64(), pa.list_(pa.float64())],
+        volatility="immutable",
+    )
+
+    # each partition is aggregated separately and the states are merged
+    result = df.aggregate([], [mean(column("a"))]).collect()[0]
+    assert result.column(0) == pa.array([4.0])
+
+    result = df.aggregate([column("b")], [mean(column("a"))]).sort(column("b"))
+    assert result.to_pydict()[result.schema().names[1]] == [3.5, 4.5]
+
+
+@udaf(pa.float64(), pa.float64(), [pa.float64()], volatility="immutable")
+class DecoratedSum(Summarize):
+    pass
+
+
+@udaf(
+    [pa.int64(), pa.int64()],
+    pa.int64(),
+    [pa.int64()],
+    volatility="immutable",
+    name="sum_product",
+)
+class SumProduct(Accumulator):
+    def __init__(self):
+        self._sum = 0
+
+    def state(self) -> List:
+        return [self._sum]
+
+    def update(self, a: pa.Array, b: pa.Array) -> None:
+        self._sum += pc.sum(pc.multiply(a, b)).as_py()
+
+    def merge(self, sums: pa.Array) -> None:
+        self._sum += pc.sum(sums).as_py()
+
+    def evaluate(self):
+        return self._sum
+
+
+def test_udaf_decorator(df):
+    assert repr(DecoratedSum) == "AggregateUDF(decoratedsum)"
+
+    result = df.aggregate([], [DecoratedSum(column("a"))]).collect()[0]
+    assert result.column(0) == pa.array([6.0])
+
+    result = df.aggregate([], [SumProduct(column("a"), column("b"))]).collect()
+    assert result[0].column(0) == pa.array([1 * 4 + 2 * 4 + 3 * 6])
+
+
+def test_udaf_decorator_register():
+    ctx = SessionContext()
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, 2, 3]), pa.array([4, 4, 6])],
+        names=["a", "b"],
+    )
+    ctx.register_record_batches("t", [[batch]])
+    ctx.register_udaf(SumProduct)
+
+    result = ctx.sql("SELECT sum_product(a, b) AS s FROM t").collect()[0]
+    assert result.column(0) == pa.array([26])
@@ -0,0 +1,190 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The tables of the files held in memory, for `SessionContext.read_parquet_bytes` and its
+//! variants
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::ObjectStore;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DFResult;
+use datafusion::execution::context::{SessionState, TaskContext};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
+};
+use datafusion_expr::{Expr, TableProviderFilterPushDown};
+
+/// A file copied into an in-memory object store, deleted from the store when dropped
+#[derive(Debug)]
+pub(crate) struct StoredBytes {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+}
+
+impl StoredBytes {
+    pub(crate) fn new(store: Arc<dyn ObjectStore>, location: Path) -> Self {
+        Self { store, location }
+    }
+
+    pub(crate) fn location(&self) -> &Path {
+        &self.location
+    }
+}
+
+impl Drop for StoredBytes {
+    fn drop(&mut self) {
+        // the in-memory store deletes without waiting on anything, so no runtime is needed
+        let _ = futures::executor::block_on(self.store.delete(&self.location));
+    }
+}
+
+/// A table reading a file held in memory, the table of the file or the view of a DataFrame
+/// reading it, which keeps the file in the store for as long as a plan or a stream of its scans
+/// refers to it
+pub(crate) struct BytesTable {
+    table: Arc<dyn TableProvider>,
+    bytes: Arc<StoredBytes>,
+}
+
+impl BytesTable {
+    pub(crate) fn new(table: Arc<dyn TableProvider>, bytes: StoredBytes) -> Self {
+        Self {
+            table,
+            bytes: Arc::new(bytes),
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for BytesTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.table.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let input = self.table.scan(state, projection, filters, limit).await?;
+        Ok(Arc::new(BytesExec {
+            input,
+            bytes: self.bytes.clone(),
+        }))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        self.table.supports_filters_pushdown(filters)
+    }
+}
+
+/// Passes the batches of its input through, keeping the file it reads in the store until its
+/// streams are dropped
+#[derive(Debug)]
+struct BytesExec {
+    input: Arc<dyn ExecutionPlan>,
+    bytes: Arc<StoredBytes>,
+}
+
+impl ExecutionPlan for BytesExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self {
+            input: children[0].clone(),
+            bytes: self.bytes.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let bytes = self.bytes.clone();
+        let batches = self.input.execute(partition, context)?.map(move |batch| {
+            // the stream owns the file until it is dropped
+            let _ = &bytes;
+            batch
+        });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.input.schema(),
+            batches,
+        )))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+impl DisplayAs for BytesExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "BytesExec: location={}", self.bytes.location())
+            }
+        }
+    }
+}
@@ -15,17 +15,24 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::collections::HashSet;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use pyo3::exceptions::PyKeyError;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyKeyError, PyTypeError};
 use pyo3::prelude::*;
 
+use crate::dataset::Dataset;
 use crate::errors::DataFusionError;
 use crate::utils::wait_for_future;
 use datafusion::{
     arrow::pyarrow::ToPyArrow,
-    catalog::{schema::SchemaProvider, CatalogProvider},
+    catalog::{
+        schema::{MemorySchemaProvider, SchemaProvider},
+        CatalogProvider,
+    },
     datasource::{TableProvider, TableType},
 };
 
@@ -143,3 +150,286 @@ impl PyTable {
     // fn has_exact_statistics
     // fn supports_filter_pushdown
 }
+
+/// Implements a DataFusion CatalogProvider that delegates to a Python object, so that
+/// schemas and tables are resolved lazily when a query references them. The Python object
+/// must implement `schema_names()`, `table_names(schema)` and `table(schema, name)`.
+///
+/// DataFusion's catalog traits cannot report failures, so an exception raised by one of
+/// the Python methods is kept until it is retrieved with `take_error`.
+#[derive(Debug)]
+pub(crate) struct PyCatalogProvider {
+    provider: PyObject,
+    error: Arc<Mutex<Option<DataFusionError>>>,
+}
+
+impl PyCatalogProvider {
+    pub fn new(provider: PyObject) -> Self {
+        Self {
+            provider,
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns, and clears, the last exception raised by the Python provider
+    pub fn take_error(&self) -> Option<DataFusionError> {
+        self.error.lock().take()
+    }
+}
+
+/// Calls into the Python provider, recording the exception if the call fails
+fn call_provider<T>(
+    provider: &PyObject,
+    error: &Mutex<Option<DataFusionError>>,
+    f: impl FnOnce(Python, &PyAny) -> PyResult<T>,
+) -> Option<T> {
+    Python::with_gil(|py| match f(py, provider.as_ref(py)) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            *error.lock() = Some(DataFusionError::PythonError(err));
+            None
+        }
+    })
+}
+
+impl CatalogProvider for PyCatalogProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        call_provider(&self.provider, &self.error, |_, provider| {
+            provider.call_method0("schema_names")?.extract()
+        })
+        .unwrap_or_default()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        if self.schema_names().iter().any(|schema| schema == name) {
+            Some(Arc::new(PySchemaProvider {
+                provider: self.provider.clone(),
+                schema: name.to_owned(),
+                error: self.error.clone(),
+            }))
+        } else {
+            None
+        }
+    }
+}
+
+/// A schema of a `PyCatalogProvider`
+#[derive(Debug)]
+struct PySchemaProvider {
+    provider: PyObject,
+    schema: String,
+    error: Arc<Mutex<Option<DataFusionError>>>,
+}
+
+#[async_trait]
+impl SchemaProvider for PySchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        call_provider(&self.provider, &self.error, |_, provider| {
+            provider
+                .call_method1("table_names", (&self.schema,))?
+                .extract()
+        })
+        .unwrap_or_default()
+    }
+
+    async fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        call_provider(&self.provider, &self.error, |py, provider| {
+            let table = provider.call_method1("table", (&self.schema, name))?;
+            if table.is_none() {
+                Ok(None)
+            } else if let Ok(table) = table.extract::<PyRef<PyTable>>() {
+                Ok(Some(table.table()))
+            } else {
+                let dataset = Dataset::new(table, py).map_err(|_| {
+                    PyTypeError::new_err(
+                        "table() must return a datafusion Table, a pyarrow.dataset.Dataset or None",
+                    )
+                })?;
+                Ok(Some(Arc::new(dataset) as Arc<dyn TableProvider>))
+            }
+        })
+        .flatten()
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names().iter().any(|table| table == name)
+    }
+}
+
+/// The default catalog of a context created by `clone_session`. Each schema of the parent
+/// catalog, including those created after the clone, is overlaid when it's first looked up,
+/// while the schemas created in the context are only visible to it.
+pub(crate) struct OverlayCatalogProvider {
+    parent: Arc<dyn CatalogProvider>,
+    /// The schemas created in the context and the overlays of the schemas of the parent
+    schemas: Mutex<HashMap<String, Arc<dyn SchemaProvider>>>,
+    /// The schemas of the parent dropped from the context, which it no longer sees
+    hidden: Mutex<HashSet<String>>,
+}
+
+impl OverlayCatalogProvider {
+    pub fn new(parent: Arc<dyn CatalogProvider>) -> Self {
+        Self {
+            parent,
+            schemas: Mutex::new(HashMap::new()),
+            hidden: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+impl CatalogProvider for OverlayCatalogProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        let schemas = self.schemas.lock();
+        let hidden = self.hidden.lock();
+        let mut names: Vec<String> = schemas.keys().cloned().collect();
+        names.extend(
+            self.parent
+                .schema_names()
+                .into_iter()
+                .filter(|name| !schemas.contains_key(name) && !hidden.contains(name)),
+        );
+        names
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        let mut schemas = self.schemas.lock();
+        if let Some(schema) = schemas.get(name) {
+            return Some(schema.clone());
+        }
+        if self.hidden.lock().contains(name) {
+            return None;
+        }
+        let overlay: Arc<dyn SchemaProvider> =
+            Arc::new(OverlaySchemaProvider::new(self.parent.schema(name)?));
+        schemas.insert(name.to_string(), overlay.clone());
+        Some(overlay)
+    }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> datafusion_common::Result<Option<Arc<dyn SchemaProvider>>> {
+        self.hidden.lock().remove(name);
+        Ok(self.schemas.lock().insert(name.to_string(), schema))
+    }
+
+    fn deregister_schema(
+        &self,
+        name: &str,
+        cascade: bool,
+    ) -> datafusion_common::Result<Option<Arc<dyn SchemaProvider>>> {
+        let schema = match self.schema(name) {
+            Some(schema) => schema,
+            None => return Ok(None),
+        };
+        let table_names = schema.table_names();
+        if !cascade && !table_names.is_empty() {
+            return Err(datafusion_common::DataFusionError::Execution(format!(
+                "Cannot drop schema {name} because other tables depend on it: {}",
+                table_names.join(", ")
+            )));
+        }
+        self.schemas.lock().remove(name);
+        if self.parent.schema(name).is_some() {
+            self.hidden.lock().insert(name.to_string());
+        }
+        Ok(Some(schema))
+    }
+}
+
+/// A schema of a context created by `clone_session`. Tables registered in the context are
+/// kept in its own schema, which is looked up first, and the tables of the schema of the
+/// parent context are visible but never changed.
+pub(crate) struct OverlaySchemaProvider {
+    own: MemorySchemaProvider,
+    parent: Arc<dyn SchemaProvider>,
+    /// The tables of the parent deregistered from the context, which it no longer sees
+    hidden: Mutex<HashSet<String>>,
+}
+
+impl OverlaySchemaProvider {
+    pub fn new(parent: Arc<dyn SchemaProvider>) -> Self {
+        Self {
+            own: MemorySchemaProvider::new(),
+            parent,
+            hidden: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for OverlaySchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        let hidden = self.hidden.lock();
+        let mut names = self.own.table_names();
+        names.extend(
+            self.parent
+                .table_names()
+                .into_iter()
+                .filter(|name| !self.own.table_exist(name) && !hidden.contains(name)),
+        );
+        names
+    }
+
+    async fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        if let Some(table) = self.own.table(name).await {
+            return Some(table);
+        }
+        if self.hidden.lock().contains(name) {
+            return None;
+        }
+        self.parent.table(name).await
+    }
+
+    fn register_table(
+        &self,
+        name: String,
+        table: Arc<dyn TableProvider>,
+    ) -> datafusion_common::Result<Option<Arc<dyn TableProvider>>> {
+        self.hidden.lock().remove(&name);
+        self.own.register_table(name, table)
+    }
+
+    /// Removes the table of the context, which uncovers the one of the parent of the same name,
+    /// or hides the table of the parent when the context has none, returning it
+    fn deregister_table(
+        &self,
+        name: &str,
+    ) -> datafusion_common::Result<Option<Arc<dyn TableProvider>>> {
+        if let Some(table) = self.own.deregister_table(name)? {
+            return Ok(Some(table));
+        }
+        let mut hidden = self.hidden.lock();
+        if hidden.contains(name) {
+            return Ok(None);
+        }
+        // the memory schemas the parents have return their tables without waiting
+        let table = futures::executor::block_on(self.parent.table(name));
+        if table.is_some() {
+            hidden.insert(name.to_string());
+        }
+        Ok(table)
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.own.table_exist(name)
+            || (!self.hidden.lock().contains(name) && self.parent.table_exist(name))
+    }
+}
@@ -15,47 +15,76 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use futures::future::try_join_all;
+use futures::TryStreamExt;
+use glob::{MatchOptions, Pattern};
+use object_store::memory::InMemory;
+use object_store::path::Path;
 use object_store::ObjectStore;
+use parking_lot::Mutex;
 use url::Url;
 use uuid::Uuid;
 
-use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::exceptions::{PyKeyError, PyUserWarning, PyValueError};
 use pyo3::prelude::*;
 
-use crate::catalog::{PyCatalog, PyTable};
+use crate::bytes_table::{BytesTable, StoredBytes};
+use crate::catalog::{OverlayCatalogProvider, PyCatalog, PyCatalogProvider, PyTable};
 use crate::dataframe::PyDataFrame;
 use crate::dataset::Dataset;
-use crate::errors::{py_datafusion_err, DataFusionError};
+use crate::display::DisplayOptions;
+use crate::errors::DataFusionError;
 use crate::expr::PyExpr;
+use crate::functions;
+use crate::json_array::JsonArrayReadOptions;
+use crate::parquet::{ParquetColumns, ParquetScanOptions};
+use crate::parquet_encryption::{has_encrypted_footer, DecryptingStore, KeyRetriever};
+use crate::parquet_metadata::PyParquetMetadata;
 use crate::physical_plan::PyExecutionPlan;
 use crate::record_batch::PyRecordBatchStream;
+use crate::result_cache::ResultCache;
+use crate::sql::function::plan_create_function;
 use crate::sql::logical::PyLogicalPlan;
+use crate::sql::parser::parse_sql_expr;
 use crate::store::StorageContexts;
 use crate::udaf::PyAggregateUDF;
 use crate::udf::PyScalarUDF;
-use crate::utils::{get_tokio_runtime, wait_for_future};
+use crate::utils::{import_arrow_c_data, wait_for_future, SchemaArg};
 use datafusion::arrow::datatypes::{DataType, Schema};
 use datafusion::arrow::pyarrow::PyArrowType;
 use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::{CatalogList, CatalogProvider, MemoryCatalogList};
 use datafusion::common::FileCompressionType;
-use datafusion::datasource::MemTable;
+use datafusion::datasource::file_format::options::ReadOptions;
+use datafusion::datasource::file_format::parquet::{fetch_parquet_metadata, ParquetFormat};
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
 use datafusion::datasource::TableProvider;
-use datafusion::execution::context::{SessionConfig, SessionContext, TaskContext};
+use datafusion::datasource::{source_as_provider, MemTable};
+use datafusion::execution::context::{SessionConfig, SessionContext, SessionState, TaskContext};
 use datafusion::execution::disk_manager::DiskManagerConfig;
 use datafusion::execution::memory_pool::{FairSpillPool, GreedyMemoryPool, UnboundedMemoryPool};
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::execution::options::ArrowReadOptions;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
-use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::logical_expr::{
+    AggregateFunction, BuiltInWindowFunction, BuiltinScalarFunction, LogicalPlan,
+    LogicalPlanBuilder, SetVariable, Statement, TypeSignature,
+};
+use datafusion::optimizer::optimizer::Optimizer;
 use datafusion::prelude::{
     AvroReadOptions, CsvReadOptions, DataFrame, NdJsonReadOptions, ParquetReadOptions,
 };
-use datafusion_common::ScalarValue;
-use pyo3::types::PyTuple;
-use tokio::task::JoinHandle;
+use datafusion::sql::sqlparser::dialect::dialect_from_str;
+use datafusion_common::{DFSchema, ScalarValue};
+use pyo3::types::{PyBytes, PyDict, PyTuple};
+use strum::IntoEnumIterator;
 
 /// Configuration options for a SessionContext
 #[pyclass(name = "SessionConfig", module = "datafusion", subclass)]
@@ -101,6 +130,8 @@ impl PySessionConfig {
         )
     }
 
+    /// Enable or disable the `information_schema` views, which describe the tables and columns
+    /// of every registered catalog and schema
     fn with_information_schema(&self, enabled: bool) -> Self {
         Self::from(self.config.clone().with_information_schema(enabled))
     }
@@ -145,9 +176,190 @@ impl PySessionConfig {
         Self::from(self.config.clone().with_parquet_pruning(enabled))
     }
 
+    /// Enable or disable reading the Parquet page index, which lets selective predicates skip
+    /// individual data pages within a row group. Enabled by default.
+    fn with_parquet_page_index(&self, enabled: bool) -> Self {
+        let mut config = self.config.clone();
+        config.options_mut().execution.parquet.enable_page_index = enabled;
+        Self::from(config)
+    }
+
+    /// Enable or disable pruning the row groups of Parquet files with their bloom filters for
+    /// the equality and `IN` predicates on a column, by default for the tables registered or
+    /// read with `register_parquet` and `read_parquet` afterwards. Disabled by default.
+    fn with_parquet_bloom_filter_on_read(&self, enabled: bool) -> Self {
+        Self::from(
+            self.config
+                .clone()
+                .with_extension(Arc::new(ParquetBloomFilterOnRead(enabled))),
+        )
+    }
+
+    /// Fetch the last `size` bytes of each Parquet file in a single request, rather than the
+    /// 8 byte footer and then the metadata it points to, saving a round trip per file on high
+    /// latency object stores. Pass `None`, the default, to disable the prefetch.
+    fn with_parquet_metadata_size_hint(&self, size: Option<usize>) -> PyResult<Self> {
+        check_metadata_size_hint(size)?;
+        let mut config = self.config.clone();
+        config.options_mut().execution.parquet.metadata_size_hint = size;
+        Ok(Self::from(config))
+    }
+
+    /// When enabled (the default) unquoted identifiers are converted to lowercase, so
+    /// `SELECT MyColumn` resolves `mycolumn`. Quoted identifiers keep their case either way.
+    /// Disable to resolve unquoted mixed-case identifiers as written.
+    fn with_enable_ident_normalization(&self, enabled: bool) -> Self {
+        let mut config = self.config.clone();
+        config.options_mut().sql_parser.enable_ident_normalization = enabled;
+        Self::from(config)
+    }
+
+    /// When enabled, float literals such as `1.5` are parsed as decimals instead of
+    /// `Float64`, avoiding binary floating point rounding.
+    fn with_parse_float_as_decimal(&self, enabled: bool) -> Self {
+        let mut config = self.config.clone();
+        config.options_mut().sql_parser.parse_float_as_decimal = enabled;
+        Self::from(config)
+    }
+
+    /// Set the dialect used to parse SQL, one of: Generic, MySQL, PostgreSQL, Hive, SQLite,
+    /// Snowflake, Redshift, MsSQL, ClickHouse, BigQuery and Ansi.
+    fn with_sql_dialect(&self, dialect: &str) -> PyResult<Self> {
+        if dialect_from_str(dialect).is_none() {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported SQL dialect: {dialect}. Available dialects: \
+                 Generic, MySQL, PostgreSQL, Hive, SQLite, Snowflake, Redshift, \
+                 MsSQL, ClickHouse, BigQuery, Ansi."
+            )));
+        }
+        let mut config = self.config.clone();
+        config.options_mut().sql_parser.dialect = dialect.to_owned();
+        Ok(Self::from(config))
+    }
+
+    /// Set how `DataFrame.show` and the notebook display render values: a chrono format
+    /// string for timestamps, the number of digits after the decimal point of floats and the
+    /// text printed for nulls. This only changes the display, never the data.
+    #[pyo3(signature = (timestamp_format=None, float_precision=None, null_repr=None))]
+    fn with_display_options(
+        &self,
+        timestamp_format: Option<String>,
+        float_precision: Option<usize>,
+        null_repr: Option<String>,
+    ) -> Self {
+        let options = DisplayOptions {
+            timestamp_format,
+            float_precision,
+            null_repr: null_repr.unwrap_or_default(),
+            ..DisplayOptions::default()
+        };
+        Self::from(self.config.clone().with_extension(Arc::new(options)))
+    }
+
+    /// Set where `DataFrame.sort` and `DataFrame.sort_by` place nulls for keys that don't
+    /// give a `nulls_first`: `"first"` or `"last"`, in ascending and descending order alike
+    fn with_default_null_ordering(&self, ordering: &str) -> PyResult<Self> {
+        let nulls_first = match ordering {
+            "first" => true,
+            "last" => false,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unrecognized null ordering {ordering}, supported values are: first and last"
+                )))
+            }
+        };
+        Ok(Self::from(self.config.clone().with_extension(Arc::new(
+            DefaultNullOrdering { nulls_first },
+        ))))
+    }
+
+    /// Skip the named logical optimizer rules, such as `push_down_filter`, in addition to those
+    /// already disabled. Names no rule has raise a warning.
+    fn with_optimizer_rules_disabled(&self, names: Vec<String>, py: Python) -> PyResult<Self> {
+        let rules = optimizer_rule_names();
+        for name in &names {
+            if !rules.contains(name) {
+                let message = format!(
+                    "There is no optimizer rule named {name}, rules are: {}",
+                    rules.join(", ")
+                );
+                PyErr::warn(py, py.get_type::<PyUserWarning>(), &message, 1)?;
+            }
+        }
+        let mut disabled = match self.config.get_extension::<DisabledOptimizerRules>() {
+            Some(disabled) => disabled.as_ref().clone(),
+            None => DisabledOptimizerRules(Some(vec![])),
+        };
+        if let Some(disabled) = disabled.0.as_mut() {
+            disabled.extend(names);
+        }
+        Ok(Self::from(
+            self.config.clone().with_extension(Arc::new(disabled)),
+        ))
+    }
+
+    /// Enable or disable the logical optimizer as a whole. Disabled, plans are executed as
+    /// they are written, after analysis such as type coercion. Enabling it runs every rule.
+    fn with_optimization(&self, enabled: bool) -> Self {
+        let disabled = DisabledOptimizerRules(if enabled { Some(vec![]) } else { None });
+        Self::from(self.config.clone().with_extension(Arc::new(disabled)))
+    }
+
     fn set(&self, key: &str, value: &str) -> Self {
         Self::from(self.config.clone().set_str(key, value))
     }
+
+    /// Returns the value of a configuration option, such as
+    /// `datafusion.sql_parser.enable_ident_normalization`, as a string
+    fn get(&self, key: &str) -> PyResult<Option<String>> {
+        self.config
+            .options()
+            .entries()
+            .into_iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.value)
+            .ok_or_else(|| PyKeyError::new_err(format!("Unknown configuration option: {key}")))
+    }
+}
+
+/// The null ordering of the sort keys that don't give one, stored as an extension of the config
+pub(crate) struct DefaultNullOrdering {
+    pub(crate) nulls_first: bool,
+}
+
+/// The logical optimizer rules a context skips, stored as an extension of its config. `None`
+/// skips them all
+#[derive(Clone)]
+struct DisabledOptimizerRules(Option<Vec<String>>);
+
+/// The names of the logical optimizer rules, in the order they first run
+fn optimizer_rule_names() -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for rule in Optimizer::new().rules {
+        if !names.iter().any(|name| name == rule.name()) {
+            names.push(rule.name().to_string());
+        }
+    }
+    names
+}
+
+/// Removes the logical optimizer rules the config of `state` disables from it
+pub(crate) fn without_disabled_optimizer_rules(state: SessionState) -> SessionState {
+    match state
+        .config()
+        .get_extension::<DisabledOptimizerRules>()
+        .as_deref()
+    {
+        Some(DisabledOptimizerRules(Some(names))) => state.with_optimizer_rules(
+            Optimizer::new()
+                .rules
+                .into_iter()
+                .filter(|rule| !names.iter().any(|name| name == rule.name()))
+                .collect(),
+        ),
+        Some(DisabledOptimizerRules(None)) => state.with_optimizer_rules(vec![]),
+        None => state,
+    }
 }
 
 /// Runtime options for a SessionContext
@@ -155,58 +367,102 @@ impl PySessionConfig {
 #[derive(Clone)]
 pub(crate) struct PyRuntimeConfig {
     pub(crate) config: RuntimeConfig,
+    /// The size of the memory pool, the pool itself doesn't expose it
+    memory_limit: Option<usize>,
+}
+
+/// The size of the memory pool of a context, stored as an extension of its config
+struct MemoryPoolLimit(usize);
+
+/// The arguments of `register_parquet` that `ParquetReadOptions` doesn't have, all unset by
+/// default
+#[derive(Default)]
+struct ParquetTableOptions<'a> {
+    enable_page_index: Option<bool>,
+    merge_schemas: bool,
+    glob: Option<&'a str>,
+    exclude: Vec<String>,
+    columns: Option<Vec<String>>,
+    metadata_size_hint: Option<usize>,
+    bloom_filter_on_read: Option<bool>,
 }
 
+/// Whether the Parquet tables of a context read the bloom filters of their files by default,
+/// stored as an extension of its config
+struct ParquetBloomFilterOnRead(bool);
+
 #[pymethods]
 impl PyRuntimeConfig {
     #[new]
     fn new() -> Self {
         Self {
             config: RuntimeConfig::default(),
+            memory_limit: None,
         }
     }
 
     fn with_disk_manager_disabled(&self) -> Self {
         let config = self.config.clone();
         let config = config.with_disk_manager(DiskManagerConfig::Disabled);
-        Self { config }
+        Self {
+            config,
+            memory_limit: self.memory_limit,
+        }
     }
 
     fn with_disk_manager_os(&self) -> Self {
         let config = self.config.clone();
         let config = config.with_disk_manager(DiskManagerConfig::NewOs);
-        Self { config }
+        Self {
+            config,
+            memory_limit: self.memory_limit,
+        }
     }
 
     fn with_disk_manager_specified(&self, paths: Vec<String>) -> Self {
         let config = self.config.clone();
         let paths = paths.iter().map(|s| s.into()).collect();
         let config = config.with_disk_manager(DiskManagerConfig::NewSpecified(paths));
-        Self { config }
+        Self {
+            config,
+            memory_limit: self.memory_limit,
+        }
     }
 
     fn with_unbounded_memory_pool(&self) -> Self {
         let config = self.config.clone();
         let config = config.with_memory_pool(Arc::new(UnboundedMemoryPool::default()));
-        Self { config }
+        Self {
+            config,
+            memory_limit: None,
+        }
     }
 
     fn with_fair_spill_pool(&self, size: usize) -> Self {
         let config = self.config.clone();
         let config = config.with_memory_pool(Arc::new(FairSpillPool::new(size)));
-        Self { config }
+        Self {
+            config,
+            memory_limit: Some(size),
+        }
     }
 
     fn with_greedy_memory_pool(&self, size: usize) -> Self {
         let config = self.config.clone();
         let config = config.with_memory_pool(Arc::new(GreedyMemoryPool::new(size)));
-        Self { config }
+        Self {
+            config,
+            memory_limit: Some(size),
+        }
     }
 
     fn with_temp_file_path(&self, path: &str) -> Self {
         let config = self.config.clone();
         let config = config.with_temp_file_path(path);
-        Self { config }
+        Self {
+            config,
+            memory_limit: self.memory_limit,
+        }
     }
 }
 
@@ -229,15 +485,94 @@ impl PySessionContext {
         } else {
             SessionConfig::default().with_information_schema(true)
         };
-        let runtime_config = if let Some(c) = runtime {
-            c.config
+        let (runtime_config, memory_limit) = if let Some(c) = runtime {
+            (c.config, c.memory_limit)
         } else {
-            RuntimeConfig::default()
+            (RuntimeConfig::default(), None)
         };
-        let runtime = Arc::new(RuntimeEnv::new(runtime_config)?);
-        Ok(PySessionContext {
-            ctx: SessionContext::with_config_rt(config, runtime),
-        })
+        let config = match memory_limit {
+            Some(limit) => config.with_extension(Arc::new(MemoryPoolLimit(limit))),
+            None => config,
+        };
+        let config = config.with_extension(Arc::new(ResultCache::default()));
+        // the temporary directories are created up front, name them when that fails, such as
+        // in a read-only container
+        let temp_dirs = match &runtime_config.disk_manager {
+            DiskManagerConfig::NewSpecified(paths) => Some(paths.clone()),
+            _ => None,
+        };
+        let runtime = RuntimeEnv::new(runtime_config).map_err(|e| match temp_dirs {
+            Some(paths) => PyValueError::new_err(format!(
+                "Unable to create a temporary directory in {paths:?}: {e}"
+            )),
+            None => DataFusionError::from(e).into(),
+        })?;
+        let runtime = Arc::new(runtime);
+        let state = SessionState::with_config_rt(config, runtime);
+        let ctx = SessionContext::with_state(without_disabled_optimizer_rules(state));
+        for udf in functions::registered_udfs() {
+            ctx.register_udf(udf);
+        }
+        Ok(PySessionContext { ctx })
+    }
+
+    /// Creates a context that shares the catalogs, object stores, functions and configuration
+    /// of this one, such as one per request of a service. Tables and views registered in the
+    /// new context are only visible to it, while it still sees those of this context.
+    fn clone_session(&self) -> PyResult<Self> {
+        let state = self.ctx.state();
+        let default_catalog = &state.config_options().catalog.default_catalog;
+        let catalogs = Arc::new(MemoryCatalogList::new());
+        for name in state.catalog_list().catalog_names() {
+            if let Some(catalog) = state.catalog_list().catalog(&name) {
+                // The schemas of the default catalog hold the tables registered without a
+                // qualified name, they are overlaid to keep the registrations of the clone apart
+                if &name == default_catalog {
+                    catalogs.register_catalog(name, Arc::new(OverlayCatalogProvider::new(catalog)));
+                } else {
+                    catalogs.register_catalog(name, catalog);
+                }
+            }
+        }
+
+        let config = state
+            .config()
+            .clone()
+            .with_create_default_catalog_and_schema(false)
+            .with_extension(Arc::new(ResultCache::default()));
+        let clone = SessionState::with_config_rt_and_catalog_list(
+            config,
+            state.runtime_env().clone(),
+            catalogs,
+        )
+        .with_physical_optimizer_rules(state.physical_optimizers().to_vec())
+        .with_serializer_registry(state.serializer_registry());
+        let ctx = SessionContext::with_state(without_disabled_optimizer_rules(clone));
+        for udf in state.scalar_functions().values() {
+            ctx.register_udf(udf.as_ref().clone());
+        }
+        for udaf in state.aggregate_functions().values() {
+            ctx.register_udaf(udaf.as_ref().clone());
+        }
+        for udwf in state.window_functions().values() {
+            ctx.register_udwf(udwf.as_ref().clone());
+        }
+        Ok(PySessionContext { ctx })
+    }
+
+    /// Caches the results of `collect` and `collect_single_batch` by the fingerprint of their
+    /// optimized plan, so that collecting an identical plan again returns the cached result.
+    /// The least recently used results are evicted once their size exceeds `max_bytes`, `0`
+    /// disables the cache
+    fn enable_result_cache(&self, max_bytes: usize) -> PyResult<()> {
+        self.result_cache()?.set_max_bytes(max_bytes);
+        Ok(())
+    }
+
+    /// Empties the result cache, such as after the files of a table were changed
+    fn clear_result_cache(&self) -> PyResult<()> {
+        self.result_cache()?.clear();
+        Ok(())
     }
 
     /// Register a an object store with the given name
@@ -276,11 +611,31 @@ impl PySessionContext {
 
     /// Returns a PyDataFrame whose plan corresponds to the SQL statement.
     fn sql(&mut self, query: &str, py: Python) -> PyResult<PyDataFrame> {
+        self.take_catalog_error();
+        let state = self.ctx.state();
+        if let Some(udf) =
+            plan_create_function(query, &state).map_err(|e| self.planning_error(e))?
+        {
+            self.ctx.register_udf(udf);
+            let plan = LogicalPlanBuilder::empty(false).build()?;
+            return Ok(PyDataFrame::new(DataFrame::new(state, plan)));
+        }
         let result = self.ctx.sql(query);
-        let df = wait_for_future(py, result).map_err(DataFusionError::from)?;
+        let df = wait_for_future(py, result).map_err(|e| self.planning_error(e))?;
         Ok(PyDataFrame::new(df))
     }
 
+    /// Parses, plans and optimizes `query` without executing it, returning the schema of its
+    /// output. DDL statements are only planned, so they have no side effects here.
+    fn validate_sql(&self, query: &str, py: Python) -> PyResult<PyArrowType<Schema>> {
+        self.take_catalog_error();
+        let state = self.ctx.state();
+        let plan = wait_for_future(py, state.create_logical_plan(query))
+            .and_then(|plan| state.optimize(&plan))
+            .map_err(|e| self.planning_error(e))?;
+        Ok(PyArrowType(plan.schema().as_ref().into()))
+    }
+
     fn create_dataframe(
         &mut self,
         partitions: PyArrowType<Vec<Vec<RecordBatch>>>,
@@ -289,27 +644,7 @@ impl PySessionContext {
     ) -> PyResult<PyDataFrame> {
         let schema = partitions.0[0][0].schema();
         let table = MemTable::try_new(schema, partitions.0).map_err(DataFusionError::from)?;
-
-        // generate a random (unique) name for this table if none is provided
-        // table name cannot start with numeric digit
-        let table_name = match name {
-            Some(val) => val.to_owned(),
-            None => {
-                "c".to_owned()
-                    + Uuid::new_v4()
-                        .simple()
-                        .encode_lower(&mut Uuid::encode_buffer())
-            }
-        };
-
-        self.ctx
-            .register_table(&*table_name, Arc::new(table))
-            .map_err(DataFusionError::from)?;
-
-        let table = wait_for_future(py, self._table(&table_name)).map_err(DataFusionError::from)?;
-
-        let df = PyDataFrame::new(table);
-        Ok(df)
+        self.memtable_dataframe(table, name, py)
     }
 
     /// Create a DataFrame from an existing logical plan
@@ -317,19 +652,27 @@ impl PySessionContext {
         PyDataFrame::new(DataFrame::new(self.ctx.state(), plan.plan.as_ref().clone()))
     }
 
-    /// Construct datafusion dataframe from Python list
+    /// Construct datafusion dataframe from Python list, with the types of `schema` if given
     #[allow(clippy::wrong_self_convention)]
+    #[pyo3(signature = (data, name=None, schema=None))]
     fn from_pylist(
         &mut self,
         data: PyObject,
         name: Option<&str>,
+        schema: Option<SchemaArg>,
         _py: Python,
     ) -> PyResult<PyDataFrame> {
         Python::with_gil(|py| {
             // Instantiate pyarrow Table object & convert to Arrow Table
             let table_class = py.import("pyarrow")?.getattr("Table")?;
             let args = PyTuple::new(py, &[data]);
-            let table = table_class.call_method1("from_pylist", args)?.into();
+            let kwargs = PyDict::new(py);
+            if let Some(schema) = schema {
+                kwargs.set_item("schema", PyArrowType(schema.0).into_py(py))?;
+            }
+            let table = table_class
+                .call_method("from_pylist", args, Some(kwargs))?
+                .into();
 
             // Convert Arrow Table to datafusion DataFrame
             let df = self.from_arrow_table(table, name, py)?;
@@ -337,19 +680,27 @@ impl PySessionContext {
         })
     }
 
-    /// Construct datafusion dataframe from Python dictionary
+    /// Construct datafusion dataframe from Python dictionary, with the types of `schema` if given
     #[allow(clippy::wrong_self_convention)]
+    #[pyo3(signature = (data, name=None, schema=None))]
     fn from_pydict(
         &mut self,
         data: PyObject,
         name: Option<&str>,
+        schema: Option<SchemaArg>,
         _py: Python,
     ) -> PyResult<PyDataFrame> {
         Python::with_gil(|py| {
             // Instantiate pyarrow Table object & convert to Arrow Table
             let table_class = py.import("pyarrow")?.getattr("Table")?;
             let args = PyTuple::new(py, &[data]);
-            let table = table_class.call_method1("from_pydict", args)?.into();
+            let kwargs = PyDict::new(py);
+            if let Some(schema) = schema {
+                kwargs.set_item("schema", PyArrowType(schema.0).into_py(py))?;
+            }
+            let table = table_class
+                .call_method("from_pydict", args, Some(kwargs))?
+                .into();
 
             // Convert Arrow Table to datafusion DataFrame
             let df = self.from_arrow_table(table, name, py)?;
@@ -378,19 +729,52 @@ impl PySessionContext {
         })
     }
 
-    /// Construct datafusion dataframe from pandas
+    /// Construct datafusion dataframe from any object implementing the Arrow PyCapsule
+    /// interface (`__arrow_c_stream__` or `__arrow_c_array__`), importing it without copying.
+    /// Falls back to pyarrow Tables and lists of RecordBatches otherwise.
+    #[allow(clippy::wrong_self_convention)]
+    #[pyo3(signature = (data, name=None))]
+    fn from_arrow(
+        &mut self,
+        data: &PyAny,
+        name: Option<&str>,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        if let Some((schema, batches)) = import_arrow_c_data(data)? {
+            let table = MemTable::try_new(schema, vec![batches]).map_err(DataFusionError::from)?;
+            return self.memtable_dataframe(table, name, py);
+        }
+        if data.hasattr("to_batches")? {
+            return self.from_arrow_table(data.into(), name, py);
+        }
+        let batches = data.extract::<PyArrowType<Vec<RecordBatch>>>()?;
+        self.create_dataframe(PyArrowType(vec![batches.0]), name, py)
+    }
+
+    /// Construct datafusion dataframe from pandas. With `preserve_index` the index is kept as
+    /// leading columns, one per level of a MultiIndex
     #[allow(clippy::wrong_self_convention)]
+    #[pyo3(signature = (data, name=None, preserve_index=true))]
     fn from_pandas(
         &mut self,
         data: PyObject,
         name: Option<&str>,
+        preserve_index: bool,
         _py: Python,
     ) -> PyResult<PyDataFrame> {
         Python::with_gil(|py| {
             // Instantiate pyarrow Table object & convert to Arrow Table
-            let table_class = py.import("pyarrow")?.getattr("Table")?;
-            let args = PyTuple::new(py, &[data]);
-            let table = table_class.call_method1("from_pandas", args)?.into();
+            let pyarrow = py.import("pyarrow")?;
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("preserve_index", false)?;
+            let mut table =
+                pyarrow
+                    .getattr("Table")?
+                    .call_method("from_pandas", (&data,), Some(kwargs))?;
+            if preserve_index {
+                table = with_pandas_index(pyarrow, data.as_ref(py), table)?;
+            }
+            let table = table.into();
 
             // Convert Arrow Table to datafusion DataFrame
             let df = self.from_arrow_table(table, name, py)?;
@@ -416,6 +800,16 @@ impl PySessionContext {
         })
     }
 
+    /// Register a Python object as a catalog whose schemas and tables are looked up lazily.
+    /// The object must implement `schema_names()`, `table_names(schema)` and
+    /// `table(schema, name)`, the latter returning a `Table`, a `pyarrow.dataset.Dataset` or
+    /// `None` if the table doesn't exist.
+    fn register_catalog_provider(&mut self, name: &str, provider: PyObject) -> PyResult<()> {
+        self.ctx
+            .register_catalog(name, Arc::new(PyCatalogProvider::new(provider)));
+        Ok(())
+    }
+
     fn register_table(&mut self, name: &str, table: &PyTable) -> PyResult<()> {
         self.ctx
             .register_table(name, table.table())
@@ -430,13 +824,29 @@ impl PySessionContext {
         Ok(())
     }
 
-    fn register_record_batches(
-        &mut self,
-        name: &str,
-        partitions: PyArrowType<Vec<Vec<RecordBatch>>>,
-    ) -> PyResult<()> {
-        let schema = partitions.0[0][0].schema();
-        let table = MemTable::try_new(schema, partitions.0)?;
+    /// Registers the plan of `df` under `name`, so later SQL queries and DataFrames can reference
+    /// it like a common table expression. The plan is inlined wherever it is referenced rather than
+    /// materialized. Returns a DataFrame reading from the new name.
+    fn with_cte(&mut self, name: &str, df: PyDataFrame, py: Python) -> PyResult<PyDataFrame> {
+        let view = df.df.as_ref().clone().into_view();
+        self.ctx
+            .register_table(name, view)
+            .map_err(DataFusionError::from)?;
+        self.table(name, py)
+    }
+
+    /// Register record batches as a table. `partitions` is either a list of partitions, each a
+    /// list of RecordBatches, or an object implementing the Arrow PyCapsule interface, which is
+    /// imported as a single partition.
+    fn register_record_batches(&mut self, name: &str, partitions: &PyAny) -> PyResult<()> {
+        let table = match import_arrow_c_data(partitions)? {
+            Some((schema, batches)) => MemTable::try_new(schema, vec![batches])?,
+            None => {
+                let partitions = partitions.extract::<PyArrowType<Vec<Vec<RecordBatch>>>>()?;
+                let schema = partitions.0[0][0].schema();
+                MemTable::try_new(schema, partitions.0)?
+            }
+        };
         self.ctx
             .register_table(name, Arc::new(table))
             .map_err(DataFusionError::from)?;
@@ -449,19 +859,40 @@ impl PySessionContext {
                         file_extension=".parquet",
                         skip_metadata=true,
                         schema=None,
-                        file_sort_order=None))]
+                        file_sort_order=None,
+                        enable_page_index=None,
+                        merge_schemas=false,
+                        glob=None,
+                        exclude=vec![],
+                        columns=None,
+                        metadata_size_hint=None,
+                        decryption_key=None,
+                        bloom_filter_on_read=None))]
     fn register_parquet(
         &mut self,
         name: &str,
-        path: &str,
+        path: TablePaths,
         table_partition_cols: Vec<(String, String)>,
         parquet_pruning: bool,
         file_extension: &str,
         skip_metadata: bool,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         file_sort_order: Option<Vec<Vec<PyExpr>>>,
+        enable_page_index: Option<bool>,
+        merge_schemas: bool,
+        glob: Option<&str>,
+        exclude: Vec<String>,
+        columns: Option<Vec<String>>,
+        metadata_size_hint: Option<usize>,
+        decryption_key: Option<&PyAny>,
+        bloom_filter_on_read: Option<bool>,
         py: Python,
     ) -> PyResult<()> {
+        check_metadata_size_hint(metadata_size_hint)?;
+        let path = match decryption_key {
+            Some(key) => self.decrypted_paths(path, key)?,
+            None => path,
+        };
         let mut options = ParquetReadOptions::default()
             .table_partition_cols(convert_table_partition_cols(table_partition_cols)?)
             .parquet_pruning(parquet_pruning)
@@ -473,10 +904,16 @@ impl PySessionContext {
             .into_iter()
             .map(|e| e.into_iter().map(|f| f.into()).collect())
             .collect();
-
-        let result = self.ctx.register_parquet(name, path, options);
-        wait_for_future(py, result).map_err(DataFusionError::from)?;
-        Ok(())
+        let table_options = ParquetTableOptions {
+            enable_page_index,
+            merge_schemas,
+            glob,
+            exclude,
+            columns,
+            metadata_size_hint,
+            bloom_filter_on_read,
+        };
+        self.register_parquet_options(name, path, options, table_options, py)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -487,41 +924,35 @@ impl PySessionContext {
                         delimiter=",",
                         schema_infer_max_records=1000,
                         file_extension=".csv",
-                        file_compression_type=None))]
+                        file_compression_type=None,
+                        glob=None,
+                        exclude=vec![]))]
     fn register_csv(
         &mut self,
         name: &str,
         path: PathBuf,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         has_header: bool,
         delimiter: &str,
         schema_infer_max_records: usize,
         file_extension: &str,
         file_compression_type: Option<String>,
+        glob: Option<&str>,
+        exclude: Vec<String>,
         py: Python,
     ) -> PyResult<()> {
         let path = path
             .to_str()
             .ok_or_else(|| PyValueError::new_err("Unable to convert path to a string"))?;
-        let delimiter = delimiter.as_bytes();
-        if delimiter.len() != 1 {
-            return Err(PyValueError::new_err(
-                "Delimiter must be a single character",
-            ));
-        }
-
         let mut options = CsvReadOptions::new()
             .has_header(has_header)
-            .delimiter(delimiter[0])
+            .delimiter(parse_delimiter(delimiter)?)
             .schema_infer_max_records(schema_infer_max_records)
             .file_extension(file_extension)
             .file_compression_type(parse_file_compression_type(file_compression_type)?);
         options.schema = schema.as_ref().map(|x| &x.0);
 
-        let result = self.ctx.register_csv(name, path, options);
-        wait_for_future(py, result).map_err(DataFusionError::from)?;
-
-        Ok(())
+        self.register_csv_options(name, path, options, glob, &exclude, py)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -531,16 +962,22 @@ impl PySessionContext {
                         schema_infer_max_records=1000,
                         file_extension=".json",
                         table_partition_cols=vec![],
-                        file_compression_type=None))]
+                        file_compression_type=None,
+                        glob=None,
+                        exclude=vec![],
+                        format="ndjson"))]
     fn register_json(
         &mut self,
         name: &str,
         path: PathBuf,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         schema_infer_max_records: usize,
         file_extension: &str,
         table_partition_cols: Vec<(String, String)>,
         file_compression_type: Option<String>,
+        glob: Option<&str>,
+        exclude: Vec<String>,
+        format: &str,
         py: Python,
     ) -> PyResult<()> {
         let path = path
@@ -553,11 +990,8 @@ impl PySessionContext {
         options.schema_infer_max_records = schema_infer_max_records;
         options.file_extension = file_extension;
         options.schema = schema.as_ref().map(|x| &x.0);
-
-        let result = self.ctx.register_json(name, path, options);
-        wait_for_future(py, result).map_err(DataFusionError::from)?;
-
-        Ok(())
+        let array = parse_json_format(format)?;
+        self.register_json_options(name, path, options, array, glob, &exclude, py)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -566,15 +1000,19 @@ impl PySessionContext {
                         schema=None,
                         file_extension=".avro",
                         table_partition_cols=vec![],
-                        infinite=false))]
+                        infinite=false,
+                        glob=None,
+                        exclude=vec![]))]
     fn register_avro(
         &mut self,
         name: &str,
         path: PathBuf,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         file_extension: &str,
         table_partition_cols: Vec<(String, String)>,
         infinite: bool,
+        glob: Option<&str>,
+        exclude: Vec<String>,
         py: Python,
     ) -> PyResult<()> {
         let path = path
@@ -587,12 +1025,126 @@ impl PySessionContext {
         options.file_extension = file_extension;
         options.schema = schema.as_ref().map(|x| &x.0);
 
-        let result = self.ctx.register_avro(name, path, options);
+        self.register_avro_options(name, path, options, glob, &exclude, py)
+    }
+
+    /// Registers the Arrow IPC files in the `file` format at `path` as a table. Unless a
+    /// `schema` is given, all the files of a directory must have the same schema, as the IPC
+    /// reader can't fill in the columns missing from a file.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (name,
+                        path,
+                        schema=None,
+                        file_extension=".arrow",
+                        table_partition_cols=vec![],
+                        glob=None,
+                        exclude=vec![]))]
+    fn register_ipc(
+        &mut self,
+        name: &str,
+        path: PathBuf,
+        schema: Option<SchemaArg>,
+        file_extension: &str,
+        table_partition_cols: Vec<(String, String)>,
+        glob: Option<&str>,
+        exclude: Vec<String>,
+        py: Python,
+    ) -> PyResult<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| PyValueError::new_err("Unable to convert path to a string"))?;
+
+        let mut options = ArrowReadOptions::default()
+            .table_partition_cols(convert_table_partition_cols(table_partition_cols)?);
+        options.file_extension = file_extension;
+        options.schema = schema.as_ref().map(|x| &x.0);
+
+        let is_directory = ListingTableUrl::parse(path)?.as_str().ends_with('/');
+        let check_schemas = is_directory && options.table_partition_cols.is_empty();
+        if glob.is_some() || !exclude.is_empty() || check_schemas {
+            let table = self.matching_files_table(
+                path,
+                &options,
+                options.schema,
+                glob,
+                &exclude,
+                false,
+                py,
+            )?;
+            self.ctx.register_table(name, table)?;
+            return Ok(());
+        }
+        let result = self.ctx.register_arrow(name, path, options);
         wait_for_future(py, result).map_err(DataFusionError::from)?;
 
         Ok(())
     }
 
+    /// Registers the files at `path` as a table, choosing the reader from their file extension,
+    /// or from `format` when given. A directory is read with the reader matching the extension
+    /// of its files, which must all share one.
+    #[pyo3(signature = (name, path, format=None))]
+    fn register(
+        &mut self,
+        name: &str,
+        path: PathBuf,
+        format: Option<&str>,
+        py: Python,
+    ) -> PyResult<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| PyValueError::new_err("Unable to convert path to a string"))?;
+        let is_directory = ListingTableUrl::parse(path)?.as_str().ends_with('/');
+        let extension = if is_directory {
+            match format {
+                Some(_) => None,
+                None => Some(self.directory_extension(path, py)?),
+            }
+        } else {
+            Some(file_extension_of(path).to_owned())
+        };
+        let format = match format {
+            Some(format) => parse_table_format(format)?,
+            None => {
+                let extension = extension.as_deref().unwrap_or_default();
+                format_from_extension(extension).ok_or_else(|| {
+                    PyValueError::new_err(format!(
+                        "Can't detect the format of {path} from the file extension \
+                         '{extension}', pass format as one of: parquet, csv, json, avro"
+                    ))
+                })?
+            }
+        };
+        let file_extension = extension.unwrap_or_else(|| format!(".{format}"));
+        let file_extension = file_extension.as_str();
+
+        match format {
+            "parquet" => {
+                let options = ParquetReadOptions {
+                    file_extension,
+                    ..Default::default()
+                };
+                let path = TablePaths::Path(path.to_owned());
+                self.register_parquet_options(name, path, options, Default::default(), py)
+            }
+            "csv" => {
+                let options = CsvReadOptions::new().file_extension(file_extension);
+                self.register_csv_options(name, path, options, None, &[], py)
+            }
+            "json" => {
+                let options = NdJsonReadOptions::default().file_extension(file_extension);
+                self.register_json_options(name, path, options, false, None, &[], py)
+            }
+            _ => {
+                let options = AvroReadOptions {
+                    file_extension,
+                    ..Default::default()
+                };
+                self.register_avro_options(name, path, options, None, &[], py)
+            }
+        }
+    }
+
     // Registers a PyArrow.Dataset
     fn register_dataset(&self, name: &str, dataset: &PyAny, py: Python) -> PyResult<()> {
         let table: Arc<dyn TableProvider> = Arc::new(Dataset::new(dataset, py)?);
@@ -604,9 +1156,23 @@ impl PySessionContext {
         Ok(())
     }
 
-    fn register_udf(&mut self, udf: PyScalarUDF) -> PyResult<()> {
-        self.ctx.register_udf(udf.function);
-        Ok(())
+    /// Registers `udf` for use in SQL and returns it, so it can also be called to build expressions
+    fn register_udf(&mut self, udf: PyScalarUDF) -> PyResult<PyScalarUDF> {
+        self.ctx.register_udf(udf.function.clone());
+        Ok(udf)
+    }
+
+    /// Looks up a registered scalar UDF by name. Calling it with expressions builds a call to it,
+    /// the same as calling it by name in SQL.
+    fn udf(&self, name: &str) -> PyResult<PyScalarUDF> {
+        match self.ctx.state().scalar_functions().get(name) {
+            Some(udf) => Ok(PyScalarUDF {
+                function: udf.as_ref().clone(),
+            }),
+            None => Err(PyKeyError::new_err(format!(
+                "Scalar UDF with name {name} doesn't exist."
+            ))),
+        }
     }
 
     fn register_udaf(&mut self, udaf: PyAggregateUDF) -> PyResult<()> {
@@ -614,6 +1180,61 @@ impl PySessionContext {
         Ok(())
     }
 
+    /// Returns the scalar, aggregate and window functions available in this context, both
+    /// built-in and user registered, along with the argument types they accept. The UDFs every
+    /// context registers count as built-in.
+    fn functions(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.ctx.state();
+        let builtin_udfs: HashSet<String> = functions::registered_udfs()
+            .into_iter()
+            .map(|udf| udf.name)
+            .collect();
+        let mut functions = Vec::new();
+        for fun in BuiltinScalarFunction::iter() {
+            let signature = fun.signature().type_signature;
+            functions.push((fun.to_string(), "scalar", false, signature));
+        }
+        for fun in AggregateFunction::iter() {
+            let signature = fun.signature().type_signature;
+            functions.push((
+                fun.to_string().to_lowercase(),
+                "aggregate",
+                false,
+                signature,
+            ));
+        }
+        for fun in BuiltInWindowFunction::iter() {
+            let signature = fun.signature().type_signature;
+            functions.push((fun.to_string().to_lowercase(), "window", false, signature));
+        }
+        for udf in state.scalar_functions().values() {
+            let signature = udf.signature.type_signature.clone();
+            let user_defined = !builtin_udfs.contains(&udf.name);
+            functions.push((udf.name.clone(), "scalar", user_defined, signature));
+        }
+        for udaf in state.aggregate_functions().values() {
+            let signature = udaf.signature.type_signature.clone();
+            functions.push((udaf.name.clone(), "aggregate", true, signature));
+        }
+        for udwf in state.window_functions().values() {
+            let signature = udwf.signature.type_signature.clone();
+            functions.push((udwf.name.clone(), "window", true, signature));
+        }
+        functions.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+
+        functions
+            .into_iter()
+            .map(|(name, kind, user_defined, signature)| {
+                let info = PyDict::new(py);
+                info.set_item("name", name)?;
+                info.set_item("kind", kind)?;
+                info.set_item("user_defined", user_defined)?;
+                info.set_item("signatures", signature_strings(&signature))?;
+                Ok(info.into())
+            })
+            .collect()
+    }
+
     #[pyo3(signature = (name="datafusion"))]
     fn catalog(&self, name: &str) -> PyResult<PyCatalog> {
         match self.ctx.catalog(name) {
@@ -625,13 +1246,68 @@ impl PySessionContext {
         }
     }
 
-    fn tables(&self) -> HashSet<String> {
-        #[allow(deprecated)]
-        self.ctx.tables().unwrap()
+    /// Returns the names of all registered catalogs
+    fn catalogs(&self) -> Vec<String> {
+        let mut names = self.ctx.catalog_names();
+        names.sort();
+        names
+    }
+
+    /// Returns the names of the schemas in a catalog, the default catalog if none is given
+    #[pyo3(signature = (catalog=None))]
+    fn schemas(&self, catalog: Option<&str>) -> PyResult<Vec<String>> {
+        let mut names = self.catalog_provider(catalog)?.schema_names();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the sorted names of the tables and views in a schema. The default catalog and
+    /// schema are used if none are given
+    #[pyo3(signature = (catalog=None, schema=None))]
+    fn tables(&self, catalog: Option<&str>, schema: Option<&str>) -> PyResult<Vec<String>> {
+        let provider = self.catalog_provider(catalog)?;
+        let schema = match schema {
+            Some(name) => name.to_owned(),
+            None => self
+                .ctx
+                .copied_config()
+                .options()
+                .catalog
+                .default_schema
+                .clone(),
+        };
+        match provider.schema(&schema) {
+            Some(schema) => {
+                let mut names = schema.table_names();
+                names.sort();
+                Ok(names)
+            }
+            None => Err(PyKeyError::new_err(format!(
+                "Schema with name {schema} doesn't exist."
+            ))),
+        }
+    }
+
+    /// Makes the catalog `name` the one unqualified table names resolve against
+    fn set_default_catalog(&self, name: &str, py: Python) -> PyResult<()> {
+        self.catalog_provider(Some(name))?;
+        self.set_option("datafusion.catalog.default_catalog", name, py)
+    }
+
+    /// Makes the schema `name` of the default catalog the one unqualified table names resolve
+    /// against
+    fn set_default_schema(&self, name: &str, py: Python) -> PyResult<()> {
+        if self.catalog_provider(None)?.schema(name).is_none() {
+            return Err(PyKeyError::new_err(format!(
+                "Schema with name {name} doesn't exist."
+            )));
+        }
+        self.set_option("datafusion.catalog.default_schema", name, py)
     }
 
     fn table(&self, name: &str, py: Python) -> PyResult<PyDataFrame> {
-        let x = wait_for_future(py, self.ctx.table(name)).map_err(DataFusionError::from)?;
+        self.take_catalog_error();
+        let x = wait_for_future(py, self.ctx.table(name)).map_err(|e| self.planning_error(e))?;
         Ok(PyDataFrame::new(x))
     }
 
@@ -643,20 +1319,35 @@ impl PySessionContext {
         Ok(PyDataFrame::new(self.ctx.read_empty()?))
     }
 
+    /// The number of bytes currently reserved in the memory pool, and the size of the pool when
+    /// it is bounded, as a dict with the keys `reserved` and `limit`
+    fn memory_pool_usage(&self, py: Python) -> PyResult<PyObject> {
+        let limit = self
+            .ctx
+            .copied_config()
+            .get_extension::<MemoryPoolLimit>()
+            .map(|limit| limit.0);
+        let usage = PyDict::new(py);
+        usage.set_item("reserved", self.ctx.runtime_env().memory_pool.reserved())?;
+        usage.set_item("limit", limit)?;
+        Ok(usage.into())
+    }
+
     fn session_id(&self) -> String {
         self.ctx.session_id()
     }
 
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (path, schema=None, schema_infer_max_records=1000, file_extension=".json", table_partition_cols=vec![], file_compression_type=None))]
+    #[pyo3(signature = (path, schema=None, schema_infer_max_records=1000, file_extension=".json", table_partition_cols=vec![], file_compression_type=None, format="ndjson"))]
     fn read_json(
         &mut self,
         path: PathBuf,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         schema_infer_max_records: usize,
         file_extension: &str,
         table_partition_cols: Vec<(String, String)>,
         file_compression_type: Option<String>,
+        format: &str,
         py: Python,
     ) -> PyResult<PyDataFrame> {
         let path = path
@@ -667,14 +1358,9 @@ impl PySessionContext {
             .file_compression_type(parse_file_compression_type(file_compression_type)?);
         options.schema_infer_max_records = schema_infer_max_records;
         options.file_extension = file_extension;
-        let df = if let Some(schema) = schema {
-            options.schema = Some(&schema.0);
-            let result = self.ctx.read_json(path, options);
-            wait_for_future(py, result).map_err(DataFusionError::from)?
-        } else {
-            let result = self.ctx.read_json(path, options);
-            wait_for_future(py, result).map_err(DataFusionError::from)?
-        };
+        options.schema = schema.as_ref().map(|x| &x.0);
+        let array = parse_json_format(format)?;
+        let df = self.read_json_options(path, options, array, py)?;
         Ok(PyDataFrame::new(df))
     }
 
@@ -691,7 +1377,7 @@ impl PySessionContext {
     fn read_csv(
         &self,
         path: PathBuf,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         has_header: bool,
         delimiter: &str,
         schema_infer_max_records: usize,
@@ -704,31 +1390,16 @@ impl PySessionContext {
             .to_str()
             .ok_or_else(|| PyValueError::new_err("Unable to convert path to a string"))?;
 
-        let delimiter = delimiter.as_bytes();
-        if delimiter.len() != 1 {
-            return Err(PyValueError::new_err(
-                "Delimiter must be a single character",
-            ));
-        };
-
         let mut options = CsvReadOptions::new()
             .has_header(has_header)
-            .delimiter(delimiter[0])
+            .delimiter(parse_delimiter(delimiter)?)
             .schema_infer_max_records(schema_infer_max_records)
             .file_extension(file_extension)
             .table_partition_cols(convert_table_partition_cols(table_partition_cols)?)
             .file_compression_type(parse_file_compression_type(file_compression_type)?);
-
-        if let Some(py_schema) = schema {
-            options.schema = Some(&py_schema.0);
-            let result = self.ctx.read_csv(path, options);
-            let df = PyDataFrame::new(wait_for_future(py, result).map_err(DataFusionError::from)?);
-            Ok(df)
-        } else {
-            let result = self.ctx.read_csv(path, options);
-            let df = PyDataFrame::new(wait_for_future(py, result).map_err(DataFusionError::from)?);
-            Ok(df)
-        }
+        options.schema = schema.as_ref().map(|x| &x.0);
+        let df = self.read_csv_options(path, options, py)?;
+        Ok(PyDataFrame::new(df))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -739,18 +1410,35 @@ impl PySessionContext {
         file_extension=".parquet",
         skip_metadata=true,
         schema=None,
-        file_sort_order=None))]
+        file_sort_order=None,
+        enable_page_index=None,
+        merge_schemas=false,
+        metadata_size_hint=None,
+        filter=None,
+        decryption_key=None,
+        bloom_filter_on_read=None))]
     fn read_parquet(
         &self,
-        path: &str,
+        path: TablePaths,
         table_partition_cols: Vec<(String, String)>,
         parquet_pruning: bool,
         file_extension: &str,
         skip_metadata: bool,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         file_sort_order: Option<Vec<Vec<PyExpr>>>,
+        enable_page_index: Option<bool>,
+        merge_schemas: bool,
+        metadata_size_hint: Option<usize>,
+        filter: Option<&str>,
+        decryption_key: Option<&PyAny>,
+        bloom_filter_on_read: Option<bool>,
         py: Python,
     ) -> PyResult<PyDataFrame> {
+        check_metadata_size_hint(metadata_size_hint)?;
+        let path = match decryption_key {
+            Some(key) => self.decrypted_paths(path, key)?,
+            None => path,
+        };
         let mut options = ParquetReadOptions::default()
             .table_partition_cols(convert_table_partition_cols(table_partition_cols)?)
             .parquet_pruning(parquet_pruning)
@@ -762,10 +1450,26 @@ impl PySessionContext {
             .into_iter()
             .map(|e| e.into_iter().map(|f| f.into()).collect())
             .collect();
+        let table_options = ParquetTableOptions {
+            enable_page_index,
+            merge_schemas,
+            metadata_size_hint,
+            bloom_filter_on_read,
+            ..Default::default()
+        };
+        let df = self.read_parquet_options(path, options, table_options, py)?;
+        Ok(PyDataFrame::new(self.with_filter(df, filter)?))
+    }
 
-        let result = self.ctx.read_parquet(path, options);
-        let df = PyDataFrame::new(wait_for_future(py, result).map_err(DataFusionError::from)?);
-        Ok(df)
+    /// Read the footer metadata of the Parquet file at `path` without reading its data
+    fn read_parquet_metadata(&self, path: &str, py: Python) -> PyResult<PyParquetMetadata> {
+        let url = ListingTableUrl::parse(path)?;
+        let store = self.ctx.runtime_env().object_store(&url)?;
+        let metadata = wait_for_future(py, async {
+            let meta = store.head(url.prefix()).await?;
+            fetch_parquet_metadata(store.as_ref(), &meta, None).await
+        })?;
+        Ok(PyParquetMetadata::new(metadata))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -773,7 +1477,7 @@ impl PySessionContext {
     fn read_avro(
         &self,
         path: &str,
-        schema: Option<PyArrowType<Schema>>,
+        schema: Option<SchemaArg>,
         table_partition_cols: Vec<(String, String)>,
         file_extension: &str,
         py: Python,
@@ -792,6 +1496,94 @@ impl PySessionContext {
         Ok(PyDataFrame::new(df))
     }
 
+    #[pyo3(signature = (path, schema=None, table_partition_cols=vec![], file_extension=".arrow"))]
+    fn read_ipc(
+        &self,
+        path: &str,
+        schema: Option<SchemaArg>,
+        table_partition_cols: Vec<(String, String)>,
+        file_extension: &str,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        let mut options = ArrowReadOptions::default()
+            .table_partition_cols(convert_table_partition_cols(table_partition_cols)?);
+        options.file_extension = file_extension;
+        options.schema = schema.as_ref().map(|x| &x.0);
+        let result = self.ctx.read_arrow(path, options);
+        let df = wait_for_future(py, result).map_err(DataFusionError::from)?;
+        Ok(PyDataFrame::new(df))
+    }
+
+    /// Reads the bytes of a Parquet file held in memory, `data` being any object supporting the
+    /// buffer protocol
+    #[pyo3(signature = (data, schema=None, skip_metadata=true))]
+    fn read_parquet_bytes(
+        &self,
+        data: &PyAny,
+        schema: Option<SchemaArg>,
+        skip_metadata: bool,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        let bytes = self.put_bytes(data, ".parquet", py)?;
+        self.read_bytes(bytes, |path| {
+            let mut options = ParquetReadOptions::default().skip_metadata(skip_metadata);
+            options.schema = schema.as_ref().map(|x| &x.0);
+            let path = TablePaths::Path(path.to_string());
+            self.read_parquet_options(path, options, ParquetTableOptions::default(), py)
+        })
+    }
+
+    /// Reads the bytes of a CSV file held in memory, see `read_parquet_bytes`
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        data,
+        schema=None,
+        has_header=true,
+        delimiter=",",
+        schema_infer_max_records=1000,
+        file_compression_type=None))]
+    fn read_csv_bytes(
+        &self,
+        data: &PyAny,
+        schema: Option<SchemaArg>,
+        has_header: bool,
+        delimiter: &str,
+        schema_infer_max_records: usize,
+        file_compression_type: Option<String>,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        let mut options = CsvReadOptions::new()
+            .has_header(has_header)
+            .delimiter(parse_delimiter(delimiter)?)
+            .schema_infer_max_records(schema_infer_max_records)
+            .file_compression_type(parse_file_compression_type(file_compression_type)?);
+        options.schema = schema.as_ref().map(|x| &x.0);
+        let bytes = self.put_bytes(data, ".csv", py)?;
+        self.read_bytes(bytes, |path| self.read_csv_options(path, options, py))
+    }
+
+    /// Reads the bytes of a JSON file held in memory, see `read_parquet_bytes`
+    #[pyo3(signature = (data, schema=None, schema_infer_max_records=1000, file_compression_type=None, format="ndjson"))]
+    fn read_json_bytes(
+        &mut self,
+        data: &PyAny,
+        schema: Option<SchemaArg>,
+        schema_infer_max_records: usize,
+        file_compression_type: Option<String>,
+        format: &str,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        let mut options = NdJsonReadOptions::default()
+            .file_compression_type(parse_file_compression_type(file_compression_type)?);
+        options.schema_infer_max_records = schema_infer_max_records;
+        options.schema = schema.as_ref().map(|x| &x.0);
+        let array = parse_json_format(format)?;
+        let bytes = self.put_bytes(data, ".json", py)?;
+        self.read_bytes(bytes, |path| {
+            self.read_json_options(path, options, array, py)
+        })
+    }
+
     fn read_table(&self, table: &PyTable) -> PyResult<PyDataFrame> {
         let df = self
             .ctx
@@ -824,21 +1616,798 @@ impl PySessionContext {
         part: usize,
         py: Python,
     ) -> PyResult<PyRecordBatchStream> {
-        let ctx: TaskContext = TaskContext::from(&self.ctx.state());
-        // create a Tokio runtime to run the async code
-        let rt = &get_tokio_runtime(py).0;
-        let plan = plan.plan.clone();
-        let fut: JoinHandle<datafusion_common::Result<SendableRecordBatchStream>> =
-            rt.spawn(async move { plan.execute(part, Arc::new(ctx)) });
-        let stream = wait_for_future(py, fut).map_err(py_datafusion_err)?;
-        Ok(PyRecordBatchStream::new(stream?))
+        plan.execute_partition(part, TaskContext::from(&self.ctx.state()), py)
     }
 }
 
 impl PySessionContext {
+    /// Returns the result cache installed in the config of the context on its creation
+    fn result_cache(&self) -> PyResult<Arc<ResultCache>> {
+        self.ctx
+            .state()
+            .config()
+            .get_extension::<ResultCache>()
+            .ok_or_else(|| {
+                DataFusionError::Common(
+                    "The context has no result cache, it wasn't created by SessionContext()"
+                        .to_string(),
+                )
+                .into()
+            })
+    }
+
     async fn _table(&self, name: &str) -> datafusion_common::Result<DataFrame> {
         self.ctx.table(name).await
     }
+
+    /// Filters `df` by the SQL predicate `filter`, whose columns are resolved against the
+    /// schema of `df`. The predicate is pushed down to the scan, so files, row groups and
+    /// pages it rules out are skipped.
+    fn with_filter(&self, df: DataFrame, filter: Option<&str>) -> PyResult<DataFrame> {
+        let filter = match filter {
+            Some(filter) => filter,
+            None => return Ok(df),
+        };
+        let predicate = parse_sql_expr(filter, df.schema(), &self.ctx.state(), false)?;
+        Ok(df.filter(predicate)?)
+    }
+
+    /// Registers the table under the given name, or a random unique one, and returns it as a
+    /// DataFrame
+    fn memtable_dataframe(
+        &mut self,
+        table: MemTable,
+        name: Option<&str>,
+        py: Python,
+    ) -> PyResult<PyDataFrame> {
+        // generate a random (unique) name for this table if none is provided
+        // table name cannot start with numeric digit
+        let table_name = match name {
+            Some(val) => val.to_owned(),
+            None => {
+                "c".to_owned()
+                    + Uuid::new_v4()
+                        .simple()
+                        .encode_lower(&mut Uuid::encode_buffer())
+            }
+        };
+
+        self.ctx
+            .register_table(&*table_name, Arc::new(table))
+            .map_err(DataFusionError::from)?;
+
+        let table = wait_for_future(py, self._table(&table_name)).map_err(DataFusionError::from)?;
+
+        let df = PyDataFrame::new(table);
+        Ok(df)
+    }
+
+    /// Creates a table over exactly the given files. When no schema is given the schemas of
+    /// the files must be equal, unless `merge_schemas` is true, in which case they are merged.
+    /// The CSV, JSON and Avro files pass true, as DataFusion merges the schemas of the files of
+    /// a directory. The Arrow IPC files, which can't be read with a merged schema, pass false,
+    /// as do the Parquet files by default, for which the error suggests merging.
+    fn files_table(
+        &self,
+        urls: Vec<ListingTableUrl>,
+        listing_options: ListingOptions,
+        schema: Option<&Schema>,
+        merge_schemas: bool,
+        py: Python,
+    ) -> PyResult<Arc<dyn TableProvider>> {
+        if urls.is_empty() {
+            return Err(PyValueError::new_err("At least one file is required"));
+        }
+        let state = self.ctx.state();
+        let schema = match schema {
+            Some(schema) => Arc::new(schema.clone()),
+            None => {
+                let schemas = urls
+                    .iter()
+                    .map(|url| listing_options.infer_schema(&state, url));
+                let schemas = wait_for_future(py, try_join_all(schemas))?;
+                if merge_schemas {
+                    let schemas = schemas.iter().map(|schema| schema.as_ref().clone());
+                    Arc::new(Schema::try_merge(schemas).map_err(DataFusionError::from)?)
+                } else {
+                    for (url, schema) in urls.iter().zip(&schemas).skip(1) {
+                        if schema.fields() != schemas[0].fields() {
+                            // only register_parquet and read_parquet have the option
+                            let format = listing_options.format.as_any();
+                            let hint = if format.is::<ParquetFormat>() {
+                                ", pass merge_schemas=True to merge them"
+                            } else {
+                                ""
+                            };
+                            return Err(PyValueError::new_err(format!(
+                                "The schema of {url} differs from the schema of {}{hint}",
+                                urls[0]
+                            )));
+                        }
+                    }
+                    schemas[0].clone()
+                }
+            }
+        };
+        let config = ListingTableConfig::new_with_multi_paths(urls)
+            .with_listing_options(listing_options)
+            .with_schema(schema);
+        Ok(Arc::new(ListingTable::try_new(config)?))
+    }
+
+    /// Copies the buffer `data` into a new file of the in-memory object store of this context,
+    /// registering the store on first use. The file is deleted when the returned value is.
+    fn put_bytes(&self, data: &PyAny, file_extension: &str, py: Python) -> PyResult<StoredBytes> {
+        let data = py.get_type::<PyBytes>().call1((data,))?;
+        let data = data.downcast::<PyBytes>()?.as_bytes().to_vec();
+        let store = self.memory_store()?;
+
+        let location = Path::from(format!("{}{file_extension}", Uuid::new_v4().simple()));
+        wait_for_future(py, store.put(&location, data.into()))
+            .map_err(datafusion_common::DataFusionError::from)?;
+        Ok(StoredBytes::new(store, location))
+    }
+
+    /// Reads the file held in memory `bytes` with `read`, which is given its URL. The file is
+    /// kept in the store for as long as the returned DataFrame, or a DataFrame or stream made
+    /// from it, refers to it.
+    fn read_bytes(
+        &self,
+        bytes: StoredBytes,
+        read: impl FnOnce(&str) -> PyResult<DataFrame>,
+    ) -> PyResult<PyDataFrame> {
+        let df = read(&format!("{MEMORY_STORE_URL}{}", bytes.location()))?;
+        // the table scanned is wrapped rather than the view, whose plan would be nested
+        let table = match df.logical_plan() {
+            LogicalPlan::TableScan(scan)
+                if scan.projection.is_none() && scan.filters.is_empty() =>
+            {
+                Some(source_as_provider(&scan.source)?)
+            }
+            _ => None,
+        };
+        let table = BytesTable::new(table.unwrap_or_else(|| df.into_view()), bytes);
+        let df = self.ctx.read_table(Arc::new(table))?;
+        Ok(PyDataFrame::new(df))
+    }
+
+    /// Returns the in-memory object store of this context, registering it on first use
+    fn memory_store(&self) -> PyResult<Arc<dyn ObjectStore>> {
+        let url = ObjectStoreUrl::parse(MEMORY_STORE_URL)?;
+        let runtime = self.ctx.runtime_env();
+        Ok(runtime.object_store(&url).unwrap_or_else(|_| {
+            let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+            runtime.register_object_store(url.as_ref(), store.clone());
+            store
+        }))
+    }
+
+    /// Returns the paths of `path` in object stores decrypting the Parquet files of the stores
+    /// `path` is in when they are read. `key` is the footer key or a function returning the key
+    /// matching the key metadata of the footer or of a column
+    fn decrypted_paths(&self, path: TablePaths, key: &PyAny) -> PyResult<TablePaths> {
+        let footer_key = match key.is_callable() {
+            true => None,
+            false => Some(key.extract::<&[u8]>().map_err(|_| {
+                PyValueError::new_err("decryption_key must be bytes or a function returning bytes")
+            })?),
+        }
+        .map(|key| key.to_vec());
+        let retriever: PyObject = key.into();
+        let keys: Mutex<HashMap<Vec<u8>, Vec<u8>>> = Mutex::new(HashMap::new());
+        let key: Arc<KeyRetriever> = Arc::new(move |column, key_metadata| {
+            match (&footer_key, column) {
+                (Some(footer_key), None) => return Ok(footer_key.clone()),
+                (Some(_), Some(column)) => {
+                    return Err(DataFusionError::Common(format!(
+                        "column {} is encrypted with its own key, pass a function returning the \
+                         key of each column as decryption_key",
+                        column.join(".")
+                    )))
+                }
+                (None, _) => {}
+            }
+            if let Some(key) = keys.lock().get(key_metadata) {
+                return Ok(key.clone());
+            }
+            let value = Python::with_gil(|py| {
+                retriever
+                    .call1(py, (PyBytes::new(py, key_metadata),))?
+                    .extract::<Vec<u8>>(py)
+            })?;
+            keys.lock().insert(key_metadata.to_vec(), value.clone());
+            Ok(value)
+        });
+
+        // the files of each object store are read through a store decrypting them, which has
+        // the same paths
+        let runtime = self.ctx.runtime_env();
+        let mut stores: HashMap<ObjectStoreUrl, ObjectStoreUrl> = HashMap::new();
+        let mut decrypted_path = |path: &str| -> PyResult<String> {
+            let url = ListingTableUrl::parse(path)?;
+            let store_url = url.object_store();
+            let decrypting_url = match stores.get(&store_url) {
+                Some(decrypting_url) => decrypting_url.clone(),
+                None => {
+                    let store = DecryptingStore::new(
+                        store_url.to_string(),
+                        runtime.object_store(&store_url)?,
+                        key.clone(),
+                    );
+                    let decrypting_url =
+                        ObjectStoreUrl::parse(format!("decrypted://{}", Uuid::new_v4().simple()))?;
+                    runtime.register_object_store(decrypting_url.as_ref(), Arc::new(store));
+                    stores.insert(store_url.clone(), decrypting_url.clone());
+                    decrypting_url
+                }
+            };
+            let location = url.as_str().strip_prefix(store_url.as_str());
+            Ok(format!("{decrypting_url}{}", location.unwrap_or_default()))
+        };
+        Ok(match path {
+            TablePaths::Path(path) => TablePaths::Path(decrypted_path(&path)?),
+            TablePaths::Paths(paths) => TablePaths::Paths(
+                paths
+                    .iter()
+                    .map(|path| decrypted_path(path))
+                    .collect::<PyResult<_>>()?,
+            ),
+        })
+    }
+
+    /// Registers the Parquet files of `path` read with `options`, see `register_parquet`
+    fn register_parquet_options(
+        &mut self,
+        name: &str,
+        path: TablePaths,
+        options: ParquetReadOptions<'_>,
+        table_options: ParquetTableOptions,
+        py: Python,
+    ) -> PyResult<()> {
+        let ParquetTableOptions {
+            enable_page_index,
+            merge_schemas,
+            glob,
+            exclude,
+            columns,
+            metadata_size_hint,
+            bloom_filter_on_read,
+        } = table_options;
+        let bloom_filter_on_read = self.bloom_filter_on_read(bloom_filter_on_read);
+        let paths = path.to_vec();
+        let file_extension = options.file_extension;
+        let table = match path {
+            TablePaths::Path(path) if glob.is_none() && exclude.is_empty() => {
+                let result = self.ctx.register_parquet(name, &path, options);
+                wait_for_future(py, result).map_err(|e| {
+                    self.parquet_read_error(&paths, file_extension, DataFusionError::from(e), py)
+                })?;
+                if enable_page_index.is_none()
+                    && metadata_size_hint.is_none()
+                    && !bloom_filter_on_read
+                    && columns.is_none()
+                {
+                    return Ok(());
+                }
+                match self.ctx.deregister_table(name)? {
+                    Some(table) => table,
+                    None => return Ok(()),
+                }
+            }
+            TablePaths::Path(path) => self
+                .matching_files_table(
+                    &path,
+                    &options,
+                    options.schema,
+                    glob,
+                    &exclude,
+                    merge_schemas,
+                    py,
+                )
+                .map_err(|e| self.parquet_read_error(&paths, file_extension, e, py))?,
+            TablePaths::Paths(paths) => self
+                .exact_files_table(&paths, &options, options.schema, merge_schemas, py)
+                .map_err(|e| self.parquet_read_error(&paths, file_extension, e, py))?,
+        };
+        let table = ParquetScanOptions::wrap(
+            table,
+            enable_page_index,
+            metadata_size_hint,
+            bloom_filter_on_read,
+        );
+        let table = match columns {
+            Some(columns) => {
+                let schema = table.schema();
+                let mut indices = Vec::with_capacity(columns.len());
+                for column in &columns {
+                    let index = schema.index_of(column).map_err(|_| {
+                        PyValueError::new_err(format!("Column '{column}' doesn't exist"))
+                    })?;
+                    if indices.contains(&index) {
+                        return Err(PyValueError::new_err(format!(
+                            "Column '{column}' is given more than once"
+                        )));
+                    }
+                    indices.push(index);
+                }
+                Arc::new(ParquetColumns::new(table, indices))
+            }
+            None => table,
+        };
+        self.ctx.register_table(name, table)?;
+        Ok(())
+    }
+
+    /// Reads the Parquet files of `path` with `options`, see `read_parquet`. The `glob`,
+    /// `exclude` and `columns` of `table_options` are only taken by `register_parquet`.
+    fn read_parquet_options(
+        &self,
+        path: TablePaths,
+        options: ParquetReadOptions<'_>,
+        table_options: ParquetTableOptions,
+        py: Python,
+    ) -> PyResult<DataFrame> {
+        let ParquetTableOptions {
+            enable_page_index,
+            merge_schemas,
+            metadata_size_hint,
+            bloom_filter_on_read,
+            ..
+        } = table_options;
+        let bloom_filter_on_read = self.bloom_filter_on_read(bloom_filter_on_read);
+        let paths = path.to_vec();
+        let file_extension = options.file_extension;
+        let path = match path {
+            TablePaths::Path(path) => path,
+            TablePaths::Paths(paths) => {
+                let table = self
+                    .exact_files_table(&paths, &options, options.schema, merge_schemas, py)
+                    .map_err(|e| self.parquet_read_error(&paths, file_extension, e, py))?;
+                let table = ParquetScanOptions::wrap(
+                    table,
+                    enable_page_index,
+                    metadata_size_hint,
+                    bloom_filter_on_read,
+                );
+                return Ok(self.ctx.read_table(table)?);
+            }
+        };
+        let result = self.ctx.read_parquet(path, options);
+        let mut df = wait_for_future(py, result).map_err(|e| {
+            self.parquet_read_error(&paths, file_extension, DataFusionError::from(e), py)
+        })?;
+
+        if enable_page_index.is_some() || metadata_size_hint.is_some() || bloom_filter_on_read {
+            if let LogicalPlan::TableScan(scan) = df.logical_plan() {
+                let table = source_as_provider(&scan.source)?;
+                let table = ParquetScanOptions::wrap(
+                    table,
+                    enable_page_index,
+                    metadata_size_hint,
+                    bloom_filter_on_read,
+                );
+                df = self.ctx.read_table(table)?;
+            }
+        }
+        Ok(df)
+    }
+
+    /// Reads the CSV files at `path` with `options`
+    fn read_csv_options(
+        &self,
+        path: &str,
+        options: CsvReadOptions<'_>,
+        py: Python,
+    ) -> PyResult<DataFrame> {
+        let result = self.ctx.read_csv(path, options);
+        Ok(wait_for_future(py, result).map_err(DataFusionError::from)?)
+    }
+
+    /// Reads the JSON files at `path` with `options`, holding an array of objects each when
+    /// `array` is true
+    fn read_json_options(
+        &self,
+        path: &str,
+        options: NdJsonReadOptions<'_>,
+        array: bool,
+        py: Python,
+    ) -> PyResult<DataFrame> {
+        if array {
+            let schema = options.schema;
+            let listing_options =
+                JsonArrayReadOptions(options).to_listing_options(&self.ctx.copied_config());
+            let urls = vec![ListingTableUrl::parse(path)?];
+            let table = self.files_table(urls, listing_options, schema, true, py)?;
+            return Ok(self.ctx.read_table(table).map_err(DataFusionError::from)?);
+        }
+        let result = self.ctx.read_json(path, options);
+        Ok(wait_for_future(py, result).map_err(DataFusionError::from)?)
+    }
+
+    /// Registers the CSV files at `path` read with `options`, or those of them `glob` and
+    /// `exclude` select
+    fn register_csv_options(
+        &mut self,
+        name: &str,
+        path: &str,
+        options: CsvReadOptions<'_>,
+        glob: Option<&str>,
+        exclude: &[String],
+        py: Python,
+    ) -> PyResult<()> {
+        if glob.is_some() || !exclude.is_empty() {
+            let table =
+                self.matching_files_table(path, &options, options.schema, glob, exclude, true, py)?;
+            self.ctx.register_table(name, table)?;
+            return Ok(());
+        }
+        let result = self.ctx.register_csv(name, path, options);
+        wait_for_future(py, result).map_err(DataFusionError::from)?;
+
+        Ok(())
+    }
+
+    /// Registers the JSON files at `path` read with `options`, holding an array of objects each
+    /// when `array` is true, or those of them `glob` and `exclude` select
+    #[allow(clippy::too_many_arguments)]
+    fn register_json_options(
+        &mut self,
+        name: &str,
+        path: &str,
+        options: NdJsonReadOptions<'_>,
+        array: bool,
+        glob: Option<&str>,
+        exclude: &[String],
+        py: Python,
+    ) -> PyResult<()> {
+        if glob.is_some() || !exclude.is_empty() {
+            let schema = options.schema;
+            let table = if array {
+                let options = JsonArrayReadOptions(options);
+                self.matching_files_table(path, &options, schema, glob, exclude, true, py)?
+            } else {
+                self.matching_files_table(path, &options, schema, glob, exclude, true, py)?
+            };
+            self.ctx.register_table(name, table)?;
+            return Ok(());
+        }
+        if array {
+            let schema = options.schema.map(|schema| Arc::new(schema.clone()));
+            let listing_options =
+                JsonArrayReadOptions(options).to_listing_options(&self.ctx.copied_config());
+            let result = self
+                .ctx
+                .register_listing_table(name, path, listing_options, schema, None);
+            wait_for_future(py, result).map_err(DataFusionError::from)?;
+            return Ok(());
+        }
+        let result = self.ctx.register_json(name, path, options);
+        wait_for_future(py, result).map_err(DataFusionError::from)?;
+
+        Ok(())
+    }
+
+    /// Registers the Avro files at `path` read with `options`, or those of them `glob` and
+    /// `exclude` select
+    fn register_avro_options(
+        &mut self,
+        name: &str,
+        path: &str,
+        options: AvroReadOptions<'_>,
+        glob: Option<&str>,
+        exclude: &[String],
+        py: Python,
+    ) -> PyResult<()> {
+        if glob.is_some() || !exclude.is_empty() {
+            let table =
+                self.matching_files_table(path, &options, options.schema, glob, exclude, true, py)?;
+            self.ctx.register_table(name, table)?;
+            return Ok(());
+        }
+        let result = self.ctx.register_avro(name, path, options);
+        wait_for_future(py, result).map_err(DataFusionError::from)?;
+
+        Ok(())
+    }
+
+    /// Whether a Parquet table reads the bloom filters of its files, `enabled` or else the
+    /// default of the session
+    fn bloom_filter_on_read(&self, enabled: Option<bool>) -> bool {
+        enabled.unwrap_or_else(|| {
+            self.ctx
+                .copied_config()
+                .get_extension::<ParquetBloomFilterOnRead>()
+                .map_or(false, |enabled| enabled.0)
+        })
+    }
+
+    /// Returns a clearer error than `error`, which was raised reading the Parquet files of
+    /// `paths`, when one of them is encrypted
+    fn parquet_read_error(
+        &self,
+        paths: &[String],
+        file_extension: &str,
+        error: impl Into<PyErr>,
+        py: Python,
+    ) -> PyErr {
+        let error = error.into();
+        if !error.to_string().contains("Corrupt footer") {
+            return error;
+        }
+        for path in paths {
+            let urls = match ListingTableUrl::parse(path) {
+                Ok(url) if url.as_str().ends_with('/') => self
+                    .list_matching_files(path, file_extension, None, &[], py)
+                    .unwrap_or_default(),
+                Ok(url) => vec![url],
+                Err(_) => vec![],
+            };
+            for url in urls {
+                let store = match self.ctx.runtime_env().object_store(&url) {
+                    Ok(store) => store,
+                    Err(_) => continue,
+                };
+                let tail = wait_for_future(py, async {
+                    let meta = store.head(url.prefix()).await?;
+                    let start = meta.size.saturating_sub(4);
+                    store.get_range(url.prefix(), start..meta.size).await
+                });
+                if matches!(tail, Ok(tail) if has_encrypted_footer(&tail)) {
+                    return DataFusionError::Common(format!(
+                        "The Parquet file {url} is encrypted, pass its key as decryption_key"
+                    ))
+                    .into();
+                }
+            }
+        }
+        error
+    }
+
+    /// Lists the files in the directory `path` that have the given extension, match `glob`
+    /// when one is given, and match none of the `exclude` patterns. Patterns are matched
+    /// against the path of a file relative to `path`, exclude patterns also against every
+    /// component of that path
+    fn list_matching_files(
+        &self,
+        path: &str,
+        file_extension: &str,
+        glob: Option<&str>,
+        exclude: &[String],
+        py: Python,
+    ) -> PyResult<Vec<ListingTableUrl>> {
+        let parse = |pattern: &str| {
+            Pattern::new(pattern).map_err(|e| {
+                PyValueError::new_err(format!("Invalid glob pattern '{pattern}': {e}"))
+            })
+        };
+        let glob = glob.map(parse).transpose()?;
+        let exclude = exclude
+            .iter()
+            .map(|pattern| parse(pattern))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let table_url = ListingTableUrl::parse(path)?;
+        if !table_url.as_str().ends_with('/') {
+            return Err(PyValueError::new_err(format!(
+                "glob and exclude require a directory, {path} is not one"
+            )));
+        }
+        let store = self.ctx.runtime_env().object_store(&table_url)?;
+        let prefix = table_url.prefix().clone();
+        let files = wait_for_future(py, async {
+            store
+                .list(Some(&prefix))
+                .await?
+                .try_collect::<Vec<_>>()
+                .await
+        })
+        .map_err(datafusion_common::DataFusionError::from)?;
+
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::default()
+        };
+        let mut urls = vec![];
+        for file in files {
+            let location = file.location.as_ref();
+            let relative = location
+                .strip_prefix(prefix.as_ref())
+                .unwrap_or(location)
+                .trim_start_matches('/');
+            let matches = |pattern: &Pattern| {
+                pattern.matches_with(relative, options)
+                    || relative
+                        .split('/')
+                        .any(|part| pattern.matches_with(part, options))
+            };
+            if !relative.ends_with(file_extension)
+                || glob
+                    .as_ref()
+                    .map_or(false, |glob| !glob.matches_with(relative, options))
+                || exclude.iter().any(matches)
+            {
+                continue;
+            }
+            let url = format!("{}{location}", table_url.object_store().as_str());
+            urls.push(ListingTableUrl::parse(url)?);
+        }
+        if urls.is_empty() {
+            return Err(PyValueError::new_err(format!(
+                "No files in {path} match the glob and exclude patterns"
+            )));
+        }
+        urls.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(urls)
+    }
+
+    /// Creates a table over exactly the files `paths`, read with `options`, see `files_table`
+    fn exact_files_table<'a>(
+        &self,
+        paths: &[String],
+        options: &impl ReadOptions<'a>,
+        schema: Option<&Schema>,
+        merge_schemas: bool,
+        py: Python,
+    ) -> PyResult<Arc<dyn TableProvider>> {
+        let urls = paths
+            .iter()
+            .map(ListingTableUrl::parse)
+            .collect::<datafusion_common::Result<Vec<_>>>()?;
+        let listing_options = options.to_listing_options(&self.ctx.copied_config());
+        self.files_table(urls, listing_options, schema, merge_schemas, py)
+    }
+
+    /// Creates a table over the files in the directory `path` selected by `glob` and
+    /// `exclude`, see `list_matching_files`. The CSV, JSON and Avro registrations merge the
+    /// schemas of the files, as DataFusion does for all the files of a directory, while
+    /// `register_parquet` passes its `merge_schemas`
+    #[allow(clippy::too_many_arguments)]
+    fn matching_files_table<'a>(
+        &self,
+        path: &str,
+        options: &impl ReadOptions<'a>,
+        schema: Option<&Schema>,
+        glob: Option<&str>,
+        exclude: &[String],
+        merge_schemas: bool,
+        py: Python,
+    ) -> PyResult<Arc<dyn TableProvider>> {
+        let listing_options = options.to_listing_options(&self.ctx.copied_config());
+        if !listing_options.table_partition_cols.is_empty() {
+            return Err(PyValueError::new_err(
+                "glob and exclude can't be combined with table_partition_cols",
+            ));
+        }
+        let urls =
+            self.list_matching_files(path, &listing_options.file_extension, glob, exclude, py)?;
+        self.files_table(urls, listing_options, schema, merge_schemas, py)
+    }
+
+    /// Returns the one supported file extension found among the files of the directory `path`,
+    /// ignoring hidden files and markers such as `_SUCCESS`
+    fn directory_extension(&self, path: &str, py: Python) -> PyResult<String> {
+        let table_url = ListingTableUrl::parse(path)?;
+        let store = self.ctx.runtime_env().object_store(&table_url)?;
+        let prefix = table_url.prefix().clone();
+        let files = wait_for_future(py, async {
+            store
+                .list(Some(&prefix))
+                .await?
+                .try_collect::<Vec<_>>()
+                .await
+        })
+        .map_err(datafusion_common::DataFusionError::from)?;
+
+        let extensions = files
+            .iter()
+            .filter_map(|file| {
+                let file_name = file.location.filename()?;
+                if file_name.starts_with('.') || file_name.starts_with('_') {
+                    return None;
+                }
+                let extension = file_extension_of(file_name);
+                format_from_extension(extension).map(|_| extension.to_owned())
+            })
+            .collect::<BTreeSet<_>>();
+        match extensions.len() {
+            1 => Ok(extensions.into_iter().next().unwrap()),
+            0 => Err(PyValueError::new_err(format!(
+                "Can't detect the format of {path}, it holds no files with a supported extension"
+            ))),
+            _ => Err(PyValueError::new_err(format!(
+                "Can't detect the format of {path}, it holds files with the extensions {}, pass \
+                 format as one of: parquet, csv, json, avro",
+                extensions.into_iter().collect::<Vec<_>>().join(", ")
+            ))),
+        }
+    }
+
+    /// Returns, and clears, the last exception raised by a registered Python catalog provider
+    fn take_catalog_error(&self) -> Option<DataFusionError> {
+        self.ctx.catalog_names().iter().find_map(|name| {
+            self.ctx
+                .catalog(name)?
+                .as_any()
+                .downcast_ref::<PyCatalogProvider>()?
+                .take_error()
+        })
+    }
+
+    /// Converts a planning error, preferring the exception raised by a Python catalog provider
+    /// as DataFusion reports those as missing tables
+    fn planning_error(&self, err: datafusion_common::DataFusionError) -> DataFusionError {
+        self.take_catalog_error()
+            .unwrap_or_else(|| DataFusionError::from(err))
+    }
+
+    /// Sets the configuration option `key` of this context, as a SQL `SET` statement would
+    fn set_option(&self, key: &str, value: &str, py: Python) -> PyResult<()> {
+        let plan = LogicalPlan::Statement(Statement::SetVariable(SetVariable {
+            variable: key.to_owned(),
+            value: value.to_owned(),
+            schema: Arc::new(DFSchema::empty()),
+        }));
+        wait_for_future(py, self.ctx.execute_logical_plan(plan)).map_err(DataFusionError::from)?;
+        Ok(())
+    }
+
+    /// Looks up a catalog by name, falling back to the default catalog
+    fn catalog_provider(&self, name: Option<&str>) -> PyResult<Arc<dyn CatalogProvider>> {
+        let name = match name {
+            Some(name) => name.to_owned(),
+            None => self
+                .ctx
+                .copied_config()
+                .options()
+                .catalog
+                .default_catalog
+                .clone(),
+        };
+        self.ctx
+            .catalog(&name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Catalog with name {name} doesn't exist.")))
+    }
+}
+
+/// Describes the argument lists accepted by a function signature, one entry per alternative
+fn signature_strings(signature: &TypeSignature) -> Vec<String> {
+    let join = |types: &[DataType], sep: &str| {
+        types
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(sep)
+    };
+    match signature {
+        TypeSignature::Variadic(types) => vec![format!("{}, ...", join(types, "|"))],
+        TypeSignature::VariadicEqual => vec!["T, ..., T".to_string()],
+        TypeSignature::VariadicAny => vec!["Any, ...".to_string()],
+        TypeSignature::Uniform(count, types) => {
+            vec![vec![join(types, "|"); *count].join(", ")]
+        }
+        TypeSignature::Exact(types) => vec![join(types, ", ")],
+        TypeSignature::Any(count) => vec![vec!["Any"; *count].join(", ")],
+        TypeSignature::OneOf(signatures) => signatures.iter().flat_map(signature_strings).collect(),
+    }
+}
+
+/// Where `read_*_bytes` put the buffers they read
+const MEMORY_STORE_URL: &str = "memory://bytes/";
+
+/// One path, which may be a directory or a glob, or a list of exact file paths
+#[derive(FromPyObject)]
+enum TablePaths {
+    Path(String),
+    Paths(Vec<String>),
+}
+
+impl TablePaths {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            TablePaths::Path(path) => vec![path.clone()],
+            TablePaths::Paths(paths) => paths.clone(),
+        }
+    }
 }
 
 fn convert_table_partition_cols(
@@ -855,6 +2424,16 @@ fn convert_table_partition_cols(
         .collect::<Result<Vec<_>, _>>()
 }
 
+/// The byte of a CSV delimiter, which must be a single character
+fn parse_delimiter(delimiter: &str) -> PyResult<u8> {
+    match delimiter.as_bytes() {
+        [delimiter] => Ok(*delimiter),
+        _ => Err(PyValueError::new_err(
+            "Delimiter must be a single character",
+        )),
+    }
+}
+
 fn parse_file_compression_type(
     file_compression_type: Option<String>,
 ) -> Result<FileCompressionType, PyErr> {
@@ -864,6 +2443,98 @@ fn parse_file_compression_type(
         })
 }
 
+/// Returns the extension of the last segment of `path`, including the dot, or an empty string
+fn file_extension_of(path: &str) -> &str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name
+        .rfind('.')
+        .filter(|&index| index > 0)
+        .map_or("", |index| &file_name[index..])
+}
+
+/// Maps a file extension to the format `SessionContext.register` reads it as
+fn format_from_extension(extension: &str) -> Option<&'static str> {
+    match extension.to_ascii_lowercase().as_str() {
+        ".parquet" => Some("parquet"),
+        ".csv" => Some("csv"),
+        ".json" | ".jsonl" | ".ndjson" => Some("json"),
+        ".avro" => Some("avro"),
+        _ => None,
+    }
+}
+
+fn parse_table_format(format: &str) -> PyResult<&'static str> {
+    match format {
+        "parquet" => Ok("parquet"),
+        "csv" => Ok("csv"),
+        "json" => Ok("json"),
+        "avro" => Ok("avro"),
+        other => Err(PyValueError::new_err(format!(
+            "Unsupported format {other}, expected one of: parquet, csv, json, avro"
+        ))),
+    }
+}
+
+/// Prepends the levels of the index of the pandas DataFrame `data` to `table`, named like
+/// `pandas.DataFrame.reset_index` does: by the name of the level, else `index` (`level_0` if
+/// there is an `index` column) or `level_i` for a MultiIndex. Names already taken get a `.1`,
+/// `.2`... suffix, as pandas does for duplicated columns.
+fn with_pandas_index<'py>(
+    pyarrow: &'py PyModule,
+    data: &'py PyAny,
+    mut table: &'py PyAny,
+) -> PyResult<&'py PyAny> {
+    let index = data.getattr("index")?;
+    let levels: Vec<Option<&PyAny>> = index
+        .getattr("names")?
+        .iter()?
+        .map(|name| name.map(|name| (!name.is_none()).then_some(name)))
+        .collect::<PyResult<_>>()?;
+    let mut taken: HashSet<String> = table.getattr("column_names")?.extract()?;
+    for (i, level) in levels.iter().enumerate() {
+        let base = match level {
+            Some(name) => name.str()?.to_string(),
+            None if levels.len() > 1 => format!("level_{i}"),
+            None if taken.contains("index") => "level_0".to_string(),
+            None => "index".to_string(),
+        };
+        let mut name = base.clone();
+        let mut suffix = 0;
+        while taken.contains(&name) {
+            suffix += 1;
+            name = format!("{base}.{suffix}");
+        }
+        taken.insert(name.clone());
+        let values =
+            pyarrow.call_method1("array", (index.call_method1("get_level_values", (i,))?,))?;
+        table = table.call_method1("add_column", (i, name, values))?;
+    }
+    Ok(table)
+}
+
+/// The footer at the end of a Parquet file, its metadata length and magic number, is 8 bytes
+/// long, a smaller prefetch can't hold any of the metadata
+fn check_metadata_size_hint(size: Option<usize>) -> PyResult<()> {
+    match size {
+        Some(size) if size < 8 => Err(PyValueError::new_err(
+            "metadata_size_hint must be at least 8 bytes, the size of the Parquet footer",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns whether a JSON file `format` is a single JSON array of rows, rather than newline
+/// delimited JSON
+fn parse_json_format(format: &str) -> PyResult<bool> {
+    match format {
+        "ndjson" => Ok(false),
+        "array" => Ok(true),
+        other => Err(PyValueError::new_err(format!(
+            "Unsupported JSON format {other}, expected 'ndjson' or 'array'"
+        ))),
+    }
+}
+
 impl From<PySessionContext> for SessionContext {
     fn from(ctx: PySessionContext) -> SessionContext {
         ctx.ctx
@@ -15,21 +15,73 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::context::{without_disabled_optimizer_rules, DefaultNullOrdering};
+use crate::display::{format_html, format_table, format_vertical, DisplayOptions};
+use crate::errors::DataFusionError;
+use crate::expr::subquery::bind_outer_references;
+use crate::expr::{PyExpr, PySortExpr};
+use crate::group_by::PyGroupBy;
+use crate::maintain_order::MaintainOrder;
+use crate::nullability::NullabilityTable;
+use crate::parquet::ParquetScanOptions;
 use crate::physical_plan::PyExecutionPlan;
+use crate::record_batch::{PyPandasChunks, PyRecordBatchStream};
+use crate::result_cache::ResultCache;
 use crate::sql::logical::PyLogicalPlan;
-use crate::utils::wait_for_future;
-use crate::{errors::DataFusionError, expr::PyExpr};
-use datafusion::arrow::datatypes::Schema;
+use crate::sql::parser::parse_sql_expr;
+use crate::substrait::{self, PySubstraitSerializer};
+use crate::udf::{udf_profile, UdfProfile};
+use crate::utils::{parse_safe, wait_for_future, wait_for_future_interruptible, SchemaArg};
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, StringArray, UInt32Array};
+use datafusion::arrow::compute::can_cast_types;
+use datafusion::arrow::compute::{self, concat_batches, take};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ipc::writer::{FileWriter, IpcWriteOptions, StreamWriter};
+use datafusion::arrow::ipc::CompressionType;
+use datafusion::arrow::json::writer::record_batches_to_json_rows;
 use datafusion::arrow::pyarrow::{PyArrowType, ToPyArrow};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::row::{RowConverter, SortField};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
 use datafusion::arrow::util::pretty;
 use datafusion::dataframe::{DataFrame, DataFrameWriteOptions};
+use datafusion::datasource::listing::ListingTable;
+use datafusion::datasource::{provider_as_source, source_as_provider, TableProvider};
+use datafusion::execution::context::SessionState;
+use datafusion::parquet::arrow::arrow_to_parquet_schema;
 use datafusion::parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
-use datafusion::parquet::file::properties::WriterProperties;
+use datafusion::parquet::file::properties::{EnabledStatistics, WriterProperties};
+use datafusion::parquet::schema::types::ColumnPath;
+use datafusion::physical_plan::{self, displayable, ExecutionPlan};
 use datafusion::prelude::*;
-use pyo3::exceptions::{PyTypeError, PyValueError};
+use datafusion_common::cast::as_float64_array;
+use datafusion_common::tree_node::{TreeNode, VisitRecursion};
+use datafusion_common::{DFField, ScalarValue};
+use datafusion_expr::expr::{self, GetFieldAccess, GetIndexedField};
+use datafusion_expr::utils::find_aggregate_exprs;
+use datafusion_expr::{window_function, AggregateFunction, Volatility, WindowFrame};
+use datafusion_expr::{LogicalPlan, LogicalPlanBuilder, UNNAMED_TABLE};
+use futures::future::try_join_all;
+use futures::StreamExt;
+use once_cell::sync::OnceCell;
+use pyo3::exceptions::{PyAssertionError, PyIndexError, PyKeyError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyTuple;
+use pyo3::types::{PyBool, PyDict, PyList, PySlice, PyString, PyTuple};
+use ring::digest;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Number of rows rendered by `_repr_html_`
+const HTML_PREVIEW_ROWS: usize = 10;
 
 /// A PyDataFrame is a representation of a logical plan and an API to compose statements.
 /// Use it to build a plan and `.collect()` to execute the plan and collect the result.
@@ -37,35 +89,336 @@ use std::sync::Arc;
 #[pyclass(name = "DataFrame", module = "datafusion", subclass)]
 #[derive(Clone)]
 pub(crate) struct PyDataFrame {
-    df: Arc<DataFrame>,
+    pub(crate) df: Arc<DataFrame>,
+    /// The optimized logical plan, created on first use. Each operation returns a new
+    /// DataFrame, with an empty cache
+    optimized_plan: Arc<OnceCell<LogicalPlan>>,
 }
 
 impl PyDataFrame {
     /// creates a new PyDataFrame
     pub fn new(df: DataFrame) -> Self {
-        Self { df: Arc::new(df) }
+        Self {
+            df: Arc::new(df),
+            optimized_plan: Arc::default(),
+        }
+    }
+
+    /// Returns the optimized logical plan, optimizing the plan only the first time it's called
+    fn optimized_plan(&self) -> PyResult<&LogicalPlan> {
+        self.optimized_plan.get_or_try_init(|| {
+            let (state, plan) = self.df.as_ref().clone().into_parts();
+            Ok(state.optimize(&plan)?)
+        })
+    }
+
+    /// The optimized logical plan, indented as `explain` shows it, with the statistics of the
+    /// physical plan of each node, which is planned without optimizing it again
+    fn logical_plan_with_statistics(&self, py: Python) -> PyResult<String> {
+        let (state, plan) = self.optimized_df()?.into_parts();
+        let mut lines = vec![];
+        let mut nodes = vec![(&plan, 0)];
+        while let Some((node, depth)) = nodes.pop() {
+            let physical = wait_for_future(py, state.create_physical_plan(node))?;
+            lines.push(format!(
+                "{:indent$}{}, statistics=[{}]",
+                "",
+                node.display(),
+                physical.statistics(),
+                indent = depth * 2
+            ));
+            nodes.extend(
+                node.inputs()
+                    .into_iter()
+                    .rev()
+                    .map(|input| (input, depth + 1)),
+            );
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// A DataFrame of the optimized plan, with a state that doesn't optimize it again when it's
+    /// executed or explained
+    fn optimized_df(&self) -> PyResult<DataFrame> {
+        let (state, _) = self.df.as_ref().clone().into_parts();
+        let state = state
+            .with_analyzer_rules(vec![])
+            .with_optimizer_rules(vec![]);
+        Ok(DataFrame::new(state, self.optimized_plan()?.clone()))
+    }
+
+    /// Executes the plan, or returns its result from the result cache of the session when it is
+    /// enabled and the same plan was collected before
+    fn collect_cached(&self, py: Python) -> PyResult<Vec<RecordBatch>> {
+        let (state, _) = self.df.as_ref().clone().into_parts();
+        let cache = state
+            .config()
+            .get_extension::<ResultCache>()
+            .filter(|cache| cache.is_enabled());
+        let cache = match cache {
+            Some(cache) => cache,
+            None => return Ok(wait_for_future(py, self.df.as_ref().clone().collect())?),
+        };
+        let plan = self.optimized_plan()?;
+        if !is_cacheable(plan)? {
+            let batches = wait_for_future(py, self.optimized_df()?.collect())?;
+            // the statement may have changed the tables read by the cached results
+            if matches!(plan, LogicalPlan::Dml(_) | LogicalPlan::Ddl(_)) {
+                cache.clear();
+            }
+            return Ok(batches);
+        }
+        // the same name may be registered to another table later on, so the tables are
+        // identified by their address too
+        let (fingerprint, tables) = fingerprint_plan(plan)?;
+        let key = tables.iter().fold(fingerprint, |key, table| {
+            format!("{key}:{:x}", Arc::as_ptr(table) as *const () as usize)
+        });
+        if let Some(batches) = cache.get(&key) {
+            return Ok(batches);
+        }
+        let batches = wait_for_future(py, self.optimized_df()?.collect())?;
+        cache.insert(key, batches.clone(), tables);
+        Ok(batches)
+    }
+
+    /// Returns whether the default null ordering of the session puts nulls first, if it has one
+    fn default_nulls_first(&self) -> Option<bool> {
+        let (state, _) = self.df.as_ref().clone().into_parts();
+        let ordering = state.config().get_extension::<DefaultNullOrdering>();
+        ordering.map(|ordering| ordering.nulls_first)
+    }
+
+    /// Returns the display options of the session, overridden by the given ones
+    fn display_options(
+        &self,
+        timestamp_format: Option<String>,
+        float_precision: Option<usize>,
+        null_repr: Option<String>,
+    ) -> DisplayOptions {
+        let (state, _) = self.df.as_ref().clone().into_parts();
+        let session = state
+            .config()
+            .get_extension::<DisplayOptions>()
+            .map(|options| options.as_ref().clone())
+            .unwrap_or_default();
+        DisplayOptions {
+            timestamp_format: timestamp_format.or(session.timestamp_format.clone()),
+            float_precision: float_precision.or(session.float_precision),
+            null_repr: null_repr.unwrap_or(session.null_repr.clone()),
+            ..session
+        }
+    }
+
+    /// The display options of `show` and `show_vertical`, rejecting a `max_width` too narrow
+    /// for the values to be cut off or wrapped
+    fn preview_options(
+        &self,
+        max_width: Option<usize>,
+        truncate: bool,
+        timestamp_format: Option<String>,
+        float_precision: Option<usize>,
+        null_repr: Option<String>,
+    ) -> PyResult<DisplayOptions> {
+        match max_width {
+            Some(width) if truncate && width < 3 => {
+                return Err(PyValueError::new_err(format!(
+                    "max_width must be at least 3 to fit the ellipsis of the values cut off, got {width}"
+                )))
+            }
+            Some(0) => return Err(PyValueError::new_err("max_width must be at least 1")),
+            _ => {}
+        }
+        Ok(DisplayOptions {
+            max_width,
+            truncate,
+            ..self.display_options(timestamp_format, float_precision, null_repr)
+        })
+    }
+
+    /// Extracts an expression, parsing strings as SQL against the schema of this DataFrame
+    fn parse_expr(&self, expr: &PyAny) -> PyResult<Expr> {
+        if let Ok(sql) = expr.extract::<&str>() {
+            let (state, _) = self.df.as_ref().clone().into_parts();
+            Ok(parse_sql_expr(sql, self.df.schema(), &state, true)?)
+        } else {
+            Ok(expr.extract::<PyExpr>()?.into())
+        }
+    }
+
+    /// Parses the grouping expressions of `aggregate`
+    pub(crate) fn parse_group_by(&self, group_by: Vec<&PyAny>) -> PyResult<Vec<Expr>> {
+        group_by.into_iter().map(|e| self.parse_expr(e)).collect()
+    }
+
+    /// Parses the aggregates of `aggregate`, raising if a SQL string isn't an aggregate
+    pub(crate) fn parse_aggregates(&self, aggs: Vec<&PyAny>) -> PyResult<Vec<Expr>> {
+        aggs.into_iter()
+            .map(|e| {
+                let expr = self.parse_expr(e)?;
+                if e.is_instance_of::<PyString>()
+                    && find_aggregate_exprs(std::slice::from_ref(&expr)).is_empty()
+                {
+                    return Err(PyValueError::new_err(format!(
+                        "'{e}' is not an aggregate expression"
+                    )));
+                }
+                Ok(expr)
+            })
+            .collect()
+    }
+
+    /// Computes the two column aggregate `fun` over the whole DataFrame
+    fn pairwise_aggregate(
+        &self,
+        fun: AggregateFunction,
+        col_a: &str,
+        col_b: &str,
+        py: Python,
+    ) -> PyResult<Option<f64>> {
+        let args = [col_a, col_b]
+            .iter()
+            .map(|name| {
+                let field = self
+                    .df
+                    .schema()
+                    .field_with_unqualified_name(name)
+                    .map_err(|_| PyValueError::new_err(format!("Column '{name}' doesn't exist")))?;
+                Ok(Expr::Column(field.qualified_column()))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let aggregate =
+            Expr::AggregateFunction(expr::AggregateFunction::new(fun, args, false, None, None));
+        let df = self
+            .df
+            .as_ref()
+            .clone()
+            .aggregate(vec![], vec![aggregate])?;
+        let batch = collect_single_batch(py, &df)?;
+        let values = as_float64_array(batch.column(0)).map_err(DataFusionError::from)?;
+        Ok(values.is_valid(0).then(|| values.value(0)))
+    }
+
+    /// Selects the columns `names` for `__getitem__`, raising a KeyError for a missing one
+    fn select_named_columns(&self, names: Vec<&str>) -> PyResult<Self> {
+        let fields = self.df.schema().fields();
+        if let Some(missing) = names
+            .iter()
+            .find(|name| !fields.iter().any(|field| field.name() == *name))
+        {
+            let columns: Vec<&str> = fields.iter().map(|field| field.name().as_str()).collect();
+            return Err(PyKeyError::new_err(format!(
+                "Column '{missing}' not found, the columns are: {}",
+                columns.join(", ")
+            )));
+        }
+        self.select_columns(names)
+    }
+
+    /// Executes the query and converts `fetch` rows after skipping `skip` to dicts
+    fn collect_rows<'py>(
+        &self,
+        skip: usize,
+        fetch: Option<usize>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyList> {
+        let df = Self::new(self.df.as_ref().clone().limit(skip, fetch)?);
+        Ok(df.to_pylist(py)?.into_ref(py).downcast()?)
+    }
+
+    /// Returns the rows of `slice`, only counting the rows when it has a negative bound or step
+    fn slice_rows(&self, slice: &PySlice, py: Python) -> PyResult<PyObject> {
+        let start: Option<isize> = slice.getattr("start")?.extract()?;
+        let stop: Option<isize> = slice.getattr("stop")?.extract()?;
+        let step: isize = slice
+            .getattr("step")?
+            .extract::<Option<isize>>()?
+            .unwrap_or(1);
+        if step == 0 {
+            return Err(PyValueError::new_err("slice step cannot be zero"));
+        }
+        let negative = |bound: Option<isize>| bound.map_or(false, |bound| bound < 0);
+        let (skip, fetch) = if step < 0 || negative(start) || negative(stop) {
+            // fetch the rows from the first to the last index of the slice
+            let indices = slice.indices(self.count(py)? as _)?;
+            if indices.slicelength == 0 {
+                return Ok(PyList::empty(py).into());
+            }
+            let last = indices.start + (indices.slicelength - 1) * indices.step;
+            let first = indices.start.min(last);
+            (first as usize, Some(indices.start.abs_diff(last) + 1))
+        } else {
+            let skip = start.unwrap_or(0) as usize;
+            (skip, stop.map(|stop| (stop as usize).saturating_sub(skip)))
+        };
+        let rows = self.collect_rows(skip, fetch, py)?;
+        let window = if step < 0 {
+            PySlice::new(py, isize::MAX, isize::MIN, step)
+        } else {
+            PySlice::new(py, 0, isize::MAX, step)
+        };
+        Ok(rows.as_ref().get_item(window)?.into())
     }
 }
 
 #[pymethods]
 impl PyDataFrame {
-    fn __getitem__(&self, key: PyObject) -> PyResult<Self> {
-        Python::with_gil(|py| {
-            if let Ok(key) = key.extract::<&str>(py) {
-                self.select_columns(vec![key])
-            } else if let Ok(tuple) = key.extract::<&PyTuple>(py) {
-                let keys = tuple
-                    .iter()
-                    .map(|item| item.extract::<&str>())
-                    .collect::<PyResult<Vec<&str>>>()?;
-                self.select_columns(keys)
-            } else if let Ok(keys) = key.extract::<Vec<&str>>(py) {
-                self.select_columns(keys)
-            } else {
-                let message = "DataFrame can only be indexed by string index or indices";
-                Err(PyTypeError::new_err(message))
-            }
-        })
+    /// Selects columns by name, returning a DataFrame also for a single name, or executes the
+    /// query and returns a row as a dict for an integer, or a list of rows for a slice, see `row`
+    fn __getitem__(&self, key: PyObject, py: Python) -> PyResult<PyObject> {
+        // bool is a subclass of int, which would index the first two rows
+        if key.as_ref(py).is_instance_of::<PyBool>() {
+            return Err(PyTypeError::new_err(
+                "DataFrame can't be indexed by a bool, pass an int index of a row",
+            ));
+        }
+        if let Ok(index) = key.extract::<isize>(py) {
+            self.row(index, py)
+        } else if let Ok(slice) = key.downcast::<PySlice>(py) {
+            self.slice_rows(slice, py)
+        } else if let Ok(key) = key.extract::<&str>(py) {
+            Ok(self.select_named_columns(vec![key])?.into_py(py))
+        } else if let Ok(tuple) = key.extract::<&PyTuple>(py) {
+            let keys = tuple
+                .iter()
+                .map(|item| item.extract::<&str>())
+                .collect::<PyResult<Vec<&str>>>()?;
+            Ok(self.select_named_columns(keys)?.into_py(py))
+        } else if let Ok(keys) = key.extract::<Vec<&str>>(py) {
+            Ok(self.select_named_columns(keys)?.into_py(py))
+        } else {
+            let message = "DataFrame can only be indexed by string index or indices";
+            Err(PyTypeError::new_err(message))
+        }
+    }
+
+    /// DataFrames aren't iterable, which Python would otherwise do by indexing the rows one at a
+    /// time from 0 until an IndexError, executing the query again for each row
+    fn __iter__(&self) -> PyResult<()> {
+        Err(PyTypeError::new_err(
+            "DataFrame is not iterable, iterate over execute_stream() or to_pylist() instead",
+        ))
+    }
+
+    /// Executes the query up to row `index` and returns that row as a dict. A negative index
+    /// counts from the end, which executes the whole query to count the rows. Only meant for
+    /// small offsets, as the rows before `index` are computed too.
+    fn row(&self, index: isize, py: Python) -> PyResult<PyObject> {
+        let position = if index < 0 {
+            self.count(py)? as isize + index
+        } else {
+            index
+        };
+        let rows = match usize::try_from(position) {
+            Ok(position) => self.collect_rows(position, Some(1), py)?,
+            Err(_) => PyList::empty(py),
+        };
+        match rows.get_item(0) {
+            Ok(row) => Ok(row.into()),
+            Err(_) => Err(PyIndexError::new_err(format!(
+                "Row index {index} is out of range"
+            ))),
+        }
     }
 
     fn __repr__(&self, py: Python) -> PyResult<String> {
@@ -78,6 +431,46 @@ impl PyDataFrame {
         }
     }
 
+    /// Renders the first rows as an HTML table for notebooks, with the column types in the
+    /// header and the display options of the session
+    fn _repr_html_(&self, py: Python) -> PyResult<String> {
+        let preview = collect_preview(py, &self.df, Some(HTML_PREVIEW_ROWS), true)?;
+        let schema: Schema = self.df.schema().into();
+        let options = self.display_options(None, None, None);
+        let html = format_html(
+            &schema,
+            &preview.batches,
+            &options,
+            preview.truncated,
+            preview.total_rows,
+        )
+        .map_err(DataFusionError::from)?;
+        Ok(html)
+    }
+
+    /// Collects both DataFrames and raises an AssertionError describing the first difference
+    /// in their column names, types, nullability or rows. Rows are compared in order only when
+    /// `check_row_order` is set, since the order isn't defined without a sort, and a differing
+    /// row is then given by its index in each DataFrame. Without `check_dtype`, the values of
+    /// `other` are cast to the types of this DataFrame and the nullability isn't compared.
+    #[pyo3(signature = (other, check_dtype=true, check_row_order=false))]
+    fn assert_frame_equal(
+        &self,
+        other: PyDataFrame,
+        check_dtype: bool,
+        check_row_order: bool,
+        py: Python,
+    ) -> PyResult<()> {
+        let left = collect_single_batch(py, &self.df)?;
+        let right = collect_single_batch(py, &other.df)?;
+        match compare_batches(&left, &right, check_dtype, check_row_order)
+            .map_err(DataFusionError::from)?
+        {
+            Some(difference) => Err(PyAssertionError::new_err(difference)),
+            None => Ok(()),
+        }
+    }
+
     /// Calculate summary statistics for a DataFrame
     fn describe(&self, py: Python) -> PyResult<Self> {
         let df = self.df.as_ref().clone();
@@ -103,8 +496,145 @@ impl PyDataFrame {
         Ok(Self::new(df))
     }
 
+    /// Cast and reorder the columns to match `target`, so the result has exactly its column
+    /// names, order and types. Raises if a target column is missing or can't be cast to the
+    /// target type. Columns not in `target` are dropped, or raise when `strict` is set.
+    /// The result has the nullability of `target`, reading a null from a column that isn't
+    /// nullable raises an error.
+    #[pyo3(signature = (target, strict=false))]
+    fn with_schema(&self, target: SchemaArg, strict: bool) -> PyResult<Self> {
+        let schema = self.df.schema();
+        if strict {
+            let extra: Vec<&str> = schema
+                .fields()
+                .iter()
+                .map(|field| field.name().as_str())
+                .filter(|name| target.0.field_with_name(name).is_err())
+                .collect();
+            if !extra.is_empty() {
+                return Err(DataFusionError::Common(format!(
+                    "Columns not in the target schema: {}",
+                    extra.join(", ")
+                ))
+                .into());
+            }
+        }
+
+        let exprs = target
+            .0
+            .fields()
+            .iter()
+            .map(|target_field| {
+                let name = target_field.name();
+                let field = schema.field_with_unqualified_name(name).map_err(|_| {
+                    DataFusionError::Common(format!(
+                        "Column '{name}' of the target schema is missing"
+                    ))
+                })?;
+                let column = Expr::Column(field.qualified_column());
+                if field.data_type() == target_field.data_type() {
+                    return Ok(column.alias(name));
+                }
+                if !can_cast_types(field.data_type(), target_field.data_type()) {
+                    return Err(DataFusionError::Common(format!(
+                        "Cannot cast column '{name}' from {} to {}",
+                        field.data_type(),
+                        target_field.data_type()
+                    )));
+                }
+                Ok(cast(column, target_field.data_type().clone()).alias(name))
+            })
+            .collect::<Result<Vec<_>, DataFusionError>>()?;
+
+        let df = self.df.as_ref().clone().select(exprs)?;
+        // A projection keeps the nullability of its input, so when it differs from the target
+        // the projection is read through a table with the target schema
+        let same_nullability = df
+            .schema()
+            .fields()
+            .iter()
+            .zip(target.0.fields())
+            .all(|(field, target_field)| field.is_nullable() == target_field.is_nullable());
+        if same_nullability {
+            return Ok(Self::new(df));
+        }
+        let (state, plan) = df.into_parts();
+        let view = DataFrame::new(state.clone(), plan).into_view();
+        let table = NullabilityTable::new(view, Arc::new(target.0));
+        let plan =
+            LogicalPlanBuilder::scan(UNNAMED_TABLE, provider_as_source(Arc::new(table)), None)?
+                .build()?;
+        Ok(Self::new(DataFrame::new(state, plan)))
+    }
+
+    /// Cast the columns named in `mapping` to its types, given as pyarrow DataTypes or type
+    /// names such as `"int64"`, keeping the other columns as they are. Values that can't be cast
+    /// raise an error unless `safe` is set, in which case they become null. `safe` and its
+    /// alias `errors` are as for `Expr.cast`.
+    #[pyo3(signature = (mapping, safe=false, errors=None))]
+    fn cast(&self, mapping: SchemaArg, safe: bool, errors: Option<&str>) -> PyResult<Self> {
+        let null_on_error = parse_safe(safe, errors)?;
+        let schema = self.df.schema();
+        for target_field in mapping.0.fields() {
+            let name = target_field.name();
+            let field = schema.field_with_unqualified_name(name).map_err(|_| {
+                DataFusionError::Common(format!("Column '{name}' to cast is missing"))
+            })?;
+            if !can_cast_types(field.data_type(), target_field.data_type()) {
+                return Err(DataFusionError::Common(format!(
+                    "Cannot cast column '{name}' from {} to {}",
+                    field.data_type(),
+                    target_field.data_type()
+                ))
+                .into());
+            }
+        }
+
+        let exprs = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = Expr::Column(field.qualified_column());
+                let data_type = match mapping.0.field_with_name(field.name()) {
+                    Ok(target_field) => target_field.data_type().clone(),
+                    Err(_) => return column,
+                };
+                if field.data_type() == &data_type {
+                    return column;
+                }
+                let expr = Box::new(column);
+                let cast = if null_on_error {
+                    Expr::TryCast(expr::TryCast::new(expr, data_type))
+                } else {
+                    Expr::Cast(expr::Cast::new(expr, data_type))
+                };
+                cast.alias(field.name())
+            })
+            .collect::<Vec<_>>();
+
+        let df = self.df.as_ref().clone().select(exprs)?;
+        Ok(Self::new(df))
+    }
+
+    /// Calls `func(df, *args, **kwargs)` with this DataFrame and returns its result, to chain
+    /// reusable transformations written as functions with the methods of the DataFrame
+    #[pyo3(signature = (func, *args, **kwargs))]
+    fn pipe(
+        slf: PyRef<Self>,
+        func: &PyAny,
+        args: &PyTuple,
+        kwargs: Option<&PyDict>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let mut func_args = vec![slf.into_py(py)];
+        func_args.extend(args.iter().map(|arg| arg.into_py(py)));
+        Ok(func.call(PyTuple::new(py, func_args), kwargs)?.into())
+    }
+
     fn filter(&self, predicate: PyExpr) -> PyResult<Self> {
-        let df = self.df.as_ref().clone().filter(predicate.into())?;
+        // correlated subqueries reference the columns of this DataFrame by name
+        let predicate = bind_outer_references(predicate.into(), self.df.schema())?;
+        let df = self.df.as_ref().clone().filter(predicate)?;
         Ok(Self::new(df))
     }
 
@@ -124,16 +654,170 @@ impl PyDataFrame {
         Ok(Self::new(df))
     }
 
-    fn aggregate(&self, group_by: Vec<PyExpr>, aggs: Vec<PyExpr>) -> PyResult<Self> {
-        let group_by = group_by.into_iter().map(|e| e.into()).collect();
-        let aggs = aggs.into_iter().map(|e| e.into()).collect();
+    /// Rename every column, assigning `names` to the columns in order
+    fn rename_all(&self, names: Vec<String>) -> PyResult<Self> {
+        let fields = self.df.schema().fields();
+        if names.len() != fields.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected {} names, one per column, but got {}",
+                fields.len(),
+                names.len()
+            )));
+        }
+        let mut seen = HashSet::new();
+        if let Some(name) = names.iter().find(|name| !seen.insert(name.as_str())) {
+            return Err(PyValueError::new_err(format!(
+                "Column name '{name}' is given more than once"
+            )));
+        }
+
+        let exprs = fields
+            .iter()
+            .zip(names)
+            .map(|(field, name)| Expr::Column(field.qualified_column()).alias(name))
+            .collect::<Vec<_>>();
+        Ok(Self::new(self.df.as_ref().clone().select(exprs)?))
+    }
+
+    /// Expand struct columns into a column per field, named `<column><separator><field>`.
+    /// Nested structs are expanded too, up to `max_depth` levels. Only the struct columns in
+    /// `columns` are expanded, or all of them when it's not given.
+    #[pyo3(signature = (columns=None, separator=".", max_depth=None))]
+    fn flatten(
+        &self,
+        columns: Option<Vec<String>>,
+        separator: &str,
+        max_depth: Option<usize>,
+    ) -> PyResult<Self> {
+        let schema = self.df.schema();
+        for name in columns.iter().flatten() {
+            let field = schema
+                .field_with_unqualified_name(name)
+                .map_err(|_| PyValueError::new_err(format!("Column '{name}' doesn't exist")))?;
+            if !matches!(field.data_type(), DataType::Struct(_)) {
+                return Err(PyValueError::new_err(format!(
+                    "Column '{name}' is not a struct and can't be flattened"
+                )));
+            }
+        }
+
+        let mut flattened = Vec::new();
+        for field in schema.fields() {
+            let column = Expr::Column(field.qualified_column());
+            let name = field.name().clone();
+            if columns
+                .as_ref()
+                .map_or(true, |columns| columns.contains(&name))
+            {
+                let depth = max_depth.unwrap_or(usize::MAX);
+                flatten_fields(
+                    column,
+                    name,
+                    field.data_type(),
+                    separator,
+                    depth,
+                    &mut flattened,
+                );
+            } else {
+                flattened.push((name, column));
+            }
+        }
+        let mut seen = HashSet::new();
+        if let Some((name, _)) = flattened
+            .iter()
+            .find(|(name, _)| !seen.insert(name.as_str()))
+        {
+            return Err(PyValueError::new_err(format!(
+                "Flattening produces the column name '{name}' more than once, \
+                 pass a different separator"
+            )));
+        }
+
+        let exprs = flattened
+            .into_iter()
+            .map(|(name, expr)| expr.alias(name))
+            .collect::<Vec<_>>();
+        Ok(Self::new(self.df.as_ref().clone().select(exprs)?))
+    }
+
+    /// Group by `group_by` and compute `aggs`. Both lists hold expressions or SQL strings,
+    /// such as `"region"` and `"sum(sales) AS total"`, which are parsed against the schema
+    /// of the DataFrame. An aggregate string without an alias names its column as written.
+    fn aggregate(&self, group_by: Vec<&PyAny>, aggs: Vec<&PyAny>) -> PyResult<Self> {
+        let group_by = self.parse_group_by(group_by)?;
+        let aggs = self.parse_aggregates(aggs)?;
         let df = self.df.as_ref().clone().aggregate(group_by, aggs)?;
         Ok(Self::new(df))
     }
 
+    /// Returns a GroupBy grouping by `keys`, expressions or SQL strings as for `aggregate`, for
+    /// computing aggregates with its `agg` method or one of its shortcuts
+    #[pyo3(signature = (*keys))]
+    fn group_by(&self, keys: Vec<&PyAny>) -> PyResult<PyGroupBy> {
+        Ok(PyGroupBy::new(self.clone(), self.parse_group_by(keys)?))
+    }
+
+    /// Sort by the given expressions, each either a sort expression created with
+    /// `Expr.sort(ascending, nulls_first)` or a list of them. Plain expressions sort
+    /// ascending, and keys without a `nulls_first` order nulls by the default null ordering of
+    /// the session, first unless configured otherwise.
     #[pyo3(signature = (*exprs))]
-    fn sort(&self, exprs: Vec<PyExpr>) -> PyResult<Self> {
-        let exprs = exprs.into_iter().map(|e| e.into()).collect();
+    fn sort(&self, exprs: &PyTuple) -> PyResult<Self> {
+        let mut sort_exprs = vec![];
+        for expr in exprs {
+            match expr.extract::<Vec<&PyAny>>() {
+                Ok(list) => sort_exprs.extend(list),
+                Err(_) => sort_exprs.push(expr),
+            }
+        }
+        let default = self.default_nulls_first();
+        let exprs = sort_exprs
+            .into_iter()
+            .map(|e| {
+                let nulls_first_given = match e.downcast::<PyCell<PySortExpr>>() {
+                    Ok(sort) => sort.borrow().nulls_first_given,
+                    Err(_) => true,
+                };
+                Ok(match e.extract::<PyExpr>()?.expr {
+                    Expr::Sort(mut sort) => {
+                        if let (false, Some(nulls_first)) = (nulls_first_given, default) {
+                            sort.nulls_first = nulls_first;
+                        }
+                        Expr::Sort(sort)
+                    }
+                    expr => expr.sort(true, default.unwrap_or(true)),
+                })
+            })
+            .collect::<PyResult<_>>()?;
+        let df = self.df.as_ref().clone().sort(exprs)?;
+        Ok(Self::new(df))
+    }
+
+    /// Sort by the named columns. `ascending` is either a single flag for all the columns or
+    /// one flag per column. Nulls sort as in `sort`, by the default null ordering of the
+    /// session, first unless configured otherwise.
+    #[pyo3(signature = (columns, ascending=None))]
+    fn sort_by(&self, columns: Vec<&str>, ascending: Option<&PyAny>) -> PyResult<Self> {
+        let ascending = match ascending {
+            None => vec![true; columns.len()],
+            Some(ascending) => match ascending.extract::<bool>() {
+                Ok(ascending) => vec![ascending; columns.len()],
+                Err(_) => ascending.extract::<Vec<bool>>()?,
+            },
+        };
+        if ascending.len() != columns.len() {
+            return Err(PyValueError::new_err(format!(
+                "ascending has {} values but {} columns are sorted",
+                ascending.len(),
+                columns.len()
+            )));
+        }
+        let default = self.default_nulls_first();
+        let exprs = columns
+            .into_iter()
+            .zip(ascending)
+            .map(|(name, asc)| col(name).sort(asc, default.unwrap_or(true)))
+            .collect();
         let df = self.df.as_ref().clone().sort(exprs)?;
         Ok(Self::new(df))
     }
@@ -146,14 +830,86 @@ impl PyDataFrame {
 
     /// Executes the plan, returning a list of `RecordBatch`es.
     /// Unless some order is specified in the plan, there is no
-    /// guarantee of the order of the result.
-    fn collect(&self, py: Python) -> PyResult<Vec<PyObject>> {
-        let batches = wait_for_future(py, self.df.as_ref().clone().collect())?;
+    /// guarantee of the order of the result. The optional `progress_callback` is called
+    /// with the cumulative row count after each batch.
+    #[pyo3(signature = (progress_callback=None))]
+    fn collect(&self, progress_callback: Option<PyObject>, py: Python) -> PyResult<Vec<PyObject>> {
+        if progress_callback.is_some() {
+            let mut stream = self.execute_stream(progress_callback, py)?;
+            let mut batches = vec![];
+            while let Some(batch) = stream.next_batch(py)? {
+                batches.push(batch.to_pyarrow(py)?);
+            }
+            return Ok(batches);
+        }
+        let batches = self.collect_cached(py)?;
         // cannot use PyResult<Vec<RecordBatch>> return type due to
         // https://github.com/PyO3/pyo3/issues/1813
         batches.into_iter().map(|rb| rb.to_pyarrow(py)).collect()
     }
 
+    /// Executes the plan and returns its result as a single `RecordBatch`, concatenating the
+    /// batches it produces. This defeats streaming: the whole result is held in memory, and
+    /// copied once more to concatenate it, so prefer `collect` or `execute_stream` for large
+    /// results.
+    fn collect_single_batch(&self, py: Python) -> PyResult<PyObject> {
+        let batches = self.collect_cached(py)?;
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => Arc::new(Schema::from(self.df.schema())),
+        };
+        for (i, field) in schema.fields().iter().enumerate() {
+            let columns: Vec<&dyn Array> = batches.iter().map(|b| b.column(i).as_ref()).collect();
+            check_offsets(field.name(), &columns)?;
+        }
+        let batch = concat_batches(&schema, &batches).map_err(DataFusionError::from)?;
+        batch.to_pyarrow(py)
+    }
+
+    /// Executes the plan like `collect`, and also returns the metrics recorded by each
+    /// operator of the physical plan. The metrics are a list with one entry per operator,
+    /// parents before their children, holding the `operator`, its `depth` in the plan and
+    /// its `metrics` summed over all partitions
+    fn collect_with_metrics(&self, py: Python) -> PyResult<(Vec<PyObject>, Vec<PyObject>)> {
+        let df = self.df.as_ref().clone();
+        let task_ctx = Arc::new(df.task_ctx());
+        let plan = wait_for_future(py, df.create_physical_plan())?;
+        let batches = wait_for_future(py, physical_plan::collect(plan.clone(), task_ctx))?;
+        let batches = batches
+            .into_iter()
+            .map(|rb| rb.to_pyarrow(py))
+            .collect::<PyResult<_>>()?;
+
+        let mut metrics = vec![];
+        collect_plan_metrics(py, &plan, 0, &mut metrics)?;
+        Ok((batches, metrics))
+    }
+
+    /// Executes the plan, discarding its result, and returns the metrics recorded by its physical
+    /// plan as a tree of dicts, one per operator, holding the `operator`, its `output_rows` and
+    /// `elapsed_compute_nanos`, all of its `metrics` and its `children`. The values are summed
+    /// over the partitions of an operator, and are `None` for those it doesn't record
+    fn explain_analyze_to_metrics(&self, py: Python) -> PyResult<PyObject> {
+        let df = self.df.as_ref().clone();
+        let task_ctx = Arc::new(df.task_ctx());
+        let plan = wait_for_future(py, df.create_physical_plan())?;
+        wait_for_future(py, async {
+            let mut stream = physical_plan::execute_stream(plan.clone(), task_ctx)?;
+            while let Some(batch) = stream.next().await {
+                batch?;
+            }
+            Ok::<_, datafusion::error::DataFusionError>(())
+        })?;
+        plan_metrics_tree(py, &plan)
+    }
+
+    /// Returns the number of rows the planner estimates this DataFrame to produce, based on
+    /// the statistics of its physical plan. Returns `None` when no estimate is available
+    fn estimated_row_count(&self, py: Python) -> PyResult<Option<usize>> {
+        let plan = wait_for_future(py, self.df.as_ref().clone().create_physical_plan())?;
+        Ok(plan.statistics().num_rows)
+    }
+
     /// Cache DataFrame.
     fn cache(&self, py: Python) -> PyResult<Self> {
         let df = wait_for_future(py, self.df.as_ref().clone().cache())?;
@@ -171,11 +927,83 @@ impl PyDataFrame {
             .collect()
     }
 
-    /// Print the result, 20 lines by default
-    #[pyo3(signature = (num=20))]
-    fn show(&self, py: Python, num: usize) -> PyResult<()> {
-        let df = self.df.as_ref().clone().limit(0, Some(num))?;
-        print_dataframe(py, df)
+    /// Executes this DataFrame and returns a stream over all of its record batches. The
+    /// optional `progress_callback` is called with the cumulative row count after each batch
+    #[pyo3(signature = (progress_callback=None))]
+    fn execute_stream(
+        &self,
+        progress_callback: Option<PyObject>,
+        py: Python,
+    ) -> PyResult<PyRecordBatchStream> {
+        let stream = wait_for_future(py, self.df.as_ref().clone().execute_stream())?;
+        Ok(PyRecordBatchStream::new(stream).with_progress_callback(progress_callback))
+    }
+
+    /// Executes this DataFrame and returns one stream of record batches per output partition.
+    /// The number of streams depends on the output partitioning of the plan
+    fn execute_stream_partitioned(&self, py: Python) -> PyResult<Vec<PyRecordBatchStream>> {
+        let streams = wait_for_future(py, self.df.as_ref().clone().execute_stream_partitioned())?;
+        Ok(streams.into_iter().map(PyRecordBatchStream::new).collect())
+    }
+
+    /// Print the result, 20 lines by default. Only the printed rows are computed, and a footer
+    /// tells when there are more, with their number if the planner knows it exactly. The
+    /// query can be interrupted with Ctrl-C or by interrupting the notebook kernel.
+    /// Values wider than `max_width` characters are cut off, ending with an ellipsis so that
+    /// `max_width` is at least 3, or wrapped onto more lines if `truncate` is false. The
+    /// header is printed for an empty result too. The formatting arguments override the
+    /// display options of the session, see `SessionConfig.with_display_options`.
+    #[pyo3(signature = (num=20, max_width=None, truncate=true, timestamp_format=None, float_precision=None, null_repr=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn show(
+        &self,
+        py: Python,
+        num: usize,
+        max_width: Option<usize>,
+        truncate: bool,
+        timestamp_format: Option<String>,
+        float_precision: Option<usize>,
+        null_repr: Option<String>,
+    ) -> PyResult<()> {
+        let options = self.preview_options(
+            max_width,
+            truncate,
+            timestamp_format,
+            float_precision,
+            null_repr,
+        )?;
+        print_preview(py, &self.df, Some(num), true, |schema, batches| {
+            Ok(format!(
+                "DataFrame()\n{}",
+                format_table(schema, batches, &options)?
+            ))
+        })
+    }
+
+    /// Print the result with one line per field, which suits tables with many columns.
+    /// Takes the same arguments as `show`, but prints no footer.
+    #[pyo3(signature = (num=20, max_width=None, truncate=true, timestamp_format=None, float_precision=None, null_repr=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn show_vertical(
+        &self,
+        py: Python,
+        num: usize,
+        max_width: Option<usize>,
+        truncate: bool,
+        timestamp_format: Option<String>,
+        float_precision: Option<usize>,
+        null_repr: Option<String>,
+    ) -> PyResult<()> {
+        let options = self.preview_options(
+            max_width,
+            truncate,
+            timestamp_format,
+            float_precision,
+            null_repr,
+        )?;
+        print_preview(py, &self.df, Some(num), false, |schema, batches| {
+            format_vertical(schema, batches, &options)
+        })
     }
 
     /// Filter out duplicate rows
@@ -184,42 +1012,91 @@ impl PyDataFrame {
         Ok(Self::new(df))
     }
 
+    /// Join with another `DataFrame` on the given key columns. If `null_equals_null` is true,
+    /// keys are compared with `IS NOT DISTINCT FROM` so that a null key matches a null key.
+    #[pyo3(signature = (right, join_keys, how, null_equals_null=false))]
     fn join(
         &self,
         right: PyDataFrame,
         join_keys: (Vec<&str>, Vec<&str>),
         how: &str,
+        null_equals_null: bool,
     ) -> PyResult<Self> {
         let join_type = match how {
             "inner" => JoinType::Inner,
             "left" => JoinType::Left,
             "right" => JoinType::Right,
             "full" => JoinType::Full,
-            "semi" => JoinType::LeftSemi,
-            "anti" => JoinType::LeftAnti,
+            "semi" | "leftsemi" => JoinType::LeftSemi,
+            "anti" | "leftanti" => JoinType::LeftAnti,
+            "rightsemi" => JoinType::RightSemi,
+            "rightanti" => JoinType::RightAnti,
             how => {
                 return Err(DataFusionError::Common(format!(
-                    "The join type {how} does not exist or is not implemented"
+                    "The join type {how} does not exist or is not implemented, \
+                     supported values are: inner, left, right, full, semi, anti, \
+                     leftsemi, leftanti, rightsemi and rightanti."
                 ))
                 .into());
             }
         };
 
-        let df = self.df.as_ref().clone().join(
-            right.df.as_ref().clone(),
-            join_type,
-            &join_keys.0,
-            &join_keys.1,
-            None,
-        )?;
-        Ok(Self::new(df))
+        let (state, plan) = self.df.as_ref().clone().into_parts();
+        let plan = LogicalPlanBuilder::from(plan)
+            .join_detailed(
+                right.df.logical_plan().clone(),
+                join_type,
+                join_keys,
+                None,
+                null_equals_null,
+            )?
+            .build()?;
+        Ok(Self::new(DataFrame::new(state, plan)))
     }
 
-    /// Print the query plan
-    #[pyo3(signature = (verbose=false, analyze=false))]
-    fn explain(&self, py: Python, verbose: bool, analyze: bool) -> PyResult<()> {
-        let df = self.df.as_ref().clone().explain(verbose, analyze)?;
-        print_dataframe(py, df)
+    /// Print the query plan. With `with_statistics` each node of the optimized logical plan and
+    /// each operator of the physical plan is annotated with its estimated row count and size,
+    /// without running the query. A logical node is given the estimates of the physical plan it
+    /// is planned into, and the plan explained by `analyze` only has those of its operators.
+    #[pyo3(signature = (verbose=false, analyze=false, with_statistics=false))]
+    fn explain(
+        &self,
+        py: Python,
+        verbose: bool,
+        analyze: bool,
+        with_statistics: bool,
+    ) -> PyResult<()> {
+        let mut df = if verbose || analyze {
+            self.df.as_ref().clone()
+        } else {
+            // The final plans are the same on every call, unlike the run of `analyze` and the
+            // intermediate plans of `verbose`, so the plan optimized once is explained
+            self.optimized_df()?
+        };
+        if with_statistics {
+            let (state, plan) = df.into_parts();
+            let config = state
+                .config()
+                .clone()
+                .set_bool("datafusion.explain.show_statistics", true);
+            df = DataFrame::new(with_session_config(&state, config), plan);
+        }
+        let logical_plan = if with_statistics && !analyze {
+            Some(self.logical_plan_with_statistics(py)?)
+        } else {
+            None
+        };
+        let df = df.explain(verbose, analyze)?;
+        print_preview(py, &df, None, false, |schema, batches| {
+            let batches = match &logical_plan {
+                Some(text) => replace_plan(batches, "logical_plan", text)?,
+                None => batches.to_vec(),
+            };
+            Ok(format!(
+                "DataFrame()\n{}",
+                format_table(schema, &batches, &DisplayOptions::default())?
+            ))
+        })
     }
 
     /// Get the logical plan for this `DataFrame`
@@ -229,7 +1106,111 @@ impl PyDataFrame {
 
     /// Get the optimized logical plan for this `DataFrame`
     fn optimized_logical_plan(&self) -> PyResult<PyLogicalPlan> {
-        Ok(self.df.as_ref().clone().into_optimized_plan()?.into())
+        Ok(self.optimized_plan()?.clone().into())
+    }
+
+    /// Returns a SHA-256 hex digest of the optimized logical plan, including its literals and
+    /// schemas, which is the same for equal plans in every run of the same version. Tables are
+    /// identified by their name, unregistered files by their paths and other unregistered
+    /// tables, such as in-memory data, by their identity, so that only the DataFrames deriving
+    /// from the same one match
+    fn plan_fingerprint(&self) -> PyResult<String> {
+        let (fingerprint, _) = fingerprint_plan(self.optimized_plan()?)?;
+        Ok(fingerprint)
+    }
+
+    /// Serialize the logical plan of this `DataFrame` to Substrait bytes
+    fn to_substrait_bytes(&self, py: Python) -> PyResult<PyObject> {
+        PySubstraitSerializer::serialize_df(self, py)
+    }
+
+    /// Converts the optimized logical plan of this `DataFrame` to Substrait and back, and
+    /// returns a dict of the indented text of the `original` plan and of the `round_trip` plan,
+    /// and of the `schema_differences` between them, empty when the schemas are equal
+    fn explain_substrait(&self, py: Python) -> PyResult<PyObject> {
+        let (state, plan) = self.df.as_ref().clone().into_parts();
+        let plan = state.optimize(&plan)?;
+        let ctx = SessionContext::with_state(state);
+        let round_trip = wait_for_future(py, substrait::round_trip(&plan, &ctx))?;
+
+        let describe = |field: &DFField| {
+            let nullable = if field.is_nullable() { "" } else { " not null" };
+            format!("{}: {}{nullable}", field.name(), field.data_type())
+        };
+        let (fields, round_trip_fields) = (plan.schema().fields(), round_trip.schema().fields());
+        let mut differences = vec![];
+        if fields.len() != round_trip_fields.len() {
+            differences.push(format!(
+                "{} columns != {} columns",
+                fields.len(),
+                round_trip_fields.len()
+            ));
+        }
+        for (i, (field, round_trip_field)) in fields.iter().zip(round_trip_fields).enumerate() {
+            let (field, round_trip_field) = (describe(field), describe(round_trip_field));
+            if field != round_trip_field {
+                differences.push(format!("column {i}: {field} != {round_trip_field}"));
+            }
+        }
+
+        let explain = PyDict::new(py);
+        explain.set_item("original", plan.display_indent().to_string())?;
+        explain.set_item("round_trip", round_trip.display_indent().to_string())?;
+        explain.set_item("schema_differences", differences)?;
+        Ok(explain.into())
+    }
+
+    /// Selects `udf_expr`, which calls Python UDFs, from this `DataFrame`, executes it and returns
+    /// a dict of the `batches` and `rows` computed by the UDFs, the `total_seconds` of the query,
+    /// the `udf_seconds` spent in the Python functions and the `conversion_seconds` spent passing
+    /// their arguments and results between DataFusion and pyarrow, also divided by the batches
+    /// as `conversion_seconds_per_batch`. Other queries calling the same UDFs meanwhile are
+    /// counted too
+    fn profile_udf_overhead(&self, udf_expr: PyExpr, py: Python) -> PyResult<PyObject> {
+        let mut profiles: Vec<Arc<UdfProfile>> = vec![];
+        udf_expr.expr.apply(&mut |expr| {
+            if let Expr::ScalarUDF(function) = expr {
+                if let Some(profile) = udf_profile(&function.fun) {
+                    if !profiles.iter().any(|p| Arc::ptr_eq(p, &profile)) {
+                        profiles.push(profile);
+                    }
+                }
+            }
+            Ok(VisitRecursion::Continue)
+        })?;
+        if profiles.is_empty() {
+            return Err(PyValueError::new_err("udf_expr doesn't call a Python UDF"));
+        }
+        let totals = || {
+            profiles.iter().fold([0; 4], |mut totals, profile| {
+                for (total, value) in totals.iter_mut().zip(profile.snapshot()) {
+                    *total += value;
+                }
+                totals
+            })
+        };
+
+        let df = self.df.as_ref().clone().select(vec![udf_expr.expr])?;
+        let before = totals();
+        let start = Instant::now();
+        wait_for_future(py, df.collect())?;
+        let total = start.elapsed();
+        let after = totals();
+        let [batches, rows, conversion_nanos, call_nanos] =
+            [0, 1, 2, 3].map(|i| after[i] - before[i]);
+
+        let seconds = |nanos: u64| nanos as f64 / 1e9;
+        let profile = PyDict::new(py);
+        profile.set_item("batches", batches)?;
+        profile.set_item("rows", rows)?;
+        profile.set_item("total_seconds", total.as_secs_f64())?;
+        profile.set_item("udf_seconds", seconds(call_nanos))?;
+        profile.set_item("conversion_seconds", seconds(conversion_nanos))?;
+        profile.set_item(
+            "conversion_seconds_per_batch",
+            seconds(conversion_nanos) / batches.max(1) as f64,
+        )?;
+        Ok(profile.into())
     }
 
     /// Get the execution plan for this `DataFrame`
@@ -238,6 +1219,74 @@ impl PyDataFrame {
         Ok(plan.into())
     }
 
+    /// Writes the logical, optimized and physical plans of this `DataFrame`, and its schema, as
+    /// text files to the directory `path`, creating it if needed. The query is not executed.
+    fn export_plan(&self, path: PathBuf, py: Python) -> PyResult<()> {
+        let df = self.df.as_ref().clone();
+        let logical_plan = df.logical_plan().clone();
+        let optimized_plan = df.clone().into_optimized_plan()?;
+        let physical_plan = wait_for_future(py, df.create_physical_plan())?;
+        let schema = self
+            .df
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let nullable = if field.is_nullable() { "" } else { " not null" };
+                format!("{}: {}{nullable}\n", field.name(), field.data_type())
+            })
+            .collect::<String>();
+
+        fs::create_dir_all(&path)?;
+        let files = [
+            (
+                "logical_plan.txt",
+                logical_plan.display_indent().to_string(),
+            ),
+            (
+                "optimized_plan.txt",
+                optimized_plan.display_indent().to_string(),
+            ),
+            (
+                "physical_plan.txt",
+                displayable(physical_plan.as_ref()).indent(true).to_string(),
+            ),
+            ("schema.txt", schema),
+        ];
+        for (name, contents) in files {
+            fs::write(path.join(name), contents)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a `DataFrame` that produces its rows in the order of its input, such as the order
+    /// of the rows in a file. Repartitioning is disabled and partitions are read one after the
+    /// other instead of concurrently, so queries run with less parallelism.
+    fn maintain_order(&self) -> Self {
+        let (state, plan) = self.df.as_ref().clone().into_parts();
+        if state
+            .physical_optimizers()
+            .iter()
+            .any(|rule| rule.name() == MaintainOrder::NAME)
+        {
+            return self.clone();
+        }
+
+        let config = state
+            .config()
+            .clone()
+            .with_target_partitions(1)
+            .with_repartition_joins(false)
+            .with_repartition_aggregations(false)
+            .with_repartition_windows(false)
+            .with_repartition_sorts(false)
+            .with_repartition_file_scans(false);
+        let mut rules = state.physical_optimizers().to_vec();
+        rules.push(Arc::new(MaintainOrder));
+        let ordered = with_session_config(&state, config).with_physical_optimizer_rules(rules);
+        PyDataFrame::new(DataFrame::new(ordered, plan))
+    }
+
     /// Repartition a `DataFrame` based on a logical partitioning scheme.
     fn repartition(&self, num: usize) -> PyResult<Self> {
         let new_df = self
@@ -315,23 +1364,42 @@ impl PyDataFrame {
         Ok(())
     }
 
-    /// Write a `DataFrame` to a Parquet file.
+    /// Write a `DataFrame` to a Parquet file. `statistics_enabled` maps column names to whether
+    /// min/max statistics are written for them, which they are by default.
     #[pyo3(signature = (
         path,
         compression="uncompressed",
-        compression_level=None
+        compression_level=None,
+        max_row_group_size=None,
+        data_page_size_limit=None,
+        write_batch_size=None,
+        statistics_enabled=None
         ))]
+    #[allow(clippy::too_many_arguments)]
     fn write_parquet(
         &self,
         path: &str,
         compression: &str,
         compression_level: Option<u32>,
+        max_row_group_size: Option<i64>,
+        data_page_size_limit: Option<i64>,
+        write_batch_size: Option<i64>,
+        statistics_enabled: Option<HashMap<String, bool>>,
         py: Python,
     ) -> PyResult<()> {
         fn verify_compression_level(cl: Option<u32>) -> Result<u32, PyErr> {
             cl.ok_or(PyValueError::new_err("compression_level is not defined"))
         }
 
+        fn verify_positive(name: &str, value: Option<i64>) -> Result<Option<usize>, PyErr> {
+            match value {
+                Some(value) if value <= 0 => Err(PyValueError::new_err(format!(
+                    "{name} must be a positive integer, got {value}"
+                ))),
+                value => Ok(value.map(|value| value as usize)),
+            }
+        }
+
         let compression_type = match compression.to_lowercase().as_str() {
             "snappy" => Compression::SNAPPY,
             "gzip" => Compression::GZIP(
@@ -357,9 +1425,43 @@ impl PyDataFrame {
             }
         };
 
-        let writer_properties = WriterProperties::builder()
-            .set_compression(compression_type)
-            .build();
+        let mut writer_properties = WriterProperties::builder().set_compression(compression_type);
+        if let Some(size) = verify_positive("max_row_group_size", max_row_group_size)? {
+            writer_properties = writer_properties.set_max_row_group_size(size);
+        }
+        if let Some(size) = verify_positive("data_page_size_limit", data_page_size_limit)? {
+            writer_properties = writer_properties.set_data_page_size_limit(size);
+        }
+        if let Some(size) = verify_positive("write_batch_size", write_batch_size)? {
+            writer_properties = writer_properties.set_write_batch_size(size);
+        }
+        if let Some(statistics_enabled) = statistics_enabled {
+            // the statistics are set per leaf column, so a struct column sets those of its fields
+            let schema = arrow_to_parquet_schema(&Schema::from(self.df.schema()))
+                .map_err(|e| DataFusionError::Common(e.to_string()))?;
+            for (name, enabled) in statistics_enabled {
+                let paths: Vec<ColumnPath> = schema
+                    .columns()
+                    .iter()
+                    .map(|column| column.path().clone())
+                    .filter(|path| path.parts()[0] == name)
+                    .collect();
+                if paths.is_empty() {
+                    return Err(PyValueError::new_err(format!(
+                        "Column '{name}' doesn't exist"
+                    )));
+                }
+                let statistics = match enabled {
+                    true => EnabledStatistics::Page,
+                    false => EnabledStatistics::None,
+                };
+                for path in paths {
+                    writer_properties =
+                        writer_properties.set_column_statistics_enabled(path, statistics);
+                }
+            }
+        }
+        let writer_properties = writer_properties.build();
 
         wait_for_future(
             py,
@@ -372,22 +1474,132 @@ impl PyDataFrame {
         Ok(())
     }
 
-    /// Executes a query and writes the results to a partitioned JSON file.
-    fn write_json(&self, path: &str, py: Python) -> PyResult<()> {
-        wait_for_future(
-            py,
-            self.df
-                .as_ref()
-                .clone()
-                .write_json(path, DataFrameWriteOptions::new()),
-        )?;
+    /// Write a `DataFrame` to a single Arrow IPC file, in the random access `file` format or the
+    /// `stream` format. The record batches can be compressed with `lz4` or `zstd`.
+    #[pyo3(signature = (path, format="file", compression="uncompressed"))]
+    fn write_ipc(
+        &self,
+        path: PathBuf,
+        format: &str,
+        compression: &str,
+        py: Python,
+    ) -> PyResult<()> {
+        let stream_format = match format {
+            "file" => false,
+            "stream" => true,
+            format => {
+                return Err(PyValueError::new_err(format!(
+                    "Unrecognized IPC format {format}, supported values are: file and stream"
+                )));
+            }
+        };
+        let compression_type = match compression.to_lowercase().as_str() {
+            "lz4" => Some(CompressionType::LZ4_FRAME),
+            "zstd" => Some(CompressionType::ZSTD),
+            "uncompressed" => None,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unrecognized compression type {compression}"
+                )));
+            }
+        };
+        let options = IpcWriteOptions::default()
+            .try_with_compression(compression_type)
+            .map_err(DataFusionError::from)?;
+
+        let schema: Schema = self.df.schema().into();
+        let file = fs::File::create(path)?;
+        if stream_format {
+            let mut writer = StreamWriter::try_new_with_options(file, &schema, options)
+                .map_err(DataFusionError::from)?;
+            write_batches(py, &self.df, |batch| writer.write(batch))?;
+            writer.finish().map_err(DataFusionError::from)?;
+        } else {
+            let mut writer = FileWriter::try_new_with_options(file, &schema, options)
+                .map_err(DataFusionError::from)?;
+            write_batches(py, &self.df, |batch| writer.write(batch))?;
+            writer.finish().map_err(DataFusionError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Executes a query and writes the results to a partitioned JSON file, with null values
+    /// written as `null` rather than left out of their objects if `explicit_nulls` is true.
+    /// With a `format` of `array`, the results are written to the single file `path` instead,
+    /// as one JSON array rather than one object per line, which `pretty` indents
+    #[pyo3(signature = (path, format="ndjson", pretty=false, explicit_nulls=false))]
+    fn write_json(
+        &self,
+        path: &str,
+        format: &str,
+        pretty: bool,
+        explicit_nulls: bool,
+        py: Python,
+    ) -> PyResult<()> {
+        let array = match format {
+            "ndjson" => false,
+            "array" => true,
+            format => {
+                return Err(PyValueError::new_err(format!(
+                    "Unrecognized JSON format {format}, supported values are: ndjson and array"
+                )));
+            }
+        };
+        if pretty && !array {
+            return Err(PyValueError::new_err(
+                "pretty requires format=\"array\", as ndjson holds one object per line",
+            ));
+        }
+        if !array && !explicit_nulls {
+            wait_for_future(
+                py,
+                self.df
+                    .as_ref()
+                    .clone()
+                    .write_json(path, DataFrameWriteOptions::new()),
+            )?;
+            return Ok(());
+        }
+        if !array {
+            // the same layout as the files DataFusion writes, one per partition of the result
+            fs::create_dir_all(path)?;
+            let write_id = Uuid::new_v4().simple();
+            wait_for_future(py, async {
+                let streams = self
+                    .df
+                    .as_ref()
+                    .clone()
+                    .execute_stream_partitioned()
+                    .await?;
+                let writes = streams.into_iter().enumerate().map(|(i, mut stream)| {
+                    let path = PathBuf::from(path).join(format!("{write_id}_{i}.json"));
+                    async move {
+                        let file = fs::File::create(path).map_err(ArrowError::from)?;
+                        let file = io::BufWriter::new(file);
+                        let mut writer = JsonRowWriter::new(file, false, false, true);
+                        while let Some(batch) = stream.next().await {
+                            writer.write(&batch?)?;
+                        }
+                        writer.finish()?;
+                        Ok::<_, DataFusionError>(())
+                    }
+                });
+                try_join_all(writes).await
+            })?;
+            return Ok(());
+        }
+
+        let file = io::BufWriter::new(fs::File::create(path)?);
+        let mut writer = JsonRowWriter::new(file, true, pretty, explicit_nulls);
+        write_batches(py, &self.df, |batch| writer.write(batch))?;
+        writer.finish().map_err(DataFusionError::from)?;
         Ok(())
     }
 
     /// Convert to Arrow Table
     /// Collect the batches and pass to Arrow Table
     fn to_arrow_table(&self, py: Python) -> PyResult<PyObject> {
-        let batches = self.collect(py)?.to_object(py);
+        let batches = self.collect(None, py)?.to_object(py);
         let schema: PyObject = self.schema().into_py(py);
 
         Python::with_gil(|py| {
@@ -401,18 +1613,87 @@ impl PyDataFrame {
 
     /// Convert to pandas dataframe with pyarrow
     /// Collect the batches, pass to Arrow Table & then convert to Pandas DataFrame
-    fn to_pandas(&self, py: Python) -> PyResult<PyObject> {
-        let table = self.to_arrow_table(py)?;
+    /// `decimal` sets how decimal columns are converted: "arrow" keeps them as a pandas
+    /// `ArrowDtype`, "decimal" converts them to Python `Decimal` objects and "float" casts
+    /// them to float64. It defaults to "arrow", or to "decimal" with pandas versions older than
+    /// 1.5, which have no `ArrowDtype`
+    #[pyo3(signature = (decimal=None))]
+    fn to_pandas(&self, decimal: Option<&str>, py: Python) -> PyResult<PyObject> {
+        let decimal = match decimal {
+            Some(decimal) => DecimalOutput::parse(decimal)?,
+            None if py.import("pandas")?.hasattr("ArrowDtype")? => DecimalOutput::Arrow,
+            None => DecimalOutput::Decimal,
+        };
+        let schema = self.df.schema();
+        let is_decimal = |data_type: &DataType| {
+            matches!(
+                data_type,
+                DataType::Decimal128(_, _) | DataType::Decimal256(_, _)
+            )
+        };
+        let table = if decimal == DecimalOutput::Float {
+            let exprs = schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let column = Expr::Column(field.qualified_column());
+                    if is_decimal(field.data_type()) {
+                        cast(column, DataType::Float64).alias(field.name())
+                    } else {
+                        column
+                    }
+                })
+                .collect::<Vec<_>>();
+            let df = self.df.as_ref().clone().select(exprs)?;
+            PyDataFrame::new(df).to_arrow_table(py)?
+        } else {
+            self.to_arrow_table(py)?
+        };
 
         Python::with_gil(|py| {
             // See also: https://arrow.apache.org/docs/python/generated/pyarrow.Table.html#pyarrow.Table.to_pandas
-            let result = table.call_method0(py, "to_pandas")?;
+            let kwargs = PyDict::new(py);
+            let decimal_types = schema
+                .fields()
+                .iter()
+                .map(|field| field.data_type())
+                .filter(|data_type| is_decimal(data_type))
+                .collect::<Vec<_>>();
+            if decimal == DecimalOutput::Arrow && !decimal_types.is_empty() {
+                // Map the type of every decimal column to its ArrowDtype, leaving other
+                // columns to the default conversion
+                let arrow_dtype = py.import("pandas")?.getattr("ArrowDtype")?;
+                let dtypes = PyDict::new(py);
+                for data_type in decimal_types {
+                    let data_type = data_type.to_pyarrow(py)?;
+                    dtypes.set_item(&data_type, arrow_dtype.call1((&data_type,))?)?;
+                }
+                kwargs.set_item("types_mapper", dtypes.getattr("get")?)?;
+            }
+            let result = table.call_method(py, "to_pandas", (), Some(kwargs))?;
             Ok(result)
         })
     }
 
+    /// Executes this DataFrame and returns an iterator over the result as pandas DataFrames of
+    /// `chunk_rows` rows, the last of which may be shorter. Only the batches needed for the next
+    /// chunk are held in memory, so results larger than memory can be processed in pieces.
+    #[pyo3(signature = (chunk_rows=65536))]
+    fn to_pandas_iter(&self, chunk_rows: usize, py: Python) -> PyResult<PyPandasChunks> {
+        if chunk_rows == 0 {
+            return Err(PyValueError::new_err("chunk_rows must be greater than 0"));
+        }
+        let stream = wait_for_future(py, self.df.as_ref().clone().execute_stream())?;
+        Ok(PyPandasChunks::new(
+            PyRecordBatchStream::new(stream),
+            chunk_rows,
+        ))
+    }
+
     /// Convert to Python list using pyarrow
     /// Each list item represents one row encoded as dictionary
+    /// Nested values are converted recursively, structs to dictionaries and lists to lists.
+    /// The whole result is materialized as Python objects, so this is meant for small results
     fn to_pylist(&self, py: Python) -> PyResult<PyObject> {
         let table = self.to_arrow_table(py)?;
 
@@ -425,6 +1706,8 @@ impl PyDataFrame {
 
     /// Convert to Python dictionary using pyarrow
     /// Each dictionary key is a column and the dictionary value represents the column values
+    /// Nested values are converted recursively, structs to dictionaries and lists to lists.
+    /// The whole result is materialized as Python objects, so this is meant for small results
     fn to_pydict(&self, py: Python) -> PyResult<PyObject> {
         let table = self.to_arrow_table(py)?;
 
@@ -452,17 +1735,190 @@ impl PyDataFrame {
     fn count(&self, py: Python) -> PyResult<usize> {
         Ok(wait_for_future(py, self.df.as_ref().clone().count())?)
     }
+
+    /// Returns whether this DataFrame has no rows. Execution stops at the first row, so this is
+    /// much cheaper than counting the rows.
+    fn is_empty(&self, py: Python) -> PyResult<bool> {
+        Ok(!self.has_rows(py)?)
+    }
+
+    /// Returns whether this DataFrame has at least one row, see `is_empty`
+    fn has_rows(&self, py: Python) -> PyResult<bool> {
+        let df = self.df.as_ref().clone().limit(0, Some(1))?;
+        let has_rows = wait_for_future(py, async {
+            let mut stream = df.execute_stream().await?;
+            while let Some(batch) = stream.next().await {
+                if batch?.num_rows() > 0 {
+                    return Ok(true);
+                }
+            }
+            Ok::<_, DataFusionError>(false)
+        })?;
+        Ok(has_rows)
+    }
+
+    /// Computes the Pearson correlation of two columns. Rows where either column is null are
+    /// skipped, and None is returned when no rows remain.
+    fn corr(&self, col_a: &str, col_b: &str, py: Python) -> PyResult<Option<f64>> {
+        self.pairwise_aggregate(AggregateFunction::Correlation, col_a, col_b, py)
+    }
+
+    /// Computes the sample covariance of two columns, or the population covariance when
+    /// `population` is true. Rows where either column is null are skipped.
+    #[pyo3(signature = (col_a, col_b, population=false))]
+    fn cov(&self, col_a: &str, col_b: &str, population: bool, py: Python) -> PyResult<Option<f64>> {
+        let fun = if population {
+            AggregateFunction::CovariancePop
+        } else {
+            AggregateFunction::Covariance
+        };
+        self.pairwise_aggregate(fun, col_a, col_b, py)
+    }
+
+    /// Count the rows holding each value of `column`, in a `count` column, or the fraction of
+    /// all rows in a `proportion` column when `normalize` is true. Null is counted as a value of
+    /// its own. With `sort` the most frequent values come first.
+    #[pyo3(signature = (column, normalize=false, sort=true))]
+    fn value_counts(&self, column: &str, normalize: bool, sort: bool) -> PyResult<Self> {
+        let field = self
+            .df
+            .schema()
+            .field_with_unqualified_name(column)
+            .map_err(|_| PyValueError::new_err(format!("Column '{column}' doesn't exist")))?;
+        let value = Expr::Column(field.qualified_column());
+        let df = self
+            .df
+            .as_ref()
+            .clone()
+            .aggregate(vec![value.clone()], vec![count(lit(1)).alias("count")])?;
+
+        let (df, counts) = if normalize {
+            let total = Expr::WindowFunction(expr::WindowFunction::new(
+                window_function::WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                vec![col("count")],
+                vec![],
+                vec![],
+                WindowFrame::new(false),
+            ));
+            let proportion =
+                cast(col("count"), DataType::Float64) / cast(col("__total"), DataType::Float64);
+            let (state, plan) = df.into_parts();
+            let plan = LogicalPlanBuilder::from(plan)
+                .window(vec![total.alias("__total")])?
+                .project(vec![value.clone(), proportion.alias("proportion")])?
+                .build()?;
+            (DataFrame::new(state, plan), col("proportion"))
+        } else {
+            (df, col("count"))
+        };
+        if !sort {
+            return Ok(Self::new(df));
+        }
+        let df = df.sort(vec![counts.sort(false, false), value.sort(true, false)])?;
+        Ok(Self::new(df))
+    }
+}
+
+/// How `to_pandas` converts decimal columns
+#[derive(Clone, Copy, PartialEq)]
+enum DecimalOutput {
+    /// A pandas `ArrowDtype`, which keeps the values exact
+    Arrow,
+    /// Python `Decimal` objects in an object column
+    Decimal,
+    /// float64, which loses the digits beyond its precision
+    Float,
+}
+
+impl DecimalOutput {
+    fn parse(decimal: &str) -> PyResult<Self> {
+        match decimal {
+            "arrow" => Ok(Self::Arrow),
+            "decimal" => Ok(Self::Decimal),
+            "float" => Ok(Self::Float),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported decimal output {other}, expected one of: arrow, decimal, float"
+            ))),
+        }
+    }
 }
 
-/// Print DataFrame
-fn print_dataframe(py: Python, df: DataFrame) -> PyResult<()> {
+/// The rows of a DataFrame shown by `show` and `_repr_html_`
+struct Preview {
+    batches: Vec<RecordBatch>,
+    /// Whether the DataFrame has more rows than are shown
+    truncated: bool,
+    /// The number of rows of the DataFrame when it is truncated and the count is known without
+    /// running the query
+    total_rows: Option<usize>,
+}
+
+/// Collects the first `num` rows of `df`, or all of them, waiting for them so that the query
+/// can be interrupted. With `footer`, one more row is fetched to know whether the preview is
+/// truncated, and the row count looked up when it is.
+fn collect_preview(
+    py: Python,
+    df: &DataFrame,
+    num: Option<usize>,
+    footer: bool,
+) -> PyResult<Preview> {
+    let fetch = match num {
+        Some(num) if footer => Some(num.saturating_add(1)),
+        num => num,
+    };
+    let limited = match fetch {
+        Some(fetch) => df.clone().limit(0, Some(fetch))?,
+        None => df.clone(),
+    };
+    let mut batches = wait_for_future_interruptible(py, limited.collect())??;
+    let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+    let truncated = match num {
+        Some(num) if footer && num_rows > num => {
+            let batch =
+                concat_batches(&batches[0].schema(), &batches).map_err(DataFusionError::from)?;
+            batches = vec![batch.slice(0, num)];
+            true
+        }
+        _ => false,
+    };
+    let total_rows = if truncated {
+        let plan = wait_for_future_interruptible(py, df.clone().create_physical_plan())??;
+        let statistics = plan.statistics();
+        statistics.num_rows.filter(|_| statistics.is_exact)
+    } else {
+        None
+    };
+    Ok(Preview {
+        batches,
+        truncated,
+        total_rows,
+    })
+}
+
+/// Prints the rows `collect_preview` collects, formatted with `format`, followed by a line
+/// saying how many are shown when the preview is truncated
+fn print_preview(
+    py: Python,
+    df: &DataFrame,
+    num: Option<usize>,
+    footer: bool,
+    format: impl FnOnce(&Schema, &[RecordBatch]) -> Result<String, ArrowError>,
+) -> PyResult<()> {
+    let preview = collect_preview(py, df, num, footer)?;
+
     // Get string representation of record batches
-    let batches = wait_for_future(py, df.collect())?;
-    let batches_as_string = pretty::pretty_format_batches(&batches);
-    let result = match batches_as_string {
-        Ok(batch) => format!("DataFrame()\n{batch}"),
+    let schema: Schema = df.schema().into();
+    let mut result = match format(&schema, &preview.batches) {
+        Ok(batches_as_string) => batches_as_string,
         Err(err) => format!("Error: {:?}", err.to_string()),
     };
+    if preview.truncated {
+        let shown = preview.batches[0].num_rows();
+        match preview.total_rows {
+            Some(total) => result.push_str(&format!("\nshowing {shown} of {total} rows")),
+            None => result.push_str(&format!("\nshowing the first {shown} rows")),
+        }
+    }
 
     // Import the Python 'builtins' module to access the print function
     // Note that println! does not print to the Python debug console and is not visible in notebooks for instance
@@ -470,3 +1926,588 @@ fn print_dataframe(py: Python, df: DataFrame) -> PyResult<()> {
     print.call1((result,))?;
     Ok(())
 }
+
+/// Replaces the text of the plans of type `plan_type` in the output of `DataFrame::explain`
+fn replace_plan(
+    batches: &[RecordBatch],
+    plan_type: &str,
+    text: &str,
+) -> Result<Vec<RecordBatch>, ArrowError> {
+    batches
+        .iter()
+        .map(|batch| {
+            let types = batch.column(0).as_string::<i32>();
+            let plans = batch.column(1).as_string::<i32>();
+            let plans: StringArray = types
+                .iter()
+                .zip(plans.iter())
+                .map(|(t, plan)| {
+                    if t == Some(plan_type) {
+                        Some(text)
+                    } else {
+                        plan
+                    }
+                })
+                .collect();
+            RecordBatch::try_new(
+                batch.schema(),
+                vec![batch.column(0).clone(), Arc::new(plans)],
+            )
+        })
+        .collect()
+}
+
+/// The metrics `plan` recorded, by name, summed over its partitions
+fn operator_metrics<'py>(py: Python<'py>, plan: &Arc<dyn ExecutionPlan>) -> PyResult<&'py PyDict> {
+    let values = PyDict::new(py);
+    if let Some(set) = plan.metrics() {
+        for metric in set.aggregate_by_name().iter() {
+            values.set_item(metric.value().name(), metric.value().as_usize())?;
+        }
+    }
+    Ok(values)
+}
+
+/// The one line description of the operator of `plan`
+fn operator_name(plan: &Arc<dyn ExecutionPlan>) -> String {
+    displayable(plan.as_ref())
+        .one_line()
+        .to_string()
+        .trim()
+        .to_string()
+}
+
+/// Appends the metrics of `plan` and its children, in pre-order, to `metrics`
+fn collect_plan_metrics(
+    py: Python,
+    plan: &Arc<dyn ExecutionPlan>,
+    depth: usize,
+    metrics: &mut Vec<PyObject>,
+) -> PyResult<()> {
+    let operator = PyDict::new(py);
+    operator.set_item("operator", operator_name(plan))?;
+    operator.set_item("depth", depth)?;
+    operator.set_item("metrics", operator_metrics(py, plan)?)?;
+    metrics.push(operator.into());
+
+    for child in plan.children() {
+        collect_plan_metrics(py, &child, depth + 1, metrics)?;
+    }
+    Ok(())
+}
+
+/// The metrics of `plan` as a dict holding those of its children
+fn plan_metrics_tree(py: Python, plan: &Arc<dyn ExecutionPlan>) -> PyResult<PyObject> {
+    let metrics = operator_metrics(py, plan)?;
+    let operator = PyDict::new(py);
+    operator.set_item("operator", operator_name(plan))?;
+    operator.set_item("output_rows", metrics.get_item("output_rows"))?;
+    operator.set_item("elapsed_compute_nanos", metrics.get_item("elapsed_compute"))?;
+    operator.set_item("metrics", metrics)?;
+    let children = plan
+        .children()
+        .iter()
+        .map(|child| plan_metrics_tree(py, child))
+        .collect::<PyResult<Vec<_>>>()?;
+    operator.set_item("children", children)?;
+    Ok(operator.into())
+}
+
+/// Checks that the 32 bit offsets of `arrays`, and of their nested fields and list values, can
+/// address their concatenated values, which would otherwise overflow
+fn check_offsets(name: &str, arrays: &[&dyn Array]) -> PyResult<()> {
+    let span = |offsets: &[i32]| match (offsets.first(), offsets.last()) {
+        (Some(first), Some(last)) => (*last - *first) as usize,
+        _ => 0,
+    };
+    let total: usize = arrays
+        .iter()
+        .map(|array| match array.data_type() {
+            DataType::Utf8 => span(array.as_string::<i32>().value_offsets()),
+            DataType::Binary => span(array.as_binary::<i32>().value_offsets()),
+            DataType::List(_) => span(array.as_list::<i32>().value_offsets()),
+            DataType::Map(_, _) => span(array.as_map().value_offsets()),
+            _ => 0,
+        })
+        .sum();
+    if total > i32::MAX as usize {
+        return Err(PyValueError::new_err(format!(
+            "The result doesn't fit in a single batch: column {name} holds {total} values or \
+             bytes, more than its 32 bit offsets can address. Cast it to a large type or use \
+             collect instead"
+        )));
+    }
+    let fields: Vec<&Field> = match arrays.first().map(|a| a.data_type()) {
+        Some(DataType::Struct(fields)) => fields.iter().map(|f| f.as_ref()).collect(),
+        Some(
+            DataType::List(field)
+            | DataType::LargeList(field)
+            | DataType::FixedSizeList(field, _)
+            | DataType::Map(field, _),
+        ) => vec![field.as_ref()],
+        _ => return Ok(()),
+    };
+    for (i, field) in fields.iter().enumerate() {
+        let children: Vec<ArrayRef> = arrays.iter().map(|a| child_values(*a, i)).collect();
+        let children: Vec<&dyn Array> = children.iter().map(|c| c.as_ref()).collect();
+        check_offsets(&format!("{name}.{}", field.name()), &children)?;
+    }
+    Ok(())
+}
+
+/// The values of the `i`th child of the nested `array` that its rows refer to, which are all
+/// its values only when the array isn't sliced
+fn child_values(array: &dyn Array, i: usize) -> ArrayRef {
+    let slice = |values: &ArrayRef, first: usize, last: usize| values.slice(first, last - first);
+    match array.data_type() {
+        DataType::List(_) => {
+            let list = array.as_list::<i32>();
+            let offsets = list.value_offsets();
+            slice(
+                list.values(),
+                offsets[0] as usize,
+                offsets[list.len()] as usize,
+            )
+        }
+        DataType::LargeList(_) => {
+            let list = array.as_list::<i64>();
+            let offsets = list.value_offsets();
+            slice(
+                list.values(),
+                offsets[0] as usize,
+                offsets[list.len()] as usize,
+            )
+        }
+        DataType::FixedSizeList(_, _) => {
+            let list = array.as_fixed_size_list();
+            let first = list.value_offset(0) as usize;
+            let length = list.value_length() as usize * list.len();
+            slice(list.values(), first, first + length)
+        }
+        DataType::Map(_, _) => {
+            let map = array.as_map();
+            let offsets = map.value_offsets();
+            let entries: ArrayRef = Arc::new(map.entries().clone());
+            slice(&entries, offsets[0] as usize, offsets[map.len()] as usize)
+        }
+        _ => array.as_struct().column(i).clone(),
+    }
+}
+
+/// Copies `state` with a different config, keeping its catalogs, optimizer rules and functions.
+/// A `SessionState` can't have its config changed once it's been created.
+fn with_session_config(state: &SessionState, config: SessionConfig) -> SessionState {
+    let config = config.with_create_default_catalog_and_schema(false);
+    let copy = SessionState::with_config_rt_and_catalog_list(
+        config,
+        state.runtime_env().clone(),
+        state.catalog_list(),
+    )
+    .with_session_id(state.session_id().to_owned())
+    .with_physical_optimizer_rules(state.physical_optimizers().to_vec())
+    .with_serializer_registry(state.serializer_registry());
+
+    let ctx = SessionContext::with_state(without_disabled_optimizer_rules(copy));
+    for udf in state.scalar_functions().values() {
+        ctx.register_udf(udf.as_ref().clone());
+    }
+    for udaf in state.aggregate_functions().values() {
+        ctx.register_udaf(udaf.as_ref().clone());
+    }
+    for udwf in state.window_functions().values() {
+        ctx.register_udwf(udwf.as_ref().clone());
+    }
+    ctx.state()
+}
+
+/// Appends a column for each field of `expr` when it's a struct, expanding nested structs up to
+/// `depth` levels, or `expr` itself otherwise
+fn flatten_fields(
+    expr: Expr,
+    name: String,
+    data_type: &DataType,
+    separator: &str,
+    depth: usize,
+    columns: &mut Vec<(String, Expr)>,
+) {
+    match data_type {
+        DataType::Struct(fields) if depth > 0 && !fields.is_empty() => {
+            for field in fields {
+                let child = Expr::GetIndexedField(GetIndexedField::new(
+                    Box::new(expr.clone()),
+                    GetFieldAccess::NamedStructField {
+                        name: ScalarValue::Utf8(Some(field.name().clone())),
+                    },
+                ));
+                let child_name = format!("{name}{separator}{}", field.name());
+                flatten_fields(
+                    child,
+                    child_name,
+                    field.data_type(),
+                    separator,
+                    depth - 1,
+                    columns,
+                );
+            }
+        }
+        _ => columns.push((name, expr)),
+    }
+}
+
+/// Executes the DataFrame and passes each of the resulting batches to `write`
+fn write_batches(
+    py: Python,
+    df: &DataFrame,
+    mut write: impl FnMut(&RecordBatch) -> Result<(), ArrowError> + Send,
+) -> PyResult<()> {
+    wait_for_future(py, async {
+        let mut stream = df.clone().execute_stream().await?;
+        while let Some(batch) = stream.next().await {
+            write(&batch?)?;
+        }
+        Ok::<_, DataFusionError>(())
+    })?;
+    Ok(())
+}
+
+/// Writes record batches as JSON objects, one per line or as the elements of a single array
+struct JsonRowWriter<W: Write> {
+    writer: W,
+    array: bool,
+    pretty: bool,
+    explicit_nulls: bool,
+    // The number of rows written so far
+    rows: usize,
+}
+
+impl<W: Write> JsonRowWriter<W> {
+    fn new(writer: W, array: bool, pretty: bool, explicit_nulls: bool) -> Self {
+        Self {
+            writer,
+            array,
+            pretty,
+            explicit_nulls,
+            rows: 0,
+        }
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        let schema = batch.schema();
+        for row in record_batches_to_json_rows(&[batch])? {
+            let row = JsonRow {
+                row: &row,
+                fields: schema.fields(),
+                explicit_nulls: self.explicit_nulls,
+            };
+            let separator = match (self.rows, self.array) {
+                (_, false) => "",
+                (0, true) => "[",
+                (_, true) => ",",
+            };
+            self.writer.write_all(separator.as_bytes())?;
+            let json_error = |e: serde_json::Error| ArrowError::JsonError(e.to_string());
+            if self.pretty {
+                let row = serde_json::to_string_pretty(&row).map_err(json_error)?;
+                for line in row.lines() {
+                    write!(self.writer, "\n  {line}")?;
+                }
+            } else {
+                serde_json::to_writer(&mut self.writer, &row).map_err(json_error)?;
+                if !self.array {
+                    self.writer.write_all(b"\n")?;
+                }
+            }
+            self.rows += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), ArrowError> {
+        if self.array {
+            let end = match (self.rows, self.pretty) {
+                (0, _) => "[]\n",
+                (_, true) => "\n]\n",
+                (_, false) => "]\n",
+            };
+            self.writer.write_all(end.as_bytes())?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A row given by arrow as a JSON object, serialized with its fields in the order of `fields`,
+/// as the objects arrow builds sort their keys. With `explicit_nulls`, the fields arrow leaves
+/// out as their values are null are written as `null`, in nested objects too.
+struct JsonRow<'a> {
+    row: &'a Map<String, Value>,
+    fields: &'a Fields,
+    explicit_nulls: bool,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for field in self.fields {
+            match self.row.get(field.name()) {
+                Some(value) => {
+                    let value = JsonValue {
+                        value,
+                        data_type: field.data_type(),
+                        explicit_nulls: self.explicit_nulls,
+                    };
+                    map.serialize_entry(field.name(), &value)?;
+                }
+                None if self.explicit_nulls => map.serialize_entry(field.name(), &Value::Null)?,
+                None => {}
+            }
+        }
+        map.end()
+    }
+}
+
+/// A value of a `JsonRow`, whose objects are serialized as rows of the fields of its type
+struct JsonValue<'a> {
+    value: &'a Value,
+    data_type: &'a DataType,
+    explicit_nulls: bool,
+}
+
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.value, self.data_type) {
+            (Value::Object(row), DataType::Struct(fields)) => JsonRow {
+                row,
+                fields,
+                explicit_nulls: self.explicit_nulls,
+            }
+            .serialize(serializer),
+            (
+                Value::Array(values),
+                DataType::List(field)
+                | DataType::LargeList(field)
+                | DataType::FixedSizeList(field, _),
+            ) => serializer.collect_seq(values.iter().map(|value| JsonValue {
+                value,
+                data_type: field.data_type(),
+                explicit_nulls: self.explicit_nulls,
+            })),
+            (value, _) => value.serialize(serializer),
+        }
+    }
+}
+
+/// Returns the hex digest of `DataFrame.plan_fingerprint` for an optimized plan, along with the
+/// tables it scans
+fn fingerprint_plan(
+    plan: &LogicalPlan,
+) -> datafusion_common::Result<(String, Vec<Arc<dyn TableProvider>>)> {
+    let mut hasher = DigestHasher(digest::Context::new(&digest::SHA256));
+    let mut tables = vec![];
+    plan.hash(&mut hasher);
+    plan.apply(&mut |plan| {
+        if let LogicalPlan::TableScan(scan) = plan {
+            let table = source_as_provider(&scan.source)?;
+            tables.push(table.clone());
+            let table = match table.as_any().downcast_ref::<ParquetScanOptions>() {
+                Some(options) => options.table().clone(),
+                None => table,
+            };
+            match table.as_any().downcast_ref::<ListingTable>() {
+                Some(listing) => {
+                    for path in listing.table_paths() {
+                        path.as_str().hash(&mut hasher);
+                    }
+                }
+                None if scan.table_name.table() == UNNAMED_TABLE => {
+                    (Arc::as_ptr(&table) as *const () as usize).hash(&mut hasher)
+                }
+                None => {}
+            }
+        }
+        Ok(VisitRecursion::Continue)
+    })?;
+    let digest = hasher.0.finish();
+    let fingerprint = digest.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    Ok((fingerprint, tables))
+}
+
+/// Whether the result of a plan may be cached: it must only read data, and the volatile
+/// functions it calls, such as `random`, return a different result on every run
+fn is_cacheable(plan: &LogicalPlan) -> datafusion_common::Result<bool> {
+    let mut cacheable = true;
+    plan.apply(&mut |plan| {
+        if matches!(
+            plan,
+            LogicalPlan::Dml(_) | LogicalPlan::Ddl(_) | LogicalPlan::Copy(_)
+        ) {
+            cacheable = false;
+        }
+        for expr in plan.expressions() {
+            expr.apply(&mut |expr| {
+                let volatility = match expr {
+                    Expr::ScalarFunction(function) => function.fun.volatility(),
+                    Expr::ScalarUDF(function) => function.fun.signature.volatility,
+                    _ => Volatility::Immutable,
+                };
+                if volatility == Volatility::Volatile {
+                    cacheable = false;
+                }
+                Ok(VisitRecursion::Continue)
+            })?;
+        }
+        Ok(match cacheable {
+            true => VisitRecursion::Continue,
+            false => VisitRecursion::Stop,
+        })
+    })?;
+    Ok(cacheable)
+}
+
+/// Feeds the bytes hashed by `Hash` implementations to a digest
+struct DigestHasher(digest::Context);
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finish();
+        u64::from_le_bytes(digest.as_ref()[..8].try_into().unwrap())
+    }
+}
+
+/// Executes the DataFrame and concatenates the results into a single batch
+fn collect_single_batch(py: Python, df: &DataFrame) -> PyResult<RecordBatch> {
+    let batches = wait_for_future(py, df.clone().collect())?;
+    let schema: Schema = df.schema().into();
+    let batch = concat_batches(&Arc::new(schema), &batches).map_err(DataFusionError::from)?;
+    Ok(batch)
+}
+
+/// Sorts the rows of a batch by all of its columns, returning the sorted batch and the index
+/// each of its rows had in `batch`
+fn sort_rows(batch: &RecordBatch) -> Result<(RecordBatch, Vec<u32>), ArrowError> {
+    let fields = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| SortField::new(field.data_type().clone()))
+        .collect();
+    let rows = RowConverter::new(fields)?.convert_columns(batch.columns())?;
+    let mut indices: Vec<u32> = (0..batch.num_rows() as u32).collect();
+    indices.sort_by(|a, b| rows.row(*a as usize).cmp(&rows.row(*b as usize)));
+    let take_indices = UInt32Array::from(indices.clone());
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &take_indices, None))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((RecordBatch::try_new(batch.schema(), columns)?, indices))
+}
+
+/// Describes the first difference between two batches, or returns `None` when they're equal
+fn compare_batches(
+    left: &RecordBatch,
+    right: &RecordBatch,
+    check_dtype: bool,
+    check_row_order: bool,
+) -> Result<Option<String>, ArrowError> {
+    let left_schema = left.schema();
+    let right_schema = right.schema();
+    let left_names: Vec<&String> = left_schema.fields().iter().map(|f| f.name()).collect();
+    let right_names: Vec<&String> = right_schema.fields().iter().map(|f| f.name()).collect();
+    if left_names != right_names {
+        return Ok(Some(format!(
+            "Column names differ: {left_names:?} != {right_names:?}"
+        )));
+    }
+
+    let mut right_columns = vec![];
+    let mut right_fields = vec![];
+    for (i, (left_field, right_field)) in left_schema
+        .fields()
+        .iter()
+        .zip(right_schema.fields())
+        .enumerate()
+    {
+        let column = right.column(i);
+        // the nullability of `other` is kept, its nulls make the values differ otherwise
+        right_fields.push(
+            left_field
+                .as_ref()
+                .clone()
+                .with_nullable(right_field.is_nullable()),
+        );
+        if left_field.data_type() == right_field.data_type() {
+            right_columns.push(column.clone());
+        } else if check_dtype {
+            return Ok(Some(format!(
+                "Column '{}' types differ: {} != {}",
+                left_field.name(),
+                left_field.data_type(),
+                right_field.data_type()
+            )));
+        } else {
+            right_columns.push(compute::cast(column, left_field.data_type())?);
+        }
+        if check_dtype && left_field.is_nullable() != right_field.is_nullable() {
+            let nullability = |field: &Field| match field.is_nullable() {
+                true => "nullable",
+                false => "not nullable",
+            };
+            return Ok(Some(format!(
+                "Column '{}' nullability differs: {} != {}",
+                left_field.name(),
+                nullability(left_field),
+                nullability(right_field)
+            )));
+        }
+    }
+    let right = RecordBatch::try_new(Arc::new(Schema::new(right_fields)), right_columns)?;
+
+    if left.num_rows() != right.num_rows() {
+        return Ok(Some(format!(
+            "Row counts differ: {} != {}",
+            left.num_rows(),
+            right.num_rows()
+        )));
+    }
+
+    // the indices of the rows in the DataFrames, which are sorted unless the order is checked
+    let (left, right, indices) = if check_row_order {
+        (left.clone(), right, None)
+    } else {
+        let (left, left_indices) = sort_rows(left)?;
+        let (right, right_indices) = sort_rows(&right)?;
+        (left, right, Some((left_indices, right_indices)))
+    };
+
+    let format_options = FormatOptions::default();
+    for (i, field) in left_schema.fields().iter().enumerate() {
+        let mut converter = RowConverter::new(vec![SortField::new(field.data_type().clone())])?;
+        let left_rows = converter.convert_columns(&[left.column(i).clone()])?;
+        let right_rows = converter.convert_columns(&[right.column(i).clone()])?;
+        if let Some(row) =
+            (0..left.num_rows()).find(|row| left_rows.row(*row) != right_rows.row(*row))
+        {
+            let left_value = ArrayFormatter::try_new(left.column(i).as_ref(), &format_options)?;
+            let right_value = ArrayFormatter::try_new(right.column(i).as_ref(), &format_options)?;
+            let rows = match &indices {
+                Some((left_indices, right_indices)) => format!(
+                    "row {} of this DataFrame and row {} of the other",
+                    left_indices[row], right_indices[row]
+                ),
+                None => format!("row {row}"),
+            };
+            return Ok(Some(format!(
+                "Values differ at {rows}, column '{}': {} != {}",
+                field.name(),
+                left_value.value(row),
+                right_value.value(row)
+            )));
+        }
+    }
+    Ok(None)
+}
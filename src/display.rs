@@ -0,0 +1,226 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Formatting of query results for display, used by `DataFrame.show` and its variants
+
+use comfy_table::{Cell, Table};
+use datafusion::arrow::array::{Array, AsArray};
+use datafusion::arrow::datatypes::{DataType, Float16Type, Float32Type, Float64Type, Schema};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+
+/// Options controlling how values are rendered. They only affect the display, never the data.
+#[derive(Debug, Clone)]
+pub(crate) struct DisplayOptions {
+    /// Maximum width of a value, in characters
+    pub max_width: Option<usize>,
+    /// Whether values wider than `max_width` are cut off, rather than wrapped onto more lines
+    pub truncate: bool,
+    /// chrono format string for timestamps, with or without a time zone
+    pub timestamp_format: Option<String>,
+    /// Number of digits printed after the decimal point of floats
+    pub float_precision: Option<usize>,
+    /// Text printed for null values
+    pub null_repr: String,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            truncate: true,
+            timestamp_format: None,
+            float_precision: None,
+            null_repr: String::new(),
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Formats a single value of `array`
+    fn format_value(&self, array: &dyn Array, formatter: &ArrayFormatter, row: usize) -> String {
+        let value = match (self.float_precision, array.data_type()) {
+            (Some(_), _) if array.is_null(row) => self.null_repr.clone(),
+            (Some(precision), DataType::Float16) => {
+                format!(
+                    "{:.precision$}",
+                    array.as_primitive::<Float16Type>().value(row)
+                )
+            }
+            (Some(precision), DataType::Float32) => {
+                format!(
+                    "{:.precision$}",
+                    array.as_primitive::<Float32Type>().value(row)
+                )
+            }
+            (Some(precision), DataType::Float64) => {
+                format!(
+                    "{:.precision$}",
+                    array.as_primitive::<Float64Type>().value(row)
+                )
+            }
+            _ => formatter.value(row).to_string(),
+        };
+        self.fit(value)
+    }
+
+    fn fit(&self, value: String) -> String {
+        let max_width = match self.max_width {
+            Some(max_width) if value.chars().count() > max_width => max_width,
+            _ => return value,
+        };
+        let chars: Vec<char> = value.chars().collect();
+        if self.truncate {
+            // keep room for the ellipsis in the width
+            let keep = max_width.saturating_sub(3);
+            chars[..keep].iter().collect::<String>() + "..."
+        } else {
+            chars
+                .chunks(max_width.max(1))
+                .map(|chunk| chunk.iter().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
+
+/// Formats the values of every row as strings
+fn format_rows(
+    batches: &[RecordBatch],
+    options: &DisplayOptions,
+) -> Result<Vec<Vec<String>>, ArrowError> {
+    let timestamp_format = options.timestamp_format.as_deref();
+    let format_options = FormatOptions::default()
+        .with_display_error(true)
+        .with_null(&options.null_repr)
+        .with_timestamp_format(timestamp_format)
+        .with_timestamp_tz_format(timestamp_format);
+    let mut rows = vec![];
+    for batch in batches {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|c| ArrayFormatter::try_new(c.as_ref(), &format_options))
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+        for row in 0..batch.num_rows() {
+            rows.push(
+                batch
+                    .columns()
+                    .iter()
+                    .zip(&formatters)
+                    .map(|(array, formatter)| options.format_value(array.as_ref(), formatter, row))
+                    .collect(),
+            );
+        }
+    }
+    Ok(rows)
+}
+
+/// Formats the batches as a table with a header, one line per row. The header is printed for
+/// an empty result too.
+pub(crate) fn format_table(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    options: &DisplayOptions,
+) -> Result<String, ArrowError> {
+    let mut table = Table::new();
+    table.load_preset("||--+-++|    ++++++");
+    table.set_header(schema.fields().iter().map(|f| Cell::new(f.name())));
+    for row in format_rows(batches, options)? {
+        table.add_row(row);
+    }
+    Ok(table.to_string())
+}
+
+/// Formats the batches with one line per field, which suits results with many columns
+pub(crate) fn format_vertical(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    options: &DisplayOptions,
+) -> Result<String, ArrowError> {
+    let names: Vec<&String> = schema.fields().iter().map(|f| f.name()).collect();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![];
+    for (i, row) in format_rows(batches, options)?.into_iter().enumerate() {
+        lines.push(format!("-[ RECORD {} ]-", i + 1));
+        for (name, value) in names.iter().zip(row) {
+            // indent continuation lines of wrapped values under the value column
+            let value = value.replace('\n', &format!("\n{:name_width$} | ", ""));
+            lines.push(format!("{name:name_width$} | {value}"));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats the batches as an HTML table with the column types in the header. `truncated`
+/// tells whether there are more rows than shown, and `total_rows` the row count, if known.
+pub(crate) fn format_html(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    options: &DisplayOptions,
+    truncated: bool,
+    total_rows: Option<usize>,
+) -> Result<String, ArrowError> {
+    let mut html = String::from("<table border=\"1\">\n<thead>\n<tr>");
+    for field in schema.fields() {
+        html.push_str(&format!(
+            "<th>{}<br><small>{}</small></th>",
+            escape_html(field.name()),
+            escape_html(&field.data_type().to_string())
+        ));
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    let rows = format_rows(batches, options)?;
+    let shown = rows.len();
+    for row in rows {
+        html.push_str("<tr>");
+        for value in row {
+            html.push_str(&format!("<td>{}</td>", escape_html(&value)));
+        }
+        html.push_str("</tr>\n");
+    }
+    if truncated {
+        html.push_str(&format!(
+            "<tr>{}</tr>\n",
+            "<td>...</td>".repeat(schema.fields().len())
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let summary = match (total_rows, truncated) {
+        (Some(total), true) => format!("showing {shown} of {total} rows"),
+        (None, true) => format!("showing the first {shown} rows"),
+        (_, false) => format!("{shown} rows"),
+    };
+    html.push_str(&format!("<p>{summary}</p>\n"));
+    Ok(html)
+}
@@ -15,10 +15,11 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use pyo3::{basic::CompareOp, prelude::*};
+use pyo3::types::PyBytes;
+use pyo3::{basic::CompareOp, exceptions::PyValueError, prelude::*};
 use std::convert::{From, Into};
 
-use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::datatypes::{DataType, DECIMAL128_MAX_PRECISION, DECIMAL256_MAX_PRECISION};
 use datafusion::arrow::pyarrow::PyArrowType;
 use datafusion::scalar::ScalarValue;
 use datafusion_common::DFField;
@@ -28,7 +29,7 @@ use datafusion_expr::{
         AggregateFunction, AggregateUDF, InList, InSubquery, ScalarFunction, ScalarUDF, Sort,
         WindowFunction,
     },
-    lit,
+    lit, lower,
     utils::exprlist_to_fields,
     Between, BinaryExpr, Case, Cast, Expr, GetFieldAccess, GetIndexedField, Like, LogicalPlan,
     Operator, TryCast,
@@ -41,13 +42,14 @@ use crate::expr::binary_expr::PyBinaryExpr;
 use crate::expr::column::PyColumn;
 use crate::expr::literal::PyLiteral;
 use crate::sql::logical::PyLogicalPlan;
+use crate::utils::parse_safe;
 
 use self::alias::PyAlias;
 use self::bool_expr::{
     PyIsFalse, PyIsNotFalse, PyIsNotNull, PyIsNotTrue, PyIsNotUnknown, PyIsNull, PyIsTrue,
     PyIsUnknown, PyNegative, PyNot,
 };
-use self::like::{PyILike, PyLike, PySimilarTo};
+use self::like::{escape_like_pattern, similar_to_regex, PyILike, PyLike, PySimilarTo};
 use self::scalar_variable::PyScalarVariable;
 
 pub mod aggregate;
@@ -112,6 +114,37 @@ impl From<Expr> for PyExpr {
     }
 }
 
+/// A sort expression that remembers whether its null ordering was given, those without one
+/// are ordered by the default null ordering of the session in `DataFrame.sort`
+#[pyclass(name = "SortExpr", module = "datafusion.expr", extends = PyExpr)]
+pub struct PySortExpr {
+    pub(crate) nulls_first_given: bool,
+}
+
+impl PySortExpr {
+    /// Creates the sort expression of `expr`, ordering nulls first unless `nulls_first` is
+    /// given
+    pub(crate) fn create(
+        py: Python,
+        expr: Expr,
+        ascending: bool,
+        nulls_first: Option<bool>,
+    ) -> PyResult<Py<Self>> {
+        let sort = Expr::Sort(Sort::new(
+            Box::new(expr),
+            ascending,
+            nulls_first.unwrap_or(true),
+        ));
+        let sort_expr = Self {
+            nulls_first_given: nulls_first.is_some(),
+        };
+        Py::new(
+            py,
+            PyClassInitializer::from(PyExpr::from(sort)).add_subclass(sort_expr),
+        )
+    }
+}
+
 #[pymethods]
 impl PyExpr {
     /// Return the specific expression
@@ -236,21 +269,117 @@ impl PyExpr {
         self.expr.clone().alias(name).into()
     }
 
-    /// Create a sort PyExpr from an existing PyExpr.
-    #[pyo3(signature = (ascending=true, nulls_first=true))]
-    pub fn sort(&self, ascending: bool, nulls_first: bool) -> PyExpr {
-        self.expr.clone().sort(ascending, nulls_first).into()
+    /// Create a sort PyExpr from an existing PyExpr. Without `nulls_first`, nulls sort by the
+    /// default null ordering of the session in `DataFrame.sort`, and first elsewhere.
+    #[pyo3(signature = (ascending=true, nulls_first=None))]
+    pub fn sort(
+        &self,
+        ascending: bool,
+        nulls_first: Option<bool>,
+        py: Python,
+    ) -> PyResult<Py<PySortExpr>> {
+        PySortExpr::create(py, self.expr.clone(), ascending, nulls_first)
     }
 
     pub fn is_null(&self) -> PyExpr {
         self.expr.clone().is_null().into()
     }
 
-    pub fn cast(&self, to: PyArrowType<DataType>) -> PyExpr {
-        // self.expr.cast_to() requires DFSchema to validate that the cast
-        // is supported, omit that for now
-        let expr = Expr::Cast(Cast::new(Box::new(self.expr.clone()), to.0));
-        expr.into()
+    /// Cast to the type `to`. Values that can't be cast raise an error, as in DataFusion SQL,
+    /// unless `safe` is set, in which case they become null. `errors="raise"` or `"null"` is
+    /// accepted as an alias of `safe`.
+    #[pyo3(signature = (to, safe=false, errors=None))]
+    pub fn cast(
+        &self,
+        to: PyArrowType<DataType>,
+        safe: bool,
+        errors: Option<&str>,
+    ) -> PyResult<PyExpr> {
+        Ok(self.cast_to(to.0, parse_safe(safe, errors)?))
+    }
+
+    /// Cast to the type `to`, producing null for values that can't be cast
+    pub fn try_cast(&self, to: PyArrowType<DataType>) -> PyExpr {
+        self.cast_to(to.0, true)
+    }
+
+    /// Cast to a decimal with the given precision and scale, a `Decimal128` when the precision
+    /// allows and a `Decimal256` otherwise. `safe` and `errors` are as for `cast`.
+    #[pyo3(signature = (precision, scale, safe=false, errors=None))]
+    pub fn cast_decimal(
+        &self,
+        precision: u8,
+        scale: i8,
+        safe: bool,
+        errors: Option<&str>,
+    ) -> PyResult<PyExpr> {
+        if precision == 0 || precision > DECIMAL256_MAX_PRECISION || scale > precision as i8 {
+            return Err(PyValueError::new_err(format!(
+                "Decimal(precision = {precision}, scale = {scale}) should satisfy \
+                 `0 < precision <= {DECIMAL256_MAX_PRECISION}`, and `scale <= precision`"
+            )));
+        }
+        let to = if precision <= DECIMAL128_MAX_PRECISION {
+            DataType::Decimal128(precision, scale)
+        } else {
+            DataType::Decimal256(precision, scale)
+        };
+        Ok(self.cast_to(to, parse_safe(safe, errors)?))
+    }
+
+    /// Apply a collation to this string expression for use in comparisons and sorts. DataFusion
+    /// compares strings byte by byte, so `"case_insensitive"` is implemented by lowercasing the
+    /// value and `"binary"` leaves it unchanged. Both sides of a comparison must be collated.
+    pub fn collate(&self, collation: &str) -> PyResult<PyExpr> {
+        match collation {
+            "binary" => Ok(self.clone()),
+            "case_insensitive" => Ok(lower(self.expr.clone()).into()),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported collation '{other}', supported values are: binary and \
+                 case_insensitive"
+            ))),
+        }
+    }
+
+    /// Match this string expression against a SQL `LIKE` pattern, a string or an expression, in
+    /// which `%` matches any characters and `_` a single one. A wildcard following the `escape`
+    /// character, a backslash by default, matches itself, which requires a string pattern for
+    /// other characters.
+    #[pyo3(signature = (pattern, escape=None))]
+    pub fn like(&self, pattern: &PyAny, escape: Option<char>) -> PyResult<PyExpr> {
+        self.like_expr(pattern, escape, false, false)
+    }
+
+    /// The negation of `like`
+    #[pyo3(signature = (pattern, escape=None))]
+    pub fn not_like(&self, pattern: &PyAny, escape: Option<char>) -> PyResult<PyExpr> {
+        self.like_expr(pattern, escape, true, false)
+    }
+
+    /// Like `like`, ignoring the case of the letters
+    #[pyo3(signature = (pattern, escape=None))]
+    pub fn ilike(&self, pattern: &PyAny, escape: Option<char>) -> PyResult<PyExpr> {
+        self.like_expr(pattern, escape, false, true)
+    }
+
+    /// The negation of `ilike`
+    #[pyo3(signature = (pattern, escape=None))]
+    pub fn not_ilike(&self, pattern: &PyAny, escape: Option<char>) -> PyResult<PyExpr> {
+        self.like_expr(pattern, escape, true, true)
+    }
+
+    /// Match this string expression against a SQL `SIMILAR TO` pattern, which must match the
+    /// whole value, and in which `%` and `_` are the wildcards of `LIKE` and other characters,
+    /// such as `|`, `*` and `[]`, have their meaning in regular expressions. DataFusion doesn't
+    /// execute `SIMILAR TO`, so the pattern is rewritten to a regular expression.
+    pub fn similar_to(&self, pattern: &str) -> PyResult<PyExpr> {
+        let regex = similar_to_regex(pattern).map_err(PyValueError::new_err)?;
+        Ok(Expr::BinaryExpr(BinaryExpr::new(
+            Box::new(self.expr.clone()),
+            Operator::RegexMatch,
+            Box::new(lit(regex)),
+        ))
+        .into())
     }
 
     /// A Rex (Row Expression) specifies a single row of data. That specification
@@ -323,9 +452,11 @@ impl PyExpr {
                 ScalarValue::UInt64(v) => v.into_py(py),
                 ScalarValue::Utf8(v) => v.clone().into_py(py),
                 ScalarValue::LargeUtf8(v) => v.clone().into_py(py),
-                ScalarValue::Binary(v) => v.clone().into_py(py),
-                ScalarValue::FixedSizeBinary(_, _) => todo!(),
-                ScalarValue::LargeBinary(v) => v.clone().into_py(py),
+                ScalarValue::Binary(v)
+                | ScalarValue::FixedSizeBinary(_, v)
+                | ScalarValue::LargeBinary(v) => {
+                    v.as_ref().map(|v| PyBytes::new(py, v)).into_py(py)
+                }
                 ScalarValue::List(_, _) => todo!(),
                 ScalarValue::Date32(v) => v.into_py(py),
                 ScalarValue::Date64(v) => v.into_py(py),
@@ -526,6 +657,51 @@ impl PyExpr {
 }
 
 impl PyExpr {
+    fn cast_to(&self, to: DataType, null_on_error: bool) -> PyExpr {
+        // self.expr.cast_to() requires DFSchema to validate that the cast
+        // is supported, omit that for now
+        let expr = Box::new(self.expr.clone());
+        let expr = if null_on_error {
+            Expr::TryCast(TryCast::new(expr, to))
+        } else {
+            Expr::Cast(Cast::new(expr, to))
+        };
+        expr.into()
+    }
+
+    fn like_expr(
+        &self,
+        pattern: &PyAny,
+        escape: Option<char>,
+        negated: bool,
+        case_insensitive: bool,
+    ) -> PyResult<PyExpr> {
+        let pattern = match pattern.extract::<&str>() {
+            Ok(pattern) => lit(pattern),
+            Err(_) => pattern.extract::<PyExpr>()?.expr,
+        };
+        // DataFusion only matches patterns escaped by a backslash, others are rewritten
+        let pattern = match (escape, pattern) {
+            (None | Some('\\'), pattern) => pattern,
+            (Some(escape), Expr::Literal(ScalarValue::Utf8(Some(pattern)))) => {
+                lit(escape_like_pattern(&pattern, escape).map_err(PyValueError::new_err)?)
+            }
+            (Some(_), _) => {
+                return Err(PyValueError::new_err(
+                    "An escape character other than a backslash requires a string pattern",
+                ))
+            }
+        };
+        Ok(Expr::Like(Like::new(
+            negated,
+            Box::new(self.expr.clone()),
+            Box::new(pattern),
+            None,
+            case_insensitive,
+        ))
+        .into())
+    }
+
     pub fn _column_name(&self, plan: &LogicalPlan) -> Result<String, DataFusionError> {
         let field = Self::expr_to_field(&self.expr, plan)?;
         Ok(field.qualified_column().flat_name())
@@ -194,3 +194,95 @@ impl PySimilarTo {
         format!("Like({})", self)
     }
 }
+
+/// A character of a `LIKE` pattern, matching itself or any characters
+enum PatternChar {
+    Literal(char),
+    Wildcard(char),
+}
+
+/// Rewrites a `LIKE` pattern whose wildcards are escaped by `escape` to the syntax of
+/// DataFusion, which escapes them with a backslash. A backslash matching itself can't precede
+/// a wildcard in that syntax.
+pub(crate) fn escape_like_pattern(pattern: &str, escape: char) -> Result<String, String> {
+    let mut chars = vec![];
+    let mut iter = pattern.chars();
+    while let Some(c) = iter.next() {
+        if c == escape {
+            match iter.next() {
+                Some(next) => chars.push(PatternChar::Literal(next)),
+                None => {
+                    return Err(format!(
+                        "The LIKE pattern {pattern:?} ends with the escape character"
+                    ))
+                }
+            }
+        } else if c == '%' || c == '_' {
+            chars.push(PatternChar::Wildcard(c));
+        } else {
+            chars.push(PatternChar::Literal(c));
+        }
+    }
+
+    let mut escaped = String::with_capacity(pattern.len());
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            PatternChar::Wildcard(c) => escaped.push(*c),
+            PatternChar::Literal(c @ ('%' | '_')) => {
+                escaped.push('\\');
+                escaped.push(*c);
+            }
+            PatternChar::Literal('\\') => {
+                if let Some(PatternChar::Wildcard(_)) = chars.get(i + 1) {
+                    return Err(format!(
+                        "The LIKE pattern {pattern:?} has a backslash before a wildcard, \
+                         which isn't supported"
+                    ));
+                }
+                escaped.push('\\');
+            }
+            PatternChar::Literal(c) => escaped.push(*c),
+        }
+    }
+    Ok(escaped)
+}
+
+/// Rewrites a `SIMILAR TO` pattern to the regular expression matching the same whole values:
+/// `%` and `_` are the wildcards of `LIKE`, `.` matches itself, a backslash escapes the
+/// character following it and the other characters keep their meaning in regular expressions
+pub(crate) fn similar_to_regex(pattern: &str) -> Result<String, String> {
+    let mut regex = String::from("^(?:");
+    let mut iter = pattern.chars();
+    // whether the previous character opened a bracket expression
+    let mut bracket = false;
+    while let Some(c) = iter.next() {
+        let opens_bracket = c == '[';
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' => match iter.next() {
+                Some(next) => {
+                    if "\\.+*?()|[]{}^$".contains(next) {
+                        regex.push('\\');
+                    }
+                    regex.push(next);
+                }
+                None => {
+                    return Err(format!(
+                        "The SIMILAR TO pattern {pattern:?} ends with the escape character"
+                    ))
+                }
+            },
+            // a caret negates a bracket expression, like in regular expressions
+            '^' if bracket => regex.push(c),
+            '.' | '^' | '$' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+        bracket = opens_bracket;
+    }
+    regex.push_str(")$");
+    Ok(regex)
+}
@@ -18,6 +18,7 @@
 use crate::errors::DataFusionError;
 use datafusion_common::ScalarValue;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 
 #[pyclass(name = "Literal", module = "datafusion.expr", subclass)]
 #[derive(Clone)]
@@ -135,6 +136,17 @@ impl PyLiteral {
         }
     }
 
+    pub fn value_binary(&self, py: Python) -> PyResult<Option<PyObject>> {
+        match &self.value {
+            ScalarValue::Binary(value)
+            | ScalarValue::FixedSizeBinary(_, value)
+            | ScalarValue::LargeBinary(value) => {
+                Ok(value.as_ref().map(|v| PyBytes::new(py, v).into()))
+            }
+            other => Err(unexpected_literal_value(other)),
+        }
+    }
+
     pub fn value_interval_day_time(&self) -> PyResult<Option<(i32, i32)>> {
         match &self.value {
             ScalarValue::IntervalDayTime(Some(iv)) => {
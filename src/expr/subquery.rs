@@ -16,10 +16,15 @@
 // under the License.
 
 use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
 
-use datafusion_expr::Subquery;
+use datafusion_common::tree_node::{Transformed, TreeNode};
+use datafusion_common::DFSchema;
+use datafusion_expr::expr::{Exists, InSubquery};
+use datafusion_expr::{Expr, LogicalPlan, Subquery};
 use pyo3::prelude::*;
 
+use crate::errors::DataFusionError;
 use crate::sql::logical::PyLogicalPlan;
 
 use super::logical_node::LogicalNode;
@@ -79,3 +84,85 @@ impl LogicalNode for PySubquery {
         Ok(self.clone().into_py(py))
     }
 }
+
+/// Resolves the outer column references of every subquery contained in `expr` against
+/// `outer_schema`, the schema of the query the subquery is correlated with. References are
+/// bound by name and take the data type of the matching outer column.
+pub(crate) fn bind_outer_references(
+    expr: Expr,
+    outer_schema: &DFSchema,
+) -> Result<Expr, DataFusionError> {
+    let expr = expr.transform_up(&|expr| {
+        Ok(match expr {
+            Expr::Exists(Exists { subquery, negated }) => Transformed::Yes(Expr::Exists(Exists {
+                subquery: bind_subquery(subquery, outer_schema)?,
+                negated,
+            })),
+            Expr::InSubquery(InSubquery {
+                expr,
+                subquery,
+                negated,
+            }) => Transformed::Yes(Expr::InSubquery(InSubquery {
+                expr,
+                subquery: bind_subquery(subquery, outer_schema)?,
+                negated,
+            })),
+            Expr::ScalarSubquery(subquery) => {
+                Transformed::Yes(Expr::ScalarSubquery(bind_subquery(subquery, outer_schema)?))
+            }
+            expr => Transformed::No(expr),
+        })
+    })?;
+    Ok(expr)
+}
+
+fn bind_subquery(
+    subquery: Subquery,
+    outer_schema: &DFSchema,
+) -> datafusion_common::Result<Subquery> {
+    let plan = subquery
+        .subquery
+        .as_ref()
+        .clone()
+        .transform_up(&|plan: LogicalPlan| {
+            let exprs = plan.expressions();
+            let bound_exprs = exprs
+                .iter()
+                .map(|expr| bind_outer_columns(expr.clone(), outer_schema))
+                .collect::<datafusion_common::Result<Vec<_>>>()?;
+            if bound_exprs == exprs {
+                return Ok(Transformed::No(plan));
+            }
+            let inputs = plan.inputs().into_iter().cloned().collect::<Vec<_>>();
+            Ok(Transformed::Yes(plan.with_new_exprs(bound_exprs, &inputs)?))
+        })?;
+    let plan = Arc::new(plan);
+    Ok(Subquery {
+        outer_ref_columns: plan.all_out_ref_exprs(),
+        subquery: plan,
+    })
+}
+
+fn bind_outer_columns(expr: Expr, outer_schema: &DFSchema) -> datafusion_common::Result<Expr> {
+    expr.transform_up(&|expr| match expr {
+        Expr::OuterReferenceColumn(_, column) => {
+            let field = outer_schema.field_from_column(&column).map_err(|_| {
+                datafusion_common::DataFusionError::Plan(format!(
+                    "Unable to resolve outer column reference '{column}' in subquery, \
+                     valid outer columns are: {}",
+                    outer_schema
+                        .fields()
+                        .iter()
+                        .map(|f| f.qualified_name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })?;
+            Ok(Transformed::Yes(Expr::OuterReferenceColumn(
+                field.data_type().clone(),
+                column,
+            )))
+        }
+        expr => Ok(Transformed::No(expr)),
+    })
+}
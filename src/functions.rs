@@ -15,21 +15,38 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::types::PyDict;
 use pyo3::{prelude::*, wrap_pyfunction};
 
+mod maps;
+mod percentiles;
+mod timestamps;
+
+use crate::dataframe::PyDataFrame;
 use crate::errors::DataFusionError;
 use crate::expr::conditional_expr::PyCaseBuilder;
-use crate::expr::PyExpr;
-use datafusion_common::Column;
+use crate::expr::{PyExpr, PySortExpr};
+use crate::utils::parse_safe;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::arrow::pyarrow::{PyArrowType, ToPyArrow};
+use datafusion_common::{Column, ScalarValue};
 use datafusion_expr::expr::Alias;
 use datafusion_expr::{
     aggregate_function,
-    expr::{AggregateFunction, ScalarFunction, Sort, WindowFunction},
+    expr::{AggregateFunction, ScalarFunction, WindowFunction},
     lit,
-    window_function::find_df_window_func,
-    BuiltinScalarFunction, Expr, WindowFrame,
+    window_function::{self, find_df_window_func},
+    BuiltinScalarFunction, Expr, ScalarUDF, WindowFrame,
 };
 
+/// The UDFs of this module registered in every context, which some plans call by name
+pub(crate) fn registered_udfs() -> Vec<ScalarUDF> {
+    timestamps::parse_udfs()
+}
+
 #[pyfunction]
 fn in_list(expr: PyExpr, value: Vec<PyExpr>, negated: bool) -> PyExpr {
     datafusion_expr::in_list(
@@ -56,6 +73,11 @@ fn digest(value: PyExpr, method: PyExpr) -> PyExpr {
 #[pyfunction]
 #[pyo3(signature = (*args))]
 fn concat(args: Vec<PyExpr>) -> PyResult<PyExpr> {
+    if args.is_empty() {
+        return Err(PyValueError::new_err(
+            "concat requires at least one argument",
+        ));
+    }
     let args = args.into_iter().map(|e| e.expr).collect::<Vec<_>>();
     Ok(datafusion_expr::concat(&args).into())
 }
@@ -70,16 +92,237 @@ fn concat_ws(sep: String, args: Vec<PyExpr>) -> PyResult<PyExpr> {
     Ok(datafusion_expr::concat_ws(lit(sep), args).into())
 }
 
+/// Converts an argument of the string functions, an expression, a string or an int, to an
+/// expression
+fn string_arg(value: &PyAny) -> PyResult<Expr> {
+    if let Ok(expr) = value.extract::<PyExpr>() {
+        return Ok(expr.expr);
+    }
+    if let Ok(value) = value.extract::<&str>() {
+        return Ok(lit(value));
+    }
+    match value.extract::<i64>() {
+        Ok(value) => Ok(lit(value)),
+        Err(_) => Err(PyTypeError::new_err(format!(
+            "Expected an Expr, a str or an int, got {}",
+            value.get_type().name()?
+        ))),
+    }
+}
+
+fn string_function(fun: BuiltinScalarFunction, args: Vec<Expr>) -> PyExpr {
+    Expr::ScalarFunction(ScalarFunction::new(fun, args)).into()
+}
+
+/// Returns the substring of `string` starting at the 1-based position `start`, of `length`
+/// characters or up to its end. Positions before the first character count towards the
+/// length, so `substr(s, 0, 3)` returns the first two characters. A negative `length` raises
+/// an error.
+#[pyfunction]
+#[pyo3(signature = (string, start, length=None))]
+fn substr(string: PyExpr, start: &PyAny, length: Option<&PyAny>) -> PyResult<PyExpr> {
+    let mut args = vec![string.expr, string_arg(start)?];
+    if let Some(length) = length {
+        let length = string_arg(length)?;
+        if let Expr::Literal(ScalarValue::Int64(Some(value))) = length {
+            if value < 0 {
+                return Err(PyValueError::new_err(format!(
+                    "The length of substr can't be negative, got {value}"
+                )));
+            }
+        }
+        args.push(length);
+    }
+    Ok(string_function(BuiltinScalarFunction::Substr, args))
+}
+
+/// Removes the longest string containing only characters in `characters` (a space by default)
+/// from the start and end of `string`.
+#[pyfunction]
+#[pyo3(signature = (string, characters=None))]
+fn trim(string: PyExpr, characters: Option<&PyAny>) -> PyResult<PyExpr> {
+    trim_function(BuiltinScalarFunction::Trim, string, characters)
+}
+
+/// Removes the longest string containing only characters in `characters` (a space by default)
+/// from the start and end of `string`.
+#[pyfunction]
+#[pyo3(signature = (string, characters=None))]
+fn btrim(string: PyExpr, characters: Option<&PyAny>) -> PyResult<PyExpr> {
+    trim_function(BuiltinScalarFunction::Btrim, string, characters)
+}
+
+/// Removes the longest string containing only characters in `characters` (a space by default)
+/// from the start of `string`.
+#[pyfunction]
+#[pyo3(signature = (string, characters=None))]
+fn ltrim(string: PyExpr, characters: Option<&PyAny>) -> PyResult<PyExpr> {
+    trim_function(BuiltinScalarFunction::Ltrim, string, characters)
+}
+
+/// Removes the longest string containing only characters in `characters` (a space by default)
+/// from the end of `string`.
+#[pyfunction]
+#[pyo3(signature = (string, characters=None))]
+fn rtrim(string: PyExpr, characters: Option<&PyAny>) -> PyResult<PyExpr> {
+    trim_function(BuiltinScalarFunction::Rtrim, string, characters)
+}
+
+fn trim_function(
+    fun: BuiltinScalarFunction,
+    string: PyExpr,
+    characters: Option<&PyAny>,
+) -> PyResult<PyExpr> {
+    let mut args = vec![string.expr];
+    if let Some(characters) = characters {
+        args.push(string_arg(characters)?);
+    }
+    Ok(string_function(fun, args))
+}
+
+/// Converts the string to all upper case.
+#[pyfunction]
+fn upper(string: PyExpr) -> PyExpr {
+    string_function(BuiltinScalarFunction::Upper, vec![string.expr])
+}
+
+/// Converts the string to all lower case.
+#[pyfunction]
+fn lower(string: PyExpr) -> PyExpr {
+    string_function(BuiltinScalarFunction::Lower, vec![string.expr])
+}
+
+/// Returns the number of characters in the string, not its number of bytes.
+#[pyfunction]
+fn length(string: PyExpr) -> PyExpr {
+    string_function(BuiltinScalarFunction::CharacterLength, vec![string.expr])
+}
+
+/// Replaces all occurrences in `string` of the substring `from` with the substring `to`.
+#[pyfunction]
+#[pyo3(signature = (string, from, to))]
+fn replace(string: PyExpr, from: &PyAny, to: &PyAny) -> PyResult<PyExpr> {
+    let args = vec![string.expr, string_arg(from)?, string_arg(to)?];
+    Ok(string_function(BuiltinScalarFunction::Replace, args))
+}
+
+/// Converts `string` to a nanosecond timestamp. Without `formats` it is parsed like an RFC
+/// 3339 timestamp, and may also be an int of nanoseconds since the epoch or a timestamp. The
+/// `formats` are chrono format strings tried in order until one matches. Strings no format
+/// matches raise an error, unless `safe` is set, in which case they become null. `safe` and its
+/// alias `errors` are as for `Expr.cast`.
+#[pyfunction]
+#[pyo3(signature = (string, *formats, safe=false, errors=None))]
+fn to_timestamp(
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    safe: bool,
+    errors: Option<&str>,
+) -> PyResult<PyExpr> {
+    parse_function(
+        Some(BuiltinScalarFunction::ToTimestamp),
+        Some(TimeUnit::Nanosecond),
+        string,
+        formats,
+        parse_safe(safe, errors)?,
+    )
+}
+
+/// Like `to_timestamp`, converting to a millisecond timestamp.
+#[pyfunction]
+#[pyo3(signature = (string, *formats, safe=false, errors=None))]
+fn to_timestamp_millis(
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    safe: bool,
+    errors: Option<&str>,
+) -> PyResult<PyExpr> {
+    parse_function(
+        Some(BuiltinScalarFunction::ToTimestampMillis),
+        Some(TimeUnit::Millisecond),
+        string,
+        formats,
+        parse_safe(safe, errors)?,
+    )
+}
+
+/// Like `to_timestamp`, converting to a microsecond timestamp.
+#[pyfunction]
+#[pyo3(signature = (string, *formats, safe=false, errors=None))]
+fn to_timestamp_micros(
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    safe: bool,
+    errors: Option<&str>,
+) -> PyResult<PyExpr> {
+    parse_function(
+        Some(BuiltinScalarFunction::ToTimestampMicros),
+        Some(TimeUnit::Microsecond),
+        string,
+        formats,
+        parse_safe(safe, errors)?,
+    )
+}
+
+/// Like `to_timestamp`, converting to a second timestamp.
+#[pyfunction]
+#[pyo3(signature = (string, *formats, safe=false, errors=None))]
+fn to_timestamp_seconds(
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    safe: bool,
+    errors: Option<&str>,
+) -> PyResult<PyExpr> {
+    parse_function(
+        Some(BuiltinScalarFunction::ToTimestampSeconds),
+        Some(TimeUnit::Second),
+        string,
+        formats,
+        parse_safe(safe, errors)?,
+    )
+}
+
+/// Converts the string `string` to a date, dropping the time of day. The `formats`, `safe` and
+/// `errors` are as for `to_timestamp`.
+#[pyfunction]
+#[pyo3(signature = (string, *formats, safe=false, errors=None))]
+fn to_date(
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    safe: bool,
+    errors: Option<&str>,
+) -> PyResult<PyExpr> {
+    parse_function(None, None, string, formats, parse_safe(safe, errors)?)
+}
+
+/// Calls the built-in function when it parses the string as asked, and the UDF taking formats
+/// otherwise, which turns the strings it can't parse into null with `null_on_error`
+fn parse_function(
+    builtin: Option<BuiltinScalarFunction>,
+    unit: Option<TimeUnit>,
+    string: PyExpr,
+    formats: Vec<&PyAny>,
+    null_on_error: bool,
+) -> PyResult<PyExpr> {
+    if let (Some(fun), true, false) = (builtin, formats.is_empty(), null_on_error) {
+        return Ok(string_function(fun, vec![string.expr]));
+    }
+    let mut args = vec![string.expr];
+    for format in formats {
+        args.push(string_arg(format)?);
+    }
+    Ok(timestamps::parse_udf(unit, null_on_error).call(args).into())
+}
+
 /// Creates a new Sort Expr
 #[pyfunction]
-fn order_by(expr: PyExpr, asc: Option<bool>, nulls_first: Option<bool>) -> PyResult<PyExpr> {
-    Ok(PyExpr {
-        expr: datafusion_expr::Expr::Sort(Sort {
-            expr: Box::new(expr.expr),
-            asc: asc.unwrap_or(true),
-            nulls_first: nulls_first.unwrap_or(true),
-        }),
-    })
+fn order_by(
+    expr: PyExpr,
+    asc: Option<bool>,
+    nulls_first: Option<bool>,
+    py: Python,
+) -> PyResult<Py<PySortExpr>> {
+    PySortExpr::create(py, expr.expr, asc.unwrap_or(true), nulls_first)
 }
 
 /// Creates a new Alias Expr
@@ -101,6 +344,136 @@ fn col(name: &str) -> PyResult<PyExpr> {
     })
 }
 
+/// Create a literal of exactly the Arrow type `arrow_type`, rather than the type `literal`
+/// infers from the value. Raises if the value can't be converted to that type.
+#[pyfunction]
+fn lit_typed(value: &PyAny, arrow_type: PyArrowType<DataType>) -> PyResult<PyExpr> {
+    if value.is_none() {
+        return null_lit(arrow_type);
+    }
+    let py = value.py();
+    let pyarrow = py.import("pyarrow")?;
+    let value = if value.is_instance(pyarrow.getattr("Scalar")?)? {
+        value.call_method0("as_py")?
+    } else {
+        value
+    };
+    let data_type = arrow_type.0;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("type", data_type.to_pyarrow(py)?)?;
+    let scalar = pyarrow
+        .call_method("scalar", (value,), Some(kwargs))
+        .and_then(|scalar| scalar.extract::<ScalarValue>())
+        .map_err(|e| {
+            PyValueError::new_err(format!(
+                "Can't create a literal of type {data_type} from {value}: {e}"
+            ))
+        })?;
+    if scalar.get_datatype() != data_type {
+        return Err(PyValueError::new_err(format!(
+            "Can't create a literal of type {data_type} from {value}, it converts to {}",
+            scalar.get_datatype()
+        )));
+    }
+    Ok(lit(scalar).into())
+}
+
+/// Create a null literal of the Arrow type `arrow_type`
+#[pyfunction]
+fn null_lit(arrow_type: PyArrowType<DataType>) -> PyResult<PyExpr> {
+    let scalar = ScalarValue::try_from(&arrow_type.0).map_err(DataFusionError::from)?;
+    Ok(lit(scalar).into())
+}
+
+/// Create a fixed size binary literal of `size` bytes, such as a hash, to compare with
+/// `FixedSizeBinary` columns, which don't coerce to the `Binary` of a `bytes` literal.
+/// `None` creates a null of that type.
+#[pyfunction]
+#[pyo3(signature = (value, size))]
+fn lit_fixed_binary(value: Option<&[u8]>, size: i32) -> PyResult<PyExpr> {
+    if let Some(value) = value {
+        if value.len() as i64 != i64::from(size) {
+            return Err(PyValueError::new_err(format!(
+                "lit_fixed_binary expects a value of {size} bytes, got {} bytes",
+                value.len()
+            )));
+        }
+    }
+    let scalar = ScalarValue::FixedSizeBinary(size, value.map(<[u8]>::to_vec));
+    Ok(lit(scalar).into())
+}
+
+/// Create a reference to a column of the outer query from within a correlated subquery.
+/// The reference is resolved by name, and its data type bound, when the subquery is used
+/// in a filter on the outer DataFrame.
+#[pyfunction]
+fn outer_col(name: &str) -> PyExpr {
+    Expr::OuterReferenceColumn(DataType::Null, Column::from(name)).into()
+}
+
+/// Create an EXISTS predicate that is true when the subquery returns at least one row
+#[pyfunction]
+fn exists(subquery: PyDataFrame) -> PyExpr {
+    datafusion_expr::exists(Arc::new(subquery.df.logical_plan().clone())).into()
+}
+
+/// Create a NOT EXISTS predicate that is true when the subquery returns no rows
+#[pyfunction]
+fn not_exists(subquery: PyDataFrame) -> PyExpr {
+    datafusion_expr::not_exists(Arc::new(subquery.df.logical_plan().clone())).into()
+}
+
+/// Create a ROLLUP grouping, which groups by every prefix of the expressions, from all of
+/// them down to the grand total
+#[pyfunction]
+#[pyo3(signature = (*args))]
+fn rollup(args: Vec<PyExpr>) -> PyExpr {
+    datafusion_expr::rollup(args.into_iter().map(|e| e.expr).collect()).into()
+}
+
+/// Create a CUBE grouping, which groups by every combination of the expressions
+#[pyfunction]
+#[pyo3(signature = (*args))]
+fn cube(args: Vec<PyExpr>) -> PyExpr {
+    datafusion_expr::cube(args.into_iter().map(|e| e.expr).collect()).into()
+}
+
+/// Create a GROUPING SETS grouping, which groups by each of the given lists of expressions
+#[pyfunction]
+fn grouping_sets(sets: Vec<Vec<PyExpr>>) -> PyExpr {
+    let sets = sets
+        .into_iter()
+        .map(|set| set.into_iter().map(|e| e.expr).collect())
+        .collect();
+    datafusion_expr::grouping_set(sets).into()
+}
+
+/// Create a map from a list of keys and a list of values of the same length. Keys can't be null.
+#[pyfunction]
+fn map(keys: PyExpr, values: PyExpr) -> PyExpr {
+    maps::map_udf().call(vec![keys.expr, values.expr]).into()
+}
+
+/// Returns the value of a map stored under key, or null when the map doesn't contain the key
+#[pyfunction]
+fn map_extract(map: PyExpr, key: PyExpr) -> PyExpr {
+    maps::map_extract_udf()
+        .call(vec![map.expr, key.expr])
+        .into()
+}
+
+/// Returns the keys of a map as a list
+#[pyfunction]
+fn map_keys(map: PyExpr) -> PyExpr {
+    maps::map_keys_udf().call(vec![map.expr]).into()
+}
+
+/// Returns the values of a map as a list
+#[pyfunction]
+fn map_values(map: PyExpr) -> PyExpr {
+    maps::map_values_udf().call(vec![map.expr]).into()
+}
+
 /// Create a COUNT(1) aggregate expression
 #[pyfunction]
 fn count_star() -> PyResult<PyExpr> {
@@ -131,11 +504,25 @@ fn window(
     partition_by: Option<Vec<PyExpr>>,
     order_by: Option<Vec<PyExpr>>,
 ) -> PyResult<PyExpr> {
-    let fun = find_df_window_func(name);
-    if fun.is_none() {
-        return Err(DataFusionError::Common("window function not found".to_string()).into());
-    }
-    let fun = fun.unwrap();
+    let fun = match (find_df_window_func(name), name) {
+        (Some(fun), _) => fun,
+        (None, "percentile_cont" | "percentile_disc") => {
+            match args.get(1).map(|arg| &arg.expr) {
+                Some(Expr::Literal(value)) if args.len() == 2 => percentile_value(value)?,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "{name} expects a value and a literal percentile"
+                    )))
+                }
+            };
+            window_function::WindowFunction::AggregateUDF(Arc::new(percentiles::percentile_udaf(
+                name == "percentile_disc",
+            )))
+        }
+        (None, _) => {
+            return Err(DataFusionError::Common("window function not found".to_string()).into())
+        }
+    };
     let window_frame = WindowFrame::new(order_by.is_some());
     Ok(PyExpr {
         expr: datafusion_expr::Expr::WindowFunction(WindowFunction {
@@ -156,6 +543,49 @@ fn window(
     })
 }
 
+/// Returns the float of a literal percentile, raising if it isn't between 0 and 1
+fn percentile_value(value: &ScalarValue) -> PyResult<f64> {
+    let percentile = match value {
+        ScalarValue::Float64(Some(percentile)) => *percentile,
+        ScalarValue::Float32(Some(percentile)) => *percentile as f64,
+        ScalarValue::Int64(Some(percentile)) => *percentile as f64,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "The percentile must be a number, got {value}"
+            )))
+        }
+    };
+    if !(0.0..=1.0).contains(&percentile) {
+        return Err(PyValueError::new_err(format!(
+            "The percentile must be between 0 and 1, got {percentile}"
+        )));
+    }
+    Ok(percentile)
+}
+
+/// Calls the exact percentile aggregate, `percentile_disc` if `discrete` is set
+fn percentile_function(expr: PyExpr, percentile: f64, discrete: bool) -> PyResult<PyExpr> {
+    let percentile = percentile_value(&ScalarValue::Float64(Some(percentile)))?;
+    let udaf = percentiles::percentile_udaf(discrete);
+    Ok(udaf.call(vec![expr.expr, lit(percentile)]).into())
+}
+
+/// Computes the exact percentile of the values, between 0 and 1, interpolating linearly between
+/// the two values closest to it. Returns a float, or null when all values are null. Unlike
+/// `approx_percentile_cont`, every value of a group is kept in memory.
+#[pyfunction]
+fn percentile_cont(expr: PyExpr, percentile: f64) -> PyResult<PyExpr> {
+    percentile_function(expr, percentile, false)
+}
+
+/// Computes the exact percentile of the values, between 0 and 1, as the first value whose
+/// cumulative distribution is at least the percentile, so that the result is one of the values.
+/// Every value of a group is kept in memory.
+#[pyfunction]
+fn percentile_disc(expr: PyExpr, percentile: f64) -> PyResult<PyExpr> {
+    percentile_function(expr, percentile, true)
+}
+
 macro_rules! scalar_function {
     ($NAME: ident, $FUNC: ident) => {
         scalar_function!($NAME, $FUNC, stringify!($NAME));
@@ -210,7 +640,6 @@ scalar_function!(
     BitLength,
     "Returns number of bits in the string (8 times the octet_length)."
 );
-scalar_function!(btrim, Btrim, "Removes the longest string containing only characters in characters (a space by default) from the start and end of string.");
 scalar_function!(cbrt, Cbrt);
 scalar_function!(ceil, Ceil);
 scalar_function!(
@@ -218,7 +647,6 @@ scalar_function!(
     CharacterLength,
     "Returns number of characters in the string."
 );
-scalar_function!(length, CharacterLength);
 scalar_function!(char_length, CharacterLength);
 scalar_function!(chr, Chr, "Returns the character with the given code.");
 scalar_function!(coalesce, Coalesce);
@@ -238,9 +666,7 @@ scalar_function!(ln, Ln);
 scalar_function!(log, Log);
 scalar_function!(log10, Log10);
 scalar_function!(log2, Log2);
-scalar_function!(lower, Lower, "Converts the string to all lower case");
 scalar_function!(lpad, Lpad, "Extends the string to length length by prepending the characters fill (a space by default). If the string is already longer than length then it is truncated (on the right).");
-scalar_function!(ltrim, Ltrim, "Removes the longest string containing only characters in characters (a space by default) from the start of string.");
 scalar_function!(
     md5,
     MD5,
@@ -267,11 +693,6 @@ scalar_function!(
     Repeat,
     "Repeats string the specified number of times."
 );
-scalar_function!(
-    replace,
-    Replace,
-    "Replaces all occurrences in string of substring from with substring to."
-);
 scalar_function!(
     reverse,
     Reverse,
@@ -280,7 +701,6 @@ scalar_function!(
 scalar_function!(right, Right, "Returns last n characters in the string, or when n is negative, returns all but first |n| characters.");
 scalar_function!(round, Round);
 scalar_function!(rpad, Rpad, "Extends the string to length length by appending the characters fill (a space by default). If the string is already longer than length then it is truncated.");
-scalar_function!(rtrim, Rtrim, "Removes the longest string containing only characters in characters (a space by default) from the end of string.");
 scalar_function!(sha224, SHA224);
 scalar_function!(sha256, SHA256);
 scalar_function!(sha384, SHA384);
@@ -300,7 +720,6 @@ scalar_function!(
     "Returns true if string starts with prefix."
 );
 scalar_function!(strpos, Strpos, "Returns starting index of specified substring within string, or zero if it's not present. (Same as position(substring in string), but note the reversed argument order.)");
-scalar_function!(substr, Substr);
 scalar_function!(tan, Tan);
 scalar_function!(tanh, Tanh);
 scalar_function!(
@@ -309,10 +728,6 @@ scalar_function!(
     "Converts the number to its equivalent hexadecimal representation."
 );
 scalar_function!(now, Now);
-scalar_function!(to_timestamp, ToTimestamp);
-scalar_function!(to_timestamp_millis, ToTimestampMillis);
-scalar_function!(to_timestamp_micros, ToTimestampMicros);
-scalar_function!(to_timestamp_seconds, ToTimestampSeconds);
 scalar_function!(current_date, CurrentDate);
 scalar_function!(current_time, CurrentTime);
 scalar_function!(datepart, DatePart);
@@ -321,9 +736,7 @@ scalar_function!(date_trunc, DateTrunc);
 scalar_function!(datetrunc, DateTrunc);
 scalar_function!(date_bin, DateBin);
 scalar_function!(translate, Translate, "Replaces each character in string that matches a character in the from set with the corresponding character in the to set. If from is longer than to, occurrences of the extra characters in from are deleted.");
-scalar_function!(trim, Trim, "Removes the longest string containing only characters in characters (a space by default) from the start, end, or both ends (BOTH is the default) of string.");
 scalar_function!(trunc, Trunc);
-scalar_function!(upper, Upper, "Converts the string to all upper case.");
 scalar_function!(make_array, MakeArray);
 scalar_function!(array, MakeArray);
 scalar_function!(nullif, NullIf);
@@ -372,6 +785,8 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(approx_median))?;
     m.add_wrapped(wrap_pyfunction!(approx_percentile_cont))?;
     m.add_wrapped(wrap_pyfunction!(approx_percentile_cont_with_weight))?;
+    m.add_wrapped(wrap_pyfunction!(percentile_cont))?;
+    m.add_wrapped(wrap_pyfunction!(percentile_disc))?;
     m.add_wrapped(wrap_pyfunction!(array))?;
     m.add_wrapped(wrap_pyfunction!(array_agg))?;
     m.add_wrapped(wrap_pyfunction!(arrow_typeof))?;
@@ -402,6 +817,7 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(covar))?;
     m.add_wrapped(wrap_pyfunction!(covar_pop))?;
     m.add_wrapped(wrap_pyfunction!(covar_samp))?;
+    m.add_wrapped(wrap_pyfunction!(cube))?;
     m.add_wrapped(wrap_pyfunction!(current_date))?;
     m.add_wrapped(wrap_pyfunction!(current_time))?;
     m.add_wrapped(wrap_pyfunction!(degrees))?;
@@ -411,12 +827,14 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(datetrunc))?;
     m.add_wrapped(wrap_pyfunction!(date_trunc))?;
     m.add_wrapped(wrap_pyfunction!(digest))?;
+    m.add_wrapped(wrap_pyfunction!(exists))?;
     m.add_wrapped(wrap_pyfunction!(exp))?;
     m.add_wrapped(wrap_pyfunction!(factorial))?;
     m.add_wrapped(wrap_pyfunction!(floor))?;
     m.add_wrapped(wrap_pyfunction!(from_unixtime))?;
     m.add_wrapped(wrap_pyfunction!(gcd))?;
     m.add_wrapped(wrap_pyfunction!(grouping))?;
+    m.add_wrapped(wrap_pyfunction!(grouping_sets))?;
     m.add_wrapped(wrap_pyfunction!(in_list))?;
     m.add_wrapped(wrap_pyfunction!(initcap))?;
     m.add_wrapped(wrap_pyfunction!(isnan))?;
@@ -424,6 +842,8 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(lcm))?;
     m.add_wrapped(wrap_pyfunction!(left))?;
     m.add_wrapped(wrap_pyfunction!(length))?;
+    m.add_wrapped(wrap_pyfunction!(lit_fixed_binary))?;
+    m.add_wrapped(wrap_pyfunction!(lit_typed))?;
     m.add_wrapped(wrap_pyfunction!(ln))?;
     m.add_wrapped(wrap_pyfunction!(log))?;
     m.add_wrapped(wrap_pyfunction!(log10))?;
@@ -433,15 +853,22 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(ltrim))?;
     m.add_wrapped(wrap_pyfunction!(max))?;
     m.add_wrapped(wrap_pyfunction!(make_array))?;
+    m.add_wrapped(wrap_pyfunction!(map))?;
+    m.add_wrapped(wrap_pyfunction!(map_extract))?;
+    m.add_wrapped(wrap_pyfunction!(map_keys))?;
+    m.add_wrapped(wrap_pyfunction!(map_values))?;
     m.add_wrapped(wrap_pyfunction!(md5))?;
     m.add_wrapped(wrap_pyfunction!(mean))?;
     m.add_wrapped(wrap_pyfunction!(median))?;
     m.add_wrapped(wrap_pyfunction!(min))?;
     m.add_wrapped(wrap_pyfunction!(nanvl))?;
+    m.add_wrapped(wrap_pyfunction!(not_exists))?;
     m.add_wrapped(wrap_pyfunction!(now))?;
+    m.add_wrapped(wrap_pyfunction!(null_lit))?;
     m.add_wrapped(wrap_pyfunction!(nullif))?;
     m.add_wrapped(wrap_pyfunction!(octet_length))?;
     m.add_wrapped(wrap_pyfunction!(order_by))?;
+    m.add_wrapped(wrap_pyfunction!(outer_col))?;
     m.add_wrapped(wrap_pyfunction!(pi))?;
     m.add_wrapped(wrap_pyfunction!(power))?;
     m.add_wrapped(wrap_pyfunction!(pow))?;
@@ -453,6 +880,7 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(replace))?;
     m.add_wrapped(wrap_pyfunction!(reverse))?;
     m.add_wrapped(wrap_pyfunction!(right))?;
+    m.add_wrapped(wrap_pyfunction!(rollup))?;
     m.add_wrapped(wrap_pyfunction!(round))?;
     m.add_wrapped(wrap_pyfunction!(rpad))?;
     m.add_wrapped(wrap_pyfunction!(rtrim))?;
@@ -476,6 +904,7 @@ pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(tan))?;
     m.add_wrapped(wrap_pyfunction!(tanh))?;
     m.add_wrapped(wrap_pyfunction!(to_hex))?;
+    m.add_wrapped(wrap_pyfunction!(to_date))?;
     m.add_wrapped(wrap_pyfunction!(to_timestamp))?;
     m.add_wrapped(wrap_pyfunction!(to_timestamp_millis))?;
     m.add_wrapped(wrap_pyfunction!(to_timestamp_micros))?;
@@ -0,0 +1,235 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Functions to build and read map columns, which DataFusion has no built-in functions for.
+//! They are scalar UDFs, so they are only available through the expression API.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, ArrayRef, ListArray, MapArray, StructArray, UInt32Array, UInt32Builder,
+};
+use datafusion::arrow::buffer::{NullBuffer, OffsetBuffer};
+use datafusion::arrow::compute::kernels::cmp::eq;
+use datafusion::arrow::compute::{cast, take};
+use datafusion::arrow::datatypes::{DataType, Field, Fields};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::physical_plan::functions::make_scalar_function;
+use datafusion_common::cast::{as_list_array, as_map_array};
+use datafusion_expr::{ReturnTypeFunction, ScalarUDF, Signature, Volatility};
+
+/// The type of a map built from lists of `keys` and `values`, laid out the same way as pyarrow
+/// lays out its maps
+fn map_type(keys: &DataType, values: &DataType) -> DataType {
+    let entries = Fields::from(vec![
+        Field::new("key", keys.clone(), false),
+        Field::new("value", values.clone(), true),
+    ]);
+    DataType::Map(
+        Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+        false,
+    )
+}
+
+/// Returns the key and value fields of a map type
+fn map_fields(name: &str, data_type: &DataType) -> Result<(Field, Field)> {
+    match data_type {
+        DataType::Map(entries, _) => match entries.data_type() {
+            DataType::Struct(fields) if fields.len() == 2 => {
+                Ok((fields[0].as_ref().clone(), fields[1].as_ref().clone()))
+            }
+            other => Err(DataFusionError::Plan(format!(
+                "{name} expects map entries to be a struct of a key and a value, got {other}"
+            ))),
+        },
+        other => Err(DataFusionError::Plan(format!(
+            "{name} expects a map argument, got {other}"
+        ))),
+    }
+}
+
+/// Returns the type of the elements of a list type
+fn list_item(name: &str, data_type: &DataType) -> Result<DataType> {
+    match data_type {
+        DataType::List(item) => Ok(item.data_type().clone()),
+        other => Err(DataFusionError::Plan(format!(
+            "{name} expects list arguments, got {other}"
+        ))),
+    }
+}
+
+/// `map(keys, values)` builds a map from a list of keys and a list of values of the same length.
+/// The map is null when either list is null.
+pub(crate) fn map_udf() -> ScalarUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|args| {
+        let keys = list_item("map", &args[0])?;
+        let values = list_item("map", &args[1])?;
+        Ok(Arc::new(map_type(&keys, &values)))
+    });
+    let fun = make_scalar_function(|args: &[ArrayRef]| {
+        let keys = as_list_array(&args[0])?;
+        let values = as_list_array(&args[1])?;
+        let mut key_indices = UInt32Builder::new();
+        let mut value_indices = UInt32Builder::new();
+        let mut offsets = vec![0];
+        for row in 0..keys.len() {
+            if keys.is_valid(row) && values.is_valid(row) {
+                let key_offsets = &keys.value_offsets()[row..row + 2];
+                let value_offsets = &values.value_offsets()[row..row + 2];
+                let len = key_offsets[1] - key_offsets[0];
+                if len != value_offsets[1] - value_offsets[0] {
+                    return Err(DataFusionError::Execution(format!(
+                        "map expects the same number of keys and values, got {} keys and {} values",
+                        len,
+                        value_offsets[1] - value_offsets[0],
+                    )));
+                }
+                key_indices.append_slice(
+                    &(key_offsets[0] as u32..key_offsets[1] as u32).collect::<Vec<_>>(),
+                );
+                value_indices.append_slice(
+                    &(value_offsets[0] as u32..value_offsets[1] as u32).collect::<Vec<_>>(),
+                );
+                offsets.push(offsets[row] + len);
+            } else {
+                offsets.push(offsets[row]);
+            }
+        }
+
+        let map_keys = take(keys.values().as_ref(), &key_indices.finish(), None)?;
+        if map_keys.null_count() > 0 {
+            return Err(DataFusionError::Execution(
+                "map keys can't be null".to_string(),
+            ));
+        }
+        let map_values = take(values.values().as_ref(), &value_indices.finish(), None)?;
+        let data_type = map_type(map_keys.data_type(), map_values.data_type());
+        let (entries_field, ordered) = match data_type {
+            DataType::Map(entries_field, ordered) => (entries_field, ordered),
+            _ => unreachable!(),
+        };
+        let fields = match entries_field.data_type() {
+            DataType::Struct(fields) => fields.clone(),
+            _ => unreachable!(),
+        };
+        let entries = StructArray::new(fields, vec![map_keys, map_values], None);
+        let nulls = NullBuffer::union(keys.nulls(), values.nulls());
+        let map = MapArray::try_new(
+            entries_field.clone(),
+            OffsetBuffer::new(offsets.into()),
+            entries,
+            nulls,
+            ordered,
+        )?;
+        Ok(Arc::new(map) as ArrayRef)
+    });
+    ScalarUDF::new(
+        "map",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+/// `map_extract(map, key)` returns the value stored under `key`, or null when the map is null
+/// or doesn't contain the key
+pub(crate) fn map_extract_udf() -> ScalarUDF {
+    let return_type: ReturnTypeFunction = Arc::new(|args| {
+        let (_, value) = map_fields("map_extract", &args[0])?;
+        Ok(Arc::new(value.data_type().clone()))
+    });
+    let fun = make_scalar_function(|args: &[ArrayRef]| {
+        let map = as_map_array(&args[0])?;
+        let keys = cast(&args[1], map.keys().data_type())?;
+
+        // Compare every entry of each map with the key looked up in that row
+        let mut entry_rows = UInt32Builder::with_capacity(map.keys().len());
+        for (row, window) in map.value_offsets().windows(2).enumerate() {
+            for _ in window[0]..window[1] {
+                entry_rows.append_value(row as u32);
+            }
+        }
+        let first = map.value_offsets()[0] as usize;
+        let entry_count = map.value_offsets()[map.len()] as usize - first;
+        let entry_keys = map.keys().slice(first, entry_count);
+        let lookup_keys = take(keys.as_ref(), &entry_rows.finish(), None)?;
+        let matches = eq(&entry_keys, &lookup_keys)?;
+
+        let indices = map
+            .value_offsets()
+            .windows(2)
+            .enumerate()
+            .map(|(row, window)| {
+                if map.is_null(row) {
+                    return None;
+                }
+                (window[0] as usize..window[1] as usize)
+                    .find(|entry| matches.is_valid(entry - first) && matches.value(entry - first))
+                    .map(|entry| entry as u32)
+            })
+            .collect::<UInt32Array>();
+        Ok(take(map.values().as_ref(), &indices, None)?)
+    });
+    ScalarUDF::new(
+        "map_extract",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+/// A function returning a list of the keys or of the values of each map
+fn map_list_udf(name: &'static str, use_keys: bool) -> ScalarUDF {
+    let item_field = move |data_type: &DataType| -> Result<Field> {
+        let (key, value) = map_fields(name, data_type)?;
+        let item = if use_keys { key } else { value };
+        Ok(Field::new(
+            "item",
+            item.data_type().clone(),
+            item.is_nullable(),
+        ))
+    };
+    let return_type: ReturnTypeFunction =
+        Arc::new(move |args| Ok(Arc::new(DataType::List(Arc::new(item_field(&args[0])?)))));
+    let fun = make_scalar_function(move |args: &[ArrayRef]| {
+        let map = as_map_array(&args[0])?;
+        let values = if use_keys { map.keys() } else { map.values() };
+        let list = ListArray::try_new(
+            Arc::new(item_field(args[0].data_type())?),
+            map.offsets().clone(),
+            values.clone(),
+            map.nulls().cloned(),
+        )?;
+        Ok(Arc::new(list) as ArrayRef)
+    });
+    ScalarUDF::new(
+        name,
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+/// `map_keys(map)` returns the keys of each map as a list
+pub(crate) fn map_keys_udf() -> ScalarUDF {
+    map_list_udf("map_keys", true)
+}
+
+/// `map_values(map)` returns the values of each map as a list
+pub(crate) fn map_values_udf() -> ScalarUDF {
+    map_list_udf("map_values", false)
+}
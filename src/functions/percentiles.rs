@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Exact percentiles, which DataFusion only has approximate built-in aggregates for. They keep
+//! every value of a group in memory, as the percentile can only be known once all are seen.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{make_array, Array, ArrayData, ArrayRef};
+use datafusion::arrow::buffer::Buffer;
+use datafusion::arrow::compute::{cast, concat, filter, is_not_null, sort};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::Result;
+use datafusion_common::cast::{as_binary_array, as_float64_array};
+use datafusion_common::ScalarValue;
+use datafusion_expr::type_coercion::aggregates::NUMERICS;
+use datafusion_expr::{
+    Accumulator, AccumulatorFactoryFunction, AggregateUDF, ReturnTypeFunction, Signature,
+    StateTypeFunction, TypeSignature, Volatility,
+};
+
+/// `percentile_cont(value, percentile)` interpolates linearly between the two values closest to
+/// the percentile and returns a float. `percentile_disc(value, percentile)` returns the first
+/// value whose cumulative distribution is at least the percentile, of the type of the values.
+/// Nulls are ignored, and the percentile, a float literal, is the same for every row.
+pub(crate) fn percentile_udaf(discrete: bool) -> AggregateUDF {
+    let name = if discrete {
+        "percentile_disc"
+    } else {
+        "percentile_cont"
+    };
+    let signature = Signature::one_of(
+        NUMERICS
+            .iter()
+            .map(|data_type| TypeSignature::Exact(vec![data_type.clone(), DataType::Float64]))
+            .collect(),
+        Volatility::Immutable,
+    );
+    let return_type: ReturnTypeFunction = Arc::new(move |args| {
+        Ok(Arc::new(if discrete {
+            args[0].clone()
+        } else {
+            DataType::Float64
+        }))
+    });
+    let accumulator: AccumulatorFactoryFunction = Arc::new(move |return_type| {
+        Ok(Box::new(PercentileAccumulator {
+            discrete,
+            return_type: return_type.clone(),
+            percentile: None,
+            values: vec![],
+        }))
+    });
+    // The values of a group are kept in the state as the bytes of their buffer, copied at once,
+    // as a list scalar is made of a scalar per value
+    let state_type: StateTypeFunction =
+        Arc::new(|_| Ok(Arc::new(vec![DataType::Binary, DataType::Float64])));
+    AggregateUDF::new(name, &signature, &return_type, &accumulator, &state_type)
+}
+
+#[derive(Debug)]
+struct PercentileAccumulator {
+    discrete: bool,
+    return_type: DataType,
+    percentile: Option<f64>,
+    /// The non-null values seen so far, of the return type, which is a primitive type
+    values: Vec<ArrayRef>,
+}
+
+impl PercentileAccumulator {
+    fn set_percentile(&mut self, percentiles: &ArrayRef) -> Result<()> {
+        let percentiles = as_float64_array(percentiles)?;
+        if self.percentile.is_none() {
+            self.percentile = percentiles.iter().flatten().next();
+        }
+        Ok(())
+    }
+
+    /// Adds the non-null `values`, as floats for `percentile_cont`, so that the values, and the
+    /// state holding them, have the return type
+    fn push(&mut self, values: &ArrayRef) -> Result<()> {
+        let values = cast(values, &self.return_type)?;
+        let values = if values.null_count() > 0 {
+            filter(&values, &is_not_null(&values)?)?
+        } else {
+            values
+        };
+        if !values.is_empty() {
+            self.values.push(values);
+        }
+        Ok(())
+    }
+
+    /// All the values seen so far, sorted
+    fn sorted_values(&self) -> Result<Option<ArrayRef>> {
+        if self.values.is_empty() {
+            return Ok(None);
+        }
+        let arrays: Vec<&dyn Array> = self.values.iter().map(|array| array.as_ref()).collect();
+        Ok(Some(sort(&concat(&arrays)?, None)?))
+    }
+
+    /// The size in bytes of a value of the return type
+    fn value_width(&self) -> usize {
+        self.return_type.primitive_width().unwrap()
+    }
+}
+
+impl Accumulator for PercentileAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let bytes = match self.sorted_values()? {
+            Some(values) => {
+                let data = values.to_data();
+                let width = self.value_width();
+                let start = data.offset() * width;
+                data.buffers()[0].as_slice()[start..start + data.len() * width].to_vec()
+            }
+            None => vec![],
+        };
+        Ok(vec![
+            ScalarValue::Binary(Some(bytes)),
+            ScalarValue::Float64(self.percentile),
+        ])
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.set_percentile(&values[1])?;
+        self.push(&values[0])
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        self.set_percentile(&states[1])?;
+        let width = self.value_width();
+        for bytes in as_binary_array(&states[0])?.iter().flatten() {
+            let data = ArrayData::try_new(
+                self.return_type.clone(),
+                bytes.len() / width,
+                None,
+                0,
+                vec![Buffer::from(bytes)],
+                vec![],
+            )?;
+            self.push(&make_array(data))?;
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let (values, percentile) = match (self.sorted_values()?, self.percentile) {
+            (Some(values), Some(percentile)) => (values, percentile),
+            _ => return ScalarValue::try_from(&self.return_type),
+        };
+        let count = values.len();
+        if self.discrete {
+            let index = ((percentile * count as f64).ceil() as usize).saturating_sub(1);
+            return ScalarValue::try_from_array(&values, index.min(count - 1));
+        }
+        let values = as_float64_array(&values)?;
+        let position = percentile * (count - 1) as f64;
+        let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+        let fraction = position - lower as f64;
+        let value = values.value(lower) + (values.value(upper) - values.value(lower)) * fraction;
+        Ok(ScalarValue::Float64(Some(value)))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self
+                .values
+                .iter()
+                .map(|values| values.get_array_memory_size())
+                .sum::<usize>()
+    }
+}
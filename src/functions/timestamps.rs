@@ -0,0 +1,198 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Parsing of strings to timestamps and dates with format strings, which the `to_timestamp`
+//! functions of DataFusion don't take, and to nulls rather than errors for unparseable values.
+//! They are scalar UDFs registered in every context, under names of their own so that the
+//! plans calling them can be serialized and read back.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use datafusion::arrow::array::{
+    Array, ArrayRef, Date32Array, TimestampMicrosecondArray, TimestampMillisecondArray,
+    TimestampNanosecondArray, TimestampSecondArray,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::compute::kernels::cast_utils::string_to_datetime;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::physical_plan::functions::make_scalar_function;
+use datafusion_common::cast::as_string_array;
+use datafusion_expr::{ReturnTypeFunction, ScalarUDF, Signature, Volatility};
+
+/// Every UDF of `parse_udf`, to register in a context
+pub(crate) fn parse_udfs() -> Vec<ScalarUDF> {
+    let units = [
+        Some(TimeUnit::Second),
+        Some(TimeUnit::Millisecond),
+        Some(TimeUnit::Microsecond),
+        Some(TimeUnit::Nanosecond),
+        None,
+    ];
+    units
+        .into_iter()
+        .flat_map(|unit| [false, true].map(|null_on_error| parse_udf(unit.clone(), null_on_error)))
+        .collect()
+}
+
+/// Returns the UDF `name(string, *formats)` parsing `string` to a timestamp without a time zone
+/// of the unit `unit`, or to a date without one. The formats are chrono format strings tried in
+/// order, strings are parsed like `to_timestamp` parses them without one. Strings matching no
+/// format raise an error, or become null with `null_on_error`. Without formats, values that
+/// aren't strings, such as integers and timestamps, are cast like `to_timestamp` casts them.
+pub(crate) fn parse_udf(unit: Option<TimeUnit>, null_on_error: bool) -> ScalarUDF {
+    // the name of the function of the Python API, which the errors refer to
+    let function = match &unit {
+        Some(TimeUnit::Second) => "to_timestamp_seconds",
+        Some(TimeUnit::Millisecond) => "to_timestamp_millis",
+        Some(TimeUnit::Microsecond) => "to_timestamp_micros",
+        Some(TimeUnit::Nanosecond) => "to_timestamp",
+        None => "to_date",
+    };
+    let name = match null_on_error {
+        true => format!("try_{function}_with_formats"),
+        false => format!("{function}_with_formats"),
+    };
+    let data_type = match &unit {
+        Some(unit) => DataType::Timestamp(unit.clone(), None),
+        None => DataType::Date32,
+    };
+    let return_type: ReturnTypeFunction = {
+        let data_type = data_type.clone();
+        Arc::new(move |_| Ok(Arc::new(data_type.clone())))
+    };
+    let fun = make_scalar_function(move |args: &[ArrayRef]| {
+        let strings = match args[0].data_type() {
+            DataType::Utf8 => args[0].clone(),
+            DataType::LargeUtf8 | DataType::Dictionary(_, _) => cast(&args[0], &DataType::Utf8)?,
+            _ if args.len() == 1 => return Ok(cast(&args[0], &data_type)?),
+            other => {
+                return Err(DataFusionError::Execution(format!(
+                    "{function} parses strings with formats, got {other}"
+                )))
+            }
+        };
+        let strings = as_string_array(&strings)?;
+        let formats = args[1..]
+            .iter()
+            .map(|formats| as_string_array(formats))
+            .collect::<Result<Vec<_>>>()?;
+        let mut parsed = Vec::with_capacity(strings.len());
+        for row in 0..strings.len() {
+            let row_formats: Vec<&str> = formats
+                .iter()
+                .filter(|formats| formats.is_valid(row))
+                .map(|formats| formats.value(row))
+                .collect();
+            if strings.is_null(row) || (!formats.is_empty() && row_formats.is_empty()) {
+                parsed.push(None);
+                continue;
+            }
+            let string = strings.value(row);
+            match parse_datetime(string, &row_formats) {
+                Some(datetime) => parsed.push(Some(datetime)),
+                None if null_on_error => parsed.push(None),
+                None if row_formats.is_empty() => {
+                    return Err(DataFusionError::Execution(format!(
+                        "{function} can't parse '{string}'"
+                    )))
+                }
+                None => {
+                    return Err(DataFusionError::Execution(format!(
+                        "{function} can't parse '{string}' with any of the formats {}",
+                        row_formats.join(", ")
+                    )))
+                }
+            }
+        }
+        let parsed = parsed.into_iter();
+        let array: ArrayRef = match unit {
+            Some(TimeUnit::Second) => Arc::new(
+                parsed
+                    .map(|datetime| datetime.map(|d| d.timestamp()))
+                    .collect::<TimestampSecondArray>(),
+            ),
+            Some(TimeUnit::Millisecond) => Arc::new(
+                parsed
+                    .map(|datetime| datetime.map(|d| d.timestamp_millis()))
+                    .collect::<TimestampMillisecondArray>(),
+            ),
+            Some(TimeUnit::Microsecond) => Arc::new(
+                parsed
+                    .map(|datetime| datetime.map(|d| d.timestamp_micros()))
+                    .collect::<TimestampMicrosecondArray>(),
+            ),
+            Some(TimeUnit::Nanosecond) => Arc::new(
+                parsed
+                    .map(|datetime| datetime.map(timestamp_nanos).transpose())
+                    .collect::<Result<TimestampNanosecondArray>>()?,
+            ),
+            None => Arc::new(
+                parsed
+                    .map(|datetime| datetime.map(epoch_days))
+                    .collect::<Date32Array>(),
+            ),
+        };
+        Ok(array)
+    });
+    ScalarUDF::new(
+        &name,
+        &Signature::variadic_any(Volatility::Immutable),
+        &return_type,
+        &fun,
+    )
+}
+
+/// Parses `string` with the first of `formats` matching it, in UTC when the format has an
+/// offset, or like `to_timestamp` when there are no formats
+fn parse_datetime(string: &str, formats: &[&str]) -> Option<NaiveDateTime> {
+    if formats.is_empty() {
+        return string_to_datetime(&Utc, string)
+            .ok()
+            .map(|datetime| datetime.naive_utc());
+    }
+    formats.iter().find_map(|format| {
+        if let Ok(datetime) = DateTime::parse_from_str(string, format) {
+            return Some(datetime.naive_utc());
+        }
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(string, format) {
+            return Some(datetime);
+        }
+        NaiveDate::parse_from_str(string, format)
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+}
+
+/// Returns the nanoseconds since the epoch, which only span the years 1677 to 2262
+fn timestamp_nanos(datetime: NaiveDateTime) -> Result<i64> {
+    datetime
+        .timestamp()
+        .checked_mul(1_000_000_000)
+        .and_then(|nanos| nanos.checked_add(datetime.timestamp_subsec_nanos() as i64))
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "{datetime} is out of the range of nanosecond timestamps"
+            ))
+        })
+}
+
+fn epoch_days(datetime: NaiveDateTime) -> i32 {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    (datetime.date() - epoch).num_days() as i32
+}
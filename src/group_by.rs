@@ -0,0 +1,138 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datafusion_common::Column;
+use datafusion_expr::{avg, count, lit, sum, Expr};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::dataframe::PyDataFrame;
+
+/// A DataFrame grouped by some keys, created by `DataFrame.group_by`. Its methods return a
+/// DataFrame with the keys followed by the aggregates, as `DataFrame.aggregate` does.
+#[pyclass(name = "GroupBy", module = "datafusion", subclass)]
+#[derive(Clone)]
+pub struct PyGroupBy {
+    df: PyDataFrame,
+    keys: Vec<Expr>,
+}
+
+impl PyGroupBy {
+    pub(crate) fn new(df: PyDataFrame, keys: Vec<Expr>) -> Self {
+        Self { df, keys }
+    }
+
+    /// The names of the key columns of the results
+    fn key_names(&self) -> PyResult<Vec<String>> {
+        let names = self
+            .keys
+            .iter()
+            .map(|key| match key {
+                Expr::Column(column) => Ok(column.name.clone()),
+                key => key.display_name(),
+            })
+            .collect::<datafusion_common::Result<_>>()?;
+        Ok(names)
+    }
+
+    fn aggregate(&self, aggs: Vec<Expr>) -> PyResult<PyDataFrame> {
+        let df = self.df.df.as_ref().clone();
+        Ok(PyDataFrame::new(df.aggregate(self.keys.clone(), aggs)?))
+    }
+
+    /// Applies `fun` to `columns`, or to every numeric column that isn't a key when none are
+    /// given, naming the results after the columns
+    fn aggregate_columns(
+        &self,
+        name: &str,
+        columns: Vec<&str>,
+        fun: fn(Expr) -> Expr,
+    ) -> PyResult<PyDataFrame> {
+        let schema = self.df.df.schema();
+        let columns: Vec<Column> = if columns.is_empty() {
+            let keys = self.key_names()?;
+            schema
+                .fields()
+                .iter()
+                .filter(|field| field.data_type().is_numeric())
+                .filter(|field| !keys.iter().any(|key| key == field.name()))
+                .map(|field| field.qualified_column())
+                .collect()
+        } else {
+            columns
+                .into_iter()
+                .map(|name| {
+                    let field = schema
+                        .field_with_unqualified_name(name)
+                        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                    Ok(field.qualified_column())
+                })
+                .collect::<PyResult<_>>()?
+        };
+        if columns.is_empty() {
+            return Err(PyValueError::new_err(format!(
+                "{name} found no numeric columns to aggregate, pass their names"
+            )));
+        }
+        let aggs = columns
+            .into_iter()
+            .map(|column| {
+                let name = column.name.clone();
+                fun(Expr::Column(column)).alias(name)
+            })
+            .collect();
+        self.aggregate(aggs)
+    }
+}
+
+#[pymethods]
+impl PyGroupBy {
+    /// Computes the aggregates `aggs`, expressions or SQL strings such as
+    /// `"sum(sales) AS total"`, for each group
+    #[pyo3(signature = (*aggs))]
+    fn agg(&self, aggs: Vec<&PyAny>) -> PyResult<PyDataFrame> {
+        self.aggregate(self.df.parse_aggregates(aggs)?)
+    }
+
+    /// Counts the rows of each group, in a column named `name`, which can't be the name of a key
+    #[pyo3(signature = (name="count"))]
+    fn count(&self, name: &str) -> PyResult<PyDataFrame> {
+        if self.key_names()?.iter().any(|key| key == name) {
+            return Err(PyValueError::new_err(format!(
+                "The count column can't be named {name}, the name of a key, pass another name"
+            )));
+        }
+        self.aggregate(vec![count(lit(1)).alias(name)])
+    }
+
+    /// Sums the given columns, or all numeric columns that aren't keys, keeping their names
+    #[pyo3(signature = (*columns))]
+    fn sum(&self, columns: Vec<&str>) -> PyResult<PyDataFrame> {
+        self.aggregate_columns("sum", columns, sum)
+    }
+
+    /// Averages the given columns, or all numeric columns that aren't keys, keeping their names
+    #[pyo3(signature = (*columns))]
+    fn mean(&self, columns: Vec<&str>) -> PyResult<PyDataFrame> {
+        self.aggregate_columns("mean", columns, avg)
+    }
+
+    fn __repr__(&self) -> String {
+        let keys: Vec<String> = self.keys.iter().map(|key| key.to_string()).collect();
+        format!("GroupBy({})", keys.join(", "))
+    }
+}
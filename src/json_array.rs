@@ -0,0 +1,387 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reads files holding a single JSON array of objects, each of which is a row. DataFusion only
+//! reads newline delimited JSON, so the elements of the array are rewritten to one per line as
+//! the file is streamed and then decoded the same way as newline delimited JSON.
+
+use std::any::Any;
+use std::sync::Arc;
+use std::task::Poll;
+
+use async_trait::async_trait;
+use futures::{ready, stream, StreamExt, TryStreamExt};
+use object_store::{ObjectMeta, ObjectStore};
+
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::json::reader::infer_json_schema;
+use datafusion::arrow::json::ReaderBuilder;
+use datafusion::datasource::file_format::options::{NdJsonReadOptions, ReadOptions};
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, NdJsonExec,
+};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{SessionConfig, SessionState, TaskContext};
+use datafusion::physical_expr::{OrderingEquivalenceProperties, PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
+};
+use datafusion_common::{FileCompressionType, FileType};
+
+#[derive(Debug, Default, PartialEq)]
+enum DelimiterState {
+    #[default]
+    Start,
+    Array,
+    End,
+}
+
+/// Rewrites a JSON array, fed to it in chunks, as newline delimited JSON. Whitespace outside of
+/// strings is dropped, so each element of the array ends up on a line of its own.
+#[derive(Debug, Default)]
+struct ArrayDelimiter {
+    state: DelimiterState,
+    /// Nesting depth within the current element
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    in_element: bool,
+    /// Number of complete elements written so far
+    records: usize,
+}
+
+impl ArrayDelimiter {
+    fn push(&mut self, input: &[u8]) -> Result<Vec<u8>, ArrowError> {
+        let mut output = Vec::with_capacity(input.len());
+        for &byte in input {
+            match self.state {
+                DelimiterState::Start => match byte {
+                    b'[' => self.state = DelimiterState::Array,
+                    b if b.is_ascii_whitespace() => {}
+                    _ => return Err(json_error("Expected the file to hold a JSON array")),
+                },
+                DelimiterState::Array if self.in_string => {
+                    output.push(byte);
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if byte == b'\\' {
+                        self.escaped = true;
+                    } else if byte == b'"' {
+                        self.in_string = false;
+                    }
+                }
+                DelimiterState::Array => match byte {
+                    b if b.is_ascii_whitespace() => {}
+                    b',' if self.depth == 0 => self.end_element(&mut output),
+                    b']' if self.depth == 0 => {
+                        self.end_element(&mut output);
+                        self.state = DelimiterState::End;
+                    }
+                    _ => {
+                        match byte {
+                            b'"' => self.in_string = true,
+                            b'{' | b'[' => self.depth += 1,
+                            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                            _ => {}
+                        }
+                        self.in_element = true;
+                        output.push(byte);
+                    }
+                },
+                DelimiterState::End => {
+                    if !byte.is_ascii_whitespace() {
+                        return Err(json_error(
+                            "Unexpected data after the end of the JSON array",
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Checks that the whole array has been read once the input is exhausted
+    fn finish(&self) -> Result<(), ArrowError> {
+        match self.state {
+            DelimiterState::End => Ok(()),
+            DelimiterState::Start => Err(json_error("Expected the file to hold a JSON array")),
+            DelimiterState::Array => Err(json_error("Unexpected end of the JSON array")),
+        }
+    }
+
+    fn end_element(&mut self, output: &mut Vec<u8>) {
+        if self.in_element {
+            output.push(b'\n');
+            self.records += 1;
+            self.in_element = false;
+        }
+    }
+}
+
+fn json_error(message: &str) -> ArrowError {
+    ArrowError::JsonError(message.to_string())
+}
+
+/// A `FileFormat` for files holding a JSON array of objects
+#[derive(Debug)]
+struct JsonArrayFormat {
+    schema_infer_max_rec: usize,
+    file_compression_type: FileCompressionType,
+}
+
+#[async_trait]
+impl FileFormat for JsonArrayFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        let mut schemas = Vec::new();
+        let mut records_to_read = self.schema_infer_max_rec;
+        for object in objects {
+            let input = store.get(&object.location).await?.into_stream();
+            let mut input = self
+                .file_compression_type
+                .convert_stream(input.map_err(DataFusionError::from).boxed())?;
+
+            // Only read as much of the file as is needed to infer the schema
+            let mut delimiter = ArrayDelimiter::default();
+            let mut ndjson = Vec::new();
+            while delimiter.records < records_to_read {
+                match input.next().await.transpose()? {
+                    Some(bytes) => ndjson.extend(delimiter.push(&bytes)?),
+                    None => {
+                        delimiter.finish()?;
+                        break;
+                    }
+                }
+            }
+            let records = delimiter.records.min(records_to_read);
+            schemas.push(infer_json_schema(ndjson.as_slice(), Some(records))?);
+
+            records_to_read -= records;
+            if records_to_read == 0 {
+                break;
+            }
+        }
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &SessionState,
+        _store: &Arc<dyn ObjectStore>,
+        _table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::default())
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &SessionState,
+        conf: FileScanConfig,
+        _filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(JsonArrayExec {
+            inner: NdJsonExec::new(conf, self.file_compression_type),
+            file_compression_type: self.file_compression_type,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }))
+    }
+
+    fn file_type(&self) -> FileType {
+        FileType::JSON
+    }
+}
+
+/// Scans files holding JSON arrays. Everything but opening the files is the same as for
+/// newline delimited JSON, so it's delegated to an `NdJsonExec` over the same files.
+#[derive(Debug)]
+struct JsonArrayExec {
+    inner: NdJsonExec,
+    file_compression_type: FileCompressionType,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl DisplayAs for JsonArrayExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "JsonArrayExec: ")?;
+        self.inner.base_config().fmt_as(t, f)
+    }
+}
+
+impl ExecutionPlan for JsonArrayExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.inner.output_partitioning()
+    }
+
+    fn unbounded_output(&self, children: &[bool]) -> Result<bool> {
+        self.inner.unbounded_output(children)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.inner.output_ordering()
+    }
+
+    fn ordering_equivalence_properties(&self) -> OrderingEquivalenceProperties {
+        self.inner.ordering_equivalence_properties()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        Vec::new()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let base_config = self.inner.base_config();
+        let (projected_schema, ..) = base_config.project();
+        let opener = JsonArrayOpener {
+            batch_size: context.session_config().batch_size(),
+            projected_schema,
+            file_compression_type: self.file_compression_type,
+            object_store: context
+                .runtime_env()
+                .object_store(&base_config.object_store_url)?,
+        };
+        let stream = FileStream::new(base_config, partition, opener, &self.metrics)?;
+        Ok(Box::pin(stream))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.inner.statistics()
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+}
+
+/// Opens a file holding a JSON array and decodes its elements as they are streamed
+struct JsonArrayOpener {
+    batch_size: usize,
+    projected_schema: SchemaRef,
+    file_compression_type: FileCompressionType,
+    object_store: Arc<dyn ObjectStore>,
+}
+
+impl FileOpener for JsonArrayOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let store = self.object_store.clone();
+        let schema = self.projected_schema.clone();
+        let batch_size = self.batch_size;
+        let file_compression_type = self.file_compression_type;
+        Ok(Box::pin(async move {
+            let input = store.get(file_meta.location()).await?.into_stream();
+            let mut input = file_compression_type
+                .convert_stream(input.map_err(DataFusionError::from).boxed())?
+                .fuse();
+            let mut decoder = ReaderBuilder::new(schema)
+                .with_batch_size(batch_size)
+                .build_decoder()?;
+            let mut delimiter = ArrayDelimiter::default();
+            let mut buffered = Vec::new();
+            let mut offset = 0;
+
+            let batches = stream::poll_fn(move |cx| {
+                loop {
+                    if offset == buffered.len() {
+                        let next = match ready!(input.poll_next_unpin(cx)) {
+                            Some(Ok(bytes)) => delimiter.push(&bytes),
+                            Some(Err(e)) => Err(e.into()),
+                            None => match delimiter.finish() {
+                                Ok(()) => break,
+                                Err(e) => Err(e),
+                            },
+                        };
+                        match next {
+                            Ok(ndjson) => buffered = ndjson,
+                            Err(e) => return Poll::Ready(Some(Err(e))),
+                        }
+                        offset = 0;
+                    }
+
+                    match decoder.decode(&buffered[offset..]) {
+                        Ok(decoded) => offset += decoded,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    }
+                    // The decoder stops early once it has a full batch
+                    if offset != buffered.len() {
+                        break;
+                    }
+                }
+                Poll::Ready(decoder.flush().transpose())
+            });
+            Ok(batches.boxed())
+        }))
+    }
+}
+
+/// Options for reading JSON arrays, which are the same as for newline delimited JSON
+pub(crate) struct JsonArrayReadOptions<'a>(pub NdJsonReadOptions<'a>);
+
+#[async_trait]
+impl ReadOptions<'_> for JsonArrayReadOptions<'_> {
+    fn to_listing_options(&self, config: &SessionConfig) -> ListingOptions {
+        let format = JsonArrayFormat {
+            schema_infer_max_rec: self.0.schema_infer_max_records,
+            file_compression_type: self.0.file_compression_type,
+        };
+        ListingOptions {
+            format: Arc::new(format),
+            ..self.0.to_listing_options(config)
+        }
+    }
+
+    async fn get_resolved_schema(
+        &self,
+        config: &SessionConfig,
+        state: SessionState,
+        table_path: ListingTableUrl,
+    ) -> Result<SchemaRef> {
+        self._get_resolved_schema(config, state, table_path, self.0.schema, false)
+            .await
+    }
+}
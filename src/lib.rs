@@ -24,9 +24,12 @@ pub use datafusion;
 pub use datafusion_common;
 pub use datafusion_expr;
 pub use datafusion_optimizer;
+#[cfg(feature = "proto")]
+pub use datafusion_proto;
 pub use datafusion_sql;
 pub use datafusion_substrait;
 
+mod bytes_table;
 #[allow(clippy::borrow_deref_ref)]
 pub mod catalog;
 pub mod common;
@@ -38,14 +41,24 @@ mod context;
 mod dataframe;
 mod dataset;
 mod dataset_exec;
+mod display;
 pub mod errors;
 #[allow(clippy::borrow_deref_ref)]
 pub mod expr;
 #[allow(clippy::borrow_deref_ref)]
 mod functions;
+mod group_by;
+mod json_array;
+mod maintain_order;
+mod nullability;
+mod parquet;
+mod parquet_bloom_filter;
+mod parquet_encryption;
+mod parquet_metadata;
 pub mod physical_plan;
 mod pyarrow_filter_expression;
 mod record_batch;
+mod result_cache;
 pub mod sql;
 pub mod store;
 pub mod substrait;
@@ -82,11 +95,13 @@ fn _internal(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<context::PySessionConfig>()?;
     m.add_class::<context::PySessionContext>()?;
     m.add_class::<dataframe::PyDataFrame>()?;
+    m.add_class::<group_by::PyGroupBy>()?;
     m.add_class::<udf::PyScalarUDF>()?;
     m.add_class::<udaf::PyAggregateUDF>()?;
     m.add_class::<config::PyConfig>()?;
     m.add_class::<sql::logical::PyLogicalPlan>()?;
     m.add_class::<physical_plan::PyExecutionPlan>()?;
+    m.add_class::<parquet_metadata::PyParquetMetadata>()?;
 
     // Register `common` as a submodule. Matching `datafusion-common` https://docs.rs/datafusion-common/latest/datafusion_common/
     let common = PyModule::new(py, "common")?;
@@ -0,0 +1,151 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Keeps the rows of a plan in the order of its input, for `DataFrame.maintain_order`
+use std::any::Any;
+use std::sync::Arc;
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::config::ConfigOptions;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::error::Result as DFResult;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_optimizer::PhysicalOptimizerRule;
+use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
+};
+
+/// Physical optimizer rule that replaces every `CoalescePartitionsExec`, which interleaves its
+/// input partitions as their batches arrive, with a `ConcatPartitionsExec`, and concatenates the
+/// partitions of the plan's output. Must run last, so no other rule reintroduces a coalesce.
+pub(crate) struct MaintainOrder;
+
+impl MaintainOrder {
+    pub(crate) const NAME: &'static str = "maintain_order";
+}
+
+impl PhysicalOptimizerRule for MaintainOrder {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let plan = plan.transform_up(&|plan| {
+            Ok(
+                match plan.as_any().downcast_ref::<CoalescePartitionsExec>() {
+                    Some(coalesce) => Transformed::Yes(Arc::new(ConcatPartitionsExec::new(
+                        coalesce.input().clone(),
+                    ))),
+                    None => Transformed::No(plan),
+                },
+            )
+        })?;
+        Ok(match plan.output_partitioning().partition_count() {
+            0 | 1 => plan,
+            _ => Arc::new(ConcatPartitionsExec::new(plan)),
+        })
+    }
+
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Merges the partitions of its input into a single partition by reading them one after the
+/// other, in partition order
+#[derive(Debug)]
+pub(crate) struct ConcatPartitionsExec {
+    input: Arc<dyn ExecutionPlan>,
+}
+
+impl ConcatPartitionsExec {
+    pub(crate) fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self { input }
+    }
+}
+
+impl ExecutionPlan for ConcatPartitionsExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        match self.input.output_partitioning().partition_count() {
+            1 => self.input.output_ordering(),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::new(children[0].clone())))
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let input = self.input.clone();
+        let partitions = input.output_partitioning().partition_count();
+        let batches = stream::iter(0..partitions)
+            .map(move |partition| input.execute(partition, context.clone()))
+            .try_flatten();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            batches,
+        )))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+impl DisplayAs for ConcatPartitionsExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "ConcatPartitionsExec")
+            }
+        }
+    }
+}
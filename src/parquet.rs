@@ -0,0 +1,215 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// Implements the Datafusion TableProviders that override the Parquet read options of a single
+/// table, rather than taking them from the session config, and restrict it to some columns
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::common::{Constraints, Statistics};
+use datafusion::datasource::physical_plan::ParquetExec;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DFResult;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::TableProviderFilterPushDown;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_expr::Expr;
+
+use crate::parquet_bloom_filter::BloomFilterReaderFactory;
+
+// Wraps a Parquet listing table and applies the read options to its scans, the options left
+// unset are taken from the session config
+pub(crate) struct ParquetScanOptions {
+    table: Arc<dyn TableProvider>,
+    enable_page_index: Option<bool>,
+    metadata_size_hint: Option<usize>,
+    /// Whether the row groups are pruned with the bloom filters of the files, which the
+    /// ParquetExec of this DataFusion version doesn't read
+    bloom_filter_on_read: bool,
+}
+
+impl ParquetScanOptions {
+    /// Wraps `table` unless none of the options are set
+    pub fn wrap(
+        table: Arc<dyn TableProvider>,
+        enable_page_index: Option<bool>,
+        metadata_size_hint: Option<usize>,
+        bloom_filter_on_read: bool,
+    ) -> Arc<dyn TableProvider> {
+        if enable_page_index.is_none() && metadata_size_hint.is_none() && !bloom_filter_on_read {
+            return table;
+        }
+        Arc::new(Self {
+            table,
+            enable_page_index,
+            metadata_size_hint,
+            bloom_filter_on_read,
+        })
+    }
+
+    /// Returns the wrapped table
+    pub fn table(&self) -> &Arc<dyn TableProvider> {
+        &self.table
+    }
+}
+
+#[async_trait]
+impl TableProvider for ParquetScanOptions {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    fn constraints(&self) -> Option<&Constraints> {
+        self.table.constraints()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.table.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let plan = self.table.scan(state, projection, filters, limit).await?;
+        // the options can only be set when the ParquetExec is created, so rebuild it from
+        // the scan configuration the listing table planned
+        let plan: Arc<dyn ExecutionPlan> = match plan.as_any().downcast_ref::<ParquetExec>() {
+            Some(exec) => {
+                let metadata_size_hint = self.metadata_size_hint.or(state
+                    .config_options()
+                    .execution
+                    .parquet
+                    .metadata_size_hint);
+                let mut exec = ParquetExec::new(
+                    exec.base_config().clone(),
+                    exec.predicate().cloned(),
+                    metadata_size_hint,
+                );
+                if let Some(enabled) = self.enable_page_index {
+                    exec = exec.with_enable_page_index(enabled);
+                }
+                if let (true, Some(predicate)) = (self.bloom_filter_on_read, exec.predicate()) {
+                    let url = &exec.base_config().object_store_url;
+                    let store = state.runtime_env().object_store(url)?;
+                    if let Some(factory) = BloomFilterReaderFactory::try_new(store, predicate) {
+                        exec = exec.with_parquet_file_reader_factory(Arc::new(factory));
+                    }
+                }
+                Arc::new(exec)
+            }
+            None => plan,
+        };
+        Ok(plan)
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        self.table.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        self.table.statistics()
+    }
+}
+
+// Wraps a table and exposes only the columns at `indices` of its schema, so its scans never
+// read the others
+pub(crate) struct ParquetColumns {
+    table: Arc<dyn TableProvider>,
+    indices: Vec<usize>,
+    schema: SchemaRef,
+}
+
+impl ParquetColumns {
+    pub fn new(table: Arc<dyn TableProvider>, indices: Vec<usize>) -> Self {
+        let schema = table.schema();
+        let fields = indices.iter().map(|&i| schema.field(i).clone());
+        let schema = Arc::new(Schema::new_with_metadata(
+            fields.collect::<Vec<_>>(),
+            schema.metadata().clone(),
+        ));
+        Self {
+            table,
+            indices,
+            schema,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for ParquetColumns {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.table.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        // filters refer to the columns by name, so only the projection has to be mapped to the
+        // indices of the wrapped table
+        let projection = match projection {
+            Some(projection) => projection.iter().map(|&i| self.indices[i]).collect(),
+            None => self.indices.clone(),
+        };
+        self.table
+            .scan(state, Some(&projection), filters, limit)
+            .await
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        self.table.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        let statistics = self.table.statistics()?;
+        let column_statistics = statistics
+            .column_statistics
+            .map(|columns| self.indices.iter().map(|&i| columns[i].clone()).collect());
+        Some(Statistics {
+            column_statistics,
+            ..statistics
+        })
+    }
+}
@@ -0,0 +1,293 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Prunes the row groups of Parquet files with their bloom filters, which the ParquetExec of
+//! this DataFusion version only writes. A row group is skipped when the bloom filter of a
+//! column rules out every value an equality or `IN` predicate on the column accepts.
+use std::hash::Hasher;
+use std::io::Cursor;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use datafusion::common::ScalarValue;
+use datafusion::datasource::physical_plan::{
+    FileMeta, ParquetFileMetrics, ParquetFileReaderFactory,
+};
+use datafusion::error::Result as DFResult;
+use datafusion::logical_expr::Operator;
+use datafusion::parquet::arrow::async_reader::{AsyncFileReader, ParquetObjectReader};
+use datafusion::parquet::basic::Type as PhysicalType;
+use datafusion::parquet::errors::{ParquetError, Result as ParquetResult};
+use datafusion::parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use datafusion::parquet::format::BloomFilterHeader;
+use datafusion::physical_expr::expressions::{BinaryExpr, Column, InListExpr, Literal};
+use datafusion::physical_expr::{split_conjunction, PhysicalExpr};
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder};
+use futures::future::{BoxFuture, FutureExt};
+use object_store::ObjectStore;
+use thrift::protocol::{TCompactInputProtocol, TSerializable};
+use twox_hash::XxHash64;
+
+/// The bytes read for the header of a bloom filter, which is followed by its bit set
+const HEADER_SIZE_ESTIMATE: usize = 20;
+
+/// The salts of the split block bloom filters of the Parquet format
+const SALT: [u32; 8] = [
+    0x47b6137b, 0x44974d91, 0x8824ad5b, 0xa2b7289d, 0x705495c7, 0x2df1424b, 0x9efc4947, 0x5c6bfb31,
+];
+
+/// Creates the readers of the files of a ParquetExec, like the default factory, with the
+/// row groups that the bloom filters rule out removed from the metadata they return
+#[derive(Debug)]
+pub(crate) struct BloomFilterReaderFactory {
+    store: Arc<dyn ObjectStore>,
+    /// The columns of the equality and `IN` conjuncts of the predicate of the scan, with the
+    /// values they accept
+    lookups: Vec<(String, Vec<ScalarValue>)>,
+}
+
+impl BloomFilterReaderFactory {
+    /// Returns `None` when the predicate has no conjunct the bloom filters can rule out
+    pub(crate) fn try_new(
+        store: Arc<dyn ObjectStore>,
+        predicate: &Arc<dyn PhysicalExpr>,
+    ) -> Option<Self> {
+        let lookups: Vec<_> = split_conjunction(predicate)
+            .into_iter()
+            .filter_map(|conjunct| lookup(conjunct.as_ref()))
+            .collect();
+        if lookups.is_empty() {
+            return None;
+        }
+        Some(Self { store, lookups })
+    }
+}
+
+impl ParquetFileReaderFactory for BloomFilterReaderFactory {
+    fn create_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        metadata_size_hint: Option<usize>,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> DFResult<Box<dyn AsyncFileReader + Send>> {
+        let filename = file_meta.location().to_string();
+        let file_metrics = ParquetFileMetrics::new(partition_index, &filename, metrics);
+        let row_groups_pruned = MetricBuilder::new(metrics)
+            .with_new_label("filename", filename)
+            .counter("row_groups_pruned_bloom_filter", partition_index);
+        let mut inner = ParquetObjectReader::new(self.store.clone(), file_meta.object_meta);
+        if let Some(hint) = metadata_size_hint {
+            inner = inner.with_footer_size_hint(hint);
+        }
+        Ok(Box::new(BloomFilterReader {
+            inner,
+            bytes_scanned: file_metrics.bytes_scanned,
+            row_groups_pruned,
+            lookups: self.lookups.clone(),
+        }))
+    }
+}
+
+struct BloomFilterReader {
+    inner: ParquetObjectReader,
+    bytes_scanned: Count,
+    row_groups_pruned: Count,
+    lookups: Vec<(String, Vec<ScalarValue>)>,
+}
+
+impl AsyncFileReader for BloomFilterReader {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, ParquetResult<Bytes>> {
+        self.bytes_scanned.add(range.end - range.start);
+        self.inner.get_bytes(range)
+    }
+
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, ParquetResult<Vec<Bytes>>> {
+        self.bytes_scanned
+            .add(ranges.iter().map(|range| range.end - range.start).sum());
+        self.inner.get_byte_ranges(ranges)
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, ParquetResult<Arc<ParquetMetaData>>> {
+        async move {
+            let metadata = self.inner.get_metadata().await?;
+            let mut row_groups = Vec::with_capacity(metadata.num_row_groups());
+            for row_group in metadata.row_groups() {
+                if self.may_match(row_group).await? {
+                    row_groups.push(row_group.clone());
+                } else {
+                    self.row_groups_pruned.add(1);
+                }
+            }
+            if row_groups.len() == metadata.num_row_groups() {
+                return Ok(metadata);
+            }
+            let file_metadata = metadata.file_metadata().clone();
+            Ok(Arc::new(ParquetMetaData::new(file_metadata, row_groups)))
+        }
+        .boxed()
+    }
+}
+
+impl BloomFilterReader {
+    /// Whether the bloom filters of `row_group` let every lookup match, reading those of the
+    /// columns looked up
+    async fn may_match(&mut self, row_group: &RowGroupMetaData) -> ParquetResult<bool> {
+        for (name, values) in &self.lookups {
+            let column = row_group
+                .columns()
+                .iter()
+                .find(|column| column.column_path().parts() == [name.as_str()]);
+            let (column, offset) = match column.and_then(|c| Some((c, c.bloom_filter_offset()?))) {
+                Some(column) => column,
+                None => continue,
+            };
+            let values: Option<Vec<Vec<u8>>> = values
+                .iter()
+                .map(|value| plain_bytes(value, column.column_type()))
+                .collect();
+            let values = match values {
+                Some(values) => values,
+                None => continue,
+            };
+            let offset = usize::try_from(offset)
+                .map_err(|_| ParquetError::General("Bloom filter offset is invalid".into()))?;
+            let header = self
+                .inner
+                .get_bytes(offset..offset + HEADER_SIZE_ESTIMATE)
+                .await?;
+            let mut cursor = Cursor::new(header.as_ref());
+            let header = BloomFilterHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(
+                &mut cursor,
+            ))
+            .map_err(|e| {
+                ParquetError::General(format!("Could not read bloom filter header: {e}"))
+            })?;
+            let start = offset + cursor.position() as usize;
+            let length = usize::try_from(header.num_bytes)
+                .map_err(|_| ParquetError::General("Bloom filter length is invalid".into()))?;
+            let bitset = self.inner.get_bytes(start..start + length).await?;
+            self.bytes_scanned.add(HEADER_SIZE_ESTIMATE + length);
+            if !values.iter().any(|value| check(&bitset, value)) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The column and the values accepted by an equality, `IN` or disjunction of equalities
+/// predicate
+fn lookup(expr: &dyn PhysicalExpr) -> Option<(String, Vec<ScalarValue>)> {
+    let as_literal = |expr: &Arc<dyn PhysicalExpr>| {
+        let literal = expr.as_any().downcast_ref::<Literal>()?;
+        Some(literal.value().clone())
+    };
+    let as_column = |expr: &Arc<dyn PhysicalExpr>| {
+        let column = expr.as_any().downcast_ref::<Column>()?;
+        Some(column.name().to_string())
+    };
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        // the simplifier rewrites the short `IN` lists to disjunctions of equalities
+        if binary.op() == &Operator::Or {
+            let (column, mut values) = lookup(binary.left().as_ref())?;
+            let (other, others) = lookup(binary.right().as_ref())?;
+            if column != other {
+                return None;
+            }
+            values.extend(others);
+            return Some((column, values));
+        }
+        if binary.op() != &Operator::Eq {
+            return None;
+        }
+        let (column, value) = match as_column(binary.left()) {
+            Some(column) => (column, as_literal(binary.right())?),
+            None => (as_column(binary.right())?, as_literal(binary.left())?),
+        };
+        return Some((column, vec![value]));
+    }
+    let in_list = expr.as_any().downcast_ref::<InListExpr>()?;
+    if in_list.negated() {
+        return None;
+    }
+    let values = in_list
+        .list()
+        .iter()
+        .map(as_literal)
+        .collect::<Option<_>>()?;
+    Some((as_column(in_list.expr())?, values))
+}
+
+/// The plain encoding of `value` in a column of the physical type `column_type`, which the
+/// bloom filters hash. `None` for the values that can't be looked up: nulls, the floats, whose
+/// zeros compare equal with different encodings, and the types without an exact encoding.
+fn plain_bytes(value: &ScalarValue, column_type: PhysicalType) -> Option<Vec<u8>> {
+    let signed = match value {
+        ScalarValue::Int8(v) => v.map(i64::from),
+        ScalarValue::Int16(v) => v.map(i64::from),
+        ScalarValue::Int32(v) => v.map(i64::from),
+        ScalarValue::Int64(v) => *v,
+        _ => None,
+    };
+    match (column_type, value) {
+        (PhysicalType::INT32, ScalarValue::UInt8(Some(v))) => {
+            Some((*v as i32).to_le_bytes().into())
+        }
+        (PhysicalType::INT32, ScalarValue::UInt16(Some(v))) => {
+            Some((*v as i32).to_le_bytes().into())
+        }
+        (PhysicalType::INT32, ScalarValue::UInt32(Some(v))) => {
+            Some((*v as i32).to_le_bytes().into())
+        }
+        (PhysicalType::INT32, _) => Some(i32::try_from(signed?).ok()?.to_le_bytes().into()),
+        (PhysicalType::INT64, ScalarValue::UInt64(Some(v))) => {
+            Some((*v as i64).to_le_bytes().into())
+        }
+        (PhysicalType::INT64, _) => Some(signed?.to_le_bytes().into()),
+        (
+            PhysicalType::BYTE_ARRAY,
+            ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)),
+        ) => Some(v.as_bytes().to_vec()),
+        (
+            PhysicalType::BYTE_ARRAY,
+            ScalarValue::Binary(Some(v)) | ScalarValue::LargeBinary(Some(v)),
+        ) => Some(v.clone()),
+        _ => None,
+    }
+}
+
+/// Whether the split block bloom filter `bitset` may contain the value encoded as `bytes`
+fn check(bitset: &[u8], bytes: &[u8]) -> bool {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(bytes);
+    let hash = hasher.finish();
+    let blocks = (bitset.len() / 32) as u64;
+    if blocks == 0 {
+        return true;
+    }
+    let block = &bitset[(((hash >> 32) * blocks) >> 32) as usize * 32..][..32];
+    let key = hash as u32;
+    SALT.iter().enumerate().all(|(i, salt)| {
+        let word = u32::from_le_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+        word & (1 << (key.wrapping_mul(*salt) >> 27)) != 0
+    })
+}
@@ -0,0 +1,618 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// Decrypts files written with Parquet Modular Encryption into plain Parquet files, which the
+/// Parquet reader of DataFusion can scan, when they are read from an object store. Both the
+/// encrypted and the plaintext footer modes and both the AES_GCM_V1 and AES_GCM_CTR_V1
+/// algorithms are supported
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::Cursor;
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::parquet::format::{
+    ColumnCryptoMetaData, EncryptionAlgorithm, FileCryptoMetaData, FileMetaData, PageHeader,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, GetResultPayload, ListResult, MultipartId, ObjectMeta, ObjectStore,
+};
+use parking_lot::Mutex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM, AES_256_GCM, NONCE_LEN};
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol, TSerializable};
+use tokio::io::AsyncWrite;
+
+use crate::errors::{DataFusionError, Result};
+
+/// Returns the key of the footer, when called with no column path, or of the column with the
+/// given path, from the key metadata stored in the file
+pub(crate) type KeyRetriever = dyn Fn(Option<&[String]>, &[u8]) -> Result<Vec<u8>> + Send + Sync;
+
+/// The number of decrypted files a `DecryptingStore` keeps, so that the reads of the footer and
+/// of the column chunks of a file don't each decrypt it again
+const DECRYPTED_FILES: usize = 4;
+
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+const ENCRYPTED_MAGIC: &[u8; 4] = b"PARE";
+const TAG_LEN: usize = 16;
+
+// The module types of the additional authenticated data of each encrypted module
+const FOOTER: u8 = 0;
+const COLUMN_META_DATA: u8 = 1;
+const DATA_PAGE: u8 = 2;
+const DICTIONARY_PAGE: u8 = 3;
+const DATA_PAGE_HEADER: u8 = 4;
+const DICTIONARY_PAGE_HEADER: u8 = 5;
+
+/// Returns whether `tail`, the last bytes of a file, ends with the magic of a Parquet file with
+/// an encrypted footer
+pub(crate) fn has_encrypted_footer(tail: &[u8]) -> bool {
+    tail.ends_with(ENCRYPTED_MAGIC)
+}
+
+/// An object store reading the Parquet files of another store decrypted. The files are listed
+/// from the other store, each decrypted file having the size of the encrypted one, and are
+/// decrypted when they are read. Only the last few files read are kept decrypted
+pub(crate) struct DecryptingStore {
+    /// The URL of `inner`, which the errors name the files with
+    url: String,
+    inner: Arc<dyn ObjectStore>,
+    key: Arc<KeyRetriever>,
+    /// The files read last, as listed by `inner`, and their decrypted data, which is `None` for
+    /// files which aren't encrypted
+    decrypted: Mutex<VecDeque<(ObjectMeta, Option<Bytes>)>>,
+}
+
+impl DecryptingStore {
+    pub(crate) fn new(url: String, inner: Arc<dyn ObjectStore>, key: Arc<KeyRetriever>) -> Self {
+        Self {
+            url,
+            inner,
+            key,
+            decrypted: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns the decrypted data of the file `meta` describes, reading and decrypting it unless
+    /// it was read last, or `None` when the file isn't encrypted
+    async fn decrypted(&self, meta: &ObjectMeta) -> object_store::Result<Option<Bytes>> {
+        let cached = self
+            .decrypted
+            .lock()
+            .iter()
+            .find(|(cached, _)| cached == meta)
+            .map(|(_, data)| data.clone());
+        if let Some(data) = cached {
+            return Ok(data);
+        }
+        let data = self.inner.get(&meta.location).await?.bytes().await?;
+        let location = format!("{}{}", self.url, meta.location);
+        let data = decrypt_parquet(&location, &data, self.key.as_ref())
+            .map_err(|e| object_store::Error::Generic {
+                store: "Parquet decryption",
+                source: Box::new(e),
+            })?
+            .map(Bytes::from);
+
+        let mut decrypted = self.decrypted.lock();
+        decrypted.retain(|(cached, _)| cached.location != meta.location);
+        if decrypted.len() == DECRYPTED_FILES {
+            decrypted.pop_front();
+        }
+        decrypted.push_back((meta.clone(), data.clone()));
+        Ok(data)
+    }
+}
+
+impl Display for DecryptingStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Decrypting({})", self.inner)
+    }
+}
+
+impl Debug for DecryptingStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecryptingStore")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for DecryptingStore {
+    async fn put(&self, _location: &Path, _bytes: Bytes) -> object_store::Result<()> {
+        Err(read_only())
+    }
+
+    async fn put_multipart(
+        &self,
+        _location: &Path,
+    ) -> object_store::Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        Err(read_only())
+    }
+
+    async fn abort_multipart(
+        &self,
+        _location: &Path,
+        _multipart_id: &MultipartId,
+    ) -> object_store::Result<()> {
+        Err(read_only())
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let meta = self.inner.head(location).await?;
+        let data = match self.decrypted(&meta).await? {
+            Some(data) => data,
+            None => return self.inner.get_opts(location, options).await,
+        };
+        let conditional = options.if_match.is_some()
+            || options.if_none_match.is_some()
+            || options.if_modified_since.is_some()
+            || options.if_unmodified_since.is_some();
+        if conditional {
+            return Err(object_store::Error::NotSupported {
+                source: "Conditional reads of decrypted files aren't supported".into(),
+            });
+        }
+        let range: Range<usize> = options.range.unwrap_or(0..data.len());
+        if range.start > range.end || range.end > data.len() {
+            return Err(object_store::Error::Generic {
+                store: "Parquet decryption",
+                source: format!("The range {range:?} exceeds the size of {location}").into(),
+            });
+        }
+        let payload = stream::once(futures::future::ready(Ok(data.slice(range.clone()))));
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(payload.boxed()),
+            meta,
+            range,
+        })
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, _location: &Path) -> object_store::Result<()> {
+        Err(read_only())
+    }
+
+    async fn list(
+        &self,
+        prefix: Option<&Path>,
+    ) -> object_store::Result<BoxStream<'_, object_store::Result<ObjectMeta>>> {
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, _from: &Path, _to: &Path) -> object_store::Result<()> {
+        Err(read_only())
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> object_store::Result<()> {
+        Err(read_only())
+    }
+}
+
+fn read_only() -> object_store::Error {
+    object_store::Error::NotSupported {
+        source: "The decrypted Parquet files can't be written".into(),
+    }
+}
+
+/// Decrypts the Parquet file `data` read from `location` and returns the equivalent plain
+/// Parquet file, padded to the size of `data`, or `None` when the file isn't encrypted. Page
+/// indexes and bloom filters aren't kept in the decrypted file
+fn decrypt_parquet(location: &str, data: &[u8], key: &KeyRetriever) -> Result<Option<Vec<u8>>> {
+    decrypt(data, key).map_err(|e| match e {
+        DataFusionError::Common(message) => DataFusionError::Common(format!(
+            "Failed to decrypt the Parquet file {location}: {message}"
+        )),
+        e => e,
+    })
+}
+
+fn decrypt(data: &[u8], key: &KeyRetriever) -> Result<Option<Vec<u8>>> {
+    if data.len() < 12 {
+        return Ok(None);
+    }
+    let footer_len = u32::from_le_bytes(data[data.len() - 8..data.len() - 4].try_into().unwrap());
+    let footer_start = (data.len() - 8)
+        .checked_sub(footer_len as usize)
+        .ok_or_else(|| error("the footer length exceeds the file size"))?;
+    let footer = &data[footer_start..data.len() - 8];
+
+    let (mut metadata, decryptor) = if has_encrypted_footer(data) {
+        let (crypto, read) = read_thrift::<FileCryptoMetaData>(footer, "file crypto metadata")?;
+        let footer_key = key(None, crypto.key_metadata.as_deref().unwrap_or_default())?;
+        let decryptor = Decryptor::try_new(&crypto.encryption_algorithm, &footer_key)?;
+        let aad = decryptor.module_aad(FOOTER, None, None);
+        let (module, _) = split_module(&footer[read..]).map_err(|e| error(&e))?;
+        let plaintext = decryptor
+            .open_module(module, &aad)
+            .ok_or_else(|| error("the footer can't be decrypted, the footer key is wrong"))?;
+        (
+            read_thrift::<FileMetaData>(&plaintext, "footer")?.0,
+            decryptor,
+        )
+    } else if data.ends_with(PARQUET_MAGIC) {
+        let (metadata, read) = read_thrift::<FileMetaData>(footer, "footer")?;
+        let algorithm = match &metadata.encryption_algorithm {
+            Some(algorithm) => algorithm,
+            None => return Ok(None),
+        };
+        let key_metadata = metadata.footer_signing_key_metadata.as_deref();
+        let footer_key = key(None, key_metadata.unwrap_or_default())?;
+        let decryptor = Decryptor::try_new(algorithm, &footer_key)?;
+        decryptor.verify_footer(&footer[..read], &footer[read..])?;
+        (metadata, decryptor)
+    } else {
+        return Ok(None);
+    };
+
+    let mut output = PARQUET_MAGIC.to_vec();
+    for (row_group_ordinal, row_group) in metadata.row_groups.iter_mut().enumerate() {
+        let row_group_ordinal = ordinal(row_group_ordinal)?;
+        let row_group_start = output.len();
+        for (column_ordinal, column) in row_group.columns.iter_mut().enumerate() {
+            let column_ordinal = ordinal(column_ordinal)?;
+            let mut path = column
+                .meta_data
+                .as_ref()
+                .map(|meta_data| meta_data.path_in_schema.clone())
+                .unwrap_or_default();
+            let column_decryptor = match column.crypto_metadata.take() {
+                None => None,
+                Some(ColumnCryptoMetaData::ENCRYPTIONWITHFOOTERKEY(_)) => Some(decryptor.clone()),
+                Some(ColumnCryptoMetaData::ENCRYPTIONWITHCOLUMNKEY(crypto)) => {
+                    let key_metadata = crypto.key_metadata.as_deref().unwrap_or_default();
+                    let column_key = key(Some(&crypto.path_in_schema), key_metadata)?;
+                    path = crypto.path_in_schema;
+                    Some(decryptor.with_key(&column_key)?)
+                }
+            };
+            if let (Some(encrypted), Some(decryptor)) = (
+                column.encrypted_column_metadata.take(),
+                column_decryptor.as_ref(),
+            ) {
+                let aad = decryptor.module_aad(
+                    COLUMN_META_DATA,
+                    Some((row_group_ordinal, column_ordinal)),
+                    None,
+                );
+                let (module, _) = split_module(&encrypted).map_err(|e| error(&e))?;
+                let plaintext = decryptor.open_module(module, &aad).ok_or_else(|| {
+                    column_error(&path, "its metadata can't be decrypted, its key is wrong")
+                })?;
+                column.meta_data = Some(read_thrift(&plaintext, "column metadata")?.0);
+            }
+            let meta_data = column.meta_data.as_mut().ok_or_else(|| {
+                error(&format!(
+                    "column {column_ordinal} of row group {row_group_ordinal} has no metadata"
+                ))
+            })?;
+
+            let start = match meta_data.dictionary_page_offset {
+                Some(offset) if offset > 0 && offset < meta_data.data_page_offset => offset,
+                _ => meta_data.data_page_offset,
+            } as usize;
+            let end = start + meta_data.total_compressed_size as usize;
+            if end > footer_start {
+                return Err(column_error(&path, "its pages exceed the file size"));
+            }
+            let column_start = output.len();
+            let data_start = match &column_decryptor {
+                None => {
+                    output.extend_from_slice(&data[start..end]);
+                    column_start + (meta_data.data_page_offset as usize - start)
+                }
+                Some(decryptor) => {
+                    let has_dictionary = start < meta_data.data_page_offset as usize;
+                    decrypt_pages(
+                        &data[start..end],
+                        has_dictionary,
+                        decryptor,
+                        (row_group_ordinal, column_ordinal),
+                        &mut output,
+                    )
+                    .map_err(|e| column_error(&path, &e))?
+                }
+            };
+            if data_start > column_start {
+                meta_data.dictionary_page_offset = Some(column_start as i64);
+            } else {
+                meta_data.dictionary_page_offset = None;
+            }
+            meta_data.data_page_offset = data_start as i64;
+            meta_data.index_page_offset = None;
+            meta_data.total_compressed_size = (output.len() - column_start) as i64;
+            meta_data.bloom_filter_offset = None;
+            column.file_path = None;
+            column.file_offset = output.len() as i64;
+            column.offset_index_offset = None;
+            column.offset_index_length = None;
+            column.column_index_offset = None;
+            column.column_index_length = None;
+        }
+        // the optional fields are only updated when set, so that the footer doesn't grow
+        if row_group.file_offset.is_some() {
+            row_group.file_offset = Some(row_group_start as i64);
+        }
+        if row_group.total_compressed_size.is_some() {
+            row_group.total_compressed_size = Some((output.len() - row_group_start) as i64);
+        }
+    }
+    metadata.encryption_algorithm = None;
+    metadata.footer_signing_key_metadata = None;
+
+    let mut footer = vec![];
+    let mut protocol = TCompactOutputProtocol::new(&mut footer);
+    metadata
+        .write_to_out_protocol(&mut protocol)
+        .map_err(|e| error(&format!("the footer can't be written: {e}")))?;
+    // the file is padded between its column chunks and its footer to the size of the encrypted
+    // file, which is the size the object store lists
+    let padding = data
+        .len()
+        .checked_sub(output.len() + footer.len() + 8)
+        .ok_or_else(|| error("the decrypted file is larger than the encrypted file"))?;
+    output.resize(output.len() + padding, 0);
+    output.extend_from_slice(&footer);
+    output.extend_from_slice(&(footer.len() as u32).to_le_bytes());
+    output.extend_from_slice(PARQUET_MAGIC);
+    Ok(Some(output))
+}
+
+/// Decrypts the pages of a column chunk into `output`, with their headers, and returns the
+/// offset of its first data page in `output`
+fn decrypt_pages(
+    mut pages: &[u8],
+    has_dictionary: bool,
+    decryptor: &Decryptor,
+    ordinals: (i16, i16),
+    output: &mut Vec<u8>,
+) -> std::result::Result<usize, String> {
+    let mut data_start = output.len();
+    let mut page_ordinal = 0;
+    let mut dictionary = has_dictionary;
+    while !pages.is_empty() {
+        let (header_type, page_type, page) = match dictionary {
+            true => (DICTIONARY_PAGE_HEADER, DICTIONARY_PAGE, None),
+            false => (DATA_PAGE_HEADER, DATA_PAGE, Some(page_ordinal)),
+        };
+        let (header, rest) = split_module(pages)?;
+        let aad = decryptor.module_aad(header_type, Some(ordinals), page);
+        let header = decryptor
+            .open_module(header, &aad)
+            .ok_or_else(|| "a page header can't be decrypted".to_string())?;
+        let mut header = read_thrift::<PageHeader>(&header, "page header")
+            .map_err(|e| e.to_string())?
+            .0;
+        let (body, rest) = split_module(rest)?;
+        let aad = decryptor.module_aad(page_type, Some(ordinals), page);
+        let body = decryptor
+            .open_page(body, &aad)
+            .ok_or_else(|| "a page can't be decrypted".to_string())?;
+        pages = rest;
+
+        header.compressed_page_size = body.len() as i32;
+        header.crc = None;
+        let mut protocol = TCompactOutputProtocol::new(&mut *output);
+        header
+            .write_to_out_protocol(&mut protocol)
+            .map_err(|e| format!("a page header can't be written: {e}"))?;
+        output.extend_from_slice(&body);
+        if dictionary {
+            data_start = output.len();
+            dictionary = false;
+        } else {
+            page_ordinal = page_ordinal
+                .checked_add(1)
+                .ok_or_else(|| "it has too many pages".to_string())?;
+        }
+    }
+    Ok(data_start)
+}
+
+/// Splits the length prefixed module at the start of `data` from the rest of it
+fn split_module(data: &[u8]) -> std::result::Result<(&[u8], &[u8]), String> {
+    let truncated = || "a module is truncated".to_string();
+    let len = data.get(..4).ok_or_else(truncated)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    if data.len() < 4 + len {
+        return Err(truncated());
+    }
+    Ok((&data[4..4 + len], &data[4 + len..]))
+}
+
+/// Decrypts the modules of a file with one key
+#[derive(Clone)]
+struct Decryptor {
+    key: std::sync::Arc<LessSafeKey>,
+    file_aad: Vec<u8>,
+    // Whether the pages are encrypted with AES CTR rather than AES GCM
+    ctr: bool,
+}
+
+impl Decryptor {
+    fn try_new(algorithm: &EncryptionAlgorithm, key: &[u8]) -> Result<Self> {
+        let (aad_prefix, aad_file_unique, supply_aad_prefix, ctr) = match algorithm {
+            EncryptionAlgorithm::AESGCMV1(a) => (
+                &a.aad_prefix,
+                &a.aad_file_unique,
+                a.supply_aad_prefix,
+                false,
+            ),
+            EncryptionAlgorithm::AESGCMCTRV1(a) => {
+                (&a.aad_prefix, &a.aad_file_unique, a.supply_aad_prefix, true)
+            }
+        };
+        if supply_aad_prefix == Some(true) {
+            return Err(error(
+                "it was written with an AAD prefix that isn't stored in the file, which isn't \
+                 supported",
+            ));
+        }
+        let mut file_aad = aad_prefix.clone().unwrap_or_default();
+        file_aad.extend(aad_file_unique.iter().flatten());
+        Ok(Self {
+            key: std::sync::Arc::new(cipher_key(key)?),
+            file_aad,
+            ctr,
+        })
+    }
+
+    fn with_key(&self, key: &[u8]) -> Result<Self> {
+        Ok(Self {
+            key: std::sync::Arc::new(cipher_key(key)?),
+            ..self.clone()
+        })
+    }
+
+    fn module_aad(&self, module: u8, columns: Option<(i16, i16)>, page: Option<i16>) -> Vec<u8> {
+        let mut aad = self.file_aad.clone();
+        aad.push(module);
+        if let Some((row_group, column)) = columns {
+            aad.extend(row_group.to_le_bytes());
+            aad.extend(column.to_le_bytes());
+        }
+        if let Some(page) = page {
+            aad.extend(page.to_le_bytes());
+        }
+        aad
+    }
+
+    /// Decrypts an AES GCM module, made of its nonce, ciphertext and tag
+    fn open_module(&self, module: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if module.len() < NONCE_LEN + TAG_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = module.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut buffer = ciphertext.to_vec();
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(aad), &mut buffer)
+            .ok()?
+            .len();
+        buffer.truncate(len);
+        Some(buffer)
+    }
+
+    /// Decrypts a page, which is an AES GCM module or, with AES_GCM_CTR_V1, an AES CTR module
+    /// made of its nonce and ciphertext
+    fn open_page(&self, module: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if !self.ctr {
+            return self.open_module(module, aad);
+        }
+        if module.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = module.split_at(NONCE_LEN);
+        // The counter of AES CTR starts at 1, as the one of AES GCM, which encrypts the tag with
+        // the first block of the key stream and the data with the following ones. The tag of
+        // empty data is that first block, as its hash is zero
+        let nonce = || Nonce::try_assume_unique_for_key(nonce).ok();
+        let first = self
+            .key
+            .seal_in_place_separate_tag(nonce()?, Aad::empty(), &mut [])
+            .ok()?;
+        let mut stream = vec![0; ciphertext.len().saturating_sub(TAG_LEN)];
+        let _ = self
+            .key
+            .seal_in_place_separate_tag(nonce()?, Aad::empty(), &mut stream)
+            .ok()?;
+        Some(
+            ciphertext
+                .iter()
+                .zip(first.as_ref().iter().chain(stream.iter()))
+                .map(|(c, k)| c ^ k)
+                .collect(),
+        )
+    }
+
+    /// Checks the signature of a plaintext footer, its nonce and tag, against the footer key
+    fn verify_footer(&self, footer: &[u8], signature: &[u8]) -> Result<()> {
+        let wrong_key = || error("the footer signature doesn't match, the footer key is wrong");
+        if signature.len() < NONCE_LEN + TAG_LEN {
+            return Err(error("the signature of the footer is missing"));
+        }
+        let (nonce, tag) = signature.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| wrong_key())?;
+        let mut buffer = footer.to_vec();
+        let aad = self.module_aad(FOOTER, None, None);
+        let expected = self
+            .key
+            .seal_in_place_separate_tag(nonce, Aad::from(aad), &mut buffer)
+            .map_err(|_| wrong_key())?;
+        if expected.as_ref() != &tag[..TAG_LEN] {
+            return Err(wrong_key());
+        }
+        Ok(())
+    }
+}
+
+fn cipher_key(key: &[u8]) -> Result<LessSafeKey> {
+    let algorithm = match key.len() {
+        16 => &AES_128_GCM,
+        32 => &AES_256_GCM,
+        24 => {
+            return Err(error(
+                "AES-192 keys aren't supported, expected a 16 or 32 byte key",
+            ))
+        }
+        n => {
+            return Err(error(&format!(
+                "a decryption key has {n} bytes, expected 16 or 32 bytes"
+            )))
+        }
+    };
+    let key = UnboundKey::new(algorithm, key).map_err(|_| error("a decryption key is invalid"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Reads a thrift structure from the start of `data`, returning it and the number of bytes read
+fn read_thrift<T: TSerializable>(data: &[u8], what: &str) -> Result<(T, usize)> {
+    let mut cursor = Cursor::new(data);
+    let mut protocol = TCompactInputProtocol::new(&mut cursor);
+    let value = T::read_from_in_protocol(&mut protocol)
+        .map_err(|e| error(&format!("the {what} can't be read: {e}")))?;
+    Ok((value, cursor.position() as usize))
+}
+
+fn ordinal(index: usize) -> Result<i16> {
+    i16::try_from(index).map_err(|_| error("it has more than 32767 row groups or columns"))
+}
+
+fn column_error(path: &[String], message: &str) -> DataFusionError {
+    error(&format!("column {}: {message}", path.join(".")))
+}
+
+fn error(message: &str) -> DataFusionError {
+    DataFusionError::Common(message.to_string())
+}
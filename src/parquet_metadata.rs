@@ -0,0 +1,109 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::arrow::pyarrow::ToPyArrow;
+use datafusion::parquet::arrow::parquet_to_arrow_schema;
+use datafusion::parquet::file::metadata::ParquetMetaData;
+use pyo3::prelude::*;
+
+/// The footer metadata of a Parquet file
+#[pyclass(name = "ParquetMetadata", module = "datafusion", subclass)]
+#[derive(Debug, Clone)]
+pub struct PyParquetMetadata {
+    metadata: Arc<ParquetMetaData>,
+}
+
+impl PyParquetMetadata {
+    pub fn new(metadata: ParquetMetaData) -> Self {
+        Self {
+            metadata: Arc::new(metadata),
+        }
+    }
+}
+
+#[pymethods]
+impl PyParquetMetadata {
+    /// The Arrow schema of the file
+    #[getter]
+    fn schema(&self, py: Python) -> PyResult<PyObject> {
+        let file_metadata = self.metadata.file_metadata();
+        let schema = parquet_to_arrow_schema(
+            file_metadata.schema_descr(),
+            file_metadata.key_value_metadata(),
+        )
+        .map_err(datafusion_common::DataFusionError::from)?;
+        schema.to_pyarrow(py)
+    }
+
+    #[getter]
+    fn num_rows(&self) -> i64 {
+        self.metadata.file_metadata().num_rows()
+    }
+
+    #[getter]
+    fn num_row_groups(&self) -> usize {
+        self.metadata.num_row_groups()
+    }
+
+    /// The layout of the row groups, one dict with `num_rows`, `num_columns`,
+    /// `total_byte_size` and `compressed_size` per row group
+    #[getter]
+    fn row_groups(&self) -> Vec<HashMap<&str, i64>> {
+        self.metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| {
+                HashMap::from([
+                    ("num_rows", row_group.num_rows()),
+                    ("num_columns", row_group.num_columns() as i64),
+                    ("total_byte_size", row_group.total_byte_size()),
+                    ("compressed_size", row_group.compressed_size()),
+                ])
+            })
+            .collect()
+    }
+
+    /// The key/value metadata of the file
+    #[getter]
+    fn metadata(&self) -> HashMap<String, Option<String>> {
+        self.metadata
+            .file_metadata()
+            .key_value_metadata()
+            .map(|kv| {
+                kv.iter()
+                    .map(|kv| (kv.key.clone(), kv.value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[getter]
+    fn created_by(&self) -> Option<&str> {
+        self.metadata.file_metadata().created_by()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ParquetMetadata(num_rows={}, num_row_groups={})",
+            self.num_rows(),
+            self.num_row_groups()
+        )
+    }
+}
@@ -15,10 +15,24 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use datafusion::physical_plan::{displayable, ExecutionPlan};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::{displayable, ExecutionPlan, SendableRecordBatchStream};
+#[cfg(feature = "proto")]
+use datafusion_proto::bytes::{physical_plan_from_bytes, physical_plan_to_bytes};
 use std::sync::Arc;
+use tokio::task::JoinHandle;
 
+use pyo3::exceptions::PyIndexError;
 use pyo3::prelude::*;
+#[cfg(feature = "proto")]
+use pyo3::types::PyBytes;
+
+use crate::context::PySessionContext;
+use crate::errors::py_datafusion_err;
+#[cfg(feature = "proto")]
+use crate::errors::DataFusionError;
+use crate::record_batch::PyRecordBatchStream;
+use crate::utils::{get_tokio_runtime, wait_for_future};
 
 #[pyclass(name = "ExecutionPlan", module = "datafusion", subclass)]
 #[derive(Debug, Clone)]
@@ -31,6 +45,28 @@ impl PyExecutionPlan {
     pub fn new(plan: Arc<dyn ExecutionPlan>) -> Self {
         Self { plan }
     }
+
+    /// Execute a single partition of this plan, checking the partition index first
+    pub(crate) fn execute_partition(
+        &self,
+        partition: usize,
+        ctx: TaskContext,
+        py: Python,
+    ) -> PyResult<PyRecordBatchStream> {
+        let partition_count = self.partition_count();
+        if partition >= partition_count {
+            return Err(PyIndexError::new_err(format!(
+                "Partition {partition} is out of range, the plan has {partition_count} partitions"
+            )));
+        }
+        // create a Tokio runtime to run the async code
+        let rt = &get_tokio_runtime(py).0;
+        let plan = self.plan.clone();
+        let fut: JoinHandle<datafusion_common::Result<SendableRecordBatchStream>> =
+            rt.spawn(async move { plan.execute(partition, Arc::new(ctx)) });
+        let stream = wait_for_future(py, fut).map_err(py_datafusion_err)?;
+        Ok(PyRecordBatchStream::new(stream?))
+    }
 }
 
 #[pymethods]
@@ -62,6 +98,40 @@ impl PyExecutionPlan {
     pub fn partition_count(&self) -> usize {
         self.plan.output_partitioning().partition_count()
     }
+
+    /// Execute a single partition of this plan and return a stream of record batches. The
+    /// runtime of the given context is used, a default one if none is given
+    #[pyo3(signature = (partition, ctx=None))]
+    fn execute(
+        &self,
+        partition: usize,
+        ctx: Option<&PySessionContext>,
+        py: Python,
+    ) -> PyResult<PyRecordBatchStream> {
+        let task_ctx = match ctx {
+            Some(ctx) => TaskContext::from(&ctx.ctx.state()),
+            None => TaskContext::default(),
+        };
+        self.execute_partition(partition, task_ctx, py)
+    }
+
+    /// Serialize this plan to DataFusion's own protobuf format
+    #[cfg(feature = "proto")]
+    pub fn to_proto_bytes(&self, py: Python) -> PyResult<PyObject> {
+        let proto_bytes =
+            physical_plan_to_bytes(self.plan.clone()).map_err(DataFusionError::from)?;
+        Ok(PyBytes::new(py, &proto_bytes).into())
+    }
+
+    /// Deserialize a plan from DataFusion's protobuf format, resolving functions and object
+    /// stores against the given context
+    #[cfg(feature = "proto")]
+    #[staticmethod]
+    fn from_proto_bytes(ctx: &PySessionContext, proto_bytes: &PyBytes) -> PyResult<Self> {
+        let plan = physical_plan_from_bytes(proto_bytes.as_bytes(), &ctx.ctx)
+            .map_err(DataFusionError::from)?;
+        Ok(Self::new(plan))
+    }
 }
 
 impl From<PyExecutionPlan> for Arc<dyn ExecutionPlan> {
@@ -15,12 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::VecDeque;
+
 use crate::utils::wait_for_future;
+use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::arrow::pyarrow::ToPyArrow;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use futures::StreamExt;
-use pyo3::{pyclass, pymethods, PyObject, PyResult, Python};
+use pyo3::{pyclass, pymethods, PyObject, PyRef, PyResult, Python};
 
 #[pyclass(name = "RecordBatch", module = "datafusion", subclass)]
 pub struct PyRecordBatch {
@@ -43,22 +46,123 @@ impl From<RecordBatch> for PyRecordBatch {
 #[pyclass(name = "RecordBatchStream", module = "datafusion", subclass)]
 pub struct PyRecordBatchStream {
     stream: SendableRecordBatchStream,
+    progress_callback: Option<PyObject>,
+    rows: usize,
 }
 
 impl PyRecordBatchStream {
     pub fn new(stream: SendableRecordBatchStream) -> Self {
-        Self { stream }
+        Self {
+            stream,
+            progress_callback: None,
+            rows: 0,
+        }
     }
-}
 
-#[pymethods]
-impl PyRecordBatchStream {
-    fn next(&mut self, py: Python) -> PyResult<Option<PyRecordBatch>> {
+    /// Calls `callback` with the cumulative number of rows after every batch
+    pub fn with_progress_callback(mut self, callback: Option<PyObject>) -> Self {
+        self.progress_callback = callback;
+        self
+    }
+
+    pub(crate) fn schema(&self) -> SchemaRef {
+        self.stream.schema()
+    }
+
+    pub(crate) fn next_batch(&mut self, py: Python) -> PyResult<Option<RecordBatch>> {
         let result = self.stream.next();
         match wait_for_future(py, result) {
             None => Ok(None),
-            Some(Ok(b)) => Ok(Some(b.into())),
+            Some(Ok(b)) => {
+                self.rows += b.num_rows();
+                if let Some(callback) = &self.progress_callback {
+                    callback.call1(py, (self.rows,))?;
+                }
+                Ok(Some(b))
+            }
             Some(Err(e)) => Err(e.into()),
         }
     }
 }
+
+#[pymethods]
+impl PyRecordBatchStream {
+    fn next(&mut self, py: Python) -> PyResult<Option<PyRecordBatch>> {
+        Ok(self.next_batch(py)?.map(PyRecordBatch::from))
+    }
+
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyRecordBatch>> {
+        self.next(py)
+    }
+}
+
+/// Iterates over a stream of record batches as pandas DataFrames of `chunk_rows` rows, the last
+/// of which may be shorter. Batches are only read from the stream as they're needed.
+#[pyclass(name = "PandasChunks", module = "datafusion", subclass)]
+pub struct PyPandasChunks {
+    stream: PyRecordBatchStream,
+    chunk_rows: usize,
+    pending: VecDeque<RecordBatch>,
+    pending_rows: usize,
+}
+
+impl PyPandasChunks {
+    pub fn new(stream: PyRecordBatchStream, chunk_rows: usize) -> Self {
+        Self {
+            stream,
+            chunk_rows,
+            pending: VecDeque::new(),
+            pending_rows: 0,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPandasChunks {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        while self.pending_rows < self.chunk_rows {
+            match self.stream.next_batch(py)? {
+                Some(batch) => {
+                    self.pending_rows += batch.num_rows();
+                    self.pending.push_back(batch);
+                }
+                None => break,
+            }
+        }
+        if self.pending_rows == 0 {
+            return Ok(None);
+        }
+
+        // Split the last batch of the chunk, keeping its remaining rows for the next one
+        let mut chunk = Vec::new();
+        let mut rows = 0;
+        while let Some(batch) = self.pending.pop_front() {
+            let take = batch.num_rows().min(self.chunk_rows - rows);
+            if take < batch.num_rows() {
+                let rest = batch.slice(take, batch.num_rows() - take);
+                self.pending.push_front(rest);
+            }
+            chunk.push(batch.slice(0, take).to_pyarrow(py)?);
+            rows += take;
+            if rows == self.chunk_rows {
+                break;
+            }
+        }
+        self.pending_rows -= rows;
+
+        let schema = self.stream.schema().as_ref().to_pyarrow(py)?;
+        let table = py
+            .import("pyarrow")?
+            .getattr("Table")?
+            .call_method1("from_batches", (chunk, schema))?;
+        Ok(Some(table.call_method0("to_pandas")?.into()))
+    }
+}
@@ -0,0 +1,129 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The results of collected DataFrames kept by a context, for `SessionContext.enable_result_cache`
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::TableProvider;
+
+/// A cache of collected results keyed by the fingerprint of their plan, evicting the least
+/// recently used results once their size exceeds the budget. It is stored as an extension of
+/// the session config and disabled until a budget is set.
+#[derive(Default)]
+pub(crate) struct ResultCache {
+    inner: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    max_bytes: usize,
+    used_bytes: usize,
+    /// Incremented on every access, to order the entries by their last use
+    clock: u64,
+    entries: HashMap<String, CacheEntry>,
+    /// The keys of the entries by their last use, the least recently used first
+    order: BTreeMap<u64, String>,
+}
+
+struct CacheEntry {
+    batches: Vec<RecordBatch>,
+    size: usize,
+    last_used: u64,
+    /// The tables read by the plan, which are part of the key by their address, kept alive so
+    /// the address isn't reused by another table
+    _tables: Vec<Arc<dyn TableProvider>>,
+}
+
+impl ResultCache {
+    /// Sets the budget of the cache, evicting results until they fit, `0` disables it
+    pub(crate) fn set_max_bytes(&self, max_bytes: usize) {
+        let mut state = self.inner.lock().unwrap();
+        state.max_bytes = max_bytes;
+        state.evict(0);
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.inner.lock().unwrap().max_bytes > 0
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.entries.clear();
+        state.order.clear();
+        state.used_bytes = 0;
+    }
+
+    /// Returns the result cached for `key`, marking it as the most recently used
+    pub(crate) fn get(&self, key: &str) -> Option<Vec<RecordBatch>> {
+        let mut state = self.inner.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(key)?;
+        let last_used = std::mem::replace(&mut entry.last_used, clock);
+        let batches = entry.batches.clone();
+        state.order.remove(&last_used);
+        state.order.insert(clock, key.to_string());
+        Some(batches)
+    }
+
+    /// Caches the result of a plan, unless it alone exceeds the budget
+    pub(crate) fn insert(
+        &self,
+        key: String,
+        batches: Vec<RecordBatch>,
+        tables: Vec<Arc<dyn TableProvider>>,
+    ) {
+        let size = batches.iter().map(|b| b.get_array_memory_size()).sum();
+        let mut state = self.inner.lock().unwrap();
+        if size > state.max_bytes {
+            return;
+        }
+        if let Some(previous) = state.entries.remove(&key) {
+            state.order.remove(&previous.last_used);
+            state.used_bytes -= previous.size;
+        }
+        state.evict(size);
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = CacheEntry {
+            batches,
+            size,
+            last_used: clock,
+            _tables: tables,
+        };
+        state.used_bytes += size;
+        state.order.insert(clock, key.clone());
+        state.entries.insert(key, entry);
+    }
+}
+
+impl CacheState {
+    /// Evicts the least recently used results until `additional` more bytes fit in the budget
+    fn evict(&mut self, additional: usize) {
+        while self.used_bytes + additional > self.max_bytes {
+            let last_used = match self.order.keys().next() {
+                Some(last_used) => *last_used,
+                None => break,
+            };
+            let key = self.order.remove(&last_used).unwrap();
+            let entry = self.entries.remove(&key).unwrap();
+            self.used_bytes -= entry.size;
+        }
+    }
+}
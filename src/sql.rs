@@ -16,4 +16,6 @@
 // under the License.
 
 pub mod exceptions;
+pub(crate) mod function;
 pub mod logical;
+pub(crate) mod parser;
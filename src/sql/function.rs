@@ -0,0 +1,293 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use datafusion::arrow::compute::can_cast_types;
+use datafusion::arrow::datatypes::{DataType, Schema};
+use datafusion::arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::execution::context::SessionState;
+use datafusion::optimizer::analyzer::type_coercion::TypeCoercion;
+use datafusion::optimizer::analyzer::AnalyzerRule;
+use datafusion::physical_expr::create_physical_expr;
+use datafusion::sql::planner::{ContextProvider, IdentNormalizer, PlannerContext, SqlToRel};
+use datafusion::sql::sqlparser::ast::{
+    self, ArgMode, FunctionBehavior, FunctionDefinition, Statement,
+};
+use datafusion::sql::sqlparser::dialect::{Dialect, PostgreSqlDialect};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::Parser;
+use datafusion::sql::sqlparser::tokenizer::{Token, Tokenizer};
+use datafusion_common::tree_node::{Transformed, TreeNode, VisitRecursion};
+use datafusion_common::{DFField, DFSchema, DataFusionError, Result, ScalarValue};
+use datafusion_expr::{
+    cast, create_udf, ColumnarValue, EmptyRelation, Expr, ExprSchemable, LogicalPlan,
+    LogicalPlanBuilder, ScalarUDF, Volatility,
+};
+
+use super::parser::{session_dialect, session_planner, SessionContextProvider};
+
+/// Plans `sql` if it is a `CREATE FUNCTION` statement, returning the scalar function it
+/// defines, or `None` for any other statement. The statement follows the PostgreSQL syntax,
+/// whatever the dialect of the session, and the body of the function is a SQL expression of
+/// its parameters, given as `RETURN <expression>` or `AS '<expression>'`, where parameters are
+/// referenced by name or position (`$1`).
+pub(crate) fn plan_create_function(sql: &str, state: &SessionState) -> Result<Option<ScalarUDF>> {
+    if !is_create_function(sql) {
+        return Ok(None);
+    }
+    let mut statements = Parser::parse_sql(&PostgreSqlDialect {}, sql)?;
+    if statements.len() != 1 {
+        return Err(DataFusionError::Plan(
+            "The context currently only supports a single SQL statement".to_string(),
+        ));
+    }
+    let (or_replace, name, args, return_type, params) = match statements.remove(0) {
+        Statement::CreateFunction {
+            or_replace,
+            name,
+            args,
+            return_type,
+            params,
+            ..
+        } => (or_replace, name, args, return_type, params),
+        _ => return Ok(None),
+    };
+
+    let normalizer =
+        IdentNormalizer::new(state.config_options().sql_parser.enable_ident_normalization);
+    let name = match name.0.as_slice() {
+        [ident] => normalizer.normalize(ident.clone()),
+        _ => return Err(not_supported(format!("with the qualified name {name}"))),
+    };
+    if !or_replace && state.scalar_functions().contains_key(&name) {
+        return Err(DataFusionError::Plan(format!(
+            "Function {name} already exists, use CREATE OR REPLACE FUNCTION to replace it"
+        )));
+    }
+    if let Some(language) = &params.language {
+        if !language.value.eq_ignore_ascii_case("sql") {
+            return Err(not_supported(format!("with LANGUAGE {language}")));
+        }
+    }
+    let dialect = session_dialect(state)?;
+    let provider = SessionContextProvider { state };
+    let planner = session_planner(&provider);
+    let mut fields = vec![];
+    for (i, arg) in args.unwrap_or_default().into_iter().enumerate() {
+        if matches!(arg.mode, Some(ArgMode::Out | ArgMode::InOut)) {
+            return Err(not_supported("with OUT parameters"));
+        }
+        if arg.default_expr.is_some() {
+            return Err(not_supported("with parameter defaults"));
+        }
+        let arg_name = match arg.name {
+            Some(ident) => normalizer.normalize(ident),
+            None => format!("${}", i + 1),
+        };
+        let data_type = sql_data_type(&planner, arg.data_type)?;
+        fields.push(DFField::new_unqualified(&arg_name, data_type, true));
+    }
+    let schema = DFSchema::new_with_metadata(fields, HashMap::new())?;
+
+    let body = match (params.return_, params.as_) {
+        (Some(expr), _) => expr,
+        (
+            None,
+            Some(
+                FunctionDefinition::SingleQuotedDef(body)
+                | FunctionDefinition::DoubleDollarDef(body),
+            ),
+        ) => parse_body(&body, dialect.as_ref())?,
+        (None, None) => {
+            return Err(DataFusionError::Plan(format!(
+            "CREATE FUNCTION {name} has no body, expected RETURN <expression> or AS '<expression>'"
+        )))
+        }
+    };
+    let expr = planner
+        .sql_to_expr(body, &schema, &mut PlannerContext::new())?
+        .transform(&|expr| match expr {
+            Expr::Placeholder(placeholder) => {
+                let field = placeholder
+                    .id
+                    .strip_prefix('$')
+                    .and_then(|position| position.parse::<usize>().ok())
+                    .and_then(|position| position.checked_sub(1))
+                    .and_then(|i| schema.fields().get(i))
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "CREATE FUNCTION {name} has no parameter {}",
+                            placeholder.id
+                        ))
+                    })?;
+                Ok(Transformed::Yes(Expr::Column(field.qualified_column())))
+            }
+            expr => Ok(Transformed::No(expr)),
+        })?;
+    let mut unsupported = None;
+    expr.apply(&mut |expr| {
+        if let Expr::AggregateFunction(_)
+        | Expr::AggregateUDF(_)
+        | Expr::WindowFunction(_)
+        | Expr::ScalarSubquery(_)
+        | Expr::Exists(_)
+        | Expr::InSubquery(_) = expr
+        {
+            unsupported = Some(expr.clone());
+            return Ok(VisitRecursion::Stop);
+        }
+        Ok(VisitRecursion::Continue)
+    })?;
+    if let Some(expr) = unsupported {
+        return Err(not_supported(format!("with {expr} in its body")));
+    }
+
+    // Coerce the types of the body as the analyzer does for queries
+    let plan = LogicalPlanBuilder::from(LogicalPlan::EmptyRelation(EmptyRelation {
+        produce_one_row: false,
+        schema: Arc::new(schema.clone()),
+    }))
+    .project(vec![expr])?
+    .build()?;
+    let projection = match TypeCoercion::new().analyze(plan, state.config_options())? {
+        LogicalPlan::Projection(projection) => projection,
+        _ => {
+            return Err(DataFusionError::Internal(
+                "Type coercion didn't return a projection".to_string(),
+            ))
+        }
+    };
+    let expr = projection.expr[0].clone().unalias();
+
+    let body_type = expr.get_type(&schema)?;
+    let return_type = match return_type {
+        Some(return_type) => sql_data_type(&planner, return_type)?,
+        None => body_type.clone(),
+    };
+    let expr = if body_type == return_type {
+        expr
+    } else if can_cast_types(&body_type, &return_type) {
+        cast(expr, return_type.clone())
+    } else {
+        return Err(DataFusionError::Plan(format!(
+            "CREATE FUNCTION {name} returns {return_type}, but its body is of type {body_type}"
+        )));
+    };
+
+    let input_schema = Arc::new(Schema::from(&schema));
+    let body = create_physical_expr(&expr, &schema, &input_schema, state.execution_props())?;
+    let input_types = input_schema
+        .fields()
+        .iter()
+        .map(|field| field.data_type().clone())
+        .collect();
+    let volatility = match params.behavior {
+        Some(FunctionBehavior::Immutable) => Volatility::Immutable,
+        Some(FunctionBehavior::Stable) => Volatility::Stable,
+        Some(FunctionBehavior::Volatile) | None => Volatility::Volatile,
+    };
+    let fun = Arc::new(move |args: &[ColumnarValue]| {
+        // Evaluate the body on a batch of the arguments, of a single row if they are all
+        // scalars. Functions without parameters get a null array of the size of the batch
+        let num_rows = args.iter().find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        });
+        let columns = args
+            .iter()
+            .take(input_schema.fields().len())
+            .map(|arg| arg.clone().into_array(num_rows.unwrap_or(1)))
+            .collect();
+        let options = RecordBatchOptions::new().with_row_count(Some(num_rows.unwrap_or(1)));
+        let batch = RecordBatch::try_new_with_options(input_schema.clone(), columns, &options)?;
+        match body.evaluate(&batch)? {
+            ColumnarValue::Array(array) if num_rows.is_none() => Ok(ColumnarValue::Scalar(
+                ScalarValue::try_from_array(&array, 0)?,
+            )),
+            result => Ok(result),
+        }
+    });
+    Ok(Some(create_udf(
+        &name,
+        input_types,
+        Arc::new(return_type),
+        volatility,
+        fun,
+    )))
+}
+
+/// Whether `sql` starts with `CREATE [OR REPLACE] [TEMPORARY] FUNCTION`
+fn is_create_function(sql: &str) -> bool {
+    let tokens = match Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize() {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+    let mut keywords = tokens.into_iter().filter_map(|token| match token {
+        Token::Whitespace(_) => None,
+        Token::Word(word) => Some(word.keyword),
+        _ => Some(Keyword::NoKeyword),
+    });
+    if keywords.next() != Some(Keyword::CREATE) {
+        return false;
+    }
+    let mut keyword = keywords.next();
+    if keyword == Some(Keyword::OR) {
+        if keywords.next() != Some(Keyword::REPLACE) {
+            return false;
+        }
+        keyword = keywords.next();
+    }
+    if matches!(keyword, Some(Keyword::TEMPORARY | Keyword::TEMP)) {
+        keyword = keywords.next();
+    }
+    keyword == Some(Keyword::FUNCTION)
+}
+
+fn not_supported(what: impl Display) -> DataFusionError {
+    DataFusionError::NotImplemented(format!("CREATE FUNCTION {what} is not supported"))
+}
+
+/// Converts a SQL type to Arrow as the planner does, by planning a cast of NULL to it
+fn sql_data_type<S: ContextProvider>(
+    planner: &SqlToRel<S>,
+    sql_type: ast::DataType,
+) -> Result<DataType> {
+    let cast = ast::Expr::Cast {
+        expr: Box::new(ast::Expr::Value(ast::Value::Null)),
+        data_type: sql_type,
+    };
+    match planner.sql_to_expr(cast, &DFSchema::empty(), &mut PlannerContext::new())? {
+        Expr::Cast(cast) => Ok(cast.data_type),
+        expr => Err(DataFusionError::Internal(format!(
+            "Expected a cast, got {expr}"
+        ))),
+    }
+}
+
+/// Parses the body given as `AS '...'`: an expression, optionally as a `SELECT` of a single
+/// expression without a `FROM` clause
+fn parse_body(body: &str, dialect: &dyn Dialect) -> Result<ast::Expr> {
+    let mut parser = Parser::new(dialect).try_with_sql(body)?;
+    let _ = parser.parse_keyword(Keyword::SELECT);
+    let expr = parser.parse_expr()?;
+    let _ = parser.consume_token(&Token::SemiColon);
+    parser.expect_token(&Token::EOF)?;
+    Ok(expr)
+}
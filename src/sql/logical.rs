@@ -17,7 +17,11 @@
 
 use std::sync::Arc;
 
+#[cfg(feature = "proto")]
+use crate::context::PySessionContext;
 use crate::errors::py_unsupported_variant_err;
+#[cfg(feature = "proto")]
+use crate::errors::DataFusionError;
 use crate::expr::aggregate::PyAggregate;
 use crate::expr::analyze::PyAnalyze;
 use crate::expr::distinct::PyDistinct;
@@ -31,8 +35,20 @@ use crate::expr::sort::PySort;
 use crate::expr::subquery::PySubquery;
 use crate::expr::subquery_alias::PySubqueryAlias;
 use crate::expr::table_scan::PyTableScan;
+#[cfg(feature = "proto")]
+use datafusion::execution::context::SessionState;
+#[cfg(feature = "proto")]
+use datafusion_common::tree_node::{TreeNode, VisitRecursion};
 use datafusion_expr::LogicalPlan;
+#[cfg(feature = "proto")]
+use datafusion_expr::{Expr, WindowFunction};
+#[cfg(feature = "proto")]
+use datafusion_proto::bytes::{logical_plan_from_bytes, logical_plan_to_bytes};
+#[cfg(feature = "proto")]
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+#[cfg(feature = "proto")]
+use pyo3::types::PyBytes;
 
 use crate::expr::logical_node::LogicalNode;
 
@@ -108,6 +124,74 @@ impl PyLogicalPlan {
     fn display_graphviz(&self) -> String {
         format!("{}", self.plan.display_graphviz())
     }
+
+    /// Serialize this plan to DataFusion's own protobuf format. The functions it calls are
+    /// decoded by name, so the user defined ones must be registered in `ctx`, the context the
+    /// plan is decoded with having the same registrations
+    #[cfg(feature = "proto")]
+    fn to_proto_bytes(&self, ctx: &PySessionContext, py: Python) -> PyResult<PyObject> {
+        if let Some(name) = unregistered_function(&self.plan, &ctx.ctx.state())? {
+            return Err(PyValueError::new_err(format!(
+                "The plan calls the function {name}, which isn't registered in the context, \
+                 register it to serialize the plan"
+            )));
+        }
+        let proto_bytes = logical_plan_to_bytes(&self.plan).map_err(DataFusionError::from)?;
+        Ok(PyBytes::new(py, &proto_bytes).into())
+    }
+
+    /// Deserialize a plan from DataFusion's protobuf format, resolving tables and functions
+    /// against the given context
+    #[cfg(feature = "proto")]
+    #[staticmethod]
+    fn from_proto_bytes(ctx: &PySessionContext, proto_bytes: &PyBytes) -> PyResult<Self> {
+        let plan = logical_plan_from_bytes(proto_bytes.as_bytes(), &ctx.ctx)
+            .map_err(DataFusionError::from)?;
+        Ok(Self::new(plan))
+    }
+}
+
+/// The name of a user defined function `plan` calls that isn't registered in `state`
+#[cfg(feature = "proto")]
+fn unregistered_function(
+    plan: &LogicalPlan,
+    state: &SessionState,
+) -> datafusion_common::Result<Option<String>> {
+    let mut unregistered = None;
+    plan.apply(&mut |plan| {
+        for expr in plan.expressions() {
+            expr.apply(&mut |expr| {
+                let aggregates = state.aggregate_functions();
+                let (name, registered) = match expr {
+                    Expr::ScalarUDF(f) => {
+                        let name = &f.fun.name;
+                        (name, state.scalar_functions().contains_key(name))
+                    }
+                    Expr::AggregateUDF(f) => (&f.fun.name, aggregates.contains_key(&f.fun.name)),
+                    Expr::WindowFunction(f) => match &f.fun {
+                        WindowFunction::AggregateUDF(fun) => {
+                            (&fun.name, aggregates.contains_key(&fun.name))
+                        }
+                        WindowFunction::WindowUDF(fun) => {
+                            (&fun.name, state.window_functions().contains_key(&fun.name))
+                        }
+                        _ => return Ok(VisitRecursion::Continue),
+                    },
+                    _ => return Ok(VisitRecursion::Continue),
+                };
+                if !registered {
+                    unregistered = Some(name.clone());
+                    return Ok(VisitRecursion::Stop);
+                }
+                Ok(VisitRecursion::Continue)
+            })?;
+        }
+        Ok(match unregistered {
+            Some(_) => VisitRecursion::Stop,
+            None => VisitRecursion::Continue,
+        })
+    })?;
+    Ok(unregistered)
 }
 
 impl From<PyLogicalPlan> for LogicalPlan {
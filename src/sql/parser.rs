@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::config::ConfigOptions;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{AggregateUDF, ScalarUDF, TableSource, WindowUDF};
+use datafusion::sql::planner::{
+    ContextProvider, IdentNormalizer, ParserOptions, PlannerContext, SqlToRel,
+};
+use datafusion::sql::sqlparser::ast::SelectItem;
+use datafusion::sql::sqlparser::dialect::{dialect_from_str, Dialect};
+use datafusion::sql::sqlparser::parser::Parser;
+use datafusion::sql::sqlparser::tokenizer::Token;
+use datafusion::sql::TableReference;
+use datafusion_common::{DFSchema, DataFusionError, Result};
+use datafusion_expr::Expr;
+
+/// Resolves the functions referenced by a SQL expression against a session
+pub(crate) struct SessionContextProvider<'a> {
+    pub(crate) state: &'a SessionState,
+}
+
+impl<'a> ContextProvider for SessionContextProvider<'a> {
+    fn get_table_provider(&self, name: TableReference) -> Result<Arc<dyn TableSource>> {
+        Err(DataFusionError::Plan(format!(
+            "Table '{name}' can't be referenced in an expression"
+        )))
+    }
+
+    fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
+        self.state.scalar_functions().get(name).cloned()
+    }
+
+    fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
+        self.state.aggregate_functions().get(name).cloned()
+    }
+
+    fn get_window_meta(&self, name: &str) -> Option<Arc<WindowUDF>> {
+        self.state.window_functions().get(name).cloned()
+    }
+
+    fn get_variable_type(&self, _variable_names: &[String]) -> Option<DataType> {
+        None
+    }
+
+    fn options(&self) -> &ConfigOptions {
+        self.state.config_options()
+    }
+}
+
+/// Parses a SQL expression, optionally followed by `AS alias`, and plans it against
+/// `schema` using the functions and SQL options of the session. Expressions without an
+/// alias are named after the SQL text when `alias_unnamed` is set.
+pub(crate) fn parse_sql_expr(
+    sql: &str,
+    schema: &DFSchema,
+    state: &SessionState,
+    alias_unnamed: bool,
+) -> Result<Expr> {
+    let options = &state.config_options().sql_parser;
+    let dialect = session_dialect(state)?;
+    let mut parser = Parser::new(dialect.as_ref()).try_with_sql(sql)?;
+    let item = parser.parse_select_item()?;
+    parser.expect_token(&Token::EOF)?;
+
+    let provider = SessionContextProvider { state };
+    let planner = session_planner(&provider);
+    let mut planner_context = PlannerContext::new();
+    match item {
+        SelectItem::UnnamedExpr(sql_expr) => {
+            let name = sql_expr.to_string();
+            let expr = planner.sql_to_expr(sql_expr, schema, &mut planner_context)?;
+            match expr {
+                Expr::Column(_) => Ok(expr),
+                expr if alias_unnamed => Ok(expr.alias(name)),
+                expr => Ok(expr),
+            }
+        }
+        SelectItem::ExprWithAlias { expr, alias } => {
+            let expr = planner.sql_to_expr(expr, schema, &mut planner_context)?;
+            let alias = IdentNormalizer::new(options.enable_ident_normalization).normalize(alias);
+            Ok(expr.alias(alias))
+        }
+        _ => Err(DataFusionError::Plan(format!(
+            "Expected an expression, got '{sql}'"
+        ))),
+    }
+}
+
+/// The SQL dialect the session parses queries with
+pub(crate) fn session_dialect(state: &SessionState) -> Result<Box<dyn Dialect>> {
+    let dialect = &state.config_options().sql_parser.dialect;
+    dialect_from_str(dialect)
+        .ok_or_else(|| DataFusionError::Plan(format!("Unsupported SQL dialect: {dialect}")))
+}
+
+/// A SQL planner with the options of the session of `provider`
+pub(crate) fn session_planner<'a>(
+    provider: &'a SessionContextProvider<'a>,
+) -> SqlToRel<'a, SessionContextProvider<'a>> {
+    let options = &provider.state.config_options().sql_parser;
+    SqlToRel::new_with_options(
+        provider,
+        ParserOptions {
+            parse_float_as_decimal: options.parse_float_as_decimal,
+            enable_ident_normalization: options.enable_ident_normalization,
+        },
+    )
+}
@@ -16,13 +16,16 @@
 // under the License.
 
 use std::sync::Arc;
+use std::time::Duration;
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
-use object_store::aws::{AmazonS3, AmazonS3Builder};
-use object_store::azure::{MicrosoftAzure, MicrosoftAzureBuilder};
-use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder};
+use object_store::aws::{AmazonS3, AmazonS3Builder, AmazonS3ConfigKey};
+use object_store::azure::{AzureConfigKey, MicrosoftAzure, MicrosoftAzureBuilder};
+use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder, GoogleConfigKey};
 use object_store::local::LocalFileSystem;
+use object_store::{BackoffConfig, ClientConfigKey, RetryConfig};
 
 #[derive(FromPyObject)]
 pub enum StorageContexts {
@@ -32,6 +35,56 @@ pub enum StorageContexts {
     LocalFileSystem(PyLocalFileSystemContext),
 }
 
+/// The retry policy and request timeout of a cloud store, object_store's defaults are kept
+/// for the settings that aren't given
+struct RetryOptions {
+    retry: RetryConfig,
+    /// The request timeout, formatted as a client config value
+    timeout: Option<String>,
+}
+
+impl RetryOptions {
+    fn new(
+        max_retries: Option<usize>,
+        retry_backoff: Option<f64>,
+        retry_timeout: Option<f64>,
+        timeout: Option<f64>,
+    ) -> PyResult<Self> {
+        let seconds = |name: &str, value: f64| {
+            // from_secs_f64 panics on values that are negative, not finite or too large
+            if value.is_finite() && value > 0.0 && value < u64::MAX as f64 {
+                let duration = Duration::from_secs_f64(value);
+                if !duration.is_zero() {
+                    return Ok(duration);
+                }
+            }
+            Err(PyValueError::new_err(format!(
+                "{name} must be a positive number of seconds"
+            )))
+        };
+        let mut retry = RetryConfig::default();
+        if let Some(max_retries) = max_retries {
+            retry.max_retries = max_retries;
+        }
+        if let Some(retry_backoff) = retry_backoff {
+            let init_backoff = seconds("retry_backoff", retry_backoff)?;
+            retry.backoff = BackoffConfig {
+                init_backoff,
+                max_backoff: retry.backoff.max_backoff.max(init_backoff),
+                ..retry.backoff
+            };
+        }
+        if let Some(retry_timeout) = retry_timeout {
+            retry.retry_timeout = seconds("retry_timeout", retry_timeout)?;
+        }
+        let timeout = timeout
+            .map(|timeout| seconds("timeout", timeout))
+            .transpose()?
+            .map(|timeout| format!("{}ms", timeout.as_millis().max(1)));
+        Ok(Self { retry, timeout })
+    }
+}
+
 #[pyclass(name = "LocalFileSystem", module = "datafusion.store", subclass)]
 #[derive(Debug, Clone)]
 pub struct PyLocalFileSystemContext {
@@ -68,7 +121,7 @@ pub struct PyMicrosoftAzureContext {
 #[pymethods]
 impl PyMicrosoftAzureContext {
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (container_name, account=None, access_key=None, bearer_token=None, client_id=None, client_secret=None, tenant_id=None, sas_query_pairs=None, use_emulator=None, allow_http=None))]
+    #[pyo3(signature = (container_name, account=None, access_key=None, bearer_token=None, client_id=None, client_secret=None, tenant_id=None, sas_query_pairs=None, use_emulator=None, allow_http=None, max_retries=None, retry_backoff=None, retry_timeout=None, timeout=None))]
     #[new]
     fn new(
         container_name: String,
@@ -81,8 +134,20 @@ impl PyMicrosoftAzureContext {
         sas_query_pairs: Option<Vec<(String, String)>>,
         use_emulator: Option<bool>,
         allow_http: Option<bool>,
-    ) -> Self {
-        let mut builder = MicrosoftAzureBuilder::from_env().with_container_name(&container_name);
+        max_retries: Option<usize>,
+        retry_backoff: Option<f64>,
+        retry_timeout: Option<f64>,
+        timeout: Option<f64>,
+    ) -> PyResult<Self> {
+        let retry = RetryOptions::new(max_retries, retry_backoff, retry_timeout, timeout)?;
+        let mut builder = MicrosoftAzureBuilder::from_env()
+            .with_container_name(&container_name)
+            .with_retry(retry.retry);
+
+        if let Some(timeout) = retry.timeout {
+            builder =
+                builder.with_config(AzureConfigKey::Client(ClientConfigKey::Timeout), timeout);
+        }
 
         if let Some(account) = account {
             builder = builder.with_account(account);
@@ -119,14 +184,14 @@ impl PyMicrosoftAzureContext {
             builder = builder.with_allow_http(allow_http);
         }
 
-        Self {
+        Ok(Self {
             inner: Arc::new(
                 builder
                     .build()
                     .expect("Could not create Azure Storage context"), //TODO: change these to PyErr
             ),
             container_name,
-        }
+        })
     }
 }
 
@@ -140,23 +205,38 @@ pub struct PyGoogleCloudContext {
 #[pymethods]
 impl PyGoogleCloudContext {
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (bucket_name, service_account_path=None))]
+    #[pyo3(signature = (bucket_name, service_account_path=None, max_retries=None, retry_backoff=None, retry_timeout=None, timeout=None))]
     #[new]
-    fn new(bucket_name: String, service_account_path: Option<String>) -> Self {
-        let mut builder = GoogleCloudStorageBuilder::new().with_bucket_name(&bucket_name);
+    fn new(
+        bucket_name: String,
+        service_account_path: Option<String>,
+        max_retries: Option<usize>,
+        retry_backoff: Option<f64>,
+        retry_timeout: Option<f64>,
+        timeout: Option<f64>,
+    ) -> PyResult<Self> {
+        let retry = RetryOptions::new(max_retries, retry_backoff, retry_timeout, timeout)?;
+        let mut builder = GoogleCloudStorageBuilder::new()
+            .with_bucket_name(&bucket_name)
+            .with_retry(retry.retry);
+
+        if let Some(timeout) = retry.timeout {
+            builder =
+                builder.with_config(GoogleConfigKey::Client(ClientConfigKey::Timeout), timeout);
+        }
 
         if let Some(credential_path) = service_account_path {
             builder = builder.with_service_account_path(credential_path);
         }
 
-        Self {
+        Ok(Self {
             inner: Arc::new(
                 builder
                     .build()
                     .expect("Could not create Google Cloud Storage"),
             ),
             bucket_name,
-        }
+        })
     }
 }
 
@@ -170,7 +250,7 @@ pub struct PyAmazonS3Context {
 #[pymethods]
 impl PyAmazonS3Context {
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (bucket_name, region=None, access_key_id=None, secret_access_key=None, endpoint=None, allow_http=false, imdsv1_fallback=false))]
+    #[pyo3(signature = (bucket_name, region=None, access_key_id=None, secret_access_key=None, endpoint=None, allow_http=false, imdsv1_fallback=false, max_retries=None, retry_backoff=None, retry_timeout=None, timeout=None))]
     #[new]
     fn new(
         bucket_name: String,
@@ -178,13 +258,22 @@ impl PyAmazonS3Context {
         access_key_id: Option<String>,
         secret_access_key: Option<String>,
         endpoint: Option<String>,
-        //retry_config: RetryConfig,
         allow_http: bool,
         imdsv1_fallback: bool,
-    ) -> Self {
+        max_retries: Option<usize>,
+        retry_backoff: Option<f64>,
+        retry_timeout: Option<f64>,
+        timeout: Option<f64>,
+    ) -> PyResult<Self> {
+        let retry = RetryOptions::new(max_retries, retry_backoff, retry_timeout, timeout)?;
         // start w/ the options that come directly from the environment
         let mut builder = AmazonS3Builder::from_env();
 
+        if let Some(timeout) = retry.timeout {
+            builder =
+                builder.with_config(AmazonS3ConfigKey::Client(ClientConfigKey::Timeout), timeout);
+        }
+
         if let Some(region) = region {
             builder = builder.with_region(region);
         }
@@ -207,15 +296,15 @@ impl PyAmazonS3Context {
 
         let store = builder
             .with_bucket_name(bucket_name.clone())
-            //.with_retry_config(retry_config) #TODO: add later
+            .with_retry(retry.retry)
             .with_allow_http(allow_http)
             .build()
             .expect("failed to build AmazonS3");
 
-        Self {
+        Ok(Self {
             inner: Arc::new(store),
             bucket_name,
-        }
+        })
     }
 }
 
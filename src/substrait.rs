@@ -15,33 +15,273 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use pyo3::{prelude::*, types::PyBytes};
+use std::collections::HashMap;
+
+use pyo3::{
+    basic::CompareOp,
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
 
 use crate::context::PySessionContext;
+use crate::dataframe::PyDataFrame;
 use crate::errors::{py_datafusion_err, DataFusionError};
 use crate::sql::logical::PyLogicalPlan;
 use crate::utils::wait_for_future;
 
+use datafusion::arrow::array::new_empty_array;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::error::{DataFusionError as InnerDataFusionError, Result as DFResult};
+use datafusion::execution::context::SessionContext;
+use datafusion::scalar::ScalarValue;
+use datafusion_common::tree_node::{TreeNode, VisitRecursion};
+use datafusion_common::{DFSchema, OwnedTableReference};
+use datafusion_expr::{
+    lit, Cast, DmlStatement, Expr, ExprSchemable, LogicalPlan, LogicalPlanBuilder, WriteOp as DmlOp,
+};
 use datafusion_substrait::logical_plan::{consumer, producer};
 use datafusion_substrait::serializer;
-use datafusion_substrait::substrait::proto::Plan;
-use prost::Message;
+use datafusion_substrait::substrait::proto::expression::literal::{LiteralType, Struct};
+use datafusion_substrait::substrait::proto::expression::{
+    field_reference, reference_segment, FieldReference, Literal, ReferenceSegment, RexType,
+};
+use datafusion_substrait::substrait::proto::extensions::simple_extension_declaration::{
+    ExtensionFunction, MappingType,
+};
+use datafusion_substrait::substrait::proto::extensions::{
+    SimpleExtensionDeclaration, SimpleExtensionUri,
+};
+use datafusion_substrait::substrait::proto::function_argument::ArgType;
+use datafusion_substrait::substrait::proto::r#type::{self, Kind};
+use datafusion_substrait::substrait::proto::read_rel::{NamedTable, ReadType};
+use datafusion_substrait::substrait::proto::rel::RelType;
+use datafusion_substrait::substrait::proto::write_rel::{self, WriteType};
+use datafusion_substrait::substrait::proto::{
+    plan_rel, Expression, FilterRel, FunctionArgument, NamedObjectWrite, NamedStruct, Plan,
+    ProjectRel, ReadRel, Rel, RelRoot, Type, WriteRel,
+};
+use prost::{EncodeError, Message};
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[pyclass(name = "plan", module = "datafusion.substrait", subclass)]
 #[derive(Debug, Clone)]
 pub(crate) struct PyPlan {
     pub(crate) plan: Plan,
+    /// The write of the relation of `plan` into a table, its input being left unset. The
+    /// Substrait version of these bindings has no `WriteRel` variant of `Rel` yet, so the write
+    /// is kept apart from the plan
+    pub(crate) write: Option<WriteRel>,
+}
+
+impl PyPlan {
+    /// Decodes the write of a plan from a newer Substrait version, where `WriteRel` is a
+    /// variant of `Rel`, and moves its input into the plan in place of the dropped relation
+    fn with_write(mut plan: Plan, proto_bytes: &[u8]) -> PyPlan {
+        let envelope = PlanWrites::decode(proto_bytes).unwrap_or_default();
+        let write = match (plan.relations.as_mut_slice(), envelope.relations.as_slice()) {
+            ([relation], [envelope]) => match (&mut relation.rel_type, envelope) {
+                (
+                    Some(plan_rel::RelType::Rel(rel)),
+                    PlanRelWrite {
+                        rel: Some(write), ..
+                    },
+                )
+                | (
+                    Some(plan_rel::RelType::Root(RelRoot {
+                        input: Some(rel), ..
+                    })),
+                    PlanRelWrite {
+                        root:
+                            Some(RelRootWrite {
+                                input: Some(write), ..
+                            }),
+                        ..
+                    },
+                ) if rel.rel_type.is_none() => write.write.clone().map(|mut write| {
+                    if let Some(input) = write.input.take() {
+                        *rel = input;
+                    }
+                    write
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+        PyPlan { plan, write }
+    }
+
+    /// Encodes the plan, with its write as newer Substrait versions do
+    fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut proto_bytes = Vec::<u8>::new();
+        let write = match &self.write {
+            Some(write) => write,
+            None => {
+                self.plan.encode(&mut proto_bytes)?;
+                return Ok(proto_bytes);
+            }
+        };
+        // the relation is encoded as the input of a `WriteRel` variant of `Rel`, after the
+        // other fields of the plan
+        let mut plan = self.plan.clone();
+        let mut write = write.clone();
+        let relation = match plan.relations.pop().and_then(|r| r.rel_type) {
+            Some(plan_rel::RelType::Rel(rel)) => {
+                write.input = Some(rel);
+                PlanRelWrite {
+                    rel: Some(RelWrite { write: Some(write) }),
+                    root: None,
+                }
+            }
+            Some(plan_rel::RelType::Root(root)) => {
+                write.input = root.input;
+                PlanRelWrite {
+                    rel: None,
+                    root: Some(RelRootWrite {
+                        input: Some(RelWrite { write: Some(write) }),
+                        names: root.names,
+                    }),
+                }
+            }
+            None => PlanRelWrite::default(),
+        };
+        plan.encode(&mut proto_bytes)?;
+        PlanWrites {
+            relations: vec![relation],
+        }
+        .encode(&mut proto_bytes)?;
+        Ok(proto_bytes)
+    }
+}
+
+/// The path to the write of a single relation plan, decoding just the fields leading to it
+#[derive(Clone, PartialEq, Message)]
+struct PlanWrites {
+    #[prost(message, repeated, tag = "3")]
+    relations: Vec<PlanRelWrite>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct PlanRelWrite {
+    #[prost(message, optional, tag = "1")]
+    rel: Option<RelWrite>,
+    #[prost(message, optional, tag = "2")]
+    root: Option<RelRootWrite>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct RelRootWrite {
+    #[prost(message, optional, tag = "1")]
+    input: Option<RelWrite>,
+    #[prost(string, repeated, tag = "2")]
+    names: Vec<String>,
+}
+
+/// A `Rel` of newer Substrait versions holding a `WriteRel`
+#[derive(Clone, PartialEq, Message)]
+struct RelWrite {
+    #[prost(message, optional, tag = "19")]
+    write: Option<WriteRel>,
 }
 
 #[pymethods]
 impl PyPlan {
     fn encode(&self, py: Python) -> PyResult<PyObject> {
-        let mut proto_bytes = Vec::<u8>::new();
-        self.plan
-            .encode(&mut proto_bytes)
-            .map_err(DataFusionError::EncodeError)?;
+        let proto_bytes = self.to_bytes().map_err(DataFusionError::EncodeError)?;
         Ok(PyBytes::new(py, &proto_bytes).into())
     }
+
+    fn __richcmp__(&self, other: PyPlan, op: CompareOp, py: Python) -> PyObject {
+        let equal = self.plan == other.plan && self.write == other.write;
+        match op {
+            CompareOp::Eq => equal.into_py(py),
+            CompareOp::Ne => (!equal).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    /// The name of the table the plan writes to, or `None` for a query
+    fn write_table(&self) -> Option<String> {
+        match self.write.as_ref()?.write_type.as_ref()? {
+            WriteType::NamedTable(table) => Some(table.names.join(".")),
+            WriteType::ExtensionTable(_) => None,
+        }
+    }
+
+    /// The URIs of the extensions declared by this plan
+    fn extension_uris(&self) -> Vec<String> {
+        self.plan
+            .extension_uris
+            .iter()
+            .map(|uri| uri.uri.clone())
+            .collect()
+    }
+
+    /// The functions declared by this plan, as dicts of the `anchor` expressions refer to them
+    /// by, their `name` and the `uri` of the extension that defines them, if it is declared
+    fn referenced_functions(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let uris: HashMap<u32, &str> = self
+            .plan
+            .extension_uris
+            .iter()
+            .map(|uri| (uri.extension_uri_anchor, uri.uri.as_str()))
+            .collect();
+        let mut functions = vec![];
+        for extension in &self.plan.extensions {
+            if let Some(MappingType::ExtensionFunction(function)) = &extension.mapping_type {
+                let dict = PyDict::new(py);
+                dict.set_item("anchor", function.function_anchor)?;
+                dict.set_item("name", &function.name)?;
+                dict.set_item("uri", uris.get(&function.extension_uri_reference))?;
+                functions.push(dict.into());
+            }
+        }
+        Ok(functions)
+    }
+
+    /// Describes where this plan differs from `other`, one difference per line. The result is
+    /// empty when the plans are equal
+    fn diff(&self, other: PyPlan) -> String {
+        let mut differences = vec![];
+        diff_plans(&self.plan, &other.plan, &mut differences);
+        differences.join("\n")
+    }
+
+    /// Returns this plan with every read of the named table `table_name` replaced by the root
+    /// relation of `subplan`. The extensions `subplan` declares are merged into those of this
+    /// plan, its anchors being remapped where they collide
+    fn replace_read(&self, table_name: &str, subplan: PyPlan) -> PyResult<PyPlan> {
+        if subplan.write.is_some() {
+            return Err(PyValueError::new_err(
+                "Can't replace a read with a plan that writes to a table",
+            ));
+        }
+        let plan = replace_read(&self.plan, table_name, subplan.plan).map_err(py_datafusion_err)?;
+        Ok(PyPlan {
+            plan,
+            write: self.write.clone(),
+        })
+    }
+
+    /// Converts this plan to a DataFusion logical plan against `ctx` and returns its indented
+    /// rendering, with the schema of every node when `with_schema` is set, without executing it
+    #[pyo3(signature = (ctx, with_schema=false))]
+    fn to_datafusion_explain(
+        &self,
+        ctx: &mut PySessionContext,
+        with_schema: bool,
+        py: Python,
+    ) -> PyResult<String> {
+        let plan = PySubstraitConsumer::from_substrait_plan(ctx, self.clone(), None, py)?.plan();
+        Ok(match with_schema {
+            true => plan.display_indent_schema().to_string(),
+            false => plan.display_indent().to_string(),
+        })
+    }
 }
 
 impl From<PyPlan> for Plan {
@@ -52,7 +292,7 @@ impl From<PyPlan> for Plan {
 
 impl From<Plan> for PyPlan {
     fn from(plan: Plan) -> PyPlan {
-        PyPlan { plan }
+        PyPlan { plan, write: None }
     }
 }
 
@@ -74,13 +314,16 @@ impl PySubstraitSerializer {
 
     #[staticmethod]
     pub fn serialize_to_plan(sql: &str, ctx: PySessionContext, py: Python) -> PyResult<PyPlan> {
-        match PySubstraitSerializer::serialize_bytes(sql, ctx, py) {
-            Ok(proto_bytes) => {
-                let proto_bytes: &PyBytes = proto_bytes.as_ref(py).downcast().unwrap();
-                PySubstraitSerializer::deserialize_bytes(proto_bytes.as_bytes().to_vec(), py)
-            }
-            Err(e) => Err(py_datafusion_err(e)),
-        }
+        PySubstraitSerializer::sql_to_plan(sql, &ctx, py)
+    }
+
+    /// Produce the Substrait plan of a SQL query directly from its optimized logical plan,
+    /// without encoding it to bytes and decoding it again
+    #[staticmethod]
+    pub fn sql_to_plan(sql: &str, ctx: &PySessionContext, py: Python) -> PyResult<PyPlan> {
+        let df = wait_for_future(py, ctx.ctx.sql(sql)).map_err(DataFusionError::from)?;
+        let plan = df.into_optimized_plan().map_err(DataFusionError::from)?;
+        Ok(to_substrait_plan(&plan, &ctx.ctx)?)
     }
 
     #[staticmethod]
@@ -90,18 +333,26 @@ impl PySubstraitSerializer {
         Ok(PyBytes::new(py, &proto_bytes).into())
     }
 
+    /// Serialize the logical plan of a DataFrame, rather than a SQL query, to Substrait bytes
+    #[staticmethod]
+    pub fn serialize_df(df: &PyDataFrame, py: Python) -> PyResult<PyObject> {
+        let (state, plan) = df.df.as_ref().clone().into_parts();
+        let plan = state.optimize(&plan)?;
+        let ctx = SessionContext::with_state(state);
+        to_substrait_plan(&plan, &ctx)?.encode(py)
+    }
+
     #[staticmethod]
     pub fn deserialize(path: &str, py: Python) -> PyResult<PyPlan> {
-        let plan =
-            wait_for_future(py, serializer::deserialize(path)).map_err(DataFusionError::from)?;
-        Ok(PyPlan { plan: *plan })
+        let proto_bytes = std::fs::read(path).map_err(InnerDataFusionError::from)?;
+        PySubstraitSerializer::deserialize_bytes(proto_bytes, py)
     }
 
     #[staticmethod]
     pub fn deserialize_bytes(proto_bytes: Vec<u8>, py: Python) -> PyResult<PyPlan> {
-        let plan = wait_for_future(py, serializer::deserialize_bytes(proto_bytes))
+        let plan = wait_for_future(py, serializer::deserialize_bytes(proto_bytes.clone()))
             .map_err(DataFusionError::from)?;
-        Ok(PyPlan { plan: *plan })
+        Ok(PyPlan::with_write(*plan, &proto_bytes))
     }
 }
 
@@ -111,13 +362,23 @@ pub(crate) struct PySubstraitProducer;
 
 #[pymethods]
 impl PySubstraitProducer {
-    /// Convert DataFusion LogicalPlan to Substrait Plan
+    /// Convert DataFusion LogicalPlan to Substrait Plan, `nullability` setting how the
+    /// nullability of DataFusion types is mapped to that of the Substrait types
     #[staticmethod]
-    pub fn to_substrait_plan(plan: PyLogicalPlan, ctx: &PySessionContext) -> PyResult<PyPlan> {
-        match producer::to_substrait_plan(&plan.plan, &ctx.ctx) {
-            Ok(plan) => Ok(PyPlan { plan: *plan }),
-            Err(e) => Err(py_datafusion_err(e)),
+    #[pyo3(signature = (plan, ctx, nullability=None))]
+    pub fn to_substrait_plan(
+        plan: PyLogicalPlan,
+        ctx: &PySessionContext,
+        nullability: Option<&str>,
+    ) -> PyResult<PyPlan> {
+        let nullability = nullability.map(Nullability::parse).transpose()?;
+        let mut substrait_plan =
+            to_substrait_plan(&plan.plan, &ctx.ctx).map_err(py_datafusion_err)?;
+        if let Some(nullability) = nullability {
+            set_nullability(&mut substrait_plan.plan, &plan.plan, nullability)
+                .map_err(py_datafusion_err)?;
         }
+        Ok(substrait_plan)
     }
 }
 
@@ -127,19 +388,1339 @@ pub(crate) struct PySubstraitConsumer;
 
 #[pymethods]
 impl PySubstraitConsumer {
-    /// Convert Substrait Plan to DataFusion DataFrame
+    /// Convert Substrait Plan to DataFusion DataFrame. Named tables found in `table_map` are
+    /// read from the table they are mapped to instead
     #[staticmethod]
+    #[pyo3(signature = (ctx, plan, table_map=None))]
     pub fn from_substrait_plan(
         ctx: &mut PySessionContext,
         plan: PyPlan,
+        table_map: Option<HashMap<String, String>>,
         py: Python,
     ) -> PyResult<PyLogicalPlan> {
-        let result = consumer::from_substrait_plan(&mut ctx.ctx, &plan.plan);
-        let logical_plan = wait_for_future(py, result).map_err(DataFusionError::from)?;
+        let PyPlan { mut plan, write } = plan;
+        if let Some(table_map) = table_map {
+            visit_reads(&mut plan, &mut |read| {
+                if let Some(ReadType::NamedTable(table)) = &mut read.read_type {
+                    if let Some(target) = table_map.get(&table.names.join(".")) {
+                        table.names = target.split('.').map(String::from).collect();
+                    }
+                }
+            });
+        }
+
+        // the consumer only reads named tables, so virtual tables are registered as memory
+        // tables for the duration of the conversion and read by name
+        let mut virtual_tables = vec![];
+        visit_reads(&mut plan, &mut |read| {
+            if let Some(ReadType::VirtualTable(table)) = &read.read_type {
+                let name = format!("__substrait_virtual_table_{}", Uuid::new_v4().simple());
+                let schema = read.base_schema.clone().unwrap_or_default();
+                virtual_tables.push((name.clone(), schema, table.values.clone()));
+                read.read_type = Some(ReadType::NamedTable(NamedTable {
+                    names: vec![name],
+                    advanced_extension: None,
+                }));
+            }
+        });
+        let result = wait_for_future(py, async {
+            for (name, schema, rows) in &virtual_tables {
+                let table = virtual_table(schema, rows).await?;
+                ctx.ctx.register_table(name.as_str(), Arc::new(table))?;
+            }
+            let plan = consumer::from_substrait_plan(&mut ctx.ctx, &plan).await?;
+            match &write {
+                Some(write) => write_plan(&ctx.ctx, write, plan).await,
+                None => Ok(plan),
+            }
+        });
+        for (name, _, _) in &virtual_tables {
+            ctx.ctx.deregister_table(name.as_str())?;
+        }
+        let logical_plan = result.map_err(DataFusionError::from)?;
         Ok(PyLogicalPlan::new(logical_plan))
     }
 }
 
+/// Produces the Substrait plan of `plan`, an insert being produced as its input and the write
+/// of the input to the table
+fn to_substrait_plan(plan: &LogicalPlan, ctx: &SessionContext) -> DFResult<PyPlan> {
+    let (plan, write) = match plan {
+        LogicalPlan::Dml(DmlStatement {
+            table_name,
+            op: DmlOp::InsertInto,
+            input,
+            ..
+        }) => {
+            let names = match table_name {
+                OwnedTableReference::Bare { table } => vec![table.to_string()],
+                OwnedTableReference::Partial { schema, table } => {
+                    vec![schema.to_string(), table.to_string()]
+                }
+                OwnedTableReference::Full {
+                    catalog,
+                    schema,
+                    table,
+                } => vec![catalog.to_string(), schema.to_string(), table.to_string()],
+            };
+            let write = WriteRel {
+                table_schema: None,
+                op: write_rel::WriteOp::Insert as i32,
+                input: None,
+                output: write_rel::OutputMode::ModifiedTuples as i32,
+                write_type: Some(WriteType::NamedTable(NamedObjectWrite {
+                    names,
+                    advanced_extension: None,
+                })),
+            };
+            (input.as_ref(), Some(write))
+        }
+        plan => (plan, None),
+    };
+    Ok(PyPlan {
+        plan: *producer::to_substrait_plan(plan, ctx)?,
+        write,
+    })
+}
+
+/// How the nullability of DataFusion types is mapped to the nullability of the Substrait types
+/// of a produced plan
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Nullability {
+    /// Every type is nullable
+    Nullable,
+    /// Types are required where DataFusion knows their values are never null
+    Strict,
+}
+
+impl Nullability {
+    fn parse(nullability: &str) -> PyResult<Self> {
+        match nullability {
+            "nullable" => Ok(Self::Nullable),
+            "strict" => Ok(Self::Strict),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported nullability {nullability}, expected one of: nullable, strict"
+            ))),
+        }
+    }
+
+    fn of(nullable: bool) -> i32 {
+        match nullable {
+            true => r#type::Nullability::Nullable as i32,
+            false => r#type::Nullability::Required as i32,
+        }
+    }
+}
+
+/// Sets the nullability of the types of `plan`, produced from `df_plan`. The schemas of the
+/// tables it reads are declared with the nullability of their columns, and literals and casts
+/// are nullable unless they are of a literal that isn't null in strict mode
+fn set_nullability(
+    plan: &mut Plan,
+    df_plan: &LogicalPlan,
+    nullability: Nullability,
+) -> DFResult<()> {
+    let mut schemas = HashMap::new();
+    df_plan.apply(&mut |node| {
+        if let LogicalPlan::TableScan(scan) = node {
+            schemas.insert(scan.table_name.to_vec(), scan.source.schema());
+        }
+        Ok(VisitRecursion::Continue)
+    })?;
+
+    let mut result = Ok(());
+    visit_reads(plan, &mut |read| {
+        let schema = match &read.read_type {
+            Some(ReadType::NamedTable(table)) => schemas.get(&table.names),
+            _ => None,
+        };
+        if let (Some(schema), Some(base_schema)) = (schema, read.base_schema.as_mut()) {
+            match named_struct(schema.fields(), nullability) {
+                Ok(named_struct) => *base_schema = named_struct,
+                Err(e) => result = Err(e),
+            }
+        }
+    });
+    result?;
+
+    visit_rels(plan, &mut |rel| {
+        for expression in rel_expressions(rel) {
+            visit_expression(
+                expression,
+                &mut |expression| match &mut expression.rex_type {
+                    Some(RexType::Literal(literal)) => {
+                        literal.nullable =
+                            nullability == Nullability::Nullable || is_null_literal(literal)
+                    }
+                    Some(RexType::Cast(cast)) => {
+                        let required = nullability == Nullability::Strict
+                            && match cast.input.as_deref() {
+                                Some(Expression {
+                                    rex_type: Some(RexType::Literal(literal)),
+                                }) => !is_null_literal(literal),
+                                _ => false,
+                            };
+                        if let Some(cast_type) = cast.r#type.as_mut() {
+                            set_type_nullability(cast_type, !required);
+                        }
+                    }
+                    _ => {}
+                },
+            )
+        }
+    });
+    Ok(())
+}
+
+fn is_null_literal(literal: &Literal) -> bool {
+    matches!(literal.literal_type, Some(LiteralType::Null(_)))
+}
+
+/// The Substrait schema of `fields`, the names of the fields of nested structs following the
+/// name of their struct
+fn named_struct(fields: &Fields, nullability: Nullability) -> DFResult<NamedStruct> {
+    let mut names = vec![];
+    let types = fields
+        .iter()
+        .map(|field| {
+            field_names(field, &mut names);
+            field_type(field, nullability)
+        })
+        .collect::<DFResult<_>>()?;
+    Ok(NamedStruct {
+        names,
+        r#struct: Some(r#type::Struct {
+            types,
+            type_variation_reference: 0,
+            nullability: r#type::Nullability::Required as i32,
+        }),
+    })
+}
+
+fn field_names(field: &Field, names: &mut Vec<String>) {
+    names.push(field.name().clone());
+    if let DataType::Struct(fields) = field.data_type() {
+        fields.iter().for_each(|field| field_names(field, names));
+    }
+}
+
+/// The Substrait type of `field`, which is required if the field and `nullability` are strict
+fn field_type(field: &Field, nullability: Nullability) -> DFResult<Type> {
+    // the producer maps a data type to a Substrait type only as the type of a cast
+    let cast = Expr::Cast(Cast::new(Box::new(lit(true)), field.data_type().clone()));
+    let expression = producer::to_substrait_rex(
+        &cast,
+        &Arc::new(DFSchema::empty()),
+        0,
+        &mut (vec![], HashMap::new()),
+    )
+    .map_err(|e| {
+        InnerDataFusionError::NotImplemented(format!(
+            "Can't declare the type of column {}: {e}",
+            field.name()
+        ))
+    })?;
+    let mut field_type = match expression.rex_type {
+        Some(RexType::Cast(cast)) => cast.r#type.unwrap_or_default(),
+        _ => Type::default(),
+    };
+    set_field_nullability(&mut field_type, field, nullability);
+    Ok(field_type)
+}
+
+fn set_field_nullability(field_type: &mut Type, field: &Field, nullability: Nullability) {
+    let nullable = nullability == Nullability::Nullable || field.is_nullable();
+    if let Some(type_nullability) = type_nullability(field_type) {
+        *type_nullability = Nullability::of(nullable);
+    }
+    match (&mut field_type.kind, field.data_type()) {
+        (Some(Kind::List(list)), DataType::List(item) | DataType::LargeList(item)) => {
+            if let Some(item_type) = list.r#type.as_deref_mut() {
+                set_field_nullability(item_type, item, nullability)
+            }
+        }
+        (Some(Kind::Struct(r#struct)), DataType::Struct(fields)) => {
+            for (field_type, field) in r#struct.types.iter_mut().zip(fields.iter()) {
+                set_field_nullability(field_type, field, nullability)
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Sets the nullability of `ty`, the types nested in it being nullable
+fn set_type_nullability(ty: &mut Type, nullable: bool) {
+    if let Some(type_nullability) = type_nullability(ty) {
+        *type_nullability = Nullability::of(nullable);
+    }
+    match &mut ty.kind {
+        Some(Kind::List(list)) => list
+            .r#type
+            .iter_mut()
+            .for_each(|ty| set_type_nullability(ty, true)),
+        Some(Kind::Struct(r#struct)) => r#struct
+            .types
+            .iter_mut()
+            .for_each(|ty| set_type_nullability(ty, true)),
+        Some(Kind::Map(map)) => map
+            .key
+            .iter_mut()
+            .chain(map.value.iter_mut())
+            .for_each(|ty| set_type_nullability(ty, true)),
+        _ => {}
+    }
+}
+
+fn type_nullability(ty: &mut Type) -> Option<&mut i32> {
+    match ty.kind.as_mut()? {
+        Kind::Bool(t) => Some(&mut t.nullability),
+        Kind::I8(t) => Some(&mut t.nullability),
+        Kind::I16(t) => Some(&mut t.nullability),
+        Kind::I32(t) => Some(&mut t.nullability),
+        Kind::I64(t) => Some(&mut t.nullability),
+        Kind::Fp32(t) => Some(&mut t.nullability),
+        Kind::Fp64(t) => Some(&mut t.nullability),
+        Kind::String(t) => Some(&mut t.nullability),
+        Kind::Binary(t) => Some(&mut t.nullability),
+        Kind::Timestamp(t) => Some(&mut t.nullability),
+        Kind::Date(t) => Some(&mut t.nullability),
+        Kind::Time(t) => Some(&mut t.nullability),
+        Kind::IntervalYear(t) => Some(&mut t.nullability),
+        Kind::IntervalDay(t) => Some(&mut t.nullability),
+        Kind::TimestampTz(t) => Some(&mut t.nullability),
+        Kind::Uuid(t) => Some(&mut t.nullability),
+        Kind::FixedChar(t) => Some(&mut t.nullability),
+        Kind::Varchar(t) => Some(&mut t.nullability),
+        Kind::FixedBinary(t) => Some(&mut t.nullability),
+        Kind::Decimal(t) => Some(&mut t.nullability),
+        Kind::Struct(t) => Some(&mut t.nullability),
+        Kind::List(t) => Some(&mut t.nullability),
+        Kind::Map(t) => Some(&mut t.nullability),
+        Kind::UserDefined(t) => Some(&mut t.nullability),
+        Kind::UserDefinedTypeReference(_) => None,
+    }
+}
+
+/// Converts `plan` to Substrait and back against `ctx`, to check what the conversion changes
+pub(crate) async fn round_trip(plan: &LogicalPlan, ctx: &SessionContext) -> DFResult<LogicalPlan> {
+    let PyPlan { plan, write } = to_substrait_plan(plan, ctx)?;
+    let consumed = consumer::from_substrait_plan(&mut ctx.clone(), &plan).await?;
+    match &write {
+        Some(write) => write_plan(ctx, write, consumed).await,
+        None => Ok(consumed),
+    }
+}
+
+/// Wraps the consumed `input` of a write into an insert into the table it targets, the columns
+/// of the input being matched to those of the table by position
+async fn write_plan(
+    ctx: &SessionContext,
+    write: &WriteRel,
+    input: LogicalPlan,
+) -> DFResult<LogicalPlan> {
+    let op = write_rel::WriteOp::from_i32(write.op).unwrap_or(write_rel::WriteOp::Unspecified);
+    if op != write_rel::WriteOp::Insert {
+        return Err(InnerDataFusionError::NotImplemented(format!(
+            "Substrait write operation {} is not supported, only WRITE_OP_INSERT is",
+            op.as_str_name()
+        )));
+    }
+    let names = match &write.write_type {
+        Some(WriteType::NamedTable(table)) => &table.names,
+        _ => {
+            return Err(InnerDataFusionError::NotImplemented(
+                "Substrait writes are only supported into named tables".to_string(),
+            ))
+        }
+    };
+    let table_name = OwnedTableReference::from(names.join("."));
+    let table = ctx.table_provider(table_name.clone()).await?;
+    let table_schema = table.schema();
+    let columns = input.schema().fields();
+    if columns.len() != table_schema.fields().len() {
+        return Err(InnerDataFusionError::Plan(format!(
+            "The write has {} columns, table {table_name} has {}",
+            columns.len(),
+            table_schema.fields().len()
+        )));
+    }
+    let exprs = columns
+        .iter()
+        .zip(table_schema.fields())
+        .map(|(column, field)| {
+            Expr::Column(column.qualified_column())
+                .cast_to(field.data_type(), input.schema())
+                .map(|expr| expr.alias(field.name()))
+        })
+        .collect::<DFResult<Vec<_>>>()?;
+    let input = LogicalPlanBuilder::from(input).project(exprs)?.build()?;
+    Ok(LogicalPlan::Dml(DmlStatement {
+        table_name: table_name.clone(),
+        table_schema: Arc::new(DFSchema::try_from_qualified_schema(
+            table_name,
+            &table_schema,
+        )?),
+        op: DmlOp::InsertInto,
+        input: Arc::new(input),
+    }))
+}
+
+/// Materializes the literal rows of a virtual table, whose columns are described by `schema`
+async fn virtual_table(schema: &NamedStruct, rows: &[Struct]) -> DFResult<MemTable> {
+    let types = schema
+        .r#struct
+        .as_ref()
+        .map(|s| s.types.as_slice())
+        .unwrap_or_default();
+    if types.len() != schema.names.len() {
+        return Err(InnerDataFusionError::Plan(
+            "Virtual tables with nested columns are not supported".to_string(),
+        ));
+    }
+
+    let mut fields = vec![];
+    for (name, data_type) in schema.names.iter().zip(types) {
+        // the consumer has no public type conversion, so convert a null of the type instead
+        let null = Literal {
+            nullable: true,
+            type_variation_reference: 0,
+            literal_type: Some(LiteralType::Null(data_type.clone())),
+        };
+        let data_type = literal_value(&null).await?.get_datatype();
+        fields.push(Field::new(name, data_type, true));
+    }
+
+    let mut columns = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let mut values = vec![];
+        for row in rows {
+            match row.fields.get(i) {
+                Some(literal) if row.fields.len() == fields.len() => {
+                    values.push(literal_value(literal).await?)
+                }
+                _ => {
+                    return Err(InnerDataFusionError::Plan(format!(
+                        "Virtual table row has {} values, expected {}",
+                        row.fields.len(),
+                        fields.len()
+                    )))
+                }
+            }
+        }
+        let column = if values.is_empty() {
+            new_empty_array(field.data_type())
+        } else {
+            cast(&ScalarValue::iter_to_array(values)?, field.data_type())?
+        };
+        columns.push(column);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    MemTable::try_new(schema, vec![vec![batch]])
+}
+
+async fn literal_value(literal: &Literal) -> DFResult<ScalarValue> {
+    let expression = Expression {
+        rex_type: Some(RexType::Literal(literal.clone())),
+    };
+    match consumer::from_substrait_rex(&expression, &DFSchema::empty(), &HashMap::new())
+        .await?
+        .as_ref()
+    {
+        Expr::Literal(value) => Ok(value.clone()),
+        expr => Err(InnerDataFusionError::Plan(format!(
+            "Expected a literal in a virtual table, got {expr}"
+        ))),
+    }
+}
+
+/// Replaces the reads of the named table `table_name` in `plan` by the root relation of
+/// `subplan`, projected and filtered like the replaced read
+fn replace_read(plan: &Plan, table_name: &str, subplan: Plan) -> DFResult<Plan> {
+    let mut plan = plan.clone();
+    let mut subrel = match subplan.relations.first().and_then(|r| r.rel_type.clone()) {
+        Some(plan_rel::RelType::Rel(rel))
+        | Some(plan_rel::RelType::Root(RelRoot {
+            input: Some(rel), ..
+        })) => rel,
+        _ => {
+            return Err(InnerDataFusionError::Plan(
+                "The subplan has no relation".to_string(),
+            ))
+        }
+    };
+
+    // the anchors of the subplan's extensions, mapped to those of the same extensions in the
+    // plan, or to new anchors following those of the plan
+    let mut uri_anchors = HashMap::new();
+    for uri in subplan.extension_uris {
+        let existing = plan.extension_uris.iter().find(|u| u.uri == uri.uri);
+        let anchor = match existing {
+            Some(existing) => existing.extension_uri_anchor,
+            None => {
+                let anchor = plan
+                    .extension_uris
+                    .iter()
+                    .map(|u| u.extension_uri_anchor + 1)
+                    .max()
+                    .unwrap_or(1);
+                plan.extension_uris.push(SimpleExtensionUri {
+                    extension_uri_anchor: anchor,
+                    uri: uri.uri,
+                });
+                anchor
+            }
+        };
+        uri_anchors.insert(uri.extension_uri_anchor, anchor);
+    }
+    let mut function_anchors = HashMap::new();
+    for extension in subplan.extensions {
+        let mut function = match extension.mapping_type {
+            Some(MappingType::ExtensionFunction(function)) => function,
+            Some(_) => {
+                return Err(InnerDataFusionError::NotImplemented(
+                    "Replacing a read with a plan declaring extension types is not supported"
+                        .to_string(),
+                ))
+            }
+            None => continue,
+        };
+        let uri = function.extension_uri_reference;
+        function.extension_uri_reference = uri_anchors.get(&uri).copied().unwrap_or(uri);
+        let mut max_anchor = 0;
+        let mut existing = None;
+        for extension in &plan.extensions {
+            if let Some(MappingType::ExtensionFunction(other)) = &extension.mapping_type {
+                max_anchor = max_anchor.max(other.function_anchor);
+                if other.name == function.name
+                    && other.extension_uri_reference == function.extension_uri_reference
+                {
+                    existing = Some(other.function_anchor);
+                }
+            }
+        }
+        let anchor = match existing {
+            Some(anchor) => anchor,
+            None => {
+                let anchor = max_anchor + 1;
+                plan.extensions.push(SimpleExtensionDeclaration {
+                    mapping_type: Some(MappingType::ExtensionFunction(ExtensionFunction {
+                        function_anchor: anchor,
+                        ..function.clone()
+                    })),
+                });
+                anchor
+            }
+        };
+        function_anchors.insert(function.function_anchor, anchor);
+    }
+    let remap = |reference: &mut u32| {
+        if let Some(anchor) = function_anchors.get(reference) {
+            *reference = *anchor;
+        }
+    };
+    visit_rel(&mut subrel, &mut |rel| {
+        if let Some(RelType::Aggregate(aggregate)) = &mut rel.rel_type {
+            for measure in aggregate.measures.iter_mut() {
+                if let Some(function) = measure.measure.as_mut() {
+                    remap(&mut function.function_reference);
+                }
+            }
+        }
+        for expression in rel_expressions(rel) {
+            visit_expression(
+                expression,
+                &mut |expression| match &mut expression.rex_type {
+                    Some(RexType::ScalarFunction(function)) => {
+                        remap(&mut function.function_reference)
+                    }
+                    Some(RexType::WindowFunction(function)) => {
+                        remap(&mut function.function_reference)
+                    }
+                    _ => {}
+                },
+            )
+        }
+    });
+
+    // the relations are visited after their inputs, so the subplan replacing a read isn't
+    // visited, which would never end if it reads the table it replaces
+    let mut replaced = false;
+    visit_rels(&mut plan, &mut |rel| {
+        let read = match &mut rel.rel_type {
+            Some(RelType::Read(read)) => read,
+            _ => return,
+        };
+        match &read.read_type {
+            Some(ReadType::NamedTable(table)) if table.names.join(".") == table_name => {}
+            _ => return,
+        }
+        let mut input = subrel.clone();
+        if let Some(condition) = read.filter.take() {
+            input = Rel {
+                rel_type: Some(RelType::Filter(Box::new(FilterRel {
+                    common: None,
+                    input: Some(Box::new(input)),
+                    condition: Some(condition),
+                    advanced_extension: None,
+                }))),
+            };
+        }
+        if let Some(projection) = read.projection.as_ref().and_then(|p| p.select.as_ref()) {
+            input = Rel {
+                rel_type: Some(RelType::Project(Box::new(ProjectRel {
+                    common: None,
+                    input: Some(Box::new(input)),
+                    expressions: projection
+                        .struct_items
+                        .iter()
+                        .map(|item| field_reference(item.field))
+                        .collect(),
+                    advanced_extension: None,
+                }))),
+            };
+        }
+        *rel = input;
+        replaced = true;
+    });
+    match replaced {
+        true => Ok(plan),
+        false => Err(InnerDataFusionError::Plan(format!(
+            "The plan doesn't read the table {table_name}"
+        ))),
+    }
+}
+
+/// A reference to the field at `index` of the input of an expression
+fn field_reference(index: i32) -> Expression {
+    Expression {
+        rex_type: Some(RexType::Selection(Box::new(FieldReference {
+            reference_type: Some(field_reference::ReferenceType::DirectReference(
+                ReferenceSegment {
+                    reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(
+                        reference_segment::StructField {
+                            field: index,
+                            child: None,
+                        },
+                    ))),
+                },
+            )),
+            root_type: Some(field_reference::RootType::RootReference(
+                field_reference::RootReference {},
+            )),
+        }))),
+    }
+}
+
+/// Calls `f` on every read relation of the plan
+fn visit_reads(plan: &mut Plan, f: &mut impl FnMut(&mut ReadRel)) {
+    visit_rels(plan, &mut |rel| {
+        if let Some(RelType::Read(read)) = &mut rel.rel_type {
+            f(read)
+        }
+    })
+}
+
+/// Calls `f` on every relation of the plan, after the relations it reads from. The inputs of a
+/// relation `f` replaces aren't visited.
+fn visit_rels(plan: &mut Plan, f: &mut impl FnMut(&mut Rel)) {
+    for relation in plan.relations.iter_mut() {
+        match &mut relation.rel_type {
+            Some(plan_rel::RelType::Rel(rel)) => visit_rel(rel, f),
+            Some(plan_rel::RelType::Root(root)) => {
+                if let Some(rel) = root.input.as_mut() {
+                    visit_rel(rel, f)
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+fn visit_rel(rel: &mut Rel, f: &mut impl FnMut(&mut Rel)) {
+    let mut visit = |input: &mut Option<Box<Rel>>| {
+        if let Some(rel) = input.as_mut() {
+            visit_rel(rel, f)
+        }
+    };
+    match &mut rel.rel_type {
+        Some(RelType::Filter(filter)) => visit(&mut filter.input),
+        Some(RelType::Fetch(fetch)) => visit(&mut fetch.input),
+        Some(RelType::Aggregate(aggregate)) => visit(&mut aggregate.input),
+        Some(RelType::Sort(sort)) => visit(&mut sort.input),
+        Some(RelType::Project(project)) => visit(&mut project.input),
+        Some(RelType::ExtensionSingle(extension)) => visit(&mut extension.input),
+        Some(RelType::Window(window)) => visit(&mut window.input),
+        Some(RelType::Exchange(exchange)) => visit(&mut exchange.input),
+        Some(RelType::Expand(expand)) => visit(&mut expand.input),
+        Some(RelType::Join(join)) => {
+            visit(&mut join.left);
+            visit(&mut join.right);
+        }
+        Some(RelType::Cross(cross)) => {
+            visit(&mut cross.left);
+            visit(&mut cross.right);
+        }
+        Some(RelType::HashJoin(join)) => {
+            visit(&mut join.left);
+            visit(&mut join.right);
+        }
+        Some(RelType::MergeJoin(join)) => {
+            visit(&mut join.left);
+            visit(&mut join.right);
+        }
+        Some(RelType::Set(set)) => set.inputs.iter_mut().for_each(|r| visit_rel(r, f)),
+        Some(RelType::ExtensionMulti(extension)) => {
+            extension.inputs.iter_mut().for_each(|r| visit_rel(r, f))
+        }
+        Some(RelType::Read(_)) | Some(RelType::ExtensionLeaf(_)) | None => {}
+    }
+    f(rel);
+}
+
+/// The expressions of a relation, not including those nested in them
+fn rel_expressions(rel: &mut Rel) -> Vec<&mut Expression> {
+    match &mut rel.rel_type {
+        Some(RelType::Read(read)) => [&mut read.filter, &mut read.best_effort_filter]
+            .into_iter()
+            .filter_map(|e| e.as_deref_mut())
+            .collect(),
+        Some(RelType::Filter(filter)) => filter.condition.as_deref_mut().into_iter().collect(),
+        Some(RelType::Project(project)) => project.expressions.iter_mut().collect(),
+        Some(RelType::Join(join)) => [&mut join.expression, &mut join.post_join_filter]
+            .into_iter()
+            .filter_map(|e| e.as_deref_mut())
+            .collect(),
+        Some(RelType::Sort(sort)) => sort
+            .sorts
+            .iter_mut()
+            .filter_map(|sort| sort.expr.as_mut())
+            .collect(),
+        Some(RelType::Aggregate(aggregate)) => {
+            let groupings = aggregate
+                .groupings
+                .iter_mut()
+                .flat_map(|grouping| grouping.grouping_expressions.iter_mut());
+            let measures = aggregate.measures.iter_mut().flat_map(|measure| {
+                let arguments = measure
+                    .measure
+                    .iter_mut()
+                    .flat_map(|function| function.arguments.iter_mut())
+                    .filter_map(|argument| match &mut argument.arg_type {
+                        Some(ArgType::Value(expression)) => Some(expression),
+                        _ => None,
+                    });
+                arguments.chain(measure.filter.as_mut())
+            });
+            groupings.chain(measures).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Calls `f` on `expression` and every expression nested in it, before the expressions it
+/// nests
+fn visit_expression(expression: &mut Expression, f: &mut impl FnMut(&mut Expression)) {
+    f(expression);
+    let mut arguments = |arguments: &mut Vec<FunctionArgument>| {
+        for argument in arguments.iter_mut() {
+            if let Some(ArgType::Value(expression)) = &mut argument.arg_type {
+                visit_expression(expression, f)
+            }
+        }
+    };
+    match &mut expression.rex_type {
+        Some(RexType::ScalarFunction(function)) => arguments(&mut function.arguments),
+        Some(RexType::WindowFunction(function)) => {
+            arguments(&mut function.arguments);
+            let partitions = function.partitions.iter_mut();
+            let sorts = function.sorts.iter_mut().filter_map(|s| s.expr.as_mut());
+            partitions.chain(sorts).for_each(|e| visit_expression(e, f))
+        }
+        Some(RexType::IfThen(if_then)) => {
+            let clauses = if_then
+                .ifs
+                .iter_mut()
+                .flat_map(|clause| clause.r#if.iter_mut().chain(clause.then.as_mut()));
+            clauses
+                .chain(if_then.r#else.as_deref_mut())
+                .for_each(|e| visit_expression(e, f))
+        }
+        Some(RexType::SwitchExpression(switch)) => {
+            let thens = switch
+                .ifs
+                .iter_mut()
+                .filter_map(|clause| clause.then.as_mut());
+            switch
+                .r#match
+                .as_deref_mut()
+                .into_iter()
+                .chain(thens)
+                .chain(switch.r#else.as_deref_mut())
+                .for_each(|e| visit_expression(e, f))
+        }
+        Some(RexType::SingularOrList(list)) => list
+            .value
+            .as_deref_mut()
+            .into_iter()
+            .chain(list.options.iter_mut())
+            .for_each(|e| visit_expression(e, f)),
+        Some(RexType::MultiOrList(list)) => {
+            let options = list.options.iter_mut().flat_map(|r| r.fields.iter_mut());
+            list.value
+                .iter_mut()
+                .chain(options)
+                .for_each(|e| visit_expression(e, f))
+        }
+        Some(RexType::Cast(cast)) => {
+            if let Some(input) = cast.input.as_deref_mut() {
+                visit_expression(input, f)
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects the differences between two plans, walking their relation trees and the
+/// expressions and types of the relations. The parts of a relation, an expression or a type
+/// that aren't walked are compared as a whole, so that equal plans are the only ones without
+/// differences
+fn diff_plans(left: &Plan, right: &Plan, differences: &mut Vec<String>) {
+    diff_lists(
+        "extension_uris",
+        &left.extension_uris,
+        &right.extension_uris,
+        differences,
+        |path, left, right, differences| {
+            diff_values(&format!("{path}.uri"), &left.uri, &right.uri, differences)
+        },
+    );
+    diff_lists(
+        "extensions",
+        &left.extensions,
+        &right.extensions,
+        differences,
+        |path, left, right, differences| match (&left.mapping_type, &right.mapping_type) {
+            (
+                Some(MappingType::ExtensionFunction(left)),
+                Some(MappingType::ExtensionFunction(right)),
+            ) => {
+                let anchor = format!("{path}.function_anchor");
+                diff_values(
+                    &anchor,
+                    &left.function_anchor,
+                    &right.function_anchor,
+                    differences,
+                );
+                diff_values(
+                    &format!("{path}.name"),
+                    &left.name,
+                    &right.name,
+                    differences,
+                );
+            }
+            (left, right) => diff_values(path, left, right, differences),
+        },
+    );
+    diff_lists(
+        "relations",
+        &left.relations,
+        &right.relations,
+        differences,
+        |path, left, right, differences| match (&left.rel_type, &right.rel_type) {
+            (Some(plan_rel::RelType::Root(left)), Some(plan_rel::RelType::Root(right))) => {
+                let names = format!("{path}.Root.names");
+                diff_values(&names, &left.names, &right.names, differences);
+                diff_optional(
+                    &format!("{path}.Root.input"),
+                    left.input.as_ref(),
+                    right.input.as_ref(),
+                    differences,
+                    diff_rel,
+                );
+            }
+            (Some(plan_rel::RelType::Rel(left)), Some(plan_rel::RelType::Rel(right))) => {
+                diff_rel(&format!("{path}.Rel"), left, right, differences)
+            }
+            (left, right) => diff_values(path, left, right, differences),
+        },
+    );
+}
+
+fn diff_rel(path: &str, left: &Rel, right: &Rel, differences: &mut Vec<String>) {
+    let (name, other_name) = (rel_name(left), rel_name(right));
+    if name != other_name {
+        differences.push(format!("{path}: {name} != {other_name}"));
+        return;
+    }
+    let path = format!("{path}.{name}");
+    let field = |field: &str| format!("{path}.{field}");
+    let found = differences.len();
+    match (&left.rel_type, &right.rel_type) {
+        (Some(RelType::Read(left)), Some(RelType::Read(right))) => {
+            diff_optional(
+                &field("base_schema"),
+                left.base_schema.as_ref(),
+                right.base_schema.as_ref(),
+                differences,
+                diff_named_struct,
+            );
+            diff_optional(
+                &field("filter"),
+                left.filter.as_deref(),
+                right.filter.as_deref(),
+                differences,
+                diff_expression,
+            );
+            if let (Some(ReadType::NamedTable(left)), Some(ReadType::NamedTable(right))) =
+                (&left.read_type, &right.read_type)
+            {
+                diff_values(&field("names"), &left.names, &right.names, differences);
+            }
+        }
+        (Some(RelType::Filter(left)), Some(RelType::Filter(right))) => {
+            diff_optional(
+                &field("condition"),
+                left.condition.as_deref(),
+                right.condition.as_deref(),
+                differences,
+                diff_expression,
+            );
+            diff_input(&field("input"), &left.input, &right.input, differences);
+        }
+        (Some(RelType::Project(left)), Some(RelType::Project(right))) => {
+            diff_lists(
+                &field("expressions"),
+                &left.expressions,
+                &right.expressions,
+                differences,
+                diff_expression,
+            );
+            diff_input(&field("input"), &left.input, &right.input, differences);
+        }
+        (Some(RelType::Fetch(left)), Some(RelType::Fetch(right))) => {
+            diff_values(&field("offset"), &left.offset, &right.offset, differences);
+            diff_values(&field("count"), &left.count, &right.count, differences);
+            diff_input(&field("input"), &left.input, &right.input, differences);
+        }
+        (Some(RelType::Sort(left)), Some(RelType::Sort(right))) => {
+            diff_lists(
+                &field("sorts"),
+                &left.sorts,
+                &right.sorts,
+                differences,
+                |path, left, right, differences| {
+                    let expr = format!("{path}.expr");
+                    let (left_expr, right_expr) = (left.expr.as_ref(), right.expr.as_ref());
+                    diff_optional(&expr, left_expr, right_expr, differences, diff_expression);
+                    let kind = format!("{path}.sort_kind");
+                    diff_values(&kind, &left.sort_kind, &right.sort_kind, differences);
+                },
+            );
+            diff_input(&field("input"), &left.input, &right.input, differences);
+        }
+        (Some(RelType::Aggregate(left)), Some(RelType::Aggregate(right))) => {
+            diff_lists(
+                &field("groupings"),
+                &left.groupings,
+                &right.groupings,
+                differences,
+                |path, left, right, differences| {
+                    diff_lists(
+                        &format!("{path}.grouping_expressions"),
+                        &left.grouping_expressions,
+                        &right.grouping_expressions,
+                        differences,
+                        diff_expression,
+                    )
+                },
+            );
+            diff_lists(
+                &field("measures"),
+                &left.measures,
+                &right.measures,
+                differences,
+                |path, left, right, differences| {
+                    diff_optional(
+                        &format!("{path}.measure"),
+                        left.measure.as_ref(),
+                        right.measure.as_ref(),
+                        differences,
+                        |path, left, right, differences| {
+                            let reference = format!("{path}.function_reference");
+                            let (left_ref, right_ref) =
+                                (&left.function_reference, &right.function_reference);
+                            diff_values(&reference, left_ref, right_ref, differences);
+                            diff_arguments(path, &left.arguments, &right.arguments, differences);
+                        },
+                    );
+                    diff_optional(
+                        &format!("{path}.filter"),
+                        left.filter.as_ref(),
+                        right.filter.as_ref(),
+                        differences,
+                        diff_expression,
+                    );
+                },
+            );
+            diff_input(&field("input"), &left.input, &right.input, differences);
+        }
+        (Some(RelType::Join(left)), Some(RelType::Join(right))) => {
+            diff_values(&field("type"), &left.r#type, &right.r#type, differences);
+            diff_optional(
+                &field("expression"),
+                left.expression.as_deref(),
+                right.expression.as_deref(),
+                differences,
+                diff_expression,
+            );
+            diff_input(&field("left"), &left.left, &right.left, differences);
+            diff_input(&field("right"), &left.right, &right.right, differences);
+        }
+        (Some(RelType::Cross(left)), Some(RelType::Cross(right))) => {
+            diff_input(&field("left"), &left.left, &right.left, differences);
+            diff_input(&field("right"), &left.right, &right.right, differences);
+        }
+        (Some(RelType::Set(left)), Some(RelType::Set(right))) => {
+            diff_values(&field("op"), &left.op, &right.op, differences);
+            let inputs = field("inputs");
+            diff_lists(&inputs, &left.inputs, &right.inputs, differences, diff_rel);
+        }
+        _ => {}
+    }
+    if differences.len() == found && left != right {
+        differences.push(format!("{path}: the relations differ"));
+    }
+}
+
+fn diff_input(
+    path: &str,
+    left: &Option<Box<Rel>>,
+    right: &Option<Box<Rel>>,
+    differences: &mut Vec<String>,
+) {
+    diff_optional(
+        path,
+        left.as_deref(),
+        right.as_deref(),
+        differences,
+        diff_rel,
+    )
+}
+
+fn diff_expression(
+    path: &str,
+    left: &Expression,
+    right: &Expression,
+    differences: &mut Vec<String>,
+) {
+    let (name, other_name) = (rex_name(left), rex_name(right));
+    if name != other_name {
+        differences.push(format!("{path}: {name} != {other_name}"));
+        return;
+    }
+    let path = format!("{path}.{name}");
+    let field = |field: &str| format!("{path}.{field}");
+    let found = differences.len();
+    match (&left.rex_type, &right.rex_type) {
+        (Some(RexType::Literal(left)), Some(RexType::Literal(right))) => {
+            diff_values(
+                &field("nullable"),
+                &left.nullable,
+                &right.nullable,
+                differences,
+            );
+            match (&left.literal_type, &right.literal_type) {
+                (Some(LiteralType::Null(left)), Some(LiteralType::Null(right))) => {
+                    diff_type(&field("Null"), left, right, differences)
+                }
+                (left, right) => {
+                    let (left, right) = (left.as_ref(), right.as_ref());
+                    diff_optional(&path, left, right, differences, diff_values)
+                }
+            }
+        }
+        (Some(RexType::ScalarFunction(left)), Some(RexType::ScalarFunction(right))) => {
+            let (left_ref, right_ref) = (&left.function_reference, &right.function_reference);
+            diff_values(
+                &field("function_reference"),
+                left_ref,
+                right_ref,
+                differences,
+            );
+            diff_arguments(&path, &left.arguments, &right.arguments, differences);
+            diff_optional(
+                &field("output_type"),
+                left.output_type.as_ref(),
+                right.output_type.as_ref(),
+                differences,
+                diff_type,
+            );
+        }
+        (Some(RexType::Cast(left)), Some(RexType::Cast(right))) => {
+            diff_optional(
+                &field("type"),
+                left.r#type.as_ref(),
+                right.r#type.as_ref(),
+                differences,
+                diff_type,
+            );
+            diff_optional(
+                &field("input"),
+                left.input.as_deref(),
+                right.input.as_deref(),
+                differences,
+                diff_expression,
+            );
+        }
+        (Some(RexType::IfThen(left)), Some(RexType::IfThen(right))) => {
+            diff_lists(
+                &field("ifs"),
+                &left.ifs,
+                &right.ifs,
+                differences,
+                |path, left, right, differences| {
+                    let (left_if, right_if) = (left.r#if.as_ref(), right.r#if.as_ref());
+                    let r#if = format!("{path}.if");
+                    diff_optional(&r#if, left_if, right_if, differences, diff_expression);
+                    let (left_then, right_then) = (left.then.as_ref(), right.then.as_ref());
+                    let then = format!("{path}.then");
+                    diff_optional(&then, left_then, right_then, differences, diff_expression);
+                },
+            );
+            diff_optional(
+                &field("else"),
+                left.r#else.as_deref(),
+                right.r#else.as_deref(),
+                differences,
+                diff_expression,
+            );
+        }
+        _ => {}
+    }
+    if differences.len() == found && left != right {
+        differences.push(format!("{path}: the expressions differ"));
+    }
+}
+
+fn diff_arguments(
+    path: &str,
+    left: &[FunctionArgument],
+    right: &[FunctionArgument],
+    differences: &mut Vec<String>,
+) {
+    diff_lists(
+        &format!("{path}.arguments"),
+        left,
+        right,
+        differences,
+        |path, left, right, differences| match (&left.arg_type, &right.arg_type) {
+            (Some(ArgType::Value(left)), Some(ArgType::Value(right))) => {
+                diff_expression(path, left, right, differences)
+            }
+            (Some(ArgType::Type(left)), Some(ArgType::Type(right))) => {
+                diff_type(path, left, right, differences)
+            }
+            (left, right) => diff_values(path, left, right, differences),
+        },
+    )
+}
+
+fn diff_named_struct(
+    path: &str,
+    left: &NamedStruct,
+    right: &NamedStruct,
+    differences: &mut Vec<String>,
+) {
+    diff_values(
+        &format!("{path}.names"),
+        &left.names,
+        &right.names,
+        differences,
+    );
+    diff_optional(
+        &format!("{path}.struct"),
+        left.r#struct.as_ref(),
+        right.r#struct.as_ref(),
+        differences,
+        |path, left, right, differences| {
+            let types = format!("{path}.types");
+            diff_lists(&types, &left.types, &right.types, differences, diff_type)
+        },
+    );
+}
+
+fn diff_type(path: &str, left: &Type, right: &Type, differences: &mut Vec<String>) {
+    let (name, other_name) = (kind_name(left), kind_name(right));
+    if name != other_name {
+        differences.push(format!("{path}: {name} != {other_name}"));
+        return;
+    }
+    let path = format!("{path}.{name}");
+    let found = differences.len();
+    let nullability = |ty: &Type| {
+        type_nullability(&mut ty.clone()).and_then(|n| r#type::Nullability::from_i32(*n))
+    };
+    diff_optional(
+        &format!("{path}.nullability"),
+        nullability(left).as_ref(),
+        nullability(right).as_ref(),
+        differences,
+        diff_values,
+    );
+    match (&left.kind, &right.kind) {
+        (Some(Kind::List(left)), Some(Kind::List(right))) => diff_optional(
+            &format!("{path}.type"),
+            left.r#type.as_deref(),
+            right.r#type.as_deref(),
+            differences,
+            diff_type,
+        ),
+        (Some(Kind::Struct(left)), Some(Kind::Struct(right))) => {
+            let types = format!("{path}.types");
+            diff_lists(&types, &left.types, &right.types, differences, diff_type)
+        }
+        (Some(Kind::Map(left)), Some(Kind::Map(right))) => {
+            let (left_key, right_key) = (left.key.as_deref(), right.key.as_deref());
+            diff_optional(
+                &format!("{path}.key"),
+                left_key,
+                right_key,
+                differences,
+                diff_type,
+            );
+            let (left_value, right_value) = (left.value.as_deref(), right.value.as_deref());
+            let value = format!("{path}.value");
+            diff_optional(&value, left_value, right_value, differences, diff_type);
+        }
+        _ => {}
+    }
+    if differences.len() == found && left != right {
+        differences.push(format!("{path}: the types differ"));
+    }
+}
+
+/// Reports `left` and `right` when they differ
+fn diff_values<T: PartialEq + std::fmt::Debug>(
+    path: &str,
+    left: &T,
+    right: &T,
+    differences: &mut Vec<String>,
+) {
+    if left != right {
+        differences.push(format!("{path}: {left:?} != {right:?}"));
+    }
+}
+
+/// Diffs `left` and `right` with `diff` when both are set
+fn diff_optional<T>(
+    path: &str,
+    left: Option<&T>,
+    right: Option<&T>,
+    differences: &mut Vec<String>,
+    diff: impl FnOnce(&str, &T, &T, &mut Vec<String>),
+) {
+    match (left, right) {
+        (Some(left), Some(right)) => diff(path, left, right, differences),
+        (Some(_), None) => differences.push(format!("{path}: Some != None")),
+        (None, Some(_)) => differences.push(format!("{path}: None != Some")),
+        (None, None) => {}
+    }
+}
+
+/// Diffs the elements of `left` and `right` at the same positions with `diff`
+fn diff_lists<T>(
+    path: &str,
+    left: &[T],
+    right: &[T],
+    differences: &mut Vec<String>,
+    diff: impl Fn(&str, &T, &T, &mut Vec<String>),
+) {
+    if left.len() != right.len() {
+        differences.push(format!(
+            "{path}: {} != {} elements",
+            left.len(),
+            right.len()
+        ));
+    }
+    for (index, (left, right)) in left.iter().zip(right).enumerate() {
+        diff(&format!("{path}[{index}]"), left, right, differences);
+    }
+}
+
+fn rel_name(rel: &Rel) -> &'static str {
+    match &rel.rel_type {
+        Some(RelType::Read(_)) => "Read",
+        Some(RelType::Filter(_)) => "Filter",
+        Some(RelType::Fetch(_)) => "Fetch",
+        Some(RelType::Aggregate(_)) => "Aggregate",
+        Some(RelType::Sort(_)) => "Sort",
+        Some(RelType::Join(_)) => "Join",
+        Some(RelType::Project(_)) => "Project",
+        Some(RelType::Set(_)) => "Set",
+        Some(RelType::ExtensionSingle(_)) => "ExtensionSingle",
+        Some(RelType::ExtensionMulti(_)) => "ExtensionMulti",
+        Some(RelType::ExtensionLeaf(_)) => "ExtensionLeaf",
+        Some(RelType::Cross(_)) => "Cross",
+        Some(RelType::HashJoin(_)) => "HashJoin",
+        Some(RelType::MergeJoin(_)) => "MergeJoin",
+        Some(RelType::Window(_)) => "Window",
+        Some(RelType::Exchange(_)) => "Exchange",
+        Some(RelType::Expand(_)) => "Expand",
+        None => "None",
+    }
+}
+
+fn rex_name(expression: &Expression) -> &'static str {
+    match &expression.rex_type {
+        Some(RexType::Literal(_)) => "Literal",
+        Some(RexType::Selection(_)) => "Selection",
+        Some(RexType::ScalarFunction(_)) => "ScalarFunction",
+        Some(RexType::WindowFunction(_)) => "WindowFunction",
+        Some(RexType::IfThen(_)) => "IfThen",
+        Some(RexType::SwitchExpression(_)) => "SwitchExpression",
+        Some(RexType::SingularOrList(_)) => "SingularOrList",
+        Some(RexType::MultiOrList(_)) => "MultiOrList",
+        Some(RexType::Cast(_)) => "Cast",
+        Some(RexType::Subquery(_)) => "Subquery",
+        Some(RexType::Nested(_)) => "Nested",
+        Some(RexType::Enum(_)) => "Enum",
+        None => "None",
+    }
+}
+
+fn kind_name(ty: &Type) -> &'static str {
+    match &ty.kind {
+        Some(Kind::Bool(_)) => "Bool",
+        Some(Kind::I8(_)) => "I8",
+        Some(Kind::I16(_)) => "I16",
+        Some(Kind::I32(_)) => "I32",
+        Some(Kind::I64(_)) => "I64",
+        Some(Kind::Fp32(_)) => "Fp32",
+        Some(Kind::Fp64(_)) => "Fp64",
+        Some(Kind::String(_)) => "String",
+        Some(Kind::Binary(_)) => "Binary",
+        Some(Kind::Timestamp(_)) => "Timestamp",
+        Some(Kind::Date(_)) => "Date",
+        Some(Kind::Time(_)) => "Time",
+        Some(Kind::IntervalYear(_)) => "IntervalYear",
+        Some(Kind::IntervalDay(_)) => "IntervalDay",
+        Some(Kind::TimestampTz(_)) => "TimestampTz",
+        Some(Kind::Uuid(_)) => "Uuid",
+        Some(Kind::FixedChar(_)) => "FixedChar",
+        Some(Kind::Varchar(_)) => "Varchar",
+        Some(Kind::FixedBinary(_)) => "FixedBinary",
+        Some(Kind::Decimal(_)) => "Decimal",
+        Some(Kind::Struct(_)) => "Struct",
+        Some(Kind::List(_)) => "List",
+        Some(Kind::Map(_)) => "Map",
+        Some(Kind::UserDefined(_)) => "UserDefined",
+        Some(Kind::UserDefinedTypeReference(_)) => "UserDefinedTypeReference",
+        None => "None",
+    }
+}
+
 pub(crate) fn init_module(m: &PyModule) -> PyResult<()> {
     m.add_class::<PyPlan>()?;
     m.add_class::<PySubstraitConsumer>()?;
@@ -17,7 +17,7 @@
 
 use std::sync::Arc;
 
-use pyo3::{prelude::*, types::PyTuple};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyTuple};
 
 use datafusion::arrow::array::{Array, ArrayRef};
 use datafusion::arrow::datatypes::DataType;
@@ -32,23 +32,64 @@ use crate::utils::parse_volatility;
 #[derive(Debug)]
 struct RustAccumulator {
     accum: PyObject,
+    return_type: DataType,
+    state_type: Vec<DataType>,
 }
 
 impl RustAccumulator {
-    fn new(accum: PyObject) -> Self {
-        Self { accum }
+    fn new(accum: PyObject, return_type: DataType, state_type: Vec<DataType>) -> Self {
+        Self {
+            accum,
+            return_type,
+            state_type,
+        }
     }
 }
 
+/// Converts a value returned by the Python accumulator to a ScalarValue of the given type.
+/// The value is either a pyarrow Scalar, which is cast if needed, or a plain Python value.
+fn to_scalar_value(value: &PyAny, data_type: &DataType) -> PyResult<ScalarValue> {
+    let py = value.py();
+    let pa = py.import("pyarrow")?;
+    let data_type = data_type.to_pyarrow(py)?;
+    let scalar = if value.is_instance(pa.getattr("Scalar")?)? {
+        if value.getattr("type")?.eq(&data_type)? {
+            value
+        } else {
+            value.call_method1("cast", (&data_type,))?
+        }
+    } else {
+        pa.call_method1("scalar", (value, &data_type))?
+    };
+    scalar.extract()
+}
+
 impl Accumulator for RustAccumulator {
     fn state(&self) -> Result<Vec<ScalarValue>> {
-        Python::with_gil(|py| self.accum.as_ref(py).call_method0("state")?.extract())
-            .map_err(|e| DataFusionError::Execution(format!("{e}")))
+        Python::with_gil(|py| {
+            let values: Vec<&PyAny> = self.accum.as_ref(py).call_method0("state")?.extract()?;
+            if values.len() != self.state_type.len() {
+                return Err(PyValueError::new_err(format!(
+                    "state() returned {} values, but the state type has {} fields",
+                    values.len(),
+                    self.state_type.len()
+                )));
+            }
+            values
+                .into_iter()
+                .zip(&self.state_type)
+                .map(|(value, data_type)| to_scalar_value(value, data_type))
+                .collect()
+        })
+        .map_err(|e| DataFusionError::Execution(format!("{e}")))
     }
 
     fn evaluate(&self) -> Result<ScalarValue> {
-        Python::with_gil(|py| self.accum.as_ref(py).call_method0("evaluate")?.extract())
-            .map_err(|e| DataFusionError::Execution(format!("{e}")))
+        Python::with_gil(|py| {
+            let value = self.accum.as_ref(py).call_method0("evaluate")?;
+            to_scalar_value(value, &self.return_type)
+        })
+        .map_err(|e| DataFusionError::Execution(format!("{e}")))
     }
 
     fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
@@ -72,18 +113,17 @@ impl Accumulator for RustAccumulator {
 
     fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
         Python::with_gil(|py| {
-            let state = &states[0];
-
-            // 1. cast states to Pyarrow array
-            let state = state
-                .into_data()
-                .to_pyarrow(py)
+            // 1. cast states to Pyarrow arrays, one per field of the state
+            let states = states
+                .iter()
+                .map(|state| state.into_data().to_pyarrow(py))
+                .collect::<PyResult<Vec<_>>>()
                 .map_err(|e| DataFusionError::Execution(format!("{e}")))?;
 
             // 2. call merge
             self.accum
                 .as_ref(py)
-                .call_method1("merge", (state,))
+                .call_method1("merge", PyTuple::new(py, states))
                 .map_err(|e| DataFusionError::Execution(format!("{e}")))?;
 
             Ok(())
@@ -95,14 +135,22 @@ impl Accumulator for RustAccumulator {
     }
 }
 
-pub fn to_rust_accumulator(accum: PyObject) -> AccumulatorFactoryFunction {
+pub fn to_rust_accumulator(
+    accum: PyObject,
+    return_type: DataType,
+    state_type: Vec<DataType>,
+) -> AccumulatorFactoryFunction {
     Arc::new(move |_| -> Result<Box<dyn Accumulator>> {
         let accum = Python::with_gil(|py| {
             accum
                 .call0(py)
                 .map_err(|e| DataFusionError::Execution(format!("{e}")))
         })?;
-        Ok(Box::new(RustAccumulator::new(accum)))
+        Ok(Box::new(RustAccumulator::new(
+            accum,
+            return_type.clone(),
+            state_type.clone(),
+        )))
     })
 }
 
@@ -115,21 +163,26 @@ pub struct PyAggregateUDF {
 
 #[pymethods]
 impl PyAggregateUDF {
+    /// `input_type` is either a single type or a list of types, one per argument
     #[new(name, accumulator, input_type, return_type, state_type, volatility)]
     fn new(
         name: &str,
         accumulator: PyObject,
-        input_type: PyArrowType<DataType>,
+        input_type: &PyAny,
         return_type: PyArrowType<DataType>,
         state_type: PyArrowType<Vec<DataType>>,
         volatility: &str,
     ) -> PyResult<Self> {
+        let input_types = match input_type.extract::<PyArrowType<Vec<DataType>>>() {
+            Ok(input_types) => input_types.0,
+            Err(_) => vec![input_type.extract::<PyArrowType<DataType>>()?.0],
+        };
         let function = create_udaf(
             name,
-            vec![input_type.0],
-            Arc::new(return_type.0),
+            input_types,
+            Arc::new(return_type.0.clone()),
             parse_volatility(volatility)?,
-            to_rust_accumulator(accumulator),
+            to_rust_accumulator(accumulator, return_type.0, state_type.0.clone()),
             Arc::new(state_type.0),
         );
         Ok(Self { function })
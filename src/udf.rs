@@ -15,13 +15,23 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::ptr::addr_of;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
+use once_cell::sync::Lazy;
+use pyo3::exceptions::PyValueError;
+use pyo3::ffi::Py_uintptr_t;
+use pyo3::sync::GILOnceCell;
 use pyo3::{prelude::*, types::PyTuple};
 
 use datafusion::arrow::array::{make_array, Array, ArrayData, ArrayRef};
 use datafusion::arrow::datatypes::DataType;
-use datafusion::arrow::pyarrow::{FromPyArrow, PyArrowType, ToPyArrow};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use datafusion::arrow::pyarrow::{FromPyArrow, PyArrowType};
 use datafusion::error::DataFusionError;
 use datafusion::physical_plan::functions::make_scalar_function;
 use datafusion::physical_plan::udf::ScalarUDF;
@@ -33,32 +43,186 @@ use crate::utils::parse_volatility;
 
 /// Create a DataFusion's UDF implementation from a python function
 /// that expects pyarrow arrays. This is more efficient as it performs
-/// a zero-copy of the contents.
-fn to_rust_function(func: PyObject) -> ScalarFunctionImplementation {
+/// a zero-copy of the contents. The array returned by the function must have the declared
+/// `return_type`, which may be nested, such as a struct or a list.
+fn to_rust_function(
+    name: String,
+    func: PyObject,
+    return_type: DataType,
+    profile: Arc<UdfProfile>,
+) -> ScalarFunctionImplementation {
     make_scalar_function(
         move |args: &[ArrayRef]| -> Result<ArrayRef, DataFusionError> {
+            // the GIL is only held for the conversions and the call, the rest of the plan runs
+            // without it
             Python::with_gil(|py| {
+                let start = Instant::now();
                 // 1. cast args to Pyarrow arrays
+                let import = import_array(py).map_err(py_error)?;
                 let py_args = args
                     .iter()
-                    .map(|arg| arg.into_data().to_pyarrow(py).unwrap())
-                    .collect::<Vec<_>>();
+                    .map(|arg| array_to_pyarrow(py, import, &arg.to_data()))
+                    .collect::<PyResult<Vec<_>>>()
+                    .map_err(py_error)?;
                 let py_args = PyTuple::new(py, py_args);
 
                 // 2. call function
-                let value = func
-                    .as_ref(py)
-                    .call(py_args, None)
-                    .map_err(|e| DataFusionError::Execution(format!("{e:?}")))?;
+                let called = Instant::now();
+                let value = func.as_ref(py).call1(py_args).map_err(py_error)?;
+                let returned = Instant::now();
 
                 // 3. cast to arrow::array::Array
-                let array_data = ArrayData::from_pyarrow(value).unwrap();
+                let array_data = ArrayData::from_pyarrow(value).map_err(|e| {
+                    DataFusionError::Execution(format!(
+                        "UDF {name} must return a pyarrow Array: {e}"
+                    ))
+                })?;
+                if !same_type(array_data.data_type(), &return_type) {
+                    return Err(DataFusionError::Execution(format!(
+                        "UDF {name} returned an array of type {}, but its return_type is {}",
+                        array_data.data_type(),
+                        return_type
+                    )));
+                }
+                // pyarrow may name the items of lists or flag the fields of structs as
+                // nullable differently, the array is labeled with the declared type
+                let array_data = with_type(array_data, &return_type)?;
+                profile.record(
+                    array_data.len(),
+                    (called - start) + returned.elapsed(),
+                    returned - called,
+                );
                 Ok(make_array(array_data))
             })
         },
     )
 }
 
+/// Wraps an exception raised while calling a UDF
+fn py_error(e: PyErr) -> DataFusionError {
+    DataFusionError::Execution(format!("{e:?}"))
+}
+
+/// Returns `pyarrow.Array._import_from_c`, looked up once rather than for every batch
+fn import_array(py: Python) -> PyResult<&PyAny> {
+    static IMPORT_ARRAY: GILOnceCell<PyObject> = GILOnceCell::new();
+    let import = IMPORT_ARRAY.get_or_try_init(py, || -> PyResult<_> {
+        Ok(py
+            .import("pyarrow")?
+            .getattr("Array")?
+            .getattr("_import_from_c")?
+            .into())
+    })?;
+    Ok(import.as_ref(py))
+}
+
+/// Exports an array to pyarrow without copying its buffers, like `ToPyArrow`
+fn array_to_pyarrow(py: Python, import: &PyAny, data: &ArrayData) -> PyResult<PyObject> {
+    let array = FFI_ArrowArray::new(data);
+    let schema = FFI_ArrowSchema::try_from(data.data_type())
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let array = import.call1((
+        addr_of!(array) as Py_uintptr_t,
+        addr_of!(schema) as Py_uintptr_t,
+    ))?;
+    Ok(array.to_object(py))
+}
+
+/// The batches and rows computed by a Python UDF and the time spent converting its arguments
+/// and results between Arrow and Python and in the call itself, summed over all its calls
+#[derive(Default)]
+pub(crate) struct UdfProfile {
+    batches: AtomicU64,
+    rows: AtomicU64,
+    conversion_nanos: AtomicU64,
+    call_nanos: AtomicU64,
+}
+
+impl UdfProfile {
+    fn record(&self, rows: usize, conversion: Duration, call: Duration) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.rows.fetch_add(rows as u64, Ordering::Relaxed);
+        self.conversion_nanos
+            .fetch_add(conversion.as_nanos() as u64, Ordering::Relaxed);
+        self.call_nanos
+            .fetch_add(call.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the batches, rows, conversion and call nanoseconds recorded so far
+    pub(crate) fn snapshot(&self) -> [u64; 4] {
+        [
+            self.batches.load(Ordering::Relaxed),
+            self.rows.load(Ordering::Relaxed),
+            self.conversion_nanos.load(Ordering::Relaxed),
+            self.call_nanos.load(Ordering::Relaxed),
+        ]
+    }
+}
+
+/// The profiles of the Python UDFs, by the address of their implementation, which is shared
+/// by all the copies of a UDF. The implementation owns the profile, so an entry is dropped
+/// along with its UDF.
+fn profiles() -> &'static Mutex<HashMap<usize, Weak<UdfProfile>>> {
+    static PROFILES: Lazy<Mutex<HashMap<usize, Weak<UdfProfile>>>> = Lazy::new(Default::default);
+    &PROFILES
+}
+
+/// Returns the profile of `udf` when it is implemented in Python
+pub(crate) fn udf_profile(udf: &ScalarUDF) -> Option<Arc<UdfProfile>> {
+    let key = Arc::as_ptr(&udf.fun) as *const () as usize;
+    profiles().lock().unwrap().get(&key)?.upgrade()
+}
+
+/// Whether an array of type `actual` may be labeled with the type `declared`: nested types must
+/// have the same structure and fields of the same names, ignoring the names of list items and
+/// the nullability and metadata of fields
+fn same_type(actual: &DataType, declared: &DataType) -> bool {
+    match (actual, declared) {
+        (DataType::List(a), DataType::List(b))
+        | (DataType::LargeList(a), DataType::LargeList(b)) => {
+            same_type(a.data_type(), b.data_type())
+        }
+        (DataType::FixedSizeList(a, a_size), DataType::FixedSizeList(b, b_size)) => {
+            a_size == b_size && same_type(a.data_type(), b.data_type())
+        }
+        (DataType::Struct(a), DataType::Struct(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| a.name() == b.name() && same_type(a.data_type(), b.data_type()))
+        }
+        (DataType::Map(a, a_sorted), DataType::Map(b, b_sorted)) => {
+            a_sorted == b_sorted && same_type(a.data_type(), b.data_type())
+        }
+        _ => actual == declared,
+    }
+}
+
+/// Labels the array and its children with `data_type`, which must pass `same_type`
+fn with_type(data: ArrayData, data_type: &DataType) -> Result<ArrayData, ArrowError> {
+    if data.data_type() == data_type {
+        return Ok(data);
+    }
+    let child_types: Vec<&DataType> = match data_type {
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _)
+        | DataType::Map(field, _) => vec![field.data_type()],
+        DataType::Struct(fields) => fields.iter().map(|field| field.data_type()).collect(),
+        _ => vec![],
+    };
+    let child_data = data
+        .child_data()
+        .iter()
+        .zip(child_types)
+        .map(|(child, child_type)| with_type(child.clone(), child_type))
+        .collect::<Result<Vec<_>, _>>()?;
+    data.into_builder()
+        .data_type(data_type.clone())
+        .child_data(child_data)
+        .build()
+}
+
 /// Represents a PyScalarUDF
 #[pyclass(name = "ScalarUDF", module = "datafusion", subclass)]
 #[derive(Debug, Clone)]
@@ -76,13 +240,18 @@ impl PyScalarUDF {
         return_type: PyArrowType<DataType>,
         volatility: &str,
     ) -> PyResult<Self> {
+        let profile = Arc::new(UdfProfile::default());
         let function = create_udf(
             name,
             input_types.0,
-            Arc::new(return_type.0),
+            Arc::new(return_type.0.clone()),
             parse_volatility(volatility)?,
-            to_rust_function(func),
+            to_rust_function(name.to_string(), func, return_type.0, profile.clone()),
         );
+        let key = Arc::as_ptr(&function.fun) as *const () as usize;
+        let mut profiles = profiles().lock().unwrap();
+        profiles.retain(|_, profile| profile.strong_count() > 0);
+        profiles.insert(key, Arc::downgrade(&profile));
         Ok(Self { function })
     }
 
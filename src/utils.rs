@@ -17,9 +17,18 @@
 
 use crate::errors::DataFusionError;
 use crate::TokioRuntime;
+use datafusion::arrow::array::StructArray;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use datafusion::arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
+use datafusion::arrow::pyarrow::PyArrowType;
+use datafusion::arrow::record_batch::{RecordBatch, RecordBatchReader};
 use datafusion_expr::Volatility;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::{PyCapsule, PyDict};
 use std::future::Future;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 /// Utility to get the Tokio Runtime from Python
@@ -38,6 +47,30 @@ where
     py.allow_threads(|| runtime.block_on(f))
 }
 
+/// How often `wait_for_future_interruptible` checks for signals
+const INTERRUPT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Like `wait_for_future`, but checks for signals while waiting, so that a KeyboardInterrupt
+/// stops waiting and drops the future, cancelling the work it was doing
+pub(crate) fn wait_for_future_interruptible<F>(py: Python, f: F) -> PyResult<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let runtime: &Runtime = &get_tokio_runtime(py).0;
+    let mut f = Box::pin(f);
+    loop {
+        // the timeout has to be created within the runtime
+        let result = py.allow_threads(|| {
+            runtime.block_on(async { tokio::time::timeout(INTERRUPT_CHECK_INTERVAL, &mut f).await })
+        });
+        match result {
+            Ok(output) => return Ok(output),
+            Err(_) => py.check_signals()?,
+        }
+    }
+}
+
 pub(crate) fn parse_volatility(value: &str) -> Result<Volatility, DataFusionError> {
     Ok(match value {
         "immutable" => Volatility::Immutable,
@@ -51,3 +84,173 @@ pub(crate) fn parse_volatility(value: &str) -> Result<Volatility, DataFusionErro
         }
     })
 }
+
+/// Parses the `errors` argument of the casts and of the parsing functions, `"raise"` to fail on
+/// the values that can't be converted and `"null"` to turn them into null. Returns whether they
+/// become null.
+pub(crate) fn parse_cast_errors(errors: &str) -> PyResult<bool> {
+    match errors {
+        "raise" => Ok(false),
+        "null" => Ok(true),
+        errors => Err(PyValueError::new_err(format!(
+            "Unrecognized errors {errors}, supported values are: raise and null"
+        ))),
+    }
+}
+
+/// Whether the values that can't be converted become null, from the `safe` flag of the casts
+/// and of the parsing functions or from its alias `errors`, see `parse_cast_errors`. Passing
+/// `safe=True` with `errors="raise"` is an error.
+pub(crate) fn parse_safe(safe: bool, errors: Option<&str>) -> PyResult<bool> {
+    let null_on_error = match errors {
+        Some(errors) => parse_cast_errors(errors)?,
+        None => return Ok(safe),
+    };
+    if safe && !null_on_error {
+        return Err(PyValueError::new_err(
+            "safe=True turns the values that can't be converted into null, which contradicts \
+             errors=\"raise\"",
+        ));
+    }
+    Ok(null_on_error)
+}
+
+/// Imports an object implementing the Arrow PyCapsule interface without copying, either as a
+/// stream (`__arrow_c_stream__`) or as a single struct array (`__arrow_c_array__`). Returns
+/// `None` if the object implements neither.
+pub(crate) fn import_arrow_c_data(data: &PyAny) -> PyResult<Option<(SchemaRef, Vec<RecordBatch>)>> {
+    if data.hasattr("__arrow_c_stream__")? {
+        let capsule: &PyCapsule = data.call_method0("__arrow_c_stream__")?.downcast()?;
+        validate_capsule(capsule, "arrow_array_stream")?;
+        // SAFETY: the capsule holds an ArrowArrayStream, which the reader moves out of,
+        // leaving a released stream behind for the capsule destructor
+        let reader = unsafe {
+            ArrowArrayStreamReader::from_raw(capsule.pointer() as *mut FFI_ArrowArrayStream)
+        }
+        .map_err(DataFusionError::from)?;
+        let schema = reader.schema();
+        let batches = reader
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(DataFusionError::from)?;
+        return Ok(Some((schema, batches)));
+    }
+
+    if data.hasattr("__arrow_c_array__")? {
+        let (schema_capsule, array_capsule): (&PyCapsule, &PyCapsule) =
+            data.call_method0("__arrow_c_array__")?.extract()?;
+        validate_capsule(schema_capsule, "arrow_schema")?;
+        validate_capsule(array_capsule, "arrow_array")?;
+        // SAFETY: the capsules hold an ArrowSchema and an ArrowArray, the latter is moved out
+        // and replaced by a released array for the capsule destructor
+        let schema = unsafe { &*(schema_capsule.pointer() as *const FFI_ArrowSchema) };
+        let array = unsafe {
+            std::ptr::replace(
+                array_capsule.pointer() as *mut FFI_ArrowArray,
+                FFI_ArrowArray::empty(),
+            )
+        };
+        let array_data = from_ffi(array, schema).map_err(DataFusionError::from)?;
+        if !matches!(array_data.data_type(), DataType::Struct(_)) {
+            return Err(PyValueError::new_err(format!(
+                "Expected a struct array to import as a record batch, got {}",
+                array_data.data_type()
+            )));
+        }
+        let batch = RecordBatch::from(StructArray::from(array_data));
+        return Ok(Some((batch.schema(), vec![batch])));
+    }
+
+    Ok(None)
+}
+
+fn validate_capsule(capsule: &PyCapsule, name: &str) -> PyResult<()> {
+    let capsule_name = capsule.name()?.map(|name| name.to_string_lossy());
+    match capsule_name {
+        Some(capsule_name) if capsule_name == name => Ok(()),
+        _ => Err(PyValueError::new_err(format!(
+            "Expected a '{name}' PyCapsule, got {capsule_name:?}"
+        ))),
+    }
+}
+
+/// The type names accepted in a schema given as a dict, see `SchemaArg`
+const TYPE_NAMES: &[(&str, DataType)] = &[
+    ("null", DataType::Null),
+    ("bool", DataType::Boolean),
+    ("boolean", DataType::Boolean),
+    ("int8", DataType::Int8),
+    ("int16", DataType::Int16),
+    ("int32", DataType::Int32),
+    ("int64", DataType::Int64),
+    ("uint8", DataType::UInt8),
+    ("uint16", DataType::UInt16),
+    ("uint32", DataType::UInt32),
+    ("uint64", DataType::UInt64),
+    ("float16", DataType::Float16),
+    ("float32", DataType::Float32),
+    ("float", DataType::Float32),
+    ("float64", DataType::Float64),
+    ("double", DataType::Float64),
+    ("string", DataType::Utf8),
+    ("utf8", DataType::Utf8),
+    ("large_string", DataType::LargeUtf8),
+    ("large_utf8", DataType::LargeUtf8),
+    ("binary", DataType::Binary),
+    ("large_binary", DataType::LargeBinary),
+    ("date32", DataType::Date32),
+    ("date64", DataType::Date64),
+    ("timestamp[s]", DataType::Timestamp(TimeUnit::Second, None)),
+    (
+        "timestamp[ms]",
+        DataType::Timestamp(TimeUnit::Millisecond, None),
+    ),
+    (
+        "timestamp[us]",
+        DataType::Timestamp(TimeUnit::Microsecond, None),
+    ),
+    (
+        "timestamp[ns]",
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+    ),
+];
+
+/// A `schema` argument, either a pyarrow Schema or a dict mapping the column names to type
+/// names such as `"int64"` or `"string"`, or to pyarrow DataTypes. The columns of a dict are
+/// nullable.
+pub(crate) struct SchemaArg(pub Schema);
+
+impl<'source> FromPyObject<'source> for SchemaArg {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let columns = match ob.downcast::<PyDict>() {
+            Ok(columns) => columns,
+            Err(_) => return Ok(Self(ob.extract::<PyArrowType<Schema>>()?.0)),
+        };
+        let fields = columns
+            .iter()
+            .map(|(name, data_type)| {
+                let name: String = name.extract()?;
+                let data_type = match data_type.extract::<&str>() {
+                    Ok(type_name) => data_type_from_name(&name, type_name)?,
+                    Err(_) => data_type.extract::<PyArrowType<DataType>>()?.0,
+                };
+                Ok(Field::new(name, data_type, true))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self(Schema::new(fields)))
+    }
+}
+
+fn data_type_from_name(column: &str, type_name: &str) -> PyResult<DataType> {
+    let lowercase = type_name.to_lowercase();
+    TYPE_NAMES
+        .iter()
+        .find(|(name, _)| *name == lowercase)
+        .map(|(_, data_type)| data_type.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = TYPE_NAMES.iter().map(|(name, _)| *name).collect();
+            PyValueError::new_err(format!(
+                "Unrecognized type {type_name:?} of column {column:?}, supported values are: {}",
+                names.join(", ")
+            ))
+        })
+}